@@ -22,3 +22,9 @@ toggle-markdown-preview-shortcut = Toggle Markdown Preview (Ctrl+M)
 toggle-editor-shortcut = Toggle Editor (Ctrl+E)
 toggle-spell-check-view-shortcut = Toggle Spell Check View (Ctrl+K)
 open-website-styles-file = Open Website Styles File
+send-checklist-item-to-tasks = Send Checklist Item to Tasks
+send-all-checklist-items-to-tasks = Send All Checklist Items to Tasks
+send-to-tasks = Send to Tasks
+checklist-items-to-send = Checklist items to send: 
+choose-task-project = Choose a Task Project
+send = Send
@@ -38,3 +38,77 @@ back = Back
 
 
 
+group = Group
+group-name = Group Name
+new-group = New Group
+rename-group = Rename Group
+delete-group = Delete Group
+open-another-database = Open Another Database
+move-to-database = Move to Database
+pick-database = Pick a database
+totp = TOTP
+paste-otpauth-uri = Paste an otpauth:// URI
+scan-qr-code-from-image = Scan QR Code From Image
+invalid-totp-uri = Invalid TOTP URI
+password-generator-options = Password Generator Options
+random-characters = Random Characters
+passphrase = Passphrase (Diceware)
+length = Length:
+lowercase = Lowercase
+uppercase = Uppercase
+digits = Digits
+symbols = Symbols
+exclude-look-alike-characters = Exclude look-alike characters (I, l, 1, O, 0...)
+include-each-character-class = Include at least one of each character type
+word-count = Words:
+word-separator = Separator:
+capitalise-words = Capitalise words
+entropy = Estimated strength:
+bits = bits of entropy
+load-preset = Load Preset
+preset-name = Preset Name
+save-preset = Save Preset
+delete-preset = Delete Preset
+password-health-report = Password Health Report
+refresh = Refresh
+checking-passwords = Checking passwords...
+no-problems-found = No problems found
+problems-found = problems found
+weak-password = Weak password
+password-used-by = Same password used by
+entries = entries
+not-changed-since = Password not changed since
+empty-username = Empty username
+insecure-url = Insecure http:// URL
+compromised = Compromised
+password-found-in-breaches = This password has appeared in data breaches
+times = times
+import-passwords = Import Passwords
+import-preview = Import Preview
+import-and-save = Import and Save
+new-entries = new entries
+duplicates-skipped = duplicates skipped
+import-into-group = Importing into group
+duplicate = Duplicate
+export-passwords = Export Passwords
+csv-file = CSV File
+encrypted-bundle = Encrypted Bundle
+csv-export-warning = CSV files aren't encrypted, anyone who gets the file can read every password in it
+bundle-password = Bundle Password
+export-groups = Groups
+export-tags = Tags
+entries-selected = entries selected
+re-enter-master-password-to-export = Re-enter the master password to export:
+export = Export
+enter-bundle-password = This is an encrypted bundle, enter its password:
+open-bundle = Open Bundle
+browser-association-request = Connect Browser
+browser-association-description = A browser extension is asking to connect to this database so it can fill in and save logins. Give the connection a name to recognise it by.
+browser-association-name = Connection name
+browser-association-name-taken = A browser connection with this name already exists
+ssh-key-use-request = Use SSH Key
+ssh-key-use-description = An SSH client is asking to sign in with the key from this entry.
+allow = Allow
+deny = Deny
+share-group-with-apps = Keep Other Apps' Secrets Here
+group-shared-with-apps = Other apps' secrets are kept in this group
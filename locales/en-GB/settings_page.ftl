@@ -28,6 +28,24 @@ autocomplete-lists = Autocomplete lists
 default-database = Default Database
 no-default-database-selected = No Default Database Selected
 select-default-database = Select Default Database
+max-password-age-months = Flag passwords unchanged for this many months in the health report:
+breached-password-hashes = Breached Password Hashes
+no-breached-password-hashes-selected = No Breached Password Hashes Selected (download them from Have I Been Pwned to check passwords offline)
+select-hash-file = Select Hash File
+select-range-folder = Select Range Folder
+clear = Clear
+clipboard-clear-seconds = Clear copied passwords from the clipboard after this many seconds:
+never = Never
+auto-lock-minutes = Lock the password database after this many minutes without activity:
+lock-on-screen-lock = Lock the password database when the screen is locked
+lock-on-minimise = Lock the password database when the window is minimised (on Wayland, when it loses focus)
+enable-browser-integration = Let browser extensions fill and save logins (KeePassXC-Browser)
+install-browser-integration = Install for Browsers
+enable-secret-service = Store other apps' secrets in the password database (Secret Service)
+secret-service-flatpak-override = Run this to let the Flatpak provide the Secret Service
+enable-ssh-agent = Serve SSH keys from the password database (SSH agent)
+ssh-agent-confirm-use = Ask before each use of an SSH key
+ssh-agent-socket = Set this for ssh to use the keys
 default-photos-folder = Default Photos Folder
 no-default-photos-folder-selected = No Default Photos Folder Selected
 select-default-photos-folder = Select Default Photos Folder
@@ -42,3 +60,13 @@ current-theme = Current theme
 
 
 
+enable-reminder-notifications = Show desktop notifications for task reminders
+enable-caldav-server = Share task projects with calendar apps using CalDAV
+caldav-server-allow-network-access = Let calendar apps on other devices on the local network connect to the CalDAV server
+caldav-server-address = CalDAV Server Address
+this-computers-ip-address = this computer's IP address
+caldav-server-port = CalDAV Server Port
+set-port = Set Port
+caldav-server-password = CalDAV Server Password
+caldav-server-password-placeholder = Required before the server will start
+set-password = Set Password
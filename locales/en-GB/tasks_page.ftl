@@ -20,3 +20,59 @@ rename-project = Rename Project
 use-list-view-shortcut = Use List View (Ctrl+L)
 use-kanban-view-shortcut = Use Kanban View (Ctrl+L)
 new-task-shortcut = New Task (Ctrl+N)
+task-labels = Labels (comma separated)
+task-due-date = Due Date (YYYY-MM-DD)
+due = Due:
+task-reminder = Reminder (YYYY-MM-DD HH:MM)
+search-all-projects = Search All Projects (Press Enter)
+search-all-projects-shortcut = Search All Projects (Ctrl+F)
+include-archived = Include Archived
+saved-filters = Saved Filters
+new-saved-filter = New Saved Filter
+saved-filter-name = Filter Name
+saved-filter-text = Matching Text
+saved-filter-label = Label
+saved-filter-due-on-or-before = Due On Or Before (YYYY-MM-DD)
+any-state = Any
+all-tasks = All Tasks
+import-tasks = Import Tasks
+export-todo-txt = Export as todo.txt
+export-csv = Export as CSV
+export-icalendar = Export as iCalendar
+start-timer = Start Timer
+stop-timer = Stop Timer
+tracked-time = Time Tracked:
+time-report = Time Report
+time-by-task = Time By Task
+time-by-day = Time By Day
+export-time-report-csv = Export as CSV
+no-time-tracked = No time has been tracked on this project yet
+blocked-by = Blocked By:
+add-blocker = Add a Blocking Task
+deleted-task = Deleted Task
+dependency-graph = Dependency Graph
+after = After:
+history = History
+task-created = Created
+task-moved = Moved:
+task-edited = Edited
+task-deleted = Deleted
+open-note = Open Note:
+line = Line
+project-statistics = Project Statistics
+average-cycle-time = Average time from Doing to Done:
+days = days
+no-finished-tasks = No tasks finished yet
+cumulative-flow = Cumulative Flow
+burndown = Open Tasks Burndown
+select-all = Select All
+selected-tasks = Selected tasks:
+clear-selection = Clear Selection
+move-to = Move to
+labels-to-add = Labels to add, comma separated
+add-labels = Add Labels
+move-to-project = Move to Project
+delete-selected-tasks = Delete the selected tasks? Tasks selected:
+reminder-project = Project:
+snooze-reminder = Snooze 10 Minutes
+mark-done = Mark Done
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use arboard::Clipboard;
 use fluent_templates::Loader;
@@ -6,9 +6,11 @@ use iced::{
     alignment::Horizontal,
     event,
     keyboard::{self, Key, Modifiers},
+    time,
     widget::{self, button, column, container, row, svg, text, tooltip::Position, Svg, Tooltip},
     window, Alignment, ContentFit, Element, Event, Length, Subscription, Task, Theme,
 };
+use zeroize::Zeroizing;
 
 use crate::{
     config::AppConfig,
@@ -24,7 +26,12 @@ use crate::{
         sync::page::{SyncPage, SyncPageMessage},
         tasks::page::{TasksPage, TasksPageMessage},
     },
-    utils::socket_utils::{self, ServerMessage},
+    utils::{
+        caldav_utils,
+        clipboard_utils::{clear_clipboard_if_unchanged, copy_secret_to_clipboard},
+        notification_utils,
+        socket_utils::{self, ServerMessage},
+    },
     Page, LOCALES,
 };
 
@@ -45,8 +52,12 @@ pub enum Message {
     SaveConfig,
     ServerMessageEvent(socket_utils::Event),
     SendServerMessage(String),
+    CaldavServerEvent(caldav_utils::Event),
+    ReminderEvent(notification_utils::Event),
     FinishSetup,
     CopyValueToClipboard(String),
+    CopySecretToClipboard(Zeroizing<String>),
+    ClipboardClearTick,
 }
 
 pub struct AppState {
@@ -65,6 +76,10 @@ pub struct AppState {
     show_toast: bool,
     is_good_toast: bool,
     toast_text: String,
+    /// Whether the toast is the clipboard countdown, which other toasts take priority over
+    is_clipboard_countdown_toast: bool,
+    copied_secret: Option<Zeroizing<String>>,
+    clipboard_clear_deadline: Option<Instant>,
     server_connection_state: ServerConnectionState,
 }
 
@@ -99,6 +114,9 @@ impl AppState {
                 show_toast: false,
                 is_good_toast: true,
                 toast_text: String::new(),
+                is_clipboard_countdown_toast: false,
+                copied_secret: None,
+                clipboard_clear_deadline: None,
                 server_connection_state: ServerConnectionState::Disconnected,
                 is_setting_up_server,
                 setup_wizard: SetupWizard::new(),
@@ -129,6 +147,7 @@ impl AppState {
                 self.show_toast = true;
                 self.is_good_toast = is_good_toast;
                 self.toast_text = content;
+                self.is_clipboard_countdown_toast = false;
                 return Task::perform(
                     async { std::thread::sleep(Duration::from_millis(5000)) },
                     |_| Message::ToastExpired,
@@ -141,6 +160,9 @@ impl AppState {
             }
             Message::CloseWindowRequest => {
                 self.is_closing = true;
+                if let Some(copied_secret) = self.copied_secret.take() {
+                    let _ = clear_clipboard_if_unchanged(&copied_secret);
+                }
                 return Task::batch([
                     self.passwords_page.closing_task(),
                     self.notes_page.closing_task(),
@@ -168,6 +190,37 @@ impl AppState {
                 socket_utils::Event::MessageReceived(message) => {
                     println!("Recieved update: {message:?}");
                 }
+                socket_utils::Event::ProjectFileChanged(project_file) => {
+                    return Task::done(Message::Tasks(TasksPageMessage::ReloadProjectFile(
+                        project_file,
+                    )));
+                }
+            },
+            Message::CaldavServerEvent(event) => match event {
+                caldav_utils::Event::ProjectFileChanged(project_file) => {
+                    return Task::done(Message::Tasks(TasksPageMessage::ReloadProjectFile(
+                        project_file,
+                    )));
+                }
+                caldav_utils::Event::Error(err_string) => {
+                    return Task::done(Message::ShowToast(false, err_string));
+                }
+            },
+            Message::ReminderEvent(event) => match event {
+                notification_utils::Event::ProjectFileChanged(project_file) => {
+                    return Task::done(Message::Tasks(TasksPageMessage::ReloadProjectFile(
+                        project_file,
+                    )));
+                }
+                notification_utils::Event::MarkTaskDone(project_file, task_id) => {
+                    return Task::done(Message::Tasks(TasksPageMessage::MarkTaskDoneInProject(
+                        project_file,
+                        task_id,
+                    )));
+                }
+                notification_utils::Event::Error(err_string) => {
+                    return Task::done(Message::ShowToast(false, err_string));
+                }
             },
             Message::SendServerMessage(message_string) => {
                 if let ServerConnectionState::Connected(connection) =
@@ -199,6 +252,52 @@ impl AppState {
                     ));
                 }
             },
+            Message::CopySecretToClipboard(secret) => {
+                if let Err(err) = copy_secret_to_clipboard(secret.to_string()) {
+                    return Task::done(Message::ShowToast(false, err));
+                }
+                let clear_seconds = self.config.passwords_config.clipboard_clear_seconds;
+                if clear_seconds > 0 {
+                    self.copied_secret = Some(secret);
+                    self.clipboard_clear_deadline =
+                        Some(Instant::now() + Duration::from_secs(clear_seconds.into()));
+                    return Task::done(Message::ClipboardClearTick);
+                }
+            }
+            Message::ClipboardClearTick => {
+                if let Some(clipboard_clear_deadline) = self.clipboard_clear_deadline {
+                    let remaining =
+                        clipboard_clear_deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        self.clipboard_clear_deadline = None;
+                        if self.is_clipboard_countdown_toast {
+                            self.show_toast = false;
+                            self.is_clipboard_countdown_toast = false;
+                        }
+                        if let Some(copied_secret) = self.copied_secret.take() {
+                            match clear_clipboard_if_unchanged(&copied_secret) {
+                                Ok(true) => {
+                                    return Task::done(Message::ShowToast(
+                                        true,
+                                        String::from("Clipboard cleared"),
+                                    ))
+                                }
+                                Ok(false) => (),
+                                Err(err) => return Task::done(Message::ShowToast(false, err)),
+                            }
+                        }
+                    } else if !self.show_toast || self.is_clipboard_countdown_toast {
+                        // Shown directly rather than through ShowToast so it isn't hidden after 5 seconds
+                        self.show_toast = true;
+                        self.is_clipboard_countdown_toast = true;
+                        self.is_good_toast = true;
+                        self.toast_text = format!(
+                            "Copied, the clipboard will be cleared in {}s",
+                            remaining.as_millis().div_ceil(1000)
+                        );
+                    }
+                }
+            }
             Message::None => (),
         }
         Task::none()
@@ -238,11 +337,25 @@ impl AppState {
                 _ => None,
             }
         }));
+        if self.clipboard_clear_deadline.is_some() {
+            subscriptions_vec
+                .push(time::every(Duration::from_secs(1)).map(|_| Message::ClipboardClearTick));
+        }
         if !self.is_setting_up_server {
+            subscriptions_vec.push(self.passwords_page.auto_lock_subscription());
+            if self.config.passwords_config.browser_integration_enabled {
+                subscriptions_vec.push(self.passwords_page.browser_integration_subscription());
+            }
+            if self.config.passwords_config.secret_service_enabled {
+                subscriptions_vec.push(self.passwords_page.secret_service_subscription());
+            }
+            if self.config.passwords_config.ssh_agent_enabled {
+                subscriptions_vec.push(self.passwords_page.ssh_agent_subscription());
+            }
             match self.current_page {
                 Page::Settings => (),
                 Page::Passwords => {
-                    subscriptions_vec.push(PasswordsPage::subscription());
+                    subscriptions_vec.push(self.passwords_page.subscription());
                 }
                 Page::Sync => (),
                 Page::Gallery => {
@@ -252,7 +365,7 @@ impl AppState {
                     subscriptions_vec.push(NotesPage::subscription());
                 }
                 Page::Tasks => {
-                    subscriptions_vec.push(TasksPage::subscription());
+                    subscriptions_vec.push(self.tasks_page.subscription());
                 }
             }
             if self.config.sync_config.should_sync
@@ -277,6 +390,42 @@ impl AppState {
                     .map(Message::ServerMessageEvent),
                 );
             }
+            if self.config.tasks_config.caldav_server_enabled {
+                if let Some(projects_folder) = self.tasks_page.selected_folder.clone() {
+                    subscriptions_vec.push(
+                        Subscription::run_with_id(
+                            (
+                                "caldav_server_subscription",
+                                self.config.tasks_config.caldav_server_port,
+                                self.config.tasks_config.caldav_server_allow_network_access,
+                                projects_folder.clone(),
+                                self.config.tasks_config.caldav_server_password_hash.clone(),
+                            ),
+                            caldav_utils::serve(
+                                self.config.tasks_config.caldav_server_port,
+                                self.config.tasks_config.caldav_server_allow_network_access,
+                                projects_folder,
+                                self.config.tasks_config.caldav_server_password_hash.clone(),
+                            ),
+                        )
+                        .map(Message::CaldavServerEvent),
+                    );
+                }
+            }
+            if self.config.tasks_config.reminder_notifications_enabled {
+                if let Some(projects_folder) = self.tasks_page.selected_folder.clone() {
+                    subscriptions_vec.push(
+                        Subscription::run_with_id(
+                            (
+                                "reminder_notifications_subscription",
+                                projects_folder.clone(),
+                            ),
+                            notification_utils::reminders(projects_folder, self.locale.clone()),
+                        )
+                        .map(Message::ReminderEvent),
+                    );
+                }
+            }
         }
         Subscription::batch(subscriptions_vec)
     }
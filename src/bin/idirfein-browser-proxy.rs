@@ -0,0 +1,51 @@
+//! The native messaging host the browser starts for KeePassXC-Browser, it relays messages between the
+//! browser on stdin and stdout and the running app's browser integration socket
+
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::{process, thread};
+
+use idirfein::utils::browser_integration_utils::socket_path;
+
+fn main() {
+    let socket = match UnixStream::connect(socket_path()) {
+        Ok(socket) => socket,
+        Err(err) => {
+            eprintln!(
+                "Couldn't connect to Idirfein, is it running with browser integration on? {err}"
+            );
+            process::exit(1);
+        }
+    };
+    let mut socket_writer = match socket.try_clone() {
+        Ok(socket_writer) => socket_writer,
+        Err(err) => {
+            eprintln!("Couldn't use the Idirfein socket: {err}");
+            process::exit(1);
+        }
+    };
+
+    // Messages use the same length prefixed framing on both sides, so they're copied as they are
+    thread::spawn(move || {
+        let _ = io::copy(&mut io::stdin().lock(), &mut socket_writer);
+        // The browser closed stdin, so the extension has disconnected
+        let _ = socket_writer.shutdown(Shutdown::Both);
+        process::exit(0);
+    });
+
+    let mut socket_reader = socket;
+    let mut stdout = io::stdout().lock();
+    let mut buffer = [0; 8192];
+    loop {
+        match socket_reader.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read_count) => {
+                // Flushed straight away as the extension waits on each reply
+                if stdout.write_all(&buffer[..read_count]).is_err() || stdout.flush().is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -5,3 +5,4 @@ pub const PKGDATADIR: &str = "/app/share/idirfein";
 pub const PROFILE: &str = "Devel";
 pub const VERSION: &str = "0.1.0-4598ab6";
 pub const LORO_NOTE_ID: &str = "LORO_NOTE_ID";
+pub const LORO_TASKS_ID: &str = "LORO_TASKS_ID";
@@ -8,6 +8,7 @@ use std::{
     path::PathBuf,
 };
 
+use loro::LoroDoc;
 use pulldown_cmark::Options;
 use walkdir::WalkDir;
 
@@ -399,6 +400,97 @@ pub fn take_first_n_chars(input: &str, n: usize) -> String {
     input[..end_index].to_string()
 }
 
+/// Matches an unticked checklist item, with the part up to the box and the item text as groups
+const UNTICKED_CHECKLIST_ITEM_PATTERN: &str = r"^(\s*(?:[-*+]|\d+\.)\s\[) \]\s(.*)$";
+
+/// Gets the line number and text of every unticked checklist item in the note
+pub fn checklist_items(note_text: &str) -> Vec<(usize, String)> {
+    let checklist_item_pattern = Regex::new(UNTICKED_CHECKLIST_ITEM_PATTERN).unwrap();
+    note_text
+        .split('\n')
+        .enumerate()
+        .filter_map(|(line_number, line)| {
+            checklist_item_pattern
+                .captures(line)
+                .map(|captures| (line_number, captures[2].trim().to_string()))
+                .filter(|(_, item_text)| !item_text.is_empty())
+        })
+        .collect()
+}
+
+/// Finds the unticked checklist item with this text, trying the given line first in case the same item is in the note twice.
+/// Returns the line and the column of the space inside the box.
+pub fn find_checklist_item(
+    note_text: &str,
+    line: usize,
+    item_text: &str,
+) -> Option<(usize, usize)> {
+    let checklist_item_pattern = Regex::new(UNTICKED_CHECKLIST_ITEM_PATTERN).unwrap();
+    let box_column = |line_text: &str| {
+        checklist_item_pattern
+            .captures(line_text)
+            .filter(|captures| captures[2].trim() == item_text.trim())
+            .map(|captures| captures[1].chars().count())
+    };
+    let lines: Vec<&str> = note_text.split('\n').collect();
+    lines
+        .get(line)
+        .and_then(|line_text| box_column(line_text))
+        .map(|column| (line, column))
+        .or_else(|| {
+            lines
+                .iter()
+                .enumerate()
+                .find_map(|(line_number, line_text)| {
+                    box_column(line_text).map(|column| (line_number, column))
+                })
+        })
+}
+
+/// Ticks a checklist item in a note that isn't open through its CRDT, then writes the note out from the CRDT like the editor does.
+/// Does nothing if the item has already been ticked or removed.
+pub async fn tick_checklist_item_in_file(
+    note_file: PathBuf,
+    line: usize,
+    item_text: String,
+) -> Result<(), String> {
+    let note_text = fs::read_to_string(&note_file).map_err(|err| err.to_string())?;
+    let note_crdt_file = note_file.with_file_name(format!(
+        ".{}.loro",
+        note_file.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    let note_crdt = fs::read(&note_crdt_file)
+        .ok()
+        .and_then(|note_crdt_bytes| {
+            let note_crdt = LoroDoc::new();
+            note_crdt.import(&note_crdt_bytes).ok()?;
+            (note_crdt.get_text(LORO_NOTE_ID).to_string() == note_text).then_some(note_crdt)
+        })
+        .unwrap_or_else(|| {
+            let note_crdt = LoroDoc::new();
+            let _ = note_crdt.get_text(LORO_NOTE_ID).insert(0, &note_text);
+            note_crdt
+        });
+    let note_crdt_text = note_crdt.get_text(LORO_NOTE_ID);
+
+    let Some((item_line, box_column)) = find_checklist_item(&note_text, line, &item_text) else {
+        return Ok(());
+    };
+    let box_char_index = note_text
+        .split('\n')
+        .take(item_line)
+        .map(|line_text| line_text.chars().count() + 1)
+        .sum::<usize>()
+        + box_column;
+    note_crdt_text
+        .delete(box_char_index, 1)
+        .and_then(|_| note_crdt_text.insert(box_char_index, "x"))
+        .map_err(|err| err.to_string())?;
+
+    fs::write(&note_file, note_crdt_text.to_string()).map_err(|err| err.to_string())?;
+    fs::write(note_crdt_file, note_crdt.export_snapshot()).map_err(|err| err.to_string())
+}
+
 pub async fn export_pdf(text_to_convert: String, md_file_path: Option<PathBuf>) -> (bool, String) {
     let input_bytes = bytes::Bytes::from(text_to_convert);
 
@@ -1015,4 +1107,25 @@ mod tests {
             test_state.note_crdt.get_text(LORO_NOTE_ID).to_string()
         );
     }
+
+    #[test]
+    fn test_checklist_items() {
+        let note_text = "# Shopping\n- [ ] Milk\n- [x] Bread\n  * [ ] Eggs \n1. [ ] Flour\n- [ ] \n- Butter\n- [ ] Milk";
+        assert_eq!(
+            checklist_items(note_text),
+            vec![
+                (1, String::from("Milk")),
+                (3, String::from("Eggs")),
+                (4, String::from("Flour")),
+                (7, String::from("Milk")),
+            ]
+        );
+
+        assert_eq!(find_checklist_item(note_text, 7, "Milk"), Some((7, 3)));
+        assert_eq!(find_checklist_item(note_text, 3, "Eggs"), Some((3, 5)));
+        // The note has changed since the task was made, so the item has moved
+        assert_eq!(find_checklist_item(note_text, 0, "Flour"), Some((4, 4)));
+        assert_eq!(find_checklist_item(note_text, 2, "Bread"), None);
+        assert_eq!(find_checklist_item(note_text, 1, "Cheese"), None);
+    }
 }
@@ -13,6 +13,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::app::Message;
 use crate::constants::APP_ID;
+use crate::pages::tasks::page::NoteLink;
 
 use super::notes_utils::{self, NoteStatistics};
 use super::update::update;
@@ -93,6 +94,14 @@ pub struct NotesPage {
     pub(crate) display_archive_view: bool,
     pub(crate) archived_notes_list: Vec<String>,
     pub(crate) show_archived_notes: bool,
+    /// Shows the view for sending checklist items to a task project
+    pub(crate) show_send_to_tasks_view: bool,
+    /// The line numbers and text of the checklist items about to be sent to a task project
+    pub(crate) checklist_items_to_send: Vec<(usize, String)>,
+    pub(crate) task_projects_list: Vec<PathBuf>,
+    pub(crate) selected_task_project_name: Option<String>,
+    /// The line to move the cursor to once the note being opened has loaded
+    pub(crate) line_to_open_at: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -141,6 +150,15 @@ pub enum NotesPageMessage {
     ToggleShowArchivedNotes,
     LoadArchivedList,
     OpenWebsiteStylesFile,
+    /// Sends the checklist item at the cursor, or every unticked one in the note if true
+    StartSendingChecklistItemsToTasks(bool),
+    CancelSendingChecklistItemsToTasks,
+    SetTaskProjectsList(Vec<PathBuf>),
+    SelectTaskProjectToSendTo(String),
+    SendChecklistItemsToTasks,
+    OpenNoteLink(NoteLink),
+    /// Ticks the checklist item a finished task was made from
+    TickChecklistItem(NoteLink, String),
 }
 
 impl NotesPage {
@@ -227,6 +245,11 @@ impl NotesPage {
             display_archive_view: false,
             archived_notes_list: vec![],
             show_archived_notes: false,
+            show_send_to_tasks_view: false,
+            checklist_items_to_send: vec![],
+            task_projects_list: vec![],
+            selected_task_project_name: None,
+            line_to_open_at: None,
         }
     }
 
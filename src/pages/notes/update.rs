@@ -9,13 +9,17 @@ use std::fs;
 use crate::{
     app::Message,
     constants::{APP_ID, LORO_NOTE_ID},
-    pages::notes::notes_utils::{move_cursor_to_position, parse_markdown_lists},
+    pages::{
+        notes::notes_utils::{move_cursor_to_position, parse_markdown_lists},
+        tasks::page::{NoteLink, TaskData, TasksPageMessage},
+    },
 };
 
 use super::{
     notes_utils::{
-        apply_edit_to_note, export_pdf, export_to_website, read_file_to_note,
-        read_notes_from_folder, select_specific_string_in_editor, NoteStatistics,
+        apply_edit_to_note, checklist_items, export_pdf, export_to_website, find_checklist_item,
+        read_file_to_note, read_notes_from_folder, select_specific_string_in_editor,
+        tick_checklist_item_in_file, NoteStatistics,
     },
     page::{
         NotesPage, NotesPageMessage, ARCHIVED_FILE_NAME, INITIAL_ORIGIN_STR, MAX_UNDO_STEPS,
@@ -180,6 +184,9 @@ pub fn update(state: &mut NotesPage, message: NotesPageMessage) -> Task<Message>
             }
         }
         NotesPageMessage::LinkClicked(link) => {
+            if let Some(note_link) = NoteLink::from_url(link.as_str()) {
+                return Task::done(Message::Notes(NotesPageMessage::OpenNoteLink(note_link)));
+            }
             opener::open(link.as_str()).unwrap();
         }
         NotesPageMessage::ToggleSidebar => state.show_sidebar = !state.show_sidebar,
@@ -281,6 +288,9 @@ pub fn update(state: &mut NotesPage, message: NotesPageMessage) -> Task<Message>
                 .undo_manager
                 .add_exclude_origin_prefix(INITIAL_ORIGIN_STR);
             state.markdown_preview_items = markdown::parse(&state.editor_content.text()).collect();
+            if let Some(line) = state.line_to_open_at.take() {
+                move_cursor_to_position(&mut state.editor_content, 0, line);
+            }
             state.is_loading_note = false;
         }
         NotesPageMessage::ToggleExtraToolsMenu => {
@@ -592,6 +602,139 @@ pub fn update(state: &mut NotesPage, message: NotesPageMessage) -> Task<Message>
                 |new_content| Message::Notes(NotesPageMessage::SetTextEditorContent(new_content)),
             );
         }
+        NotesPageMessage::StartSendingChecklistItemsToTasks(send_all) => {
+            let note_checklist_items = checklist_items(&state.editor_content.text());
+            let (cursor_line, _) = state.editor_content.cursor_position();
+            state.checklist_items_to_send = if send_all {
+                note_checklist_items
+            } else {
+                note_checklist_items
+                    .into_iter()
+                    .filter(|(line, _)| *line == cursor_line)
+                    .collect()
+            };
+            if state.checklist_items_to_send.is_empty() {
+                return Task::done(Message::ShowToast(
+                    false,
+                    String::from(if send_all {
+                        "No unticked checklist items in this note"
+                    } else {
+                        "The cursor isn't on an unticked checklist item"
+                    }),
+                ));
+            }
+            state.show_send_to_tasks_view = true;
+            state.show_extra_tools_menu = false;
+            return Task::done(Message::Tasks(
+                TasksPageMessage::SendProjectsListToNotesPage,
+            ));
+        }
+        NotesPageMessage::CancelSendingChecklistItemsToTasks => {
+            state.show_send_to_tasks_view = false;
+            state.checklist_items_to_send = vec![];
+        }
+        NotesPageMessage::SetTaskProjectsList(task_projects_list) => {
+            state.task_projects_list = task_projects_list;
+        }
+        NotesPageMessage::SelectTaskProjectToSendTo(project_name) => {
+            state.selected_task_project_name = Some(project_name);
+        }
+        NotesPageMessage::SendChecklistItemsToTasks => {
+            let (Some(current_file), Some(selected_folder)) =
+                (state.current_file.as_ref(), state.selected_folder.as_ref())
+            else {
+                return Task::none();
+            };
+            let Some(project_file) = state.task_projects_list.iter().find(|project_file| {
+                project_file.file_stem().is_some_and(|project_name| {
+                    Some(project_name.to_string_lossy().to_string())
+                        == state.selected_task_project_name
+                })
+            }) else {
+                return Task::done(Message::ShowToast(
+                    false,
+                    String::from("Choose a task project to send the checklist items to"),
+                ));
+            };
+            let note_path = current_file
+                .strip_prefix(selected_folder)
+                .unwrap_or(current_file)
+                .to_path_buf();
+            let new_tasks = state
+                .checklist_items_to_send
+                .iter()
+                .map(|(line, item_text)| TaskData {
+                    title: item_text.clone(),
+                    note_link: Some(NoteLink {
+                        note_path: note_path.clone(),
+                        line: *line,
+                    }),
+                    ..Default::default()
+                })
+                .collect();
+            let project_file = project_file.clone();
+            state.show_send_to_tasks_view = false;
+            state.checklist_items_to_send = vec![];
+            return Task::done(Message::Tasks(TasksPageMessage::AddTasksToProject(
+                project_file,
+                new_tasks,
+            )));
+        }
+        NotesPageMessage::OpenNoteLink(note_link) => {
+            let Some(selected_folder) = state.selected_folder.as_ref() else {
+                return Task::done(Message::ShowToast(
+                    false,
+                    String::from("No notes folder selected to open the linked note from"),
+                ));
+            };
+            let note_file = selected_folder.join(&note_link.note_path);
+            if state.current_file.as_ref() == Some(&note_file) {
+                move_cursor_to_position(&mut state.editor_content, 0, note_link.line);
+            } else if note_file.exists() {
+                state.line_to_open_at = Some(note_link.line);
+                return Task::done(Message::Notes(NotesPageMessage::OpenFile(note_file)));
+            } else {
+                return Task::done(Message::ShowToast(
+                    false,
+                    format!(
+                        "Couldn't find the linked note {}",
+                        note_link.note_path.display()
+                    ),
+                ));
+            }
+        }
+        NotesPageMessage::TickChecklistItem(note_link, item_text) => {
+            let Some(selected_folder) = state.selected_folder.as_ref() else {
+                return Task::none();
+            };
+            let note_file = selected_folder.join(&note_link.note_path);
+            if state.current_file.as_ref() == Some(&note_file) {
+                if let Some((item_line, box_column)) =
+                    find_checklist_item(&state.editor_content.text(), note_link.line, &item_text)
+                {
+                    let (cursor_y, cursor_x) = state.editor_content.cursor_position();
+                    move_cursor_to_position(&mut state.editor_content, box_column, item_line);
+                    apply_edit_to_note(state, text_editor::Edit::Delete);
+                    apply_edit_to_note(state, text_editor::Edit::Insert('x'));
+                    move_cursor_to_position(&mut state.editor_content, cursor_x, cursor_y);
+                    state.note_is_dirty = true;
+                    state.markdown_preview_items =
+                        markdown::parse(&state.editor_content.text()).collect();
+                    return Task::done(Message::Notes(NotesPageMessage::SaveNote));
+                }
+            } else {
+                return Task::perform(
+                    tick_checklist_item_in_file(note_file, note_link.line, item_text),
+                    |result| match result {
+                        Ok(_) => Message::None,
+                        Err(err) => Message::ShowToast(
+                            false,
+                            format!("Failed to tick the checklist item in the note: {err}"),
+                        ),
+                    },
+                );
+            }
+        }
     }
     Task::none()
 }
@@ -5,8 +5,8 @@ use iced_aw::{drop_down, DropDown};
 
 use iced::alignment::Horizontal;
 use iced::widget::{
-    button, column, markdown, row, scrollable, svg, text, text_editor, text_input, Scrollable,
-    Space, Svg, Tooltip,
+    button, column, markdown, pick_list, row, scrollable, svg, text, text_editor, text_input,
+    Scrollable, Space, Svg, Tooltip,
 };
 use iced::{highlighter, Length};
 use iced::{Element, Fill, Font};
@@ -29,15 +29,22 @@ pub fn main_view(state: &NotesPage) -> Element<Message> {
             column![]
         }],
         column![
-            row![if state.show_document_statistics_view {
-                if state.is_loading_note {
-                    loading_note_view(state)
+            row![
+                if state.show_document_statistics_view {
+                    if state.is_loading_note {
+                        loading_note_view(state)
+                    } else {
+                        document_statistics_view(state)
+                    }
                 } else {
-                    document_statistics_view(state)
-                }
-            } else {
-                column![].into()
-            },]
+                    column![].into()
+                },
+                if state.show_send_to_tasks_view {
+                    send_to_tasks_view(state)
+                } else {
+                    column![].into()
+                },
+            ]
             .spacing(10),
             if state.current_file.is_some() {
                 row![
@@ -537,6 +544,62 @@ fn document_statistics_view(state: &NotesPage) -> Element<Message> {
     .into()
 }
 
+fn send_to_tasks_view(state: &NotesPage) -> Element<Message> {
+    column![
+        row![
+            text(LOCALES.lookup(&state.locale, "send-to-tasks"))
+                .width(Length::Fill)
+                .size(24),
+            Tooltip::new(
+                button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                    "../../../icons/close.svg"
+                ))))
+                .on_press(Message::Notes(
+                    NotesPageMessage::CancelSendingChecklistItemsToTasks
+                ))
+                .width(Length::Fixed(50.0)),
+                text(LOCALES.lookup(&state.locale, "cancel")),
+                iced::widget::tooltip::Position::Bottom
+            ),
+        ],
+        text(format!(
+            "{} {}",
+            LOCALES.lookup(&state.locale, "checklist-items-to-send"),
+            state.checklist_items_to_send.len()
+        )),
+        row![
+            pick_list(
+                state
+                    .task_projects_list
+                    .iter()
+                    .map(|project_file| project_file
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string())
+                    .collect::<Vec<String>>(),
+                state.selected_task_project_name.clone(),
+                |project_name| Message::Notes(NotesPageMessage::SelectTaskProjectToSendTo(
+                    project_name
+                ))
+            )
+            .placeholder(LOCALES.lookup(&state.locale, "choose-task-project"))
+            .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "send")))
+                .on_press_maybe(
+                    state
+                        .selected_task_project_name
+                        .as_ref()
+                        .map(|_| Message::Notes(NotesPageMessage::SendChecklistItemsToTasks))
+                )
+                .style(button::success),
+        ]
+        .spacing(10),
+    ]
+    .spacing(5)
+    .into()
+}
+
 fn markdown_guide_view(state: &NotesPage) -> Element<Message> {
     scrollable(
         markdown(
@@ -560,6 +623,13 @@ pub fn tool_view(state: &NotesPage) -> Element<Message> {
         text(LOCALES.lookup(&state.locale, "more-tools")),
         iced::widget::tooltip::Position::Bottom,
     );
+    let send_checklist_items_to_tasks = |send_all| {
+        state.current_file.as_ref().map(|_| {
+            Message::Notes(NotesPageMessage::StartSendingChecklistItemsToTasks(
+                send_all,
+            ))
+        })
+    };
     let overlay = column![
         button(
             text(LOCALES.lookup(&state.locale, "select-notes-folder"))
@@ -601,6 +671,20 @@ pub fn tool_view(state: &NotesPage) -> Element<Message> {
             NotesPageMessage::ToggleDocumentStatisticsView
         ))
         .width(Length::Fill),
+        button(
+            text(LOCALES.lookup(&state.locale, "send-checklist-item-to-tasks"))
+                .width(Length::Fill)
+                .align_x(Center)
+        )
+        .on_press_maybe(send_checklist_items_to_tasks(false))
+        .width(Length::Fill),
+        button(
+            text(LOCALES.lookup(&state.locale, "send-all-checklist-items-to-tasks"))
+                .width(Length::Fill)
+                .align_x(Center)
+        )
+        .on_press_maybe(send_checklist_items_to_tasks(true))
+        .width(Length::Fill),
     ]
     .width(Length::Fixed(200.0));
 
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use sha1::{Digest, Sha1};
+
+use super::page::Password;
+
+/// The number of hex characters of the hash that name a Have I Been Pwned range file
+const RANGE_PREFIX_LENGTH: usize = 5;
+
+/// The uppercase hex SHA-1 hash of the password, as used in the Have I Been Pwned files
+fn sha1_hex(password: &str) -> String {
+    Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+/// Splits a "HASH:COUNT" line into its hash and breach count
+fn parse_hash_line(line: &str) -> Option<(&str, u64)> {
+    let (hash, count) = line.trim_end().split_once(':')?;
+    Some((hash, count.trim().parse().unwrap_or(1)))
+}
+
+/// Binary searches a file of "HASH:COUNT" lines sorted by hash for the hash, returning its breach count if found.
+/// Only reads the lines the search lands on, so works on the full multi-gigabyte hash file
+fn find_in_sorted_hash_file<R: Read + Seek>(
+    reader: &mut BufReader<R>,
+    hash: &str,
+) -> io::Result<Option<u64>> {
+    // Searching for the line starting in [low, high)
+    let mut low = 0;
+    let mut high = reader.seek(SeekFrom::End(0))?;
+    let mut line = String::new();
+    while low < high {
+        let middle = low + (high - low) / 2;
+        // Finds the first line starting at or after the middle
+        let line_start = if middle == 0 {
+            reader.seek(SeekFrom::Start(0))?
+        } else {
+            reader.seek(SeekFrom::Start(middle - 1))?;
+            middle - 1 + reader.skip_until(b'\n')? as u64
+        };
+        if line_start >= high {
+            high = middle;
+            continue;
+        }
+        line.clear();
+        let line_length = reader.read_line(&mut line)? as u64;
+        let Some((line_hash, count)) = parse_hash_line(&line) else {
+            // Blank or malformed lines are skipped over
+            low = line_start + line_length.max(1);
+            continue;
+        };
+        match line_hash.to_ascii_uppercase().as_str().cmp(hash) {
+            std::cmp::Ordering::Equal => return Ok(Some(count)),
+            std::cmp::Ordering::Less => low = line_start + line_length,
+            std::cmp::Ordering::Greater => high = middle,
+        }
+    }
+    Ok(None)
+}
+
+/// Looks the hash up in the folder of range files, named by the first 5 characters of the hashes they hold
+/// and containing "SUFFIX:COUNT" lines for the rest of each hash
+fn find_in_range_folder(folder: &Path, hash: &str) -> io::Result<Option<u64>> {
+    let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+    let range_file = [folder.join(format!("{prefix}.txt")), folder.join(prefix)]
+        .into_iter()
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No range file for {prefix} in {folder:?}"),
+            )
+        })?;
+    find_in_sorted_hash_file(&mut BufReader::new(File::open(range_file)?), suffix)
+}
+
+/// Checks every entry's password against the local Have I Been Pwned hashes at the path, which is either a
+/// sorted file of full hashes or a folder of range files. Returns the breach counts of the compromised entries
+pub fn breached_password_counts(
+    hashes_path: &Path,
+    passwords: &[Password],
+) -> Result<HashMap<uuid::Uuid, u64>, String> {
+    let mut hash_file = if hashes_path.is_dir() {
+        None
+    } else {
+        Some(BufReader::new(
+            File::open(hashes_path).map_err(|err| err.to_string())?,
+        ))
+    };
+    let mut breach_counts = HashMap::new();
+    for password in passwords
+        .iter()
+        .filter(|password| !password.password.is_empty())
+    {
+        let hash = sha1_hex(&password.password);
+        let breach_count = match &mut hash_file {
+            Some(hash_file) => find_in_sorted_hash_file(hash_file, &hash),
+            None => find_in_range_folder(hashes_path, &hash),
+        }
+        .map_err(|err| err.to_string())?;
+        if let Some(breach_count) = breach_count {
+            breach_counts.insert(password.id, breach_count);
+        }
+    }
+    Ok(breach_counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use zeroize::Zeroizing;
+
+    use super::*;
+
+    fn password(password: &str) -> Password {
+        Password {
+            id: uuid::Uuid::new_v4(),
+            title: String::new(),
+            username: String::new(),
+            url: String::new(),
+            password: Zeroizing::new(password.to_string()),
+            otp: String::new(),
+            group_id: uuid::Uuid::nil(),
+            last_modified: None,
+        }
+    }
+
+    #[test]
+    fn test_breached_password_counts() {
+        let breached = [("password", 3), ("123456", 42), ("letmein", 7)];
+        let passwords: Vec<Password> = breached
+            .iter()
+            .map(|(breached_password, _)| password(breached_password))
+            .chain([password("a password that isn't in the file"), password("")])
+            .collect();
+        let expected: HashMap<uuid::Uuid, u64> = passwords
+            .iter()
+            .zip(breached.iter())
+            .map(|(password, (_, count))| (password.id, *count))
+            .collect();
+
+        // Padded out with hashes either side of the breached ones so the search has to narrow down
+        let mut hash_lines: Vec<(String, u64)> = breached
+            .iter()
+            .map(|(breached_password, count)| (sha1_hex(breached_password), *count))
+            .chain((0..500).map(|i| (sha1_hex(&format!("filler {i}")), 1)))
+            .collect();
+        hash_lines.sort();
+
+        let folder = tempfile::tempdir().unwrap();
+        let hash_file_path = folder
+            .path()
+            .join("pwned-passwords-sha1-ordered-by-hash.txt");
+        fs::write(
+            &hash_file_path,
+            hash_lines
+                .iter()
+                .map(|(hash, count)| format!("{hash}:{count}\r\n"))
+                .collect::<String>(),
+        )
+        .unwrap();
+        assert_eq!(
+            breached_password_counts(&hash_file_path, &passwords),
+            Ok(expected.clone())
+        );
+
+        let range_folder = folder.path().join("ranges");
+        fs::create_dir(&range_folder).unwrap();
+        // Like a full download, every password's range has a file even if the password isn't in it
+        let safe_password_hash = sha1_hex("a password that isn't in the file");
+        let mut ranges: HashMap<&str, String> =
+            HashMap::from([(&safe_password_hash[..RANGE_PREFIX_LENGTH], String::new())]);
+        hash_lines.iter().for_each(|(hash, count)| {
+            let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+            ranges
+                .entry(prefix)
+                .or_default()
+                .push_str(&format!("{suffix}:{count}\n"));
+        });
+        ranges.iter().for_each(|(prefix, contents)| {
+            fs::write(range_folder.join(format!("{prefix}.txt")), contents).unwrap()
+        });
+        assert_eq!(
+            breached_password_counts(&range_folder, &passwords),
+            Ok(expected)
+        );
+
+        // A range file missing from the folder means the check can't be trusted
+        fs::remove_file(range_folder.join(format!(
+            "{}.txt",
+            &sha1_hex("password")[..RANGE_PREFIX_LENGTH]
+        )))
+        .unwrap();
+        assert!(breached_password_counts(&range_folder, &passwords).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use keepass::db::{CustomDataItem, Group, Node, Value as KeepassValue};
+use keepass::Database;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::utils::browser_integration_utils::BrowserError;
+
+use super::page::{Password, PasswordGroup};
+use super::passwords_utils::{
+    current_totp_code, find_group, passwords_from_database, update_entry, OTP_FIELD,
+};
+
+/// KeePassXC keeps the key of each browser associated with the database in the database's custom data,
+/// under this prefix followed by the name the browser was given, so the associations work with both apps
+const ASSOCIATION_KEY_PREFIX: &str = "KPXC_BROWSER_";
+
+/// The hash the extension tells databases apart by, the SHA-256 of the root group's UUID like KeePassXC
+pub fn database_hash(database: &Database) -> String {
+    Sha256::digest(database.root.uuid.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The UUID in the form KeePassXC sends it to the extension, hex without dashes
+fn browser_uuid(uuid: uuid::Uuid) -> String {
+    uuid.simple().to_string()
+}
+
+/// Whether a browser has been associated with the database under this name with this key
+fn is_associated(database: &Database, id: &str, id_key: &str) -> bool {
+    database
+        .meta
+        .custom_data
+        .items
+        .get(&format!("{ASSOCIATION_KEY_PREFIX}{id}"))
+        .and_then(|custom_data_item| custom_data_item.value.as_ref())
+        .is_some_and(|value| matches!(value, KeepassValue::Unprotected(key) if key == id_key))
+}
+
+/// Whether a name is already used by a browser associated with the database
+pub fn is_association_name_taken(database: &Database, id: &str) -> bool {
+    database
+        .meta
+        .custom_data
+        .items
+        .contains_key(&format!("{ASSOCIATION_KEY_PREFIX}{id}"))
+}
+
+/// Saves the browser's identity key in the database under the name, after the user has allowed it to connect
+pub fn add_association(database: &mut Database, id: &str, id_key: &str) {
+    database.meta.custom_data.items.insert(
+        format!("{ASSOCIATION_KEY_PREFIX}{id}"),
+        CustomDataItem {
+            value: Some(KeepassValue::Unprotected(id_key.to_string())),
+            last_modification_time: Some(chrono::Utc::now().naive_utc()),
+        },
+    );
+}
+
+/// The host of a URL, entries are often saved without the scheme so https:// is assumed for those
+fn url_host(url: &str) -> Option<String> {
+    let url = url.trim();
+    url::Url::parse(url)
+        .ok()
+        .filter(|parsed_url| parsed_url.host_str().is_some())
+        .or_else(|| url::Url::parse(&format!("https://{url}")).ok())
+        .and_then(|parsed_url| parsed_url.host_str().map(str::to_lowercase))
+}
+
+/// Whether the entry's URL is for the site, either the same host or a domain the site's host is under.
+/// Returns whether it's an exact match, for sorting
+fn url_match(entry_url: &str, site_host: &str) -> Option<bool> {
+    if entry_url.trim().is_empty() {
+        return None;
+    }
+    let entry_host = url_host(entry_url)?;
+    if entry_host == site_host {
+        Some(true)
+    } else if site_host.ends_with(&format!(".{entry_host}")) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// The entries for the site as KeePassXC sends them, exact host matches first
+fn logins_for_url(database: &Database, url: &str) -> Vec<Value> {
+    fn add_group_logins(group: &Group, site_host: &str, logins: &mut Vec<(bool, Value)>) {
+        group.children.iter().for_each(|node| match node {
+            Node::Group(child_group) => add_group_logins(child_group, site_host, logins),
+            Node::Entry(entry) => {
+                if let Some(is_exact) = url_match(entry.get_url().unwrap_or(""), site_host) {
+                    let totp = current_totp_code(entry.get(OTP_FIELD).unwrap_or(""))
+                        .map(|totp_code| totp_code.code)
+                        .unwrap_or_default();
+                    logins.push((
+                        is_exact,
+                        json!({
+                            "login": entry.get_username().unwrap_or(""),
+                            "name": entry.get_title().unwrap_or(""),
+                            "password": entry.get_password().unwrap_or(""),
+                            "uuid": browser_uuid(entry.uuid),
+                            "group": group.name,
+                            "totp": totp,
+                            "expired": "false",
+                            "skipAutoSubmit": "false",
+                            "stringFields": [],
+                        }),
+                    ));
+                }
+            }
+        });
+    }
+    let Some(site_host) = url_host(url) else {
+        return vec![];
+    };
+    let mut logins = vec![];
+    add_group_logins(&database.root, &site_host, &mut logins);
+    // Stable, so entries keep their database order within exact and partial matches
+    logins.sort_by_key(|(is_exact, _)| !is_exact);
+    logins.into_iter().map(|(_, login)| login).collect()
+}
+
+fn group_json(group: &PasswordGroup) -> Value {
+    json!({
+        "name": group.name,
+        "uuid": browser_uuid(group.id),
+        "children": group.child_groups.iter().map(group_json).collect::<Vec<Value>>(),
+    })
+}
+
+/// Parses a UUID as sent by the extension, which uses KeePassXC's hex without dashes
+fn parse_browser_uuid(uuid: &Value) -> Option<uuid::Uuid> {
+    uuid.as_str()
+        .filter(|uuid| !uuid.is_empty())
+        .and_then(|uuid| uuid::Uuid::parse_str(uuid).ok())
+}
+
+/// Adds a login saved from the browser, or updates the entry's username and password if it sent the entry's UUID.
+/// New entries are put in the group it sent, or the root group
+fn set_login(database: &mut Database, message: &Value) -> Result<(), BrowserError> {
+    let url = message["url"].as_str().unwrap_or_default();
+    if url.is_empty() {
+        return Err(BrowserError::NoUrlProvided);
+    }
+    let username = message["login"].as_str().unwrap_or_default().to_string();
+    let password = Zeroizing::new(message["password"].as_str().unwrap_or_default().to_string());
+    let (root_group, passwords) = passwords_from_database(database);
+    let entry = if message["uuid"]
+        .as_str()
+        .is_some_and(|uuid| !uuid.is_empty())
+    {
+        let entry_id =
+            parse_browser_uuid(&message["uuid"]).ok_or(BrowserError::NoValidUuidProvided)?;
+        let existing_password = passwords
+            .into_iter()
+            .find(|password| password.id == entry_id)
+            .ok_or(BrowserError::NoValidUuidProvided)?;
+        Password {
+            username,
+            password,
+            ..existing_password
+        }
+    } else {
+        let group_id = parse_browser_uuid(&message["groupUuid"])
+            .filter(|group_id| find_group(&root_group, *group_id).is_some())
+            .unwrap_or(root_group.id);
+        Password {
+            id: uuid::Uuid::new_v4(),
+            title: url_host(url).unwrap_or_else(|| url.to_string()),
+            username,
+            url: url.to_string(),
+            password,
+            otp: String::new(),
+            group_id,
+            last_modified: None,
+        }
+    };
+    update_entry(database, &entry);
+    Ok(())
+}
+
+/// Answers the extension's requests that only need the database. Returns the reply, and whether the database
+/// was changed. Associating, generating passwords and locking need the page so aren't handled here
+/// The name of the first browser in the message's `keys`, or its `id` and `key`, that is associated
+/// with the database. Checked for every action that reads or writes entries, rather than trusting
+/// that the connection associated earlier
+fn associated_id<'a>(database: &Database, message: &'a Value) -> Result<&'a str, BrowserError> {
+    message["keys"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .chain([message])
+        .find_map(|key| {
+            let id = key["id"].as_str()?;
+            is_associated(database, id, key["key"].as_str()?).then_some(id)
+        })
+        .ok_or(BrowserError::AssociationFailed)
+}
+
+pub fn handle_browser_request(
+    database: &mut Database,
+    action: &str,
+    message: &Value,
+) -> Result<(Value, bool), BrowserError> {
+    let hash = database_hash(database);
+    match action {
+        "get-databasehash" => Ok((json!({ "hash": hash }), false)),
+        "test-associate" => {
+            let id = message["id"].as_str().unwrap_or_default();
+            if is_associated(database, id, message["key"].as_str().unwrap_or_default()) {
+                Ok((json!({ "hash": hash, "id": id }), false))
+            } else {
+                Err(BrowserError::AssociationFailed)
+            }
+        }
+        "get-logins" => {
+            let associated_id = associated_id(database, message)?;
+            let url = message["url"].as_str().unwrap_or_default();
+            if url.is_empty() {
+                return Err(BrowserError::NoUrlProvided);
+            }
+            let logins = logins_for_url(database, url);
+            if logins.is_empty() {
+                return Err(BrowserError::NoLoginsFound);
+            }
+            Ok((
+                json!({
+                    "count": logins.len(),
+                    "entries": logins,
+                    "hash": hash,
+                    "id": associated_id,
+                }),
+                false,
+            ))
+        }
+        "get-totp" => {
+            associated_id(database, message)?;
+            let entry_id =
+                parse_browser_uuid(&message["uuid"]).ok_or(BrowserError::NoValidUuidProvided)?;
+            let (_, passwords) = passwords_from_database(database);
+            let password = passwords
+                .iter()
+                .find(|password| password.id == entry_id)
+                .ok_or(BrowserError::NoValidUuidProvided)?;
+            let totp = current_totp_code(&password.otp)
+                .map(|totp_code| totp_code.code)
+                .unwrap_or_default();
+            Ok((json!({ "totp": totp }), false))
+        }
+        "get-database-groups" => {
+            associated_id(database, message)?;
+            let (root_group, _) = passwords_from_database(database);
+            Ok((
+                json!({
+                    "defaultGroup": "",
+                    "defaultGroupAlwaysAsk": false,
+                    "groups": { "groups": [group_json(&root_group)] },
+                }),
+                false,
+            ))
+        }
+        "set-login" => {
+            associated_id(database, message)?;
+            set_login(database, message)?;
+            Ok((
+                json!({
+                    "count": null,
+                    "entries": null,
+                    "error": "",
+                    "hash": hash,
+                }),
+                true,
+            ))
+        }
+        _ => Err(BrowserError::IncorrectAction),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::passwords_utils::{create_group, new_database};
+    use super::*;
+
+    #[test]
+    fn test_browser_requests() {
+        let mut database = new_database();
+        let (root_group, _) = passwords_from_database(&database);
+        let work_group_id = create_group(&mut database, root_group.id, "Work").unwrap();
+        [
+            ("Mail", "https://mail.example.com", "me"),
+            ("Example", "example.com", "you"),
+            ("Other", "https://example.org", "them"),
+        ]
+        .into_iter()
+        .for_each(|(title, url, username)| {
+            update_entry(
+                &mut database,
+                &Password {
+                    id: uuid::Uuid::new_v4(),
+                    title: title.to_string(),
+                    username: username.to_string(),
+                    url: url.to_string(),
+                    password: Zeroizing::new(String::from("hunter2")),
+                    otp: String::new(),
+                    group_id: work_group_id,
+                    last_modified: None,
+                },
+            )
+        });
+
+        let get_logins = json!({
+            "action": "get-logins",
+            "url": "https://mail.example.com/inbox",
+            "keys": [{"id": "Firefox", "key": "id key"}],
+        });
+        assert_eq!(
+            handle_browser_request(&mut database, "get-logins", &get_logins),
+            Err(BrowserError::AssociationFailed)
+        );
+        assert!(!is_association_name_taken(&database, "Firefox"));
+        add_association(&mut database, "Firefox", "id key");
+        assert!(is_association_name_taken(&database, "Firefox"));
+        assert_eq!(
+            handle_browser_request(
+                &mut database,
+                "test-associate",
+                &json!({"id": "Firefox", "key": "wrong key"})
+            ),
+            Err(BrowserError::AssociationFailed)
+        );
+        assert!(handle_browser_request(
+            &mut database,
+            "test-associate",
+            &json!({"id": "Firefox", "key": "id key"})
+        )
+        .is_ok());
+
+        let (reply, is_changed) =
+            handle_browser_request(&mut database, "get-logins", &get_logins).unwrap();
+        assert!(!is_changed);
+        assert_eq!(reply["count"], 2);
+        assert_eq!(reply["id"], "Firefox");
+        assert_eq!(reply["entries"][0]["name"], "Mail");
+        assert_eq!(reply["entries"][1]["name"], "Example");
+        assert_eq!(reply["entries"][0]["group"], "Work");
+
+        // Updating the password of an existing entry, only once the browser's key is sent with it
+        let mut set_login = json!({
+            "url": "https://mail.example.com",
+            "login": "me",
+            "password": "new password",
+            "uuid": reply["entries"][0]["uuid"],
+        });
+        assert_eq!(
+            handle_browser_request(&mut database, "set-login", &set_login),
+            Err(BrowserError::AssociationFailed)
+        );
+        set_login["keys"] = get_logins["keys"].clone();
+        assert_eq!(
+            handle_browser_request(&mut database, "set-login", &set_login)
+                .map(|(_, is_changed)| is_changed),
+            Ok(true)
+        );
+        // A new login from a site without an entry
+        let set_login = json!({
+            "url": "https://shop.example.net/login",
+            "login": "buyer",
+            "password": "pass",
+            "uuid": "",
+            "groupUuid": browser_uuid(work_group_id),
+            "id": "Firefox",
+            "key": "id key",
+        });
+        assert!(handle_browser_request(&mut database, "set-login", &set_login).is_ok());
+        let (_, passwords) = passwords_from_database(&database);
+        assert_eq!(passwords.len(), 4);
+        assert!(passwords
+            .iter()
+            .any(|password| password.title == "Mail" && *password.password == "new password"));
+        assert!(passwords
+            .iter()
+            .any(|password| password.title == "shop.example.net"
+                && password.username == "buyer"
+                && password.group_id == work_group_id));
+
+        for action in ["get-totp", "get-database-groups"] {
+            assert_eq!(
+                handle_browser_request(
+                    &mut database,
+                    action,
+                    &json!({"uuid": reply["entries"][0]["uuid"]})
+                ),
+                Err(BrowserError::AssociationFailed)
+            );
+        }
+        assert!(handle_browser_request(&mut database, "get-database-groups", &get_logins).is_ok());
+
+        assert_eq!(
+            handle_browser_request(&mut database, "request-autotype", &json!({})),
+            Err(BrowserError::IncorrectAction)
+        );
+    }
+}
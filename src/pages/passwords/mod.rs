@@ -1,4 +1,12 @@
+mod breached_passwords;
+mod browser_integration;
 pub mod page;
+mod password_export;
+mod password_generator;
+mod password_health;
+mod password_import;
 mod passwords_utils;
+mod secret_service;
+mod ssh_agent;
 mod update;
 mod view;
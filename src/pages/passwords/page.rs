@@ -1,17 +1,29 @@
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use iced::event::{self, Status};
 use iced::keyboard::{Key, Modifiers};
-use iced::{keyboard, Element, Event, Task};
+use iced::{keyboard, mouse, time, window, Element, Event, Subscription, Task};
+use keepass::Database;
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
 
 use crate::app::Message;
+use crate::utils::browser_integration_utils::{self, BrowserRequest};
+use crate::utils::secret_service_utils::{self, SecretServiceConnection, SecretServiceRequest};
+use crate::utils::session_lock_utils::{self, session_locks};
+use crate::utils::ssh_agent_utils::{self, SshAgentRequest};
 
-use super::passwords_utils::save_database;
+use super::password_export::ExportFormat;
+use super::password_health::HealthFinding;
+use super::password_import::ImportedPassword;
+use super::passwords_utils::{new_database, passwords_from_database, save_database};
+use super::ssh_agent::SshKey;
 use super::update::update;
 use super::view::{main_view, tool_view};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Password {
     /// Unique identifier for the password
     pub id: uuid::Uuid,
@@ -19,13 +31,173 @@ pub struct Password {
     pub username: String,
     /// URL the entry is for, if it's for a website
     pub url: String,
-    pub password: String,
+    /// Wiped from memory when dropped, so it doesn't linger after the database is locked
+    pub password: Zeroizing<String>,
+    /// The `otpauth://` URI for the entry's TOTP codes, "" if it doesn't have one
+    pub otp: String,
+    /// The ID of the group the entry is in
+    pub group_id: uuid::Uuid,
+    /// When the entry was last changed, according to the database
+    pub last_modified: Option<chrono::NaiveDateTime>,
+}
+
+/// A group from the database's group tree, entries point to the group they're in by its ID
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordGroup {
+    /// Unique identifier for the group
+    pub id: uuid::Uuid,
+    pub name: String,
+    /// The groups nested directly inside this one, in the order they're stored in the database
+    pub child_groups: Vec<PasswordGroup>,
+}
+
+/// A database unlocked alongside the one shown on the page, kept with the key it's saved with
+#[derive(Clone)]
+pub struct OpenDatabase {
+    /// The path to the database file
+    pub path: Option<PathBuf>,
+    /// The master password it was unlocked with, "" if it only uses a keyfile
+    pub master_password: Zeroizing<String>,
+    /// The path to its keyfile, if any
+    pub key_file: Option<PathBuf>,
+    pub database: Database,
+    /// The root of the database's group tree, for the sidebar
+    pub root_group: PasswordGroup,
+    /// Every entry in the database, for searching
+    pub passwords_list: Vec<Password>,
+    /// Whether the database has been modified but not saved
+    pub is_dirty: bool,
+}
+
+impl OpenDatabase {
+    /// Saves the database to its file with its own key
+    pub async fn save(self) -> (bool, String) {
+        let master_password = if self.master_password.is_empty() {
+            None
+        } else {
+            Some(self.master_password)
+        };
+        save_database(self.path, master_password, self.key_file, self.database).await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum PasswordGeneratorMode {
+    /// Random characters from the chosen character classes
+    #[default]
+    Characters,
+    /// Random words from the bundled diceware wordlist
+    Passphrase,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PasswordGeneratorSettings {
+    pub mode: PasswordGeneratorMode,
+    /// The number of characters in the password
+    pub length: u8,
+    pub use_lowercase: bool,
+    pub use_uppercase: bool,
+    pub use_digits: bool,
+    pub use_symbols: bool,
+    /// Whether to leave out characters like I, l, 1, O and 0 that are easily mistaken for each other
+    pub exclude_look_alike_characters: bool,
+    /// Whether every character class that's turned on must appear at least once
+    pub include_each_class: bool,
+    /// The number of words in the passphrase
+    pub word_count: u8,
+    /// The string put between the words of the passphrase
+    pub word_separator: String,
+    /// Whether to start each word of the passphrase with a capital letter
+    pub capitalise_words: bool,
+}
+
+impl Default for PasswordGeneratorSettings {
+    fn default() -> Self {
+        Self {
+            mode: PasswordGeneratorMode::Characters,
+            length: 20,
+            use_lowercase: true,
+            use_uppercase: true,
+            use_digits: true,
+            use_symbols: true,
+            exclude_look_alike_characters: false,
+            include_each_class: true,
+            word_count: 6,
+            word_separator: String::from("-"),
+            capitalise_words: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PasswordGeneratorPreset {
+    /// The name to show for this preset in the generator panel
+    pub name: String,
+    pub settings: PasswordGeneratorSettings,
+}
+
+impl std::fmt::Display for PasswordGeneratorPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PasswordPageConfig {
     pub default_database: Option<PathBuf>,
     pub show_sidebar_on_start: bool,
+    #[serde(default)]
+    pub generator_presets: Vec<PasswordGeneratorPreset>,
+    /// Entries whose passwords haven't changed in this many months are flagged in the health report
+    #[serde(default = "default_max_password_age_months")]
+    pub max_password_age_months: u8,
+    /// The local Have I Been Pwned hash file, or folder of range files, passwords are checked against
+    #[serde(default)]
+    pub breached_hashes_path: Option<PathBuf>,
+    /// Copied passwords and TOTP codes are cleared from the clipboard after this many seconds, 0 to never clear them
+    #[serde(default = "default_clipboard_clear_seconds")]
+    pub clipboard_clear_seconds: u16,
+    /// The database is locked after this many minutes without keyboard or mouse activity in the app, 0 to never lock it
+    #[serde(default = "default_auto_lock_minutes")]
+    pub auto_lock_minutes: u16,
+    /// Whether to lock the database when the session is locked
+    #[serde(default = "default_lock_on_screen_lock")]
+    pub lock_on_screen_lock: bool,
+    /// Whether to lock the database when the window is minimised
+    #[serde(default = "default_lock_on_minimise")]
+    pub lock_on_minimise: bool,
+    /// Whether KeePassXC-Browser can connect to the app through the browser proxy to fill and save logins
+    #[serde(default)]
+    pub browser_integration_enabled: bool,
+    /// Whether other apps can store and look up secrets in the database through the Secret Service D-Bus API
+    #[serde(default)]
+    pub secret_service_enabled: bool,
+    /// Whether SSH clients can use the keys in the database through the app's SSH agent socket
+    #[serde(default)]
+    pub ssh_agent_enabled: bool,
+    /// Whether to ask before every use of an SSH key, otherwise only for keys whose settings ask for it
+    #[serde(default)]
+    pub ssh_agent_confirm_use: bool,
+}
+
+fn default_max_password_age_months() -> u8 {
+    12
+}
+
+fn default_clipboard_clear_seconds() -> u16 {
+    30
+}
+
+fn default_auto_lock_minutes() -> u16 {
+    10
+}
+
+fn default_lock_on_screen_lock() -> bool {
+    true
+}
+
+fn default_lock_on_minimise() -> bool {
+    true
 }
 
 impl Default for PasswordPageConfig {
@@ -33,6 +205,17 @@ impl Default for PasswordPageConfig {
         Self {
             default_database: None,
             show_sidebar_on_start: true,
+            generator_presets: vec![],
+            max_password_age_months: default_max_password_age_months(),
+            breached_hashes_path: None,
+            clipboard_clear_seconds: default_clipboard_clear_seconds(),
+            auto_lock_minutes: default_auto_lock_minutes(),
+            lock_on_screen_lock: default_lock_on_screen_lock(),
+            lock_on_minimise: default_lock_on_minimise(),
+            browser_integration_enabled: false,
+            secret_service_enabled: false,
+            ssh_agent_enabled: false,
+            ssh_agent_confirm_use: false,
         }
     }
 }
@@ -50,14 +233,26 @@ pub struct PasswordsPage {
     pub(super) show_sidebar: bool,
     /// Whether the incorrect password was entered
     pub(super) incorrect_password_entered: bool,
+    /// The database thats opened, edited in place so anything not shown on the page is kept when saving
+    pub(super) database: Database,
     /// The list of passwords from the database thats opened
     pub(super) passwords_list: Vec<Password>,
+    /// The root of the group tree of the database thats opened
+    pub(super) root_group: PasswordGroup,
+    /// The group selected in the sidebar, which new groups are created in and group actions apply to
+    pub(super) selected_group_id: uuid::Uuid,
+    /// The groups whose contents are hidden in the sidebar
+    pub(super) collapsed_group_ids: HashSet<uuid::Uuid>,
+    /// The content of the Group Name text field
+    pub(super) current_group_name_text: String,
+    /// The group the entry being edited/created will be saved in
+    pub(super) current_entry_group_id: uuid::Uuid,
     /// The path to the database file currently open, if any
     pub(super) selected_keepass_file: Option<PathBuf>,
     /// The path to the file being use as a keyfile, if any
     pub(super) selected_key_file: Option<PathBuf>,
     /// The content of the Master Password text field
-    pub(super) master_password_field_text: String,
+    pub(super) master_password_field_text: Zeroizing<String>,
     /// The content of the Title text field
     pub(super) current_title_text: String,
     /// The content of the URL text field
@@ -65,9 +260,11 @@ pub struct PasswordsPage {
     /// The content of the Username text field
     pub(super) current_username_text: String,
     /// The content of the Password text field
-    pub(super) current_password_text: String,
+    pub(super) current_password_text: Zeroizing<String>,
+    /// The content of the TOTP URI text field
+    pub(super) current_otp_text: String,
     /// The content of the Master Password Reentry text field
-    pub(super) master_password_reentry_field_text: String,
+    pub(super) master_password_reentry_field_text: Zeroizing<String>,
     /// The string to filter the titles of the passwords list by, if "" no filtering is done
     pub(super) current_passwords_list_filter: String,
     /// The entry selected to display/edit, if any
@@ -80,6 +277,77 @@ pub struct PasswordsPage {
     pub(super) hide_master_password_reentry_entry: bool,
     /// Whether the master password fields text and its reentry fields text don't match
     pub(super) passwords_dont_match: bool,
+    /// Whether to show the password generator panel in the entry edit view
+    pub(super) show_password_generator: bool,
+    /// The settings the generate password button uses
+    pub(super) generator_settings: PasswordGeneratorSettings,
+    /// The saved generator settings that can be picked in the generator panel
+    pub(super) generator_presets: Vec<PasswordGeneratorPreset>,
+    /// The content of the preset name text field in the generator panel
+    pub(super) new_generator_preset_name_text: String,
+    /// Whether to show the health report instead of the entry edit view
+    pub(super) show_health_report: bool,
+    /// Whether the health report is being worked out
+    pub(super) is_checking_health: bool,
+    /// The problems found with the entries the last time the health report was worked out
+    pub(super) health_findings: Vec<HealthFinding>,
+    /// Entries whose passwords haven't changed in this many months are flagged in the health report
+    pub(super) max_password_age_months: u8,
+    /// The local Have I Been Pwned hash file, or folder of range files, passwords are checked against
+    pub(super) breached_hashes_path: Option<PathBuf>,
+    /// Whether the passwords are being checked against the breached password hashes
+    pub(super) is_checking_breaches: bool,
+    /// How many times each compromised entry's password appears in the breached password hashes
+    pub(super) breach_counts: HashMap<uuid::Uuid, u64>,
+    /// Whether the sidebar only shows entries with compromised passwords
+    pub(super) show_only_compromised: bool,
+    /// When there was last keyboard or mouse activity in the app, for locking the database when idle
+    pub(super) last_activity: Instant,
+    /// The database is locked after this many minutes without activity, 0 to never lock it
+    pub(super) auto_lock_minutes: u16,
+    /// Whether to lock the database when the session is locked
+    pub(super) lock_on_screen_lock: bool,
+    /// Whether to lock the database when the window is minimised, or when it loses focus where
+    /// minimising can't be detected
+    pub(super) lock_on_minimise: bool,
+    /// Whether to show the menu with the import and export buttons
+    pub(super) show_extra_tools_menu: bool,
+    /// The entries read from another password manager's export, shown for confirmation before they're added
+    pub(super) import_preview: Option<Vec<ImportedPassword>>,
+    /// The encrypted bundle picked for import, waiting for its password to be entered
+    pub(super) import_bundle_path: Option<PathBuf>,
+    /// The content of the bundle password text field when importing an encrypted bundle
+    pub(super) import_bundle_password_text: Zeroizing<String>,
+    /// Whether to show the export view instead of the entry edit view
+    pub(super) show_export_view: bool,
+    /// The groups whose entries, including those in groups inside them, are exported
+    pub(super) export_group_ids: HashSet<uuid::Uuid>,
+    /// Entries with any of these tags are exported as well as those in the selected groups
+    pub(super) export_tags: HashSet<String>,
+    /// The format the export is written in
+    pub(super) export_format: ExportFormat,
+    /// The content of the master password text field in the export view, which has to be re-entered to export
+    pub(super) export_master_password_text: Zeroizing<String>,
+    /// The content of the password text field for encrypting the bundle in the export view
+    pub(super) export_bundle_password_text: Zeroizing<String>,
+    /// Whether the master password is being checked or the export is being written
+    pub(super) is_exporting: bool,
+    /// A browser asking to be associated with the database, waiting for the user to allow or deny it
+    pub(super) pending_browser_association: Option<BrowserRequest>,
+    /// The content of the name text field for the browser asking to be associated
+    pub(super) browser_association_name_text: String,
+    /// Apps waiting through the Secret Service for the database to be unlocked
+    pub(super) pending_secret_service_unlocks: Vec<SecretServiceRequest>,
+    /// Tells the Secret Service when the database locks, None until it has started
+    pub(super) secret_service_connection: Option<SecretServiceConnection>,
+    /// The SSH keys from the database, loaded when it's unlocked
+    pub(super) ssh_keys: Vec<SshKey>,
+    /// Whether to ask before every use of an SSH key
+    pub(super) ssh_agent_confirm_use: bool,
+    /// SSH clients waiting for the user to allow or deny using a key, the first is shown
+    pub(super) pending_ssh_sign_requests: Vec<SshAgentRequest>,
+    /// The other unlocked databases, shown as separate roots in the sidebar and swapped with the shown one when picked
+    pub(super) other_databases: Vec<OpenDatabase>,
 }
 
 #[derive(Debug, Clone)]
@@ -88,13 +356,24 @@ pub enum PasswordsPageMessage {
     DeletePasswordEntry(uuid::Uuid),
     TryUnlock,
     Lock,
-    RetrievedPasswordsList(Option<Vec<Password>>),
+    RetrievedDatabase(Option<Database>),
     SelectPassword(Option<Password>),
+    SelectGroup(uuid::Uuid),
+    ToggleGroupCollapsed(uuid::Uuid),
+    UpdateCurrentGroupNameText(String),
+    CreateGroup,
+    RenameSelectedGroup,
+    DeleteSelectedGroup,
+    SetCurrentEntryGroup(uuid::Uuid),
     UpdateMasterPasswordField(String),
     UpdateCurrentTitleText(String),
     UpdateCurrentUrlText(String),
     UpdateCurrentUsernameText(String),
     UpdateCurrentPasswordText(String),
+    UpdateCurrentOtpText(String),
+    PickTotpQrCodeImage,
+    ScanTotpQrCodeImage(Option<PathBuf>),
+    TotpTick,
     UpdateMasterPasswordReentryField(String),
     UpdatePasswordsFilter(String),
     SaveDatabaseToFile,
@@ -112,6 +391,58 @@ pub enum PasswordsPageMessage {
     SetKeyFile(Option<PathBuf>),
     LockAndDeselectDatabase,
     GeneratePassword,
+    ToggleShowPasswordGenerator,
+    UpdateGeneratorSettings(PasswordGeneratorSettings),
+    UpdateNewGeneratorPresetNameText(String),
+    SaveGeneratorPreset,
+    LoadGeneratorPreset(PasswordGeneratorPreset),
+    DeleteGeneratorPreset(String),
+    ToggleShowHealthReport,
+    RefreshHealthReport,
+    SetHealthFindings(Vec<HealthFinding>),
+    OpenHealthFinding(uuid::Uuid),
+    SetMaxPasswordAgeMonths(u8),
+    SetBreachedHashesPath(Option<PathBuf>),
+    CheckBreachedPasswords,
+    SetBreachCounts(Result<HashMap<uuid::Uuid, u64>, String>),
+    ToggleShowOnlyCompromised,
+    RecordActivity,
+    AutoLockTick,
+    CheckWindowMinimised(window::Id),
+    SetAutoLockMinutes(u16),
+    SetLockOnScreenLock(bool),
+    SetLockOnMinimise(bool),
+    ToggleExtraToolsMenu,
+    ImportPasswords,
+    ImportPasswordsFromFile(Option<PathBuf>),
+    SetImportPreview(Result<Vec<ImportedPassword>, String>),
+    ConfirmImport,
+    CancelImport,
+    UpdateImportBundlePasswordText(String),
+    DecryptImportBundle,
+    ToggleShowExportView,
+    ToggleExportGroup(uuid::Uuid),
+    ToggleExportTag(String),
+    SetExportFormat(ExportFormat),
+    UpdateExportMasterPasswordText(String),
+    UpdateExportBundlePasswordText(String),
+    StartExport,
+    ExportMasterPasswordChecked(bool),
+    ExportToFile(Option<PathBuf>),
+    ExportFinished(Result<usize, String>),
+    HandleBrowserRequest(BrowserRequest),
+    UpdateBrowserAssociationNameText(String),
+    ConfirmBrowserAssociation(bool),
+    SetSecretServiceConnection(SecretServiceConnection),
+    HandleSecretServiceRequest(SecretServiceRequest),
+    ShareSelectedGroupWithSecretService,
+    HandleSshAgentRequest(SshAgentRequest),
+    ConfirmSshKeyUse(bool),
+    SetSshAgentConfirmUse(bool),
+    OpenAnotherDatabase,
+    ShowOpenDatabase(usize),
+    OpenEntryInDatabase(usize, uuid::Uuid),
+    MoveSelectedEntryToDatabase(usize),
 }
 
 impl PasswordsPage {
@@ -120,18 +451,27 @@ impl PasswordsPage {
             .expect("Can't get locale")
             .parse()
             .expect("Failed to parse locale");
+        let database = new_database();
+        let (root_group, passwords_list) = passwords_from_database(&database);
         Self {
             locale,
-            passwords_list: vec![],
+            database,
+            passwords_list,
+            selected_group_id: root_group.id,
+            current_entry_group_id: root_group.id,
+            root_group,
+            collapsed_group_ids: HashSet::new(),
+            current_group_name_text: String::new(),
             selected_keepass_file: config.default_database.clone(),
             is_unlocked: false,
             incorrect_password_entered: false,
-            master_password_field_text: String::new(),
+            master_password_field_text: Zeroizing::default(),
             selected_password_entry: None,
             current_title_text: String::new(),
             current_url_text: String::new(),
             current_username_text: String::new(),
-            current_password_text: String::new(),
+            current_password_text: Zeroizing::default(),
+            current_otp_text: String::new(),
             current_passwords_list_filter: String::new(),
             is_dirty: false,
             show_sidebar: config.show_sidebar_on_start,
@@ -140,8 +480,43 @@ impl PasswordsPage {
             is_creating_new_keepass_file: false,
             hide_master_password_reentry_entry: true,
             passwords_dont_match: false,
-            master_password_reentry_field_text: String::new(),
+            master_password_reentry_field_text: Zeroizing::default(),
             selected_key_file: None,
+            show_password_generator: false,
+            generator_settings: PasswordGeneratorSettings::default(),
+            generator_presets: config.generator_presets.clone(),
+            new_generator_preset_name_text: String::new(),
+            show_health_report: false,
+            is_checking_health: false,
+            health_findings: vec![],
+            max_password_age_months: config.max_password_age_months,
+            breached_hashes_path: config.breached_hashes_path.clone(),
+            is_checking_breaches: false,
+            breach_counts: HashMap::new(),
+            show_only_compromised: false,
+            last_activity: Instant::now(),
+            auto_lock_minutes: config.auto_lock_minutes,
+            lock_on_screen_lock: config.lock_on_screen_lock,
+            lock_on_minimise: config.lock_on_minimise,
+            show_extra_tools_menu: false,
+            import_preview: None,
+            import_bundle_path: None,
+            import_bundle_password_text: Zeroizing::default(),
+            show_export_view: false,
+            export_group_ids: HashSet::new(),
+            export_tags: HashSet::new(),
+            export_format: ExportFormat::Csv,
+            export_master_password_text: Zeroizing::default(),
+            export_bundle_password_text: Zeroizing::default(),
+            is_exporting: false,
+            pending_browser_association: None,
+            browser_association_name_text: String::new(),
+            pending_secret_service_unlocks: vec![],
+            secret_service_connection: None,
+            ssh_keys: vec![],
+            ssh_agent_confirm_use: config.ssh_agent_confirm_use,
+            pending_ssh_sign_requests: vec![],
+            other_databases: vec![],
         }
     }
 
@@ -150,24 +525,29 @@ impl PasswordsPage {
     }
 
     pub fn closing_task(&mut self) -> Task<Message> {
+        let mut save_tasks: Vec<Task<Message>> = self
+            .other_databases
+            .drain(..)
+            .filter(|open_database| open_database.is_dirty)
+            .map(|open_database| Task::perform(open_database.save(), |_| Message::None))
+            .collect();
         if self.is_dirty {
             let password = if self.master_password_field_text.is_empty() {
                 None
             } else {
                 Some(self.master_password_field_text.clone())
             };
-            Task::perform(
+            save_tasks.push(Task::perform(
                 save_database(
                     self.selected_keepass_file.clone(),
                     password,
                     self.selected_key_file.clone(),
-                    self.passwords_list.clone(),
+                    self.database.clone(),
                 ),
                 |_| Message::None,
-            )
-        } else {
-            Task::none()
+            ));
         }
+        Task::batch(save_tasks)
     }
 
     pub fn update(&mut self, message: PasswordsPageMessage) -> Task<Message> {
@@ -178,32 +558,129 @@ impl PasswordsPage {
         main_view(self)
     }
 
-    pub fn subscription() -> iced::Subscription<Message> {
+    pub fn subscription(&self) -> Subscription<Message> {
         // Keyboard shortcuts
-        event::listen_with(|event, status, _id| match (event, status) {
-            (
-                Event::Keyboard(keyboard::Event::KeyPressed {
-                    key: Key::Character(pressed_char),
-                    modifiers: Modifiers::CTRL,
-                    ..
-                }),
-                Status::Ignored,
-            ) => {
-                if pressed_char.as_ref() == "n" || pressed_char.as_ref() == "N" {
-                    Some(Message::Passwords(PasswordsPageMessage::SelectPassword(
-                        None,
-                    )))
-                } else if pressed_char.as_ref() == "b" || pressed_char.as_ref() == "B" {
-                    Some(Message::Passwords(PasswordsPageMessage::ToggleShowSidebar))
-                } else if pressed_char.as_ref() == "l" || pressed_char.as_ref() == "L" {
-                    Some(Message::Passwords(
-                        PasswordsPageMessage::LockAndDeselectDatabase,
-                    ))
-                } else {
-                    None
+        let mut subscriptions_vec = vec![event::listen_with(|event, status, _id| {
+            match (event, status) {
+                (
+                    Event::Keyboard(keyboard::Event::KeyPressed {
+                        key: Key::Character(pressed_char),
+                        modifiers: Modifiers::CTRL,
+                        ..
+                    }),
+                    Status::Ignored,
+                ) => {
+                    if pressed_char.as_ref() == "n" || pressed_char.as_ref() == "N" {
+                        Some(Message::Passwords(PasswordsPageMessage::SelectPassword(
+                            None,
+                        )))
+                    } else if pressed_char.as_ref() == "b" || pressed_char.as_ref() == "B" {
+                        Some(Message::Passwords(PasswordsPageMessage::ToggleShowSidebar))
+                    } else if pressed_char.as_ref() == "l" || pressed_char.as_ref() == "L" {
+                        Some(Message::Passwords(
+                            PasswordsPageMessage::LockAndDeselectDatabase,
+                        ))
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            }
+        })];
+        if self.is_unlocked && !self.current_otp_text.is_empty() {
+            // Keeps the TOTP code and its countdown up to date
+            subscriptions_vec.push(
+                time::every(Duration::from_secs(1))
+                    .map(|_| Message::Passwords(PasswordsPageMessage::TotpTick)),
+            );
+        }
+        Subscription::batch(subscriptions_vec)
+    }
+
+    /// Locks the database when idle, when the session is locked, or when the window is minimised.
+    /// Runs whichever page is open, unlike `subscription`
+    pub fn auto_lock_subscription(&self) -> Subscription<Message> {
+        if !self.is_unlocked && self.other_databases.is_empty() {
+            return Subscription::none();
+        }
+        let mut subscriptions_vec = vec![];
+        if self.auto_lock_minutes > 0 {
+            subscriptions_vec.push(event::listen_with(|event, _status, _id| match event {
+                Event::Keyboard(keyboard::Event::KeyPressed { .. })
+                | Event::Mouse(mouse::Event::CursorMoved { .. })
+                | Event::Mouse(mouse::Event::ButtonPressed(_))
+                | Event::Mouse(mouse::Event::WheelScrolled { .. }) => {
+                    Some(Message::Passwords(PasswordsPageMessage::RecordActivity))
+                }
+                _ => None,
+            }));
+            subscriptions_vec.push(
+                time::every(Duration::from_secs(10))
+                    .map(|_| Message::Passwords(PasswordsPageMessage::AutoLockTick)),
+            );
+        }
+        if self.lock_on_minimise {
+            // Minimising unfocuses the window, so whether it was minimised is checked then
+            subscriptions_vec.push(event::listen_with(|event, _status, id| match event {
+                Event::Window(window::Event::Unfocused) => Some(Message::Passwords(
+                    PasswordsPageMessage::CheckWindowMinimised(id),
+                )),
+                _ => None,
+            }));
+        }
+        if self.lock_on_screen_lock {
+            subscriptions_vec.push(
+                Subscription::run_with_id("session_lock_subscription", session_locks()).map(
+                    |event| match event {
+                        session_lock_utils::Event::SessionLocked => {
+                            Message::Passwords(PasswordsPageMessage::Lock)
+                        }
+                        session_lock_utils::Event::Error(err) => Message::ShowToast(false, err),
+                    },
+                ),
+            );
+        }
+        Subscription::batch(subscriptions_vec)
+    }
+
+    /// Answers KeePassXC-Browser through the browser proxy. Runs whichever page is open, unlike `subscription`
+    pub fn browser_integration_subscription(&self) -> Subscription<Message> {
+        Subscription::run_with_id(
+            "browser_integration_subscription",
+            browser_integration_utils::serve(),
+        )
+        .map(|event| match event {
+            browser_integration_utils::Event::Request(request) => {
+                Message::Passwords(PasswordsPageMessage::HandleBrowserRequest(request))
+            }
+            browser_integration_utils::Event::Error(err) => Message::ShowToast(false, err),
+        })
+    }
+
+    /// Serves other apps' secrets from the shared group over D-Bus. Runs whichever page is open, like the browser integration
+    pub fn secret_service_subscription(&self) -> Subscription<Message> {
+        Subscription::run_with_id("secret_service_subscription", secret_service_utils::serve()).map(
+            |event| match event {
+                secret_service_utils::Event::Started(connection) => {
+                    Message::Passwords(PasswordsPageMessage::SetSecretServiceConnection(connection))
+                }
+                secret_service_utils::Event::Request(request) => {
+                    Message::Passwords(PasswordsPageMessage::HandleSecretServiceRequest(request))
+                }
+                secret_service_utils::Event::Error(err) => Message::ShowToast(false, err),
+            },
+        )
+    }
+
+    /// Serves the database's SSH keys on the agent socket. Runs whichever page is open, like the browser integration
+    pub fn ssh_agent_subscription(&self) -> Subscription<Message> {
+        Subscription::run_with_id("ssh_agent_subscription", ssh_agent_utils::serve()).map(|event| {
+            match event {
+                ssh_agent_utils::Event::Request(request) => {
+                    Message::Passwords(PasswordsPageMessage::HandleSshAgentRequest(request))
                 }
+                ssh_agent_utils::Event::Error(err) => Message::ShowToast(false, err),
             }
-            _ => None,
         })
     }
 
@@ -0,0 +1,360 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use keepass::db::{Entry, Group, Node};
+use keepass::Database;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::passwords_utils::OTP_FIELD;
+
+/// The `format` field that marks a JSON file as one of our encrypted bundles
+const BUNDLE_FORMAT: &str = "idirfein-password-bundle";
+const BUNDLE_VERSION: u32 = 1;
+const BUNDLE_SALT_LENGTH: usize = 16;
+
+/// How the exported entries are written out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// Unencrypted CSV, with headers other password managers' importers recognise
+    Csv,
+    /// JSON encrypted with a password separate from the master password, for sharing entries with someone else
+    EncryptedBundle,
+}
+
+/// An entry as written to an export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedPassword {
+    /// The groups the entry is in below the root group, separated by /
+    pub group: String,
+    pub title: String,
+    pub username: String,
+    pub url: String,
+    pub password: String,
+    /// The `otpauth://` URI for the entry's TOTP codes, "" if it doesn't have one
+    pub otp: String,
+    pub notes: String,
+    pub tags: Vec<String>,
+}
+
+/// The file an encrypted bundle is saved as, the entries are encrypted as JSON with AES-256-GCM
+/// using a key derived from the bundle password with Argon2id
+#[derive(Serialize, Deserialize)]
+struct EncryptedBundle {
+    format: String,
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Every tag used by an entry in the database, sorted
+pub fn database_tags(database: &Database) -> Vec<String> {
+    fn add_group_tags(group: &Group, tags: &mut BTreeSet<String>) {
+        group.children.iter().for_each(|node| match node {
+            Node::Group(child_group) => add_group_tags(child_group, tags),
+            Node::Entry(entry) => tags.extend(entry.tags.iter().cloned()),
+        });
+    }
+    let mut tags = BTreeSet::new();
+    add_group_tags(&database.root, &mut tags);
+    tags.into_iter().collect()
+}
+
+/// Calls `f` with every entry that's in one of the groups, including groups nested in them, or has one of the tags,
+/// along with the path of groups it's in below the root group
+fn for_each_selected_entry<'a>(
+    group: &'a Group,
+    group_path: &mut Vec<&'a str>,
+    is_group_selected: bool,
+    group_ids: &HashSet<uuid::Uuid>,
+    tags: &HashSet<String>,
+    f: &mut impl FnMut(&[&str], &'a Entry),
+) {
+    let is_group_selected = is_group_selected || group_ids.contains(&group.uuid);
+    group.children.iter().for_each(|node| match node {
+        Node::Group(child_group) => {
+            group_path.push(&child_group.name);
+            for_each_selected_entry(
+                child_group,
+                group_path,
+                is_group_selected,
+                group_ids,
+                tags,
+                f,
+            );
+            group_path.pop();
+        }
+        Node::Entry(entry) => {
+            if is_group_selected || entry.tags.iter().any(|tag| tags.contains(tag)) {
+                f(group_path, entry)
+            }
+        }
+    });
+}
+
+/// How many entries would be exported for the selected groups and tags
+pub fn selected_entry_count(
+    database: &Database,
+    group_ids: &HashSet<uuid::Uuid>,
+    tags: &HashSet<String>,
+) -> usize {
+    let mut count = 0;
+    for_each_selected_entry(
+        &database.root,
+        &mut vec![],
+        false,
+        group_ids,
+        tags,
+        &mut |_, _| count += 1,
+    );
+    count
+}
+
+/// The entries in the selected groups, including groups nested in them, and the entries with the selected tags
+pub fn entries_to_export(
+    database: &Database,
+    group_ids: &HashSet<uuid::Uuid>,
+    tags: &HashSet<String>,
+) -> Vec<ExportedPassword> {
+    let mut exported_passwords = vec![];
+    for_each_selected_entry(
+        &database.root,
+        &mut vec![],
+        false,
+        group_ids,
+        tags,
+        &mut |group_path, entry| {
+            exported_passwords.push(ExportedPassword {
+                group: group_path.join("/"),
+                title: String::from(entry.get_title().unwrap_or("")),
+                username: String::from(entry.get_username().unwrap_or("")),
+                url: String::from(entry.get_url().unwrap_or("")),
+                password: String::from(entry.get_password().unwrap_or("")),
+                otp: String::from(entry.get(OTP_FIELD).unwrap_or("")),
+                notes: String::from(entry.get("Notes").unwrap_or("")),
+                tags: entry.tags.clone(),
+            })
+        },
+    );
+    exported_passwords
+}
+
+/// Writes the entries as CSV, the column names match the ones our importer and other password managers look for
+fn passwords_csv(exported_passwords: &[ExportedPassword]) -> Result<Vec<u8>, String> {
+    let mut csv_writer = csv::Writer::from_writer(vec![]);
+    csv_writer
+        .write_record([
+            "group", "title", "username", "password", "url", "otp", "notes", "tags",
+        ])
+        .map_err(|err| err.to_string())?;
+    for exported_password in exported_passwords {
+        csv_writer
+            .write_record([
+                &exported_password.group,
+                &exported_password.title,
+                &exported_password.username,
+                &exported_password.password,
+                &exported_password.url,
+                &exported_password.otp,
+                &exported_password.notes,
+                &exported_password.tags.join(","),
+            ])
+            .map_err(|err| err.to_string())?;
+    }
+    csv_writer.into_inner().map_err(|err| err.to_string())
+}
+
+fn bundle_cipher(bundle_password: &str, salt: &[u8]) -> Result<Aes256Gcm, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(bundle_password.as_bytes(), salt, &mut key)
+        .map_err(|err| err.to_string())?;
+    Ok(Aes256Gcm::new(&key.into()))
+}
+
+fn encrypt_bundle(
+    exported_passwords: &[ExportedPassword],
+    bundle_password: &str,
+) -> Result<String, String> {
+    let mut salt = [0u8; BUNDLE_SALT_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(exported_passwords).map_err(|err| err.to_string())?;
+    let ciphertext = bundle_cipher(bundle_password, &salt)?
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|err| err.to_string())?;
+    serde_json::to_string_pretty(&EncryptedBundle {
+        format: String::from(BUNDLE_FORMAT),
+        version: BUNDLE_VERSION,
+        salt: BASE64_STANDARD.encode(salt),
+        nonce: BASE64_STANDARD.encode(nonce),
+        ciphertext: BASE64_STANDARD.encode(ciphertext),
+    })
+    .map_err(|err| err.to_string())
+}
+
+/// Reads the entries out of an encrypted bundle
+pub fn decrypt_bundle(
+    file_content: &str,
+    bundle_password: &str,
+) -> Result<Vec<ExportedPassword>, String> {
+    let bundle: EncryptedBundle =
+        serde_json::from_str(file_content).map_err(|err| err.to_string())?;
+    if bundle.format != BUNDLE_FORMAT || bundle.version != BUNDLE_VERSION {
+        return Err(String::from("Not a supported password bundle"));
+    }
+    let decode = |field: &str| {
+        BASE64_STANDARD
+            .decode(field)
+            .map_err(|err| format!("Damaged password bundle: {err}"))
+    };
+    let nonce = decode(&bundle.nonce)?;
+    if nonce.len() != 12 {
+        return Err(String::from("Damaged password bundle: invalid nonce"));
+    }
+    let plaintext = bundle_cipher(bundle_password, &decode(&bundle.salt)?)?
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            decode(&bundle.ciphertext)?.as_slice(),
+        )
+        .map_err(|_| String::from("Incorrect bundle password, or the bundle is damaged"))?;
+    serde_json::from_slice(&plaintext).map_err(|err| err.to_string())
+}
+
+/// Whether the file is an encrypted bundle, which needs its password before it can be imported
+pub fn is_encrypted_bundle_file(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+        && fs::read_to_string(file_path).is_ok_and(|file_content| {
+            serde_json::from_str::<EncryptedBundle>(&file_content)
+                .is_ok_and(|bundle| bundle.format == BUNDLE_FORMAT)
+        })
+}
+
+/// Writes the entries to the file in the format, returning how many were exported
+pub async fn export_passwords(
+    exported_passwords: Vec<ExportedPassword>,
+    export_format: ExportFormat,
+    bundle_password: Zeroizing<String>,
+    file_path: PathBuf,
+) -> Result<usize, String> {
+    let file_content = match export_format {
+        ExportFormat::Csv => passwords_csv(&exported_passwords)?,
+        ExportFormat::EncryptedBundle => {
+            if bundle_password.is_empty() {
+                return Err(String::from("The bundle needs a password"));
+            }
+            encrypt_bundle(&exported_passwords, &bundle_password)?.into_bytes()
+        }
+    };
+    // A CSV export holds every password in plain text, so only the user can read the file
+    let mut export_file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&file_path)
+        .map_err(|err| err.to_string())?;
+    export_file
+        .set_permissions(fs::Permissions::from_mode(0o600))
+        .map_err(|err| err.to_string())?;
+    export_file
+        .write_all(&file_content)
+        .map_err(|err| err.to_string())?;
+    Ok(exported_passwords.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use keepass::db::Value;
+
+    use super::*;
+
+    fn entry(title: &str, password: &str, tags: &[&str]) -> Entry {
+        let mut entry = Entry::new();
+        entry
+            .fields
+            .insert("Title".to_string(), Value::Unprotected(String::from(title)));
+        entry.fields.insert(
+            "Password".to_string(),
+            Value::Protected(password.as_bytes().into()),
+        );
+        entry.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        entry
+    }
+
+    #[test]
+    fn test_export_selection_and_bundle() {
+        let mut database = Database::new(Default::default());
+        let mut servers_group = Group::new("Servers");
+        servers_group.add_child(entry("Router", "pass1", &[]));
+        let mut work_group = Group::new("Work");
+        work_group.add_child(entry("VPN", "pass2", &["shared"]));
+        let servers_group_id = servers_group.uuid;
+        work_group.add_child(servers_group);
+        database.root.add_child(work_group);
+        database
+            .root
+            .add_child(entry("Email", "pass3", &["personal", "shared"]));
+        database
+            .root
+            .add_child(entry("Bank", "pass4", &["personal"]));
+
+        assert_eq!(database_tags(&database), vec!["personal", "shared"]);
+        let titles = |group_ids: &[uuid::Uuid], tags: &[&str]| {
+            let group_ids = group_ids.iter().copied().collect();
+            let tags = tags.iter().map(|tag| tag.to_string()).collect();
+            assert_eq!(
+                selected_entry_count(&database, &group_ids, &tags),
+                entries_to_export(&database, &group_ids, &tags).len()
+            );
+            entries_to_export(&database, &group_ids, &tags)
+                .into_iter()
+                .map(|exported_password| (exported_password.group, exported_password.title))
+                .collect::<Vec<(String, String)>>()
+        };
+        let owned = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(group, title)| (group.to_string(), title.to_string()))
+                .collect::<Vec<(String, String)>>()
+        };
+        assert_eq!(
+            titles(&[servers_group_id], &["shared"]),
+            owned(&[("Work", "VPN"), ("Work/Servers", "Router"), ("", "Email")])
+        );
+        assert_eq!(titles(&[database.root.uuid], &[]).len(), 4);
+        assert!(titles(&[], &[]).is_empty());
+
+        let exported_passwords = entries_to_export(
+            &database,
+            &HashSet::new(),
+            &HashSet::from([String::from("shared")]),
+        );
+        let csv = String::from_utf8(passwords_csv(&exported_passwords).unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "group,title,username,password,url,otp,notes,tags\n\
+             Work,VPN,,pass2,,,,shared\n\
+             ,Email,,pass3,,,,\"personal,shared\"\n"
+        );
+
+        let bundle = encrypt_bundle(&exported_passwords, "colleague password").unwrap();
+        assert!(!bundle.contains("pass2"));
+        assert_eq!(
+            decrypt_bundle(&bundle, "colleague password"),
+            Ok(exported_passwords)
+        );
+        assert!(decrypt_bundle(&bundle, "wrong password").is_err());
+    }
+}
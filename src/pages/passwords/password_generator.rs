@@ -0,0 +1,189 @@
+use rand::{seq::SliceRandom, thread_rng};
+
+use super::page::{PasswordGeneratorMode, PasswordGeneratorSettings};
+
+const LOWERCASE_CHARACTERS: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE_CHARACTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGIT_CHARACTERS: &str = "0123456789";
+const SYMBOL_CHARACTERS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+/// Characters that are easily mistaken for each other when a password has to be read or typed by hand
+const LOOK_ALIKE_CHARACTERS: &str = "Il1|O0o`'\"";
+
+/// A diceware list of 7776 common English words, each after the five dice rolls that pick it so it can be used with real dice.
+/// Words are picked uniformly, so each adds log2(7776), about 12.9 bits
+const PASSPHRASE_WORDLIST: &str = include_str!("../../../wordlists/diceware-english.txt");
+
+/// The characters in each character class that's turned on, with look-alikes removed if set
+fn character_classes(settings: &PasswordGeneratorSettings) -> Vec<Vec<char>> {
+    [
+        (settings.use_lowercase, LOWERCASE_CHARACTERS),
+        (settings.use_uppercase, UPPERCASE_CHARACTERS),
+        (settings.use_digits, DIGIT_CHARACTERS),
+        (settings.use_symbols, SYMBOL_CHARACTERS),
+    ]
+    .into_iter()
+    .filter(|(is_used, _)| *is_used)
+    .map(|(_, characters)| {
+        characters
+            .chars()
+            .filter(|character| {
+                !settings.exclude_look_alike_characters
+                    || !LOOK_ALIKE_CHARACTERS.contains(*character)
+            })
+            .collect::<Vec<char>>()
+    })
+    .filter(|characters| !characters.is_empty())
+    .collect()
+}
+
+fn passphrase_words() -> Vec<&'static str> {
+    PASSPHRASE_WORDLIST
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .collect()
+}
+
+/// Generates a password or passphrase, an empty string if no character classes are turned on
+pub fn generate_password(settings: &PasswordGeneratorSettings) -> String {
+    let mut rng = thread_rng();
+    match settings.mode {
+        PasswordGeneratorMode::Characters => {
+            let character_classes = character_classes(settings);
+            let all_characters: Vec<char> = character_classes.concat();
+            if all_characters.is_empty() {
+                return String::new();
+            }
+            let length = settings.length as usize;
+            let mut password_characters: Vec<char> =
+                if settings.include_each_class && length >= character_classes.len() {
+                    character_classes
+                        .iter()
+                        .filter_map(|characters| characters.choose(&mut rng).copied())
+                        .collect()
+                } else {
+                    vec![]
+                };
+            while password_characters.len() < length {
+                if let Some(character) = all_characters.choose(&mut rng) {
+                    password_characters.push(*character);
+                }
+            }
+            password_characters.shuffle(&mut rng);
+            password_characters.into_iter().collect()
+        }
+        PasswordGeneratorMode::Passphrase => {
+            let words = passphrase_words();
+            (0..settings.word_count)
+                .filter_map(|_| words.choose(&mut rng))
+                .map(|word| {
+                    if settings.capitalise_words {
+                        let mut word_characters = word.chars();
+                        word_characters
+                            .next()
+                            .map(|first_character| {
+                                first_character
+                                    .to_uppercase()
+                                    .chain(word_characters)
+                                    .collect()
+                            })
+                            .unwrap_or_default()
+                    } else {
+                        word.to_string()
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(&settings.word_separator)
+        }
+    }
+}
+
+/// Estimates the entropy in bits of passwords made with these settings, assuming an attacker knows the settings
+pub fn entropy_bits(settings: &PasswordGeneratorSettings) -> f64 {
+    match settings.mode {
+        PasswordGeneratorMode::Characters => {
+            let character_count = character_classes(settings).concat().len();
+            if character_count == 0 {
+                0.0
+            } else {
+                settings.length as f64 * (character_count as f64).log2()
+            }
+        }
+        PasswordGeneratorMode::Passphrase => {
+            settings.word_count as f64 * (passphrase_words().len() as f64).log2()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_password() {
+        let settings = PasswordGeneratorSettings {
+            length: 40,
+            exclude_look_alike_characters: true,
+            include_each_class: true,
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let password = generate_password(&settings);
+            assert_eq!(password.chars().count(), 40);
+            assert!(!password.contains(|character| LOOK_ALIKE_CHARACTERS.contains(character)));
+            [
+                LOWERCASE_CHARACTERS,
+                UPPERCASE_CHARACTERS,
+                DIGIT_CHARACTERS,
+                SYMBOL_CHARACTERS,
+            ]
+            .into_iter()
+            .for_each(|characters| assert!(password.contains(|c| characters.contains(c))));
+        }
+
+        let digits_only = PasswordGeneratorSettings {
+            length: 6,
+            use_lowercase: false,
+            use_uppercase: false,
+            use_symbols: false,
+            ..Default::default()
+        };
+        assert!(generate_password(&digits_only)
+            .chars()
+            .all(|character| character.is_ascii_digit()));
+        assert!((entropy_bits(&digits_only) - 6.0 * 10f64.log2()).abs() < 1e-9);
+
+        let nothing = PasswordGeneratorSettings {
+            use_digits: false,
+            ..digits_only
+        };
+        assert_eq!(generate_password(&nothing), "");
+        assert_eq!(entropy_bits(&nothing), 0.0);
+    }
+
+    #[test]
+    fn test_generate_passphrase() {
+        let settings = PasswordGeneratorSettings {
+            mode: PasswordGeneratorMode::Passphrase,
+            word_count: 5,
+            word_separator: String::from("-"),
+            capitalise_words: true,
+            ..Default::default()
+        };
+        assert_eq!(passphrase_words().len(), 7776);
+        assert_eq!(
+            passphrase_words()
+                .into_iter()
+                .collect::<std::collections::HashSet<&str>>()
+                .len(),
+            7776
+        );
+        let passphrase = generate_password(&settings);
+        let words: Vec<&str> = passphrase.split('-').collect();
+        assert_eq!(words.len(), 5);
+        assert!(words.iter().all(|word| {
+            word.starts_with(|character: char| character.is_ascii_uppercase())
+                && passphrase_words().contains(&word.to_lowercase().as_str())
+        }));
+        assert!((entropy_bits(&settings) - 5.0 * 7776f64.log2()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use chrono::{Months, NaiveDateTime};
+use zxcvbn::zxcvbn;
+
+use super::page::Password;
+
+/// zxcvbn scores below this are reported as weak, 3 is "safely unguessable" for online attacks
+const MINIMUM_STRENGTH_SCORE: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthIssue {
+    /// The zxcvbn score of the password, from 0 to 4
+    WeakPassword(u8),
+    /// How many entries in total use the same password
+    ReusedPassword(usize),
+    /// When the entry was last modified
+    OldPassword(NaiveDateTime),
+    EmptyUsername,
+    InsecureUrl,
+}
+
+/// A problem found with an entry in the health report
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthFinding {
+    pub password_id: uuid::Uuid,
+    pub issue: HealthIssue,
+}
+
+/// The zxcvbn strength score of the password from 0 to 4, with the entry's other fields counted as guessable
+pub fn strength_score(password: &Password) -> u8 {
+    u8::from(
+        zxcvbn(
+            &password.password,
+            &[&password.title, &password.username, &password.url],
+        )
+        .score(),
+    )
+}
+
+/// Checks every entry for weak, reused and old passwords, empty usernames and insecure URLs.
+/// Entries without a password are only checked for the username and URL issues
+pub fn health_report(
+    passwords: &[Password],
+    max_password_age_months: u8,
+    now: NaiveDateTime,
+) -> Vec<HealthFinding> {
+    let mut password_use_counts: HashMap<&str, usize> = HashMap::new();
+    passwords
+        .iter()
+        .filter(|password| !password.password.is_empty())
+        .for_each(|password| {
+            *password_use_counts
+                .entry(password.password.as_str())
+                .or_default() += 1
+        });
+    let oldest_allowed_modification = now
+        .checked_sub_months(Months::new(max_password_age_months.into()))
+        .unwrap_or(now);
+
+    let mut findings = vec![];
+    passwords.iter().for_each(|password| {
+        let mut add_finding = |issue| {
+            findings.push(HealthFinding {
+                password_id: password.id,
+                issue,
+            })
+        };
+        if !password.password.is_empty() {
+            let score = strength_score(password);
+            if score < MINIMUM_STRENGTH_SCORE {
+                add_finding(HealthIssue::WeakPassword(score));
+            }
+            let use_count = password_use_counts[password.password.as_str()];
+            if use_count > 1 {
+                add_finding(HealthIssue::ReusedPassword(use_count));
+            }
+            if let Some(last_modified) = password
+                .last_modified
+                .filter(|last_modified| *last_modified < oldest_allowed_modification)
+            {
+                add_finding(HealthIssue::OldPassword(last_modified));
+            }
+        }
+        if password.username.trim().is_empty() {
+            add_finding(HealthIssue::EmptyUsername);
+        }
+        if password
+            .url
+            .trim()
+            .get(..7)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("http://"))
+        {
+            add_finding(HealthIssue::InsecureUrl);
+        }
+    });
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use zeroize::Zeroizing;
+
+    use super::*;
+
+    fn password(title: &str, username: &str, url: &str, password: &str) -> Password {
+        Password {
+            id: uuid::Uuid::new_v4(),
+            title: title.to_string(),
+            username: username.to_string(),
+            url: url.to_string(),
+            password: Zeroizing::new(password.to_string()),
+            otp: String::new(),
+            group_id: uuid::Uuid::nil(),
+            last_modified: None,
+        }
+    }
+
+    #[test]
+    fn test_health_report() {
+        let now = NaiveDate::from_ymd_opt(2025, 6, 1)
+            .expect("Valid date")
+            .and_hms_opt(12, 0, 0)
+            .expect("Valid time");
+        let strong_password = "tangerine-Oscillate-97-Quarry-Velvet";
+        let healthy = password("Bank", "me", "https://bank.example", "nQ8#vX2!pL6@rT9$");
+        let weak = password("Forum", "me", "https://forum.example", "password1");
+        let reused_first = password("Email", "me", "https://mail.example", strong_password);
+        let reused_second = password("Shop", "me", "https://shop.example", strong_password);
+        let no_username = password("Wifi", "", "", "Vr7!kQ2#mZ9@xW4$");
+        let insecure = password("Router", "admin", "HTTP://192.168.1.1", "Jh4%tB8^nC1&yD5*");
+        let old = Password {
+            last_modified: Some(now - chrono::Duration::days(400)),
+            ..password("Old", "me", "https://old.example", "Lp3(qS7)wE2_rU6+")
+        };
+        let recent = Password {
+            last_modified: Some(now - chrono::Duration::days(30)),
+            ..password("Recent", "me", "https://recent.example", "Gk5[zM1]hN8{bV4}")
+        };
+        let passwords = vec![
+            healthy.clone(),
+            weak.clone(),
+            reused_first.clone(),
+            reused_second.clone(),
+            no_username.clone(),
+            insecure.clone(),
+            old.clone(),
+            recent.clone(),
+        ];
+
+        let findings = health_report(&passwords, 12, now);
+        let issues_for = |entry: &Password| {
+            findings
+                .iter()
+                .filter(|finding| finding.password_id == entry.id)
+                .map(|finding| finding.issue.clone())
+                .collect::<Vec<HealthIssue>>()
+        };
+        assert!(issues_for(&healthy).is_empty());
+        assert!(matches!(
+            issues_for(&weak).as_slice(),
+            [HealthIssue::WeakPassword(score)] if *score < MINIMUM_STRENGTH_SCORE
+        ));
+        assert_eq!(
+            issues_for(&reused_first),
+            vec![HealthIssue::ReusedPassword(2)]
+        );
+        assert_eq!(
+            issues_for(&reused_second),
+            vec![HealthIssue::ReusedPassword(2)]
+        );
+        assert_eq!(issues_for(&no_username), vec![HealthIssue::EmptyUsername]);
+        assert_eq!(issues_for(&insecure), vec![HealthIssue::InsecureUrl]);
+        assert_eq!(
+            issues_for(&old),
+            vec![HealthIssue::OldPassword(
+                old.last_modified.expect("Set above")
+            )]
+        );
+        assert!(issues_for(&recent).is_empty());
+    }
+}
@@ -0,0 +1,672 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+
+use keepass::db::Value;
+use keepass::Database;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use zeroize::Zeroizing;
+
+use super::page::Password;
+use super::password_export::decrypt_bundle;
+use super::passwords_utils::{
+    create_group, find_entry_mut, find_group, passwords_from_database, update_entry,
+};
+
+/// The file in a 1Password 1PUX archive holding the exported vaults
+const ONE_PUX_DATA_FILE_NAME: &str = "export.data";
+
+/// An entry read from another password manager's export, shown in the import preview before being added
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedPassword {
+    /// The folder the entry was in, outermost first, which is mapped to nested groups
+    pub folder_path: Vec<String>,
+    pub title: String,
+    pub username: String,
+    pub url: String,
+    pub password: String,
+    /// The `otpauth://` URI for the entry's TOTP codes, "" if it doesn't have one
+    pub otp: String,
+    /// Only our encrypted bundles carry notes and tags, other exports leave them empty
+    pub notes: String,
+    pub tags: Vec<String>,
+    /// Whether an existing entry, or one earlier in the import, has the same URL, username and password
+    pub is_duplicate: bool,
+}
+
+impl ImportedPassword {
+    fn new(
+        folder_path: Vec<String>,
+        title: &str,
+        username: &str,
+        url: &str,
+        password: &str,
+        otp: &str,
+    ) -> Self {
+        let title = if title.trim().is_empty() {
+            // Firefox exports don't have titles, so the site's host is used instead
+            url::Url::parse(url.trim())
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_else(|| url.trim().to_string())
+        } else {
+            title.trim().to_string()
+        };
+        Self {
+            otp: otp_uri(otp.trim(), &title),
+            title,
+            folder_path: folder_path
+                .into_iter()
+                .map(|folder_name| folder_name.trim().to_string())
+                .filter(|folder_name| !folder_name.is_empty())
+                .collect(),
+            username: username.to_string(),
+            url: url.trim().to_string(),
+            password: password.to_string(),
+            notes: String::new(),
+            tags: Vec::new(),
+            is_duplicate: false,
+        }
+    }
+
+    /// Entries without a username, password or TOTP are secure notes, cards and the like, which aren't imported
+    fn has_login(&self) -> bool {
+        !self.username.is_empty() || !self.password.is_empty() || !self.otp.is_empty()
+    }
+}
+
+/// Exports store TOTP either as an `otpauth://` URI or as just the base32 secret, which is turned into a URI
+fn otp_uri(otp: &str, title: &str) -> String {
+    if otp.is_empty() || otp.starts_with("otpauth://") {
+        otp.to_string()
+    } else {
+        format!(
+            "otpauth://totp/{}?secret={}",
+            utf8_percent_encode(title, NON_ALPHANUMERIC),
+            otp.replace(' ', "").to_uppercase()
+        )
+    }
+}
+
+/// Splits a folder name into nested folders, Bitwarden separates them with / and LastPass with \
+fn split_folder_name(folder_name: &str) -> Vec<String> {
+    folder_name.split(['/', '\\']).map(str::to_string).collect()
+}
+
+#[derive(Deserialize)]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<BitwardenFolder>,
+    #[serde(default)]
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenFolder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+    #[serde(default)]
+    name: String,
+    folder_id: Option<String>,
+    login: Option<BitwardenLogin>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenLogin {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    #[serde(default)]
+    uris: Vec<BitwardenUri>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+}
+
+fn parse_bitwarden_json(file_content: &str) -> Result<Vec<ImportedPassword>, String> {
+    let export: BitwardenExport =
+        serde_json::from_str(file_content).map_err(|err| err.to_string())?;
+    if export.encrypted {
+        return Err(String::from(
+            "This Bitwarden export is encrypted, export it again as unencrypted JSON",
+        ));
+    }
+    let folder_names: HashMap<String, String> = export
+        .folders
+        .into_iter()
+        .map(|folder| (folder.id, folder.name))
+        .collect();
+    Ok(export
+        .items
+        .into_iter()
+        .filter_map(|item| {
+            let login = item.login?;
+            Some(ImportedPassword::new(
+                item.folder_id
+                    .and_then(|folder_id| folder_names.get(&folder_id))
+                    .map(|folder_name| split_folder_name(folder_name))
+                    .unwrap_or_default(),
+                &item.name,
+                &login.username.unwrap_or_default(),
+                &login
+                    .uris
+                    .into_iter()
+                    .find_map(|uri| uri.uri)
+                    .unwrap_or_default(),
+                &login.password.unwrap_or_default(),
+                &login.totp.unwrap_or_default(),
+            ))
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct OnePuxExport {
+    #[serde(default)]
+    accounts: Vec<OnePuxAccount>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxAccount {
+    #[serde(default)]
+    vaults: Vec<OnePuxVault>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxVault {
+    attrs: OnePuxVaultAttributes,
+    #[serde(default)]
+    items: Vec<OnePuxItem>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxVaultAttributes {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct OnePuxItem {
+    #[serde(default)]
+    state: String,
+    overview: OnePuxOverview,
+    details: OnePuxDetails,
+}
+
+#[derive(Deserialize)]
+struct OnePuxOverview {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OnePuxDetails {
+    #[serde(default)]
+    login_fields: Vec<OnePuxLoginField>,
+    #[serde(default)]
+    sections: Vec<OnePuxSection>,
+    password: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxLoginField {
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    designation: String,
+}
+
+#[derive(Deserialize)]
+struct OnePuxSection {
+    #[serde(default)]
+    fields: Vec<OnePuxSectionField>,
+}
+
+#[derive(Deserialize)]
+struct OnePuxSectionField {
+    value: serde_json::Value,
+}
+
+fn parse_1pux(file_bytes: &[u8]) -> Result<Vec<ImportedPassword>, String> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(file_bytes)).map_err(|err| err.to_string())?;
+    let mut export_data = String::new();
+    archive
+        .by_name(ONE_PUX_DATA_FILE_NAME)
+        .map_err(|err| err.to_string())?
+        .read_to_string(&mut export_data)
+        .map_err(|err| err.to_string())?;
+    let export: OnePuxExport = serde_json::from_str(&export_data).map_err(|err| err.to_string())?;
+    Ok(export
+        .accounts
+        .into_iter()
+        .flat_map(|account| account.vaults)
+        .flat_map(|vault| {
+            let vault_name = vault.attrs.name;
+            vault
+                .items
+                .into_iter()
+                .filter(|item| item.state != "archived")
+                .map(move |item| {
+                    let login_field = |designation: &str| {
+                        item.details
+                            .login_fields
+                            .iter()
+                            .find(|login_field| login_field.designation == designation)
+                            .map(|login_field| login_field.value.as_str())
+                            .unwrap_or_default()
+                    };
+                    let totp = item
+                        .details
+                        .sections
+                        .iter()
+                        .flat_map(|section| &section.fields)
+                        .find_map(|field| field.value.get("totp").and_then(|totp| totp.as_str()))
+                        .unwrap_or_default();
+                    ImportedPassword::new(
+                        vec![vault_name.clone()],
+                        &item.overview.title,
+                        login_field("username"),
+                        &item.overview.url,
+                        item.details
+                            .password
+                            .as_deref()
+                            .unwrap_or(login_field("password")),
+                        totp,
+                    )
+                })
+        })
+        .collect())
+}
+
+/// Reads Chromium, Firefox, LastPass, 1Password and Bitwarden CSV exports, finding the columns by their headers
+fn parse_csv(file_content: &str) -> Result<Vec<ImportedPassword>, String> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(file_content.as_bytes());
+    let headers: Vec<String> = csv_reader
+        .headers()
+        .map_err(|err| err.to_string())?
+        .iter()
+        .map(|header| header.trim().to_lowercase().replace(' ', "_"))
+        .collect();
+    let column_index = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.as_str()))
+    };
+    let title_column = column_index(&["name", "title"]);
+    let url_column = column_index(&["url", "website", "login_uri", "uri"]);
+    let username_column = column_index(&["username", "login_username", "user"]);
+    let password_column = column_index(&["password", "login_password"])
+        .ok_or(String::from("CSV file needs a password column"))?;
+    let otp_column = column_index(&["totp", "otpauth", "login_totp", "otp"]);
+    let folder_column = column_index(&["grouping", "folder", "group"]);
+
+    csv_reader
+        .records()
+        .map(|record_result| {
+            let record = record_result.map_err(|err| err.to_string())?;
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|column| record.get(column))
+                    .unwrap_or_default()
+            };
+            Ok(ImportedPassword::new(
+                split_folder_name(field(folder_column)),
+                field(title_column),
+                field(username_column),
+                field(url_column),
+                field(Some(password_column)),
+                field(otp_column),
+            ))
+        })
+        .collect()
+}
+
+/// Reads the entries from a Bitwarden JSON, 1Password 1PUX, or CSV export, leaving out ones without login details
+pub async fn read_import_file(file_path: PathBuf) -> Result<Vec<ImportedPassword>, String> {
+    let extension = file_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let imported_passwords = match extension.as_str() {
+        "json" => {
+            parse_bitwarden_json(&fs::read_to_string(&file_path).map_err(|err| err.to_string())?)?
+        }
+        "1pux" => parse_1pux(&fs::read(&file_path).map_err(|err| err.to_string())?)?,
+        "csv" => parse_csv(&fs::read_to_string(&file_path).map_err(|err| err.to_string())?)?,
+        _ => {
+            return Err(String::from(
+                "Unsupported file type, use .json, .1pux or .csv",
+            ))
+        }
+    };
+    Ok(imported_passwords
+        .into_iter()
+        .filter(ImportedPassword::has_login)
+        .collect())
+}
+
+/// Reads the entries from an encrypted bundle exported from another database
+pub async fn read_import_bundle(
+    file_path: PathBuf,
+    bundle_password: Zeroizing<String>,
+) -> Result<Vec<ImportedPassword>, String> {
+    let file_content = fs::read_to_string(&file_path).map_err(|err| err.to_string())?;
+    Ok(decrypt_bundle(&file_content, &bundle_password)?
+        .into_iter()
+        .map(|exported_password| ImportedPassword {
+            notes: exported_password.notes.clone(),
+            tags: exported_password.tags.clone(),
+            ..ImportedPassword::new(
+                split_folder_name(&exported_password.group),
+                &exported_password.title,
+                &exported_password.username,
+                &exported_password.url,
+                &exported_password.password,
+                &exported_password.otp,
+            )
+        })
+        .filter(ImportedPassword::has_login)
+        .collect())
+}
+
+/// What makes two entries the same login, the URL is compared without case or a trailing /
+fn duplicate_key<'a>(
+    url: &str,
+    username: &'a str,
+    password: &'a str,
+) -> (String, &'a str, &'a str) {
+    (
+        url.trim().trim_end_matches('/').to_lowercase(),
+        username,
+        password,
+    )
+}
+
+/// Flags the imported entries that match an existing entry, or one earlier in the import
+pub fn mark_duplicates(
+    imported_passwords: &mut [ImportedPassword],
+    existing_passwords: &[Password],
+) {
+    let mut seen_logins: HashSet<(String, String, String)> = existing_passwords
+        .iter()
+        .map(|password| {
+            let (url, username, password) =
+                duplicate_key(&password.url, &password.username, &password.password);
+            (url, username.to_string(), password.to_string())
+        })
+        .collect();
+    imported_passwords.iter_mut().for_each(|imported_password| {
+        let (url, username, password) = duplicate_key(
+            &imported_password.url,
+            &imported_password.username,
+            &imported_password.password,
+        );
+        imported_password.is_duplicate =
+            !seen_logins.insert((url, username.to_string(), password.to_string()));
+    });
+}
+
+/// Adds the imported entries that aren't duplicates into the group, with their folders as groups inside it.
+/// Existing groups with the same name are reused. Returns how many entries were added
+pub fn add_imported_passwords(
+    database: &mut Database,
+    imported_passwords: &[ImportedPassword],
+    base_group_id: uuid::Uuid,
+) -> usize {
+    let (existing_root_group, _) = passwords_from_database(database);
+    let mut folder_group_ids: HashMap<Vec<String>, uuid::Uuid> = HashMap::new();
+    let mut added_count = 0;
+    for imported_password in imported_passwords
+        .iter()
+        .filter(|imported_password| !imported_password.is_duplicate)
+    {
+        let mut group_id = base_group_id;
+        for depth in 1..=imported_password.folder_path.len() {
+            let folder_path = &imported_password.folder_path[..depth];
+            group_id = if let Some(folder_group_id) = folder_group_ids.get(folder_path) {
+                *folder_group_id
+            } else {
+                let folder_name = &folder_path[depth - 1];
+                let folder_group_id = find_group(&existing_root_group, group_id)
+                    .and_then(|group| {
+                        group
+                            .child_groups
+                            .iter()
+                            .find(|child_group| &child_group.name == folder_name)
+                    })
+                    .map(|child_group| child_group.id)
+                    .or_else(|| create_group(database, group_id, folder_name))
+                    .unwrap_or(group_id);
+                folder_group_ids.insert(folder_path.to_vec(), folder_group_id);
+                folder_group_id
+            };
+        }
+        let entry_id = uuid::Uuid::new_v4();
+        update_entry(
+            database,
+            &Password {
+                id: entry_id,
+                title: imported_password.title.clone(),
+                username: imported_password.username.clone(),
+                url: imported_password.url.clone(),
+                password: Zeroizing::new(imported_password.password.clone()),
+                otp: imported_password.otp.clone(),
+                group_id,
+                last_modified: None,
+            },
+        );
+        if let Some(entry) = find_entry_mut(&mut database.root, entry_id) {
+            if !imported_password.notes.is_empty() {
+                entry.fields.insert(
+                    String::from("Notes"),
+                    Value::Unprotected(imported_password.notes.clone()),
+                );
+            }
+            entry.tags = imported_password.tags.clone();
+        }
+        added_count += 1;
+    }
+    added_count
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::super::passwords_utils::new_database;
+    use super::*;
+
+    fn login(imported_password: &ImportedPassword) -> (&str, &str, &str, &str) {
+        (
+            imported_password.title.as_str(),
+            imported_password.username.as_str(),
+            imported_password.url.as_str(),
+            imported_password.password.as_str(),
+        )
+    }
+
+    #[test]
+    fn test_parse_exports() {
+        let bitwarden = parse_bitwarden_json(
+            r#"{
+                "encrypted": false,
+                "folders": [{"id": "f1", "name": "Work/Servers"}],
+                "items": [
+                    {"type": 1, "name": "Router", "folderId": "f1", "login": {
+                        "username": "admin", "password": "hunter2", "totp": "JBSW Y3DP",
+                        "uris": [{"match": null, "uri": "https://192.168.1.1"}]
+                    }},
+                    {"type": 2, "name": "A note", "folderId": null, "secureNote": {"type": 0}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(bitwarden.len(), 1);
+        assert_eq!(
+            login(&bitwarden[0]),
+            ("Router", "admin", "https://192.168.1.1", "hunter2")
+        );
+        assert_eq!(bitwarden[0].folder_path, vec!["Work", "Servers"]);
+        assert_eq!(bitwarden[0].otp, "otpauth://totp/Router?secret=JBSWY3DP");
+        assert!(parse_bitwarden_json(r#"{"encrypted": true, "items": []}"#).is_err());
+
+        let lastpass = parse_csv(
+            "url,username,password,totp,extra,name,grouping,fav\n\
+             https://mail.example,me,pass1,,,Email,Personal\\Mail,0\n\
+             http://sn,,,,Secret note,Note,,0\n",
+        )
+        .unwrap();
+        assert_eq!(
+            login(&lastpass[0]),
+            ("Email", "me", "https://mail.example", "pass1")
+        );
+        assert_eq!(lastpass[0].folder_path, vec!["Personal", "Mail"]);
+        assert!(!lastpass[1].has_login());
+
+        let firefox = parse_csv(
+            "\"url\",\"username\",\"password\",\"httpRealm\",\"formActionOrigin\",\"guid\",\"timeCreated\",\"timeLastUsed\",\"timePasswordChanged\"\n\
+             \"https://forum.example.org\",\"bob\",\"pass2\",,\"\",\"{1}\",\"1\",\"1\",\"1\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            login(&firefox[0]),
+            (
+                "forum.example.org",
+                "bob",
+                "https://forum.example.org",
+                "pass2"
+            )
+        );
+        assert!(firefox[0].folder_path.is_empty());
+
+        let chromium =
+            parse_csv("name,url,username,password,note\nShop,https://shop.example/,sam,pass3,\n")
+                .unwrap();
+        assert_eq!(
+            login(&chromium[0]),
+            ("Shop", "sam", "https://shop.example/", "pass3")
+        );
+        assert!(parse_csv("name,url\nShop,https://shop.example/\n").is_err());
+
+        let mut one_pux = Cursor::new(vec![]);
+        let mut zip_writer = zip::ZipWriter::new(&mut one_pux);
+        zip_writer
+            .start_file(
+                ONE_PUX_DATA_FILE_NAME,
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        zip_writer
+            .write_all(
+                br#"{"accounts": [{"attrs": {}, "vaults": [{"attrs": {"name": "Private"}, "items": [
+                    {"state": "active", "overview": {"title": "Bank", "url": "https://bank.example"},
+                     "details": {"loginFields": [
+                        {"value": "ann", "designation": "username"},
+                        {"value": "pass4", "designation": "password"}
+                     ], "sections": [{"fields": [{"value": {"totp": "otpauth://totp/Bank?secret=ABC"}}]}]}},
+                    {"state": "archived", "overview": {"title": "Old"}, "details": {}}
+                ]}]}]}"#,
+            )
+            .unwrap();
+        zip_writer.finish().unwrap();
+        let one_pux = parse_1pux(one_pux.get_ref()).unwrap();
+        assert_eq!(one_pux.len(), 1);
+        assert_eq!(
+            login(&one_pux[0]),
+            ("Bank", "ann", "https://bank.example", "pass4")
+        );
+        assert_eq!(one_pux[0].folder_path, vec!["Private"]);
+        assert_eq!(one_pux[0].otp, "otpauth://totp/Bank?secret=ABC");
+    }
+
+    #[test]
+    fn test_import_into_database() {
+        let mut database = new_database();
+        let (root_group, _) = passwords_from_database(&database);
+        let work_group_id = create_group(&mut database, root_group.id, "Work").unwrap();
+        let existing = Password {
+            id: uuid::Uuid::new_v4(),
+            title: String::from("Email"),
+            username: String::from("me"),
+            url: String::from("https://mail.example/"),
+            password: Zeroizing::new(String::from("pass1")),
+            otp: String::new(),
+            group_id: work_group_id,
+            last_modified: None,
+        };
+        update_entry(&mut database, &existing);
+
+        let mut imported_passwords = vec![
+            ImportedPassword::new(vec![], "Email", "me", "HTTPS://mail.example", "pass1", ""),
+            ImportedPassword {
+                notes: String::from("Split tunnel"),
+                tags: vec![String::from("shared")],
+                ..ImportedPassword::new(vec![String::from("Work")], "VPN", "me", "", "pass5", "")
+            },
+            ImportedPassword::new(
+                vec![String::from("Work"), String::from("Servers")],
+                "Router",
+                "admin",
+                "",
+                "pass6",
+                "",
+            ),
+            ImportedPassword::new(vec![String::from("Work")], "VPN", "me", "", "pass5", ""),
+        ];
+        let (_, existing_passwords) = passwords_from_database(&database);
+        mark_duplicates(&mut imported_passwords, &existing_passwords);
+        assert_eq!(
+            imported_passwords
+                .iter()
+                .map(|imported_password| imported_password.is_duplicate)
+                .collect::<Vec<bool>>(),
+            vec![true, false, false, true]
+        );
+
+        assert_eq!(
+            add_imported_passwords(&mut database, &imported_passwords, root_group.id),
+            2
+        );
+        let (root_group, passwords) = passwords_from_database(&database);
+        assert_eq!(passwords.len(), 3);
+        let work_group = find_group(&root_group, work_group_id).unwrap();
+        assert_eq!(root_group.child_groups.len(), 1);
+        assert_eq!(work_group.child_groups.len(), 1);
+        let servers_group = &work_group.child_groups[0];
+        assert_eq!(servers_group.name, "Servers");
+        let group_of = |title: &str| {
+            passwords
+                .iter()
+                .find(|password| password.title == title)
+                .map(|password| password.group_id)
+        };
+        assert_eq!(group_of("VPN"), Some(work_group_id));
+        assert_eq!(group_of("Router"), Some(servers_group.id));
+
+        let vpn_id = passwords
+            .iter()
+            .find(|password| password.title == "VPN")
+            .unwrap()
+            .id;
+        let vpn_entry = find_entry_mut(&mut database.root, vpn_id).unwrap();
+        assert_eq!(vpn_entry.get("Notes"), Some("Split tunnel"));
+        assert_eq!(vpn_entry.tags, vec!["shared"]);
+    }
+}
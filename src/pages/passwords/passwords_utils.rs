@@ -1,16 +1,23 @@
 use keepass::{
-    db::{Entry, NodeRef, Value},
+    db::{Entry, Group, History, Node, Value},
     Database, DatabaseKey,
 };
-use std::{fs::File, path::PathBuf};
+use std::{
+    fs::File,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use totp_rs::{Algorithm, Secret, TOTP};
+use zeroize::Zeroizing;
 
-use super::page::Password;
+use super::page::{Password, PasswordGroup};
 
+/// Opens the database, which is then kept and edited in place so that nothing we don't show is lost on save
 pub async fn get_passwords(
     keepass_file_path: PathBuf,
-    master_password_attempt: Option<String>,
+    master_password_attempt: Option<Zeroizing<String>>,
     keyfile_option: Option<PathBuf>,
-) -> Result<Vec<Password>, String> {
+) -> Result<Database, String> {
     let mut file = File::open(keepass_file_path).map_err(|err| err.to_string())?;
     let mut key = DatabaseKey::new();
     if let Some(master_password) = master_password_attempt {
@@ -22,53 +29,364 @@ pub async fn get_passwords(
             .map_err(|err| err.to_string())?;
     }
 
-    Ok(Database::open(&mut file, key)
+    Database::open(&mut file, key).map_err(|err| err.to_string())
+}
+
+/// Gets the group tree and every entry in the database, for display and editing
+pub fn passwords_from_database(database: &Database) -> (PasswordGroup, Vec<Password>) {
+    let mut passwords = vec![];
+    let root_group = password_group_from_keepass(&database.root, &mut passwords);
+    (root_group, passwords)
+}
+
+/// Converts a KeePass group and everything nested in it, adding the entries found to `passwords`
+fn password_group_from_keepass(group: &Group, passwords: &mut Vec<Password>) -> PasswordGroup {
+    let mut child_groups = vec![];
+    group.children.iter().for_each(|node| match node {
+        Node::Group(child_group) => {
+            child_groups.push(password_group_from_keepass(child_group, passwords))
+        }
+        Node::Entry(entry) => passwords.push(Password {
+            id: entry.uuid,
+            title: String::from(entry.get_title().unwrap_or("")),
+            username: String::from(entry.get_username().unwrap_or("")),
+            url: String::from(entry.get_url().unwrap_or("")),
+            password: Zeroizing::new(String::from(entry.get_password().unwrap_or(""))),
+            otp: String::from(entry.get(OTP_FIELD).unwrap_or("")),
+            group_id: group.uuid,
+            last_modified: entry.times.get_last_modification().copied(),
+        }),
+    });
+    PasswordGroup {
+        id: group.uuid,
+        name: group.name.clone(),
+        child_groups,
+    }
+}
+
+/// The entry field KeePassXC stores the `otpauth://` URI for an entry's TOTP in
+pub const OTP_FIELD: &str = "otp";
+
+/// A TOTP code along with how long it's valid for
+#[derive(Debug, Clone, PartialEq)]
+pub struct TotpCode {
+    pub code: String,
+    pub seconds_remaining: u64,
+    /// How many seconds each code is valid for
+    pub period: u64,
+}
+
+/// Parses an `otpauth://totp/...` URI, as stored by KeePassXC and shown in authenticator setup QR codes
+pub fn parse_otp_uri(otp_uri: &str) -> Result<TOTP, String> {
+    let otp_url = url::Url::parse(otp_uri.trim()).map_err(|err| err.to_string())?;
+    if otp_url.scheme() != "otpauth" || otp_url.host_str() != Some("totp") {
+        return Err(String::from("Not an otpauth://totp/ URI"));
+    }
+    let mut secret = None;
+    let mut digits = 6;
+    let mut period = 30;
+    let mut algorithm = Algorithm::SHA1;
+    for (key, value) in otp_url.query_pairs() {
+        match key.to_lowercase().as_str() {
+            "secret" => {
+                secret = Some(
+                    Secret::Encoded(value.to_uppercase().replace(' ', ""))
+                        .to_bytes()
+                        .map_err(|err| format!("Invalid secret: {err:?}"))?,
+                )
+            }
+            "digits" => digits = value.parse().map_err(|_| "Invalid number of digits")?,
+            "period" => period = value.parse().map_err(|_| "Invalid period")?,
+            "algorithm" => {
+                algorithm = match value.to_uppercase().as_str() {
+                    "SHA1" => Algorithm::SHA1,
+                    "SHA256" => Algorithm::SHA256,
+                    "SHA512" => Algorithm::SHA512,
+                    _ => return Err(format!("Unsupported algorithm {value}")),
+                }
+            }
+            _ => (),
+        }
+    }
+    if !(6..=8).contains(&digits) || period == 0 {
+        return Err(String::from("Invalid number of digits or period"));
+    }
+    let secret = secret.ok_or("Missing secret")?;
+    Ok(TOTP::new_unchecked(algorithm, digits, 1, period, secret))
+}
+
+/// The code for the TOTP at the given Unix timestamp
+pub fn totp_code_at(totp: &TOTP, timestamp: u64) -> TotpCode {
+    TotpCode {
+        code: totp.generate(timestamp),
+        seconds_remaining: totp.step - timestamp % totp.step,
+        period: totp.step,
+    }
+}
+
+/// The current code for the TOTP URI, or None if it isn't valid
+pub fn current_totp_code(otp_uri: &str) -> Option<TotpCode> {
+    let totp = parse_otp_uri(otp_uri).ok()?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(totp_code_at(&totp, timestamp))
+}
+
+/// Finds an `otpauth://` URI in a QR code in the image, such as a screenshot of a site's 2FA setup page
+pub async fn otp_uri_from_qr_code_image(image_path: PathBuf) -> Result<String, String> {
+    let image = image::open(image_path)
         .map_err(|err| err.to_string())?
-        .root
-        .iter()
-        .filter_map(|node| {
-            if let NodeRef::Entry(entry) = node {
-                Some(Password {
-                    id: entry.uuid,
-                    title: String::from(entry.get_title().unwrap_or("")),
-                    username: String::from(entry.get_username().unwrap_or("")),
-                    url: String::from(entry.get_url().unwrap_or("")),
-                    password: String::from(entry.get_password().unwrap_or("")),
-                })
+        .to_luma8();
+    let mut prepared_image = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32).0[0],
+    );
+    prepared_image
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| grid.decode().ok())
+        .map(|(_meta_data, content)| content)
+        .find(|content| content.starts_with("otpauth://"))
+        .ok_or_else(|| String::from("No TOTP QR code found in image"))
+}
+
+/// Finds a KeePass group anywhere in the tree by its ID
+pub fn find_keepass_group_mut(group: &mut Group, id: uuid::Uuid) -> Option<&mut Group> {
+    if group.uuid == id {
+        return Some(group);
+    }
+    group.children.iter_mut().find_map(|node| match node {
+        Node::Group(child_group) => find_keepass_group_mut(child_group, id),
+        Node::Entry(_) => None,
+    })
+}
+
+/// Finds the ID of the KeePass group that the group or entry with the given ID is directly inside
+fn keepass_parent_group_id(group: &Group, id: uuid::Uuid) -> Option<uuid::Uuid> {
+    if group.children.iter().any(|node| match node {
+        Node::Group(child_group) => child_group.uuid == id,
+        Node::Entry(entry) => entry.uuid == id,
+    }) {
+        return Some(group.uuid);
+    }
+    group.children.iter().find_map(|node| match node {
+        Node::Group(child_group) => keepass_parent_group_id(child_group, id),
+        Node::Entry(_) => None,
+    })
+}
+
+/// Removes the group or entry with the given ID from wherever it is in the tree
+fn take_node(group: &mut Group, id: uuid::Uuid) -> Option<Node> {
+    if let Some(node_index) = group.children.iter().position(|node| match node {
+        Node::Group(child_group) => child_group.uuid == id,
+        Node::Entry(entry) => entry.uuid == id,
+    }) {
+        return Some(group.children.remove(node_index));
+    }
+    group.children.iter_mut().find_map(|node| match node {
+        Node::Group(child_group) => take_node(child_group, id),
+        Node::Entry(_) => None,
+    })
+}
+
+pub fn find_entry_mut(group: &mut Group, id: uuid::Uuid) -> Option<&mut Entry> {
+    group.children.iter_mut().find_map(|node| match node {
+        Node::Group(child_group) => find_entry_mut(child_group, id),
+        Node::Entry(entry) => (entry.uuid == id).then_some(entry),
+    })
+}
+
+/// Whether the fields shown in the entry edit view differ from the entry's
+fn entry_differs_from_password(entry: &Entry, password: &Password) -> bool {
+    entry.get_title().unwrap_or("") != password.title
+        || entry.get_username().unwrap_or("") != password.username
+        || entry.get_url().unwrap_or("") != password.url
+        || entry.get_password().unwrap_or("") != *password.password
+        || entry.get(OTP_FIELD).unwrap_or("") != password.otp
+}
+
+/// Sets the fields shown in the entry edit view, keeping whether each field was protected.
+/// The TOTP field is removed rather than left empty when it's cleared
+fn set_entry_fields(entry: &mut Entry, password: &Password) {
+    if password.otp.is_empty() {
+        entry.fields.remove(OTP_FIELD);
+    }
+    [
+        ("Title", &password.title, false),
+        ("UserName", &password.username, false),
+        ("URL", &password.url, false),
+        ("Password", &*password.password, true),
+        (OTP_FIELD, &password.otp, true),
+    ]
+    .into_iter()
+    .filter(|(key, value, _)| *key != OTP_FIELD || !value.is_empty())
+    .for_each(|(key, value, is_protected_by_default)| {
+        let is_protected = match entry.fields.get(key) {
+            Some(Value::Protected(_)) => true,
+            Some(_) => false,
+            None => is_protected_by_default,
+        };
+        entry.fields.insert(
+            key.to_string(),
+            if is_protected {
+                Value::Protected(value.as_bytes().into())
             } else {
-                None
+                Value::Unprotected(value.clone())
+            },
+        );
+    });
+}
+
+/// Adds or updates the entry in the database and moves it into the group it's in.
+/// Only the fields we edit are touched, and the previous version is kept in the entry's history
+pub fn update_entry(database: &mut Database, password: &Password) {
+    if let Some(entry) = find_entry_mut(&mut database.root, password.id) {
+        if entry_differs_from_password(entry, password) {
+            let mut previous_version = entry.clone();
+            previous_version.history = None;
+            entry
+                .history
+                .get_or_insert_with(History::default)
+                .add_entry(previous_version);
+            set_entry_fields(entry, password);
+            entry
+                .times
+                .set_last_modification(chrono::Utc::now().naive_utc());
+        }
+        if keepass_parent_group_id(&database.root, password.id) != Some(password.group_id)
+            && find_keepass_group_mut(&mut database.root, password.group_id).is_some()
+        {
+            if let Some(entry_node) = take_node(&mut database.root, password.id) {
+                if let Some(group) = find_keepass_group_mut(&mut database.root, password.group_id) {
+                    group.children.push(entry_node);
+                }
             }
-        })
-        .collect::<Vec<Password>>())
+        }
+    } else {
+        let mut entry = Entry::new();
+        entry.uuid = password.id;
+        set_entry_fields(&mut entry, password);
+        if let Some(group) = find_keepass_group_mut(&mut database.root, password.group_id) {
+            group.add_child(entry);
+        } else {
+            database.root.add_child(entry);
+        }
+    }
+}
+
+/// Removes the entry from the database, returning false if it wasn't found
+pub fn delete_entry(database: &mut Database, id: uuid::Uuid) -> bool {
+    find_entry_mut(&mut database.root, id).is_some() && take_node(&mut database.root, id).is_some()
+}
+
+/// Moves the entry, with its history and the fields we don't show, into the root group of another database,
+/// returning false if it wasn't found
+pub fn move_entry_to_database(from: &mut Database, to: &mut Database, id: uuid::Uuid) -> bool {
+    if find_entry_mut(&mut from.root, id).is_none() {
+        return false;
+    }
+    match take_node(&mut from.root, id) {
+        Some(node) => {
+            to.root.add_child(node);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Adds a new empty group inside the group with the given ID, returning the new group's ID
+pub fn create_group(
+    database: &mut Database,
+    parent_group_id: uuid::Uuid,
+    name: &str,
+) -> Option<uuid::Uuid> {
+    let parent_group = find_keepass_group_mut(&mut database.root, parent_group_id)?;
+    let group = Group::new(name);
+    let group_id = group.uuid;
+    parent_group.add_child(group);
+    Some(group_id)
+}
+
+/// Renames the group, returning false if it wasn't found
+pub fn rename_group(database: &mut Database, id: uuid::Uuid, name: &str) -> bool {
+    if let Some(group) = find_keepass_group_mut(&mut database.root, id) {
+        group.name = name.to_string();
+        true
+    } else {
+        false
+    }
+}
+
+/// Removes a group from the tree, moving the groups and entries inside it up into its parent group
+/// where it was. Returns false if the group wasn't found or is the root group
+pub fn delete_group(database: &mut Database, id: uuid::Uuid) -> bool {
+    let Some(parent_id) = keepass_parent_group_id(&database.root, id) else {
+        return false;
+    };
+    let Some(parent_group) = find_keepass_group_mut(&mut database.root, parent_id) else {
+        return false;
+    };
+    let Some(group_index) = parent_group
+        .children
+        .iter()
+        .position(|node| matches!(node, Node::Group(child_group) if child_group.uuid == id))
+    else {
+        return false;
+    };
+    if let Node::Group(removed_group) = parent_group.children.remove(group_index) {
+        parent_group
+            .children
+            .splice(group_index..group_index, removed_group.children);
+    }
+    true
+}
+
+/// Finds a group anywhere in the tree by its ID
+pub fn find_group(group: &PasswordGroup, id: uuid::Uuid) -> Option<&PasswordGroup> {
+    if group.id == id {
+        return Some(group);
+    }
+    group
+        .child_groups
+        .iter()
+        .find_map(|child_group| find_group(child_group, id))
+}
+
+/// Finds the ID of the group that the group with the given ID is directly inside, if it isn't the root
+pub fn parent_group_id(group: &PasswordGroup, id: uuid::Uuid) -> Option<uuid::Uuid> {
+    if group
+        .child_groups
+        .iter()
+        .any(|child_group| child_group.id == id)
+    {
+        return Some(group.id);
+    }
+    group
+        .child_groups
+        .iter()
+        .find_map(|child_group| parent_group_id(child_group, id))
+}
+
+/// Lists every group in the tree with its full path, e.g. "Root / Work / Email", in tree order
+pub fn group_paths(group: &PasswordGroup) -> Vec<(uuid::Uuid, String)> {
+    let mut paths = vec![(group.id, group.name.clone())];
+    group.child_groups.iter().for_each(|child_group| {
+        paths.extend(
+            group_paths(child_group)
+                .into_iter()
+                .map(|(id, path)| (id, format!("{} / {path}", group.name))),
+        )
+    });
+    paths
 }
 
 pub async fn save_database(
     database_path: Option<PathBuf>,
-    master_password_option: Option<String>,
+    master_password_option: Option<Zeroizing<String>>,
     keyfile_option: Option<PathBuf>,
-    passwords: Vec<Password>,
+    db: Database,
 ) -> (bool, String) {
     if let Some(database_path) = database_path {
-        let mut db = Database::new(Default::default());
-        db.meta.database_name = Some("Passwords Database".to_string());
-        passwords.into_iter().for_each(|password| {
-            let mut entry = Entry::new();
-            entry
-                .fields
-                .insert("Title".to_string(), Value::Unprotected(password.title));
-            entry
-                .fields
-                .insert("URL".to_string(), Value::Unprotected(password.url));
-            entry.fields.insert(
-                "UserName".to_string(),
-                Value::Unprotected(password.username),
-            );
-            entry.fields.insert(
-                "Password".to_string(),
-                Value::Protected(password.password.as_bytes().into()),
-            );
-            db.root.add_child(entry);
-        });
         let mut key = DatabaseKey::new();
         if let Some(master_password) = master_password_option {
             key = key.with_password(&master_password);
@@ -102,3 +420,273 @@ pub async fn save_database(
         (false, String::from("Database path was None"))
     }
 }
+
+/// A new empty database, as created from the passwords page
+pub fn new_database() -> Database {
+    let mut database = Database::new(Default::default());
+    database.meta.database_name = Some("Passwords Database".to_string());
+    database
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database with an entry that has everything we don't show, inside a nested group
+    fn test_database() -> (Database, uuid::Uuid, uuid::Uuid) {
+        let mut database = new_database();
+        database.meta.description = Some(String::from("Shared team vault"));
+        let mut email_group = Group::new("Email");
+        email_group.notes = Some(String::from("Email accounts"));
+        email_group.add_child(Group::new("Work"));
+
+        let mut entry = Entry::new();
+        entry.fields.insert(
+            "Title".to_string(),
+            Value::Unprotected(String::from("Work Email")),
+        );
+        entry.fields.insert(
+            "UserName".to_string(),
+            Value::Unprotected(String::from("user")),
+        );
+        entry.fields.insert(
+            "Password".to_string(),
+            Value::Protected("hunter2".as_bytes().into()),
+        );
+        entry.fields.insert(
+            "Notes".to_string(),
+            Value::Unprotected(String::from("Recovery codes are in the safe")),
+        );
+        entry.fields.insert(
+            "otp".to_string(),
+            Value::Protected(
+                "otpauth://totp/user?secret=JBSWY3DPEHPK3PXP"
+                    .as_bytes()
+                    .into(),
+            ),
+        );
+        entry.tags = vec![String::from("work"), String::from("email")];
+        entry.icon_id = Some(19);
+        let mut previous_version = entry.clone();
+        previous_version.fields.insert(
+            "Password".to_string(),
+            Value::Protected("hunter1".as_bytes().into()),
+        );
+        entry
+            .history
+            .get_or_insert_with(History::default)
+            .add_entry(previous_version);
+        let entry_id = entry.uuid;
+        email_group.add_child(entry);
+
+        let email_group_id = email_group.uuid;
+        database.root.add_child(email_group);
+        (database, email_group_id, entry_id)
+    }
+
+    fn password_for(database: &Database, entry_id: uuid::Uuid) -> Password {
+        passwords_from_database(database)
+            .1
+            .into_iter()
+            .find(|password| password.id == entry_id)
+            .expect("Entry should be in the database")
+    }
+
+    #[test]
+    fn test_group_tree() {
+        let (mut database, email_group_id, entry_id) = test_database();
+        let (root_group, passwords) = passwords_from_database(&database);
+        assert_eq!(
+            group_paths(&root_group)
+                .into_iter()
+                .map(|(_, path)| path)
+                .collect::<Vec<String>>(),
+            vec!["Root", "Root / Email", "Root / Email / Work"]
+        );
+        assert_eq!(passwords.len(), 1);
+        assert_eq!(passwords[0].group_id, email_group_id);
+
+        let root_group_id = root_group.id;
+        assert!(!delete_group(&mut database, root_group_id));
+        assert!(delete_group(&mut database, email_group_id));
+        let (root_group, passwords) = passwords_from_database(&database);
+        assert_eq!(
+            group_paths(&root_group)
+                .into_iter()
+                .map(|(_, path)| path)
+                .collect::<Vec<String>>(),
+            vec!["Root", "Root / Work"]
+        );
+        assert_eq!(passwords[0].id, entry_id);
+        assert_eq!(passwords[0].group_id, root_group_id);
+    }
+
+    #[test]
+    fn test_editing_is_lossless() {
+        let (mut database, email_group_id, entry_id) = test_database();
+        let original_database = database.clone();
+
+        // Saving an entry without changes leaves the database exactly as it was
+        let unchanged_password = password_for(&database, entry_id);
+        update_entry(&mut database, &unchanged_password);
+        assert_eq!(database, original_database);
+
+        let work_group_id = passwords_from_database(&database).0.child_groups[0].child_groups[0].id;
+        let mut password = password_for(&database, entry_id);
+        password.password = Zeroizing::new(String::from("correct horse battery staple"));
+        password.group_id = work_group_id;
+        update_entry(&mut database, &password);
+
+        let mut edited_entry = database.root.clone();
+        let edited_entry = find_entry_mut(&mut edited_entry, entry_id)
+            .expect("Entry should still be in the database")
+            .clone();
+        let mut original_entry = original_database.root.clone();
+        let original_entry = find_entry_mut(&mut original_entry, entry_id)
+            .expect("Entry should be in the database")
+            .clone();
+        assert_eq!(
+            edited_entry.get_password(),
+            Some("correct horse battery staple")
+        );
+        assert!(matches!(
+            edited_entry.fields.get("Password"),
+            Some(Value::Protected(_))
+        ));
+        ["Title", "UserName", "Notes", "otp"]
+            .into_iter()
+            .for_each(|key| {
+                assert_eq!(edited_entry.fields.get(key), original_entry.fields.get(key))
+            });
+        assert_eq!(edited_entry.tags, original_entry.tags);
+        assert_eq!(edited_entry.icon_id, original_entry.icon_id);
+        let history_entries = edited_entry
+            .history
+            .as_ref()
+            .expect("Entry should have a history")
+            .get_entries();
+        assert_eq!(history_entries.len(), 2);
+        assert!(history_entries
+            .iter()
+            .any(|history_entry| history_entry.get_password() == Some("hunter2")));
+        assert_eq!(
+            keepass_parent_group_id(&database.root, entry_id),
+            Some(work_group_id)
+        );
+        assert_eq!(database.meta, original_database.meta);
+
+        // Everything else in the email group is untouched
+        let mut email_group = database.root.clone();
+        let mut original_email_group = original_database.root.clone();
+        let email_group = find_keepass_group_mut(&mut email_group, email_group_id)
+            .expect("Group should still be in the database");
+        let original_email_group =
+            find_keepass_group_mut(&mut original_email_group, email_group_id)
+                .expect("Group should be in the database");
+        assert_eq!(email_group.notes, original_email_group.notes);
+        assert_eq!(email_group.times, original_email_group.times);
+    }
+
+    #[test]
+    fn test_totp() {
+        // RFC 6238 test vectors, the secret is "12345678901234567890" in base32
+        let totp = parse_otp_uri(
+            "otpauth://totp/Example:user?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&digits=8&issuer=Example",
+        )
+        .expect("URI should parse");
+        assert_eq!(totp_code_at(&totp, 59).code, "94287082");
+        assert_eq!(totp_code_at(&totp, 1111111109).code, "07081804");
+        assert_eq!(totp_code_at(&totp, 1111111109).seconds_remaining, 1);
+        assert_eq!(totp_code_at(&totp, 1234567890).code, "89005924");
+
+        let totp = parse_otp_uri("otpauth://totp/user?secret=JBSWY3DPEHPK3PXP&period=60")
+            .expect("URI should parse");
+        assert_eq!(totp_code_at(&totp, 60).code.len(), 6);
+        assert_eq!(totp_code_at(&totp, 60).seconds_remaining, 60);
+
+        assert!(parse_otp_uri("otpauth://hotp/user?secret=JBSWY3DPEHPK3PXP").is_err());
+        assert!(parse_otp_uri("otpauth://totp/user").is_err());
+        assert!(parse_otp_uri("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_move_entry_to_database() {
+        let (mut database, email_group_id, entry_id) = test_database();
+        let mut root = database.root.clone();
+        let entry = find_entry_mut(&mut root, entry_id)
+            .expect("Entry should be in the database")
+            .clone();
+        let mut team_database = new_database();
+
+        // Groups can't be moved, only entries
+        assert!(!move_entry_to_database(
+            &mut database,
+            &mut team_database,
+            email_group_id
+        ));
+        assert!(move_entry_to_database(
+            &mut database,
+            &mut team_database,
+            entry_id
+        ));
+        assert!(passwords_from_database(&database).1.is_empty());
+        assert_eq!(
+            password_for(&team_database, entry_id).group_id,
+            team_database.root.uuid
+        );
+        assert_eq!(
+            find_entry_mut(&mut team_database.root, entry_id).cloned(),
+            Some(entry)
+        );
+        assert!(!move_entry_to_database(
+            &mut database,
+            &mut team_database,
+            entry_id
+        ));
+    }
+
+    #[test]
+    fn test_save_and_reopen() {
+        let (mut database, _, entry_id) = test_database();
+        let mut password = password_for(&database, entry_id);
+        password.username = String::from("new-user");
+        update_entry(&mut database, &password);
+
+        let mut saved_database = vec![];
+        database
+            .save(
+                &mut saved_database,
+                DatabaseKey::new().with_password("master password"),
+            )
+            .expect("Database should save");
+        let reopened_database = Database::open(
+            &mut saved_database.as_slice(),
+            DatabaseKey::new().with_password("master password"),
+        )
+        .expect("Database should reopen");
+
+        assert_eq!(
+            reopened_database.meta.description,
+            database.meta.description
+        );
+        assert_eq!(
+            passwords_from_database(&reopened_database),
+            passwords_from_database(&database)
+        );
+        let mut reopened_root = reopened_database.root.clone();
+        let mut root = database.root.clone();
+        let reopened_entry =
+            find_entry_mut(&mut reopened_root, entry_id).expect("Entry should be saved");
+        let entry = find_entry_mut(&mut root, entry_id).expect("Entry should be in the database");
+        assert_eq!(reopened_entry.fields, entry.fields);
+        assert_eq!(reopened_entry.tags, entry.tags);
+        assert_eq!(reopened_entry.icon_id, entry.icon_id);
+        assert_eq!(
+            reopened_entry
+                .history
+                .as_ref()
+                .map(|history| history.get_entries().len()),
+            Some(2)
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+
+use keepass::db::{CustomDataItem, Entry, Group, History, Node, Value};
+use keepass::Database;
+
+use crate::utils::secret_service_utils::{
+    SecretError, SecretItem, SecretServiceAction, SecretServiceReply,
+};
+
+use super::passwords_utils::{
+    create_group, delete_entry, find_entry_mut, find_keepass_group_mut, OTP_FIELD,
+};
+
+/// The group shared with other apps is kept in the database's custom data, so it's the same group wherever
+/// the database is synced to
+const SECRET_SERVICE_GROUP_KEY: &str = "IDIRFEIN_SECRET_SERVICE_GROUP";
+/// The group made for other apps' secrets when one is stored before a group has been chosen
+const DEFAULT_GROUP_NAME: &str = "Secret Service";
+/// Fields that aren't given to apps as attributes
+const HIDDEN_FIELDS: [&str; 2] = ["Password", OTP_FIELD];
+/// Fields we show in the app, which are kept when an app replaces an item's attributes
+const STANDARD_FIELDS: [&str; 4] = ["Title", "UserName", "URL", "Notes"];
+
+fn find_keepass_group(group: &Group, id: uuid::Uuid) -> Option<&Group> {
+    if group.uuid == id {
+        return Some(group);
+    }
+    group.children.iter().find_map(|node| match node {
+        Node::Group(child_group) => find_keepass_group(child_group, id),
+        Node::Entry(_) => None,
+    })
+}
+
+/// The ID of the group other apps' secrets are kept in, if one has been chosen and it's still in the database
+pub fn secret_service_group_id(database: &Database) -> Option<uuid::Uuid> {
+    database
+        .meta
+        .custom_data
+        .items
+        .get(SECRET_SERVICE_GROUP_KEY)
+        .and_then(|custom_data_item| match custom_data_item.value.as_ref() {
+            Some(Value::Unprotected(group_id)) => uuid::Uuid::parse_str(group_id).ok(),
+            _ => None,
+        })
+        .filter(|group_id| find_keepass_group(&database.root, *group_id).is_some())
+}
+
+pub fn set_secret_service_group(database: &mut Database, group_id: uuid::Uuid) {
+    database.meta.custom_data.items.insert(
+        SECRET_SERVICE_GROUP_KEY.to_string(),
+        CustomDataItem {
+            value: Some(Value::Unprotected(group_id.to_string())),
+            last_modification_time: Some(chrono::Utc::now().naive_utc()),
+        },
+    );
+}
+
+/// Every entry in the shared group, including those in groups inside it
+fn shared_entries(database: &Database) -> Vec<&Entry> {
+    fn add_group_entries<'a>(group: &'a Group, entries: &mut Vec<&'a Entry>) {
+        group.children.iter().for_each(|node| match node {
+            Node::Group(child_group) => add_group_entries(child_group, entries),
+            Node::Entry(entry) => entries.push(entry),
+        });
+    }
+    let mut entries = vec![];
+    if let Some(group) = secret_service_group_id(database)
+        .and_then(|group_id| find_keepass_group(&database.root, group_id))
+    {
+        add_group_entries(group, &mut entries);
+    }
+    entries
+}
+
+fn no_such_item(id: uuid::Uuid) -> SecretError {
+    SecretError::NoSuchObject(format!("No item {id} in the shared group"))
+}
+
+fn shared_entry_mut(database: &mut Database, id: uuid::Uuid) -> Result<&mut Entry, SecretError> {
+    if !shared_entries(database)
+        .iter()
+        .any(|entry| entry.uuid == id)
+    {
+        return Err(no_such_item(id));
+    }
+    find_entry_mut(&mut database.root, id).ok_or_else(|| no_such_item(id))
+}
+
+fn entry_attributes(entry: &Entry) -> HashMap<String, String> {
+    entry
+        .fields
+        .keys()
+        .filter(|key| !HIDDEN_FIELDS.contains(&key.as_str()))
+        .filter_map(|key| Some((key.clone(), entry.get(key)?.to_string())))
+        .collect()
+}
+
+fn has_attributes(entry: &Entry, attributes: &HashMap<String, String>) -> bool {
+    attributes
+        .iter()
+        .all(|(key, value)| entry.get(key) == Some(value.as_str()))
+}
+
+fn timestamp(time: Option<&chrono::NaiveDateTime>) -> u64 {
+    time.map(|time| time.and_utc().timestamp().max(0) as u64)
+        .unwrap_or_default()
+}
+
+fn secret_item(entry: &Entry) -> SecretItem {
+    SecretItem {
+        label: entry.get_title().unwrap_or("").to_string(),
+        attributes: entry_attributes(entry),
+        secret: entry.get_password().unwrap_or("").as_bytes().to_vec(),
+        created: timestamp(entry.times.get_creation()),
+        modified: timestamp(entry.times.get_last_modification()),
+    }
+}
+
+/// Secrets are kept as the entry's password, so anything that isn't text is stored lossily
+fn set_secret(entry: &mut Entry, secret: &[u8]) {
+    entry.fields.insert(
+        String::from("Password"),
+        Value::Protected(String::from_utf8_lossy(secret).as_bytes().into()),
+    );
+}
+
+/// Replaces the attributes, fields we show in the app are only changed if the attributes include them
+fn set_attributes(entry: &mut Entry, attributes: &HashMap<String, String>) {
+    entry.fields.retain(|key, _| {
+        HIDDEN_FIELDS.contains(&key.as_str())
+            || STANDARD_FIELDS.contains(&key.as_str())
+            || attributes.contains_key(key)
+    });
+    attributes.iter().for_each(|(key, value)| {
+        entry
+            .fields
+            .insert(key.clone(), Value::Unprotected(value.clone()));
+    });
+}
+
+/// Changes the entry on behalf of an app, keeping the previous version in its history like editing it in the app
+fn modify_entry(entry: &mut Entry, modify: impl FnOnce(&mut Entry)) {
+    let mut previous_version = entry.clone();
+    previous_version.history = None;
+    entry
+        .history
+        .get_or_insert_with(History::default)
+        .add_entry(previous_version);
+    modify(entry);
+    entry
+        .times
+        .set_last_modification(chrono::Utc::now().naive_utc());
+}
+
+/// Adds the entry to the shared group, making a group for it first if none has been chosen
+fn add_shared_entry(database: &mut Database, entry: Entry) -> Result<(), SecretError> {
+    let group_id = match secret_service_group_id(database) {
+        Some(group_id) => group_id,
+        None => {
+            let root_group_id = database.root.uuid;
+            let group_id =
+                create_group(database, root_group_id, DEFAULT_GROUP_NAME).ok_or_else(|| {
+                    SecretError::ZBus(zbus::Error::Failure(String::from(
+                        "Couldn't create a group for the secrets",
+                    )))
+                })?;
+            set_secret_service_group(database, group_id);
+            group_id
+        }
+    };
+    let group = find_keepass_group_mut(&mut database.root, group_id)
+        .ok_or_else(|| SecretError::NoSuchObject(String::from("No shared group")))?;
+    group.add_child(entry);
+    Ok(())
+}
+
+/// Answers an app's request that only needs the database. Returns the reply, and whether the database was changed.
+/// Unlocking and locking need the page so aren't handled here
+pub fn handle_secret_service_request(
+    database: &mut Database,
+    action: &SecretServiceAction,
+) -> Result<(SecretServiceReply, bool), SecretError> {
+    match action {
+        SecretServiceAction::IsUnlocked
+        | SecretServiceAction::Unlock
+        | SecretServiceAction::Lock => Ok((SecretServiceReply::Done, false)),
+        SecretServiceAction::SearchItems(attributes) => Ok((
+            SecretServiceReply::Items(
+                shared_entries(database)
+                    .into_iter()
+                    .filter(|entry| has_attributes(entry, attributes))
+                    .map(|entry| entry.uuid)
+                    .collect(),
+            ),
+            false,
+        )),
+        SecretServiceAction::GetItem(id) => shared_entries(database)
+            .into_iter()
+            .find(|entry| entry.uuid == *id)
+            .map(|entry| (SecretServiceReply::Item(secret_item(entry)), false))
+            .ok_or_else(|| no_such_item(*id)),
+        SecretServiceAction::CreateItem { item, replace } => {
+            let existing_entry_id = shared_entries(database)
+                .into_iter()
+                .find(|entry| {
+                    *replace
+                        && !item.attributes.is_empty()
+                        && has_attributes(entry, &item.attributes)
+                })
+                .map(|entry| entry.uuid);
+            if let Some(existing_entry_id) = existing_entry_id {
+                modify_entry(shared_entry_mut(database, existing_entry_id)?, |entry| {
+                    entry.fields.insert(
+                        String::from("Title"),
+                        Value::Unprotected(item.label.clone()),
+                    );
+                    set_secret(entry, &item.secret);
+                });
+                return Ok((SecretServiceReply::Created(existing_entry_id), true));
+            }
+            let mut entry = Entry::new();
+            entry.fields.insert(
+                String::from("Title"),
+                Value::Unprotected(item.label.clone()),
+            );
+            set_secret(&mut entry, &item.secret);
+            set_attributes(&mut entry, &item.attributes);
+            let entry_id = entry.uuid;
+            add_shared_entry(database, entry)?;
+            Ok((SecretServiceReply::Created(entry_id), true))
+        }
+        SecretServiceAction::SetSecret(id, secret) => {
+            modify_entry(shared_entry_mut(database, *id)?, |entry| {
+                set_secret(entry, secret)
+            });
+            Ok((SecretServiceReply::Done, true))
+        }
+        SecretServiceAction::SetLabel(id, label) => {
+            modify_entry(shared_entry_mut(database, *id)?, |entry| {
+                entry
+                    .fields
+                    .insert(String::from("Title"), Value::Unprotected(label.clone()));
+            });
+            Ok((SecretServiceReply::Done, true))
+        }
+        SecretServiceAction::SetAttributes(id, attributes) => {
+            modify_entry(shared_entry_mut(database, *id)?, |entry| {
+                set_attributes(entry, attributes)
+            });
+            Ok((SecretServiceReply::Done, true))
+        }
+        SecretServiceAction::DeleteItem(id) => {
+            shared_entry_mut(database, *id)?;
+            delete_entry(database, *id);
+            Ok((SecretServiceReply::Done, true))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::passwords_utils::{new_database, passwords_from_database};
+    use super::*;
+
+    #[test]
+    fn test_secret_service_requests() {
+        let mut database = new_database();
+        let attributes = HashMap::from([
+            (String::from("service"), String::from("git")),
+            (String::from("username"), String::from("me")),
+        ]);
+        let create_item = |replace: bool, secret: &str| SecretServiceAction::CreateItem {
+            item: SecretItem {
+                label: String::from("Git"),
+                attributes: attributes.clone(),
+                secret: secret.as_bytes().to_vec(),
+                ..Default::default()
+            },
+            replace,
+        };
+
+        // The first secret makes the shared group
+        let Ok((SecretServiceReply::Created(item_id), true)) =
+            handle_secret_service_request(&mut database, &create_item(false, "hunter2"))
+        else {
+            panic!("Item wasn't created");
+        };
+        let (root_group, passwords) = passwords_from_database(&database);
+        assert_eq!(
+            secret_service_group_id(&database),
+            Some(root_group.child_groups[0].id)
+        );
+        assert_eq!(root_group.child_groups[0].name, DEFAULT_GROUP_NAME);
+        assert_eq!(*passwords[0].password, "hunter2");
+
+        let search = SecretServiceAction::SearchItems(HashMap::from([(
+            String::from("service"),
+            String::from("git"),
+        )]));
+        assert_eq!(
+            handle_secret_service_request(&mut database, &search).unwrap(),
+            (SecretServiceReply::Items(vec![item_id]), false)
+        );
+        assert_eq!(
+            handle_secret_service_request(
+                &mut database,
+                &SecretServiceAction::SearchItems(HashMap::from([(
+                    String::from("service"),
+                    String::from("ssh"),
+                )]))
+            )
+            .unwrap(),
+            (SecretServiceReply::Items(vec![]), false)
+        );
+
+        // Replacing keeps the same entry and its previous secret in its history
+        assert_eq!(
+            handle_secret_service_request(&mut database, &create_item(true, "hunter3")).unwrap(),
+            (SecretServiceReply::Created(item_id), true)
+        );
+        let Ok((SecretServiceReply::Item(item), false)) =
+            handle_secret_service_request(&mut database, &SecretServiceAction::GetItem(item_id))
+        else {
+            panic!("Item wasn't found");
+        };
+        assert_eq!(item.label, "Git");
+        assert_eq!(item.secret, b"hunter3");
+        assert_eq!(
+            item.attributes.get("username").map(String::as_str),
+            Some("me")
+        );
+        assert!(!item.attributes.contains_key("Password"));
+
+        // Entries outside the shared group aren't given to apps
+        let root_group_id = database.root.uuid;
+        let other_group_id = create_group(&mut database, root_group_id, "Other").unwrap();
+        set_secret_service_group(&mut database, other_group_id);
+        assert!(matches!(
+            handle_secret_service_request(&mut database, &SecretServiceAction::GetItem(item_id)),
+            Err(SecretError::NoSuchObject(_))
+        ));
+        set_secret_service_group(&mut database, root_group_id);
+
+        assert_eq!(
+            handle_secret_service_request(&mut database, &SecretServiceAction::DeleteItem(item_id))
+                .unwrap(),
+            (SecretServiceReply::Done, true)
+        );
+        assert_eq!(
+            handle_secret_service_request(&mut database, &search).unwrap(),
+            (SecretServiceReply::Items(vec![]), false)
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use keepass::db::{Entry, Group, Node, Value};
+use keepass::Database;
+use rsa::pkcs1v15::SigningKey;
+use rsa::signature::{SignatureEncoding, Signer};
+use sha2::{Sha256, Sha512};
+use ssh_key::{private::KeypairData, PrivateKey};
+
+use crate::utils::ssh_agent_utils::write_ssh_string;
+
+/// KeePassXC and KeeAgent keep an entry's SSH agent settings in an attachment with this name
+const KEEAGENT_SETTINGS_ATTACHMENT: &str = "KeeAgent.settings";
+/// SSH_AGENT_RSA_SHA2_256 and SSH_AGENT_RSA_SHA2_512, which ask for RSA signatures using SHA-2
+const RSA_SHA2_256_FLAG: u32 = 2;
+const RSA_SHA2_512_FLAG: u32 = 4;
+
+/// A key from an entry in the database, served by the agent while the database is unlocked
+#[derive(Clone)]
+pub struct SshKey {
+    pub entry_id: uuid::Uuid,
+    /// The entry's title, shown when asking whether the key can be used
+    pub title: String,
+    /// The public key in the SSH wire format, which clients pick the key by
+    pub key_blob: Vec<u8>,
+    pub comment: String,
+    /// Whether to ask before each use, which the entry's KeeAgent settings can turn on
+    pub needs_confirmation: bool,
+    private_key: PrivateKey,
+}
+
+/// The parts of KeeAgent's entry settings we use
+#[derive(Debug, PartialEq)]
+struct KeeAgentSettings {
+    allow_use_of_ssh_key: bool,
+    add_at_database_open: bool,
+    use_confirm_constraint: bool,
+    /// The attachment holding the private key, keys in files outside the database aren't supported
+    attachment_name: Option<String>,
+}
+
+/// KeePassXC writes the settings as UTF-16 with a byte order mark, KeeAgent as UTF-8
+fn decode_settings(settings: &[u8]) -> String {
+    let utf16_units = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        bytes
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect::<Vec<u16>>()
+    };
+    match settings {
+        [0xFF, 0xFE, rest @ ..] => String::from_utf16_lossy(&utf16_units(rest, u16::from_le_bytes)),
+        [0xFE, 0xFF, rest @ ..] => String::from_utf16_lossy(&utf16_units(rest, u16::from_be_bytes)),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(settings).into_owned(),
+    }
+}
+
+/// The text inside the first element with the tag, the settings are simple enough not to need an XML parser
+fn xml_element_text<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{tag}>"))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{tag}>"))?;
+    Some(xml[start..end].trim())
+}
+
+fn parse_keeagent_settings(settings: &[u8]) -> KeeAgentSettings {
+    let xml = decode_settings(settings);
+    let is_true = |tag: &str| xml_element_text(&xml, tag) == Some("true");
+    KeeAgentSettings {
+        allow_use_of_ssh_key: is_true("AllowUseOfSshKey"),
+        add_at_database_open: is_true("AddAtDatabaseOpen"),
+        use_confirm_constraint: is_true("UseConfirmConstraintWhenAdding"),
+        attachment_name: (xml_element_text(&xml, "SelectedType") == Some("attachment"))
+            .then(|| xml_element_text(&xml, "AttachmentName"))
+            .flatten()
+            .filter(|attachment_name| !attachment_name.is_empty())
+            .map(str::to_string),
+    }
+}
+
+fn attachment<'a>(entry: &'a Entry, name: &str) -> Option<&'a [u8]> {
+    match entry.fields.get(name)? {
+        Value::Bytes(bytes) => Some(bytes),
+        _ => None,
+    }
+}
+
+/// Encrypted keys are decrypted with the entry's password, like KeePassXC
+fn load_ssh_key(entry: &Entry, settings: KeeAgentSettings) -> Result<SshKey, String> {
+    let title = entry.get_title().unwrap_or("").to_string();
+    let attachment_name = settings
+        .attachment_name
+        .ok_or_else(|| format!("{title}: only keys attached to the entry can be used"))?;
+    let key_file = attachment(entry, &attachment_name)
+        .ok_or_else(|| format!("{title}: no attachment called {attachment_name}"))?;
+    let mut private_key =
+        PrivateKey::from_openssh(key_file).map_err(|err| format!("{title}: {err}"))?;
+    if private_key.is_encrypted() {
+        private_key = private_key
+            .decrypt(entry.get_password().unwrap_or(""))
+            .map_err(|err| {
+                format!("{title}: couldn't decrypt the key with the entry's password: {err}")
+            })?;
+    }
+    let key_blob = private_key
+        .public_key()
+        .to_bytes()
+        .map_err(|err| format!("{title}: {err}"))?;
+    let comment = if private_key.comment().is_empty() {
+        title.clone()
+    } else {
+        private_key.comment().to_string()
+    };
+    Ok(SshKey {
+        entry_id: entry.uuid,
+        title,
+        key_blob,
+        comment,
+        needs_confirmation: settings.use_confirm_constraint,
+        private_key,
+    })
+}
+
+/// The entry's key if its KeeAgent settings say to add it to the agent when the database is opened
+fn ssh_key_from_entry(entry: &Entry) -> Option<Result<SshKey, String>> {
+    let settings = parse_keeagent_settings(attachment(entry, KEEAGENT_SETTINGS_ATTACHMENT)?);
+    (settings.allow_use_of_ssh_key && settings.add_at_database_open)
+        .then(|| load_ssh_key(entry, settings))
+}
+
+/// Loads the key of every entry set to be added to the agent, returning the keys and why any others couldn't be loaded
+pub fn ssh_keys_from_database(database: &Database) -> (Vec<SshKey>, Vec<String>) {
+    fn add_group_keys(group: &Group, ssh_keys: &mut Vec<SshKey>, load_errors: &mut Vec<String>) {
+        group.children.iter().for_each(|node| match node {
+            Node::Group(child_group) => add_group_keys(child_group, ssh_keys, load_errors),
+            Node::Entry(entry) => match ssh_key_from_entry(entry) {
+                Some(Ok(ssh_key)) => ssh_keys.push(ssh_key),
+                Some(Err(err)) => load_errors.push(err),
+                None => (),
+            },
+        });
+    }
+    let mut ssh_keys = vec![];
+    let mut load_errors = vec![];
+    add_group_keys(&database.root, &mut ssh_keys, &mut load_errors);
+    (ssh_keys, load_errors)
+}
+
+/// Signs the data, returning the signature in the SSH wire format: the algorithm's name then the signature.
+/// RSA keys sign with SHA-256 or SHA-512 as asked, SHA-1 isn't supported as OpenSSH has turned it off by default
+pub fn sign(ssh_key: &SshKey, data: &[u8], flags: u32) -> Result<Vec<u8>, String> {
+    let (algorithm, signature_blob) = match ssh_key.private_key.key_data() {
+        KeypairData::Rsa(rsa_keypair) => {
+            let rsa_private_key =
+                rsa::RsaPrivateKey::try_from(rsa_keypair).map_err(|err| err.to_string())?;
+            if flags & RSA_SHA2_512_FLAG != 0 {
+                (
+                    String::from("rsa-sha2-512"),
+                    SigningKey::<Sha512>::new(rsa_private_key)
+                        .try_sign(data)
+                        .map_err(|err| err.to_string())?
+                        .to_vec(),
+                )
+            } else if flags & RSA_SHA2_256_FLAG != 0 {
+                (
+                    String::from("rsa-sha2-256"),
+                    SigningKey::<Sha256>::new(rsa_private_key)
+                        .try_sign(data)
+                        .map_err(|err| err.to_string())?
+                        .to_vec(),
+                )
+            } else {
+                return Err(String::from("SHA-1 RSA signatures aren't supported"));
+            }
+        }
+        _ => {
+            let signature = ssh_key
+                .private_key
+                .try_sign(data)
+                .map_err(|err| err.to_string())?;
+            (
+                signature.algorithm().as_str().to_string(),
+                signature.as_bytes().to_vec(),
+            )
+        }
+    };
+    let mut signature = vec![];
+    write_ssh_string(&mut signature, algorithm.as_bytes());
+    write_ssh_string(&mut signature, &signature_blob);
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::passwords_utils::new_database;
+    use super::*;
+
+    fn keeagent_settings(allow_use_of_ssh_key: bool, attachment_name: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-16"?>
+<EntrySettings xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <AllowUseOfSshKey>{allow_use_of_ssh_key}</AllowUseOfSshKey>
+  <AddAtDatabaseOpen>true</AddAtDatabaseOpen>
+  <RemoveAtDatabaseClose>true</RemoveAtDatabaseClose>
+  <UseConfirmConstraintWhenAdding>true</UseConfirmConstraintWhenAdding>
+  <UseLifetimeConstraintWhenAdding>false</UseLifetimeConstraintWhenAdding>
+  <LifetimeConstraintDuration>600</LifetimeConstraintDuration>
+  <Location>
+    <SelectedType>attachment</SelectedType>
+    <AttachmentName>{attachment_name}</AttachmentName>
+    <SaveAttachmentToTempFile>false</SaveAttachmentToTempFile>
+    <FileName />
+  </Location>
+</EntrySettings>"#
+        )
+    }
+
+    #[test]
+    fn test_keeagent_settings() {
+        let settings = keeagent_settings(true, "id_ed25519");
+        let utf16_settings: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain(settings.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let expected_settings = KeeAgentSettings {
+            allow_use_of_ssh_key: true,
+            add_at_database_open: true,
+            use_confirm_constraint: true,
+            attachment_name: Some(String::from("id_ed25519")),
+        };
+        assert_eq!(parse_keeagent_settings(&utf16_settings), expected_settings);
+        assert_eq!(
+            parse_keeagent_settings(settings.as_bytes()),
+            expected_settings
+        );
+
+        let mut database = new_database();
+        [
+            ("Turned off", keeagent_settings(false, "id_ed25519")),
+            ("Missing key", keeagent_settings(true, "id_ed25519")),
+        ]
+        .into_iter()
+        .for_each(|(title, settings)| {
+            let mut entry = Entry::new();
+            entry
+                .fields
+                .insert(String::from("Title"), Value::Unprotected(title.to_string()));
+            entry.fields.insert(
+                KEEAGENT_SETTINGS_ATTACHMENT.to_string(),
+                Value::Bytes(settings.into_bytes()),
+            );
+            database.root.add_child(entry);
+        });
+        database.root.add_child(Entry::new());
+        let (ssh_keys, load_errors) = ssh_keys_from_database(&database);
+        assert!(ssh_keys.is_empty());
+        assert_eq!(
+            load_errors,
+            vec![String::from("Missing key: no attachment called id_ed25519")]
+        );
+    }
+}
@@ -1,51 +1,187 @@
 use std::mem;
+use std::time::{Duration, Instant};
 
-use iced::Task;
-use rand::{thread_rng, Rng};
+use iced::{window, Task};
 use rfd::FileDialog;
+use serde_json::json;
+use zeroize::Zeroizing;
 
 use crate::app::Message;
+use crate::pages::settings::page::SettingsPageMessage;
+use crate::utils::browser_integration_utils::BrowserError;
+use crate::utils::secret_service_utils::{SecretError, SecretServiceAction, SecretServiceReply};
+use crate::utils::ssh_agent_utils::{SshAgentAction, SshAgentReply};
 
 use super::{
-    page::{Password, PasswordsPage, PasswordsPageMessage},
-    passwords_utils::{get_passwords, save_database},
+    breached_passwords::breached_password_counts,
+    browser_integration::{add_association, database_hash, handle_browser_request},
+    page::{OpenDatabase, Password, PasswordGeneratorPreset, PasswordsPage, PasswordsPageMessage},
+    password_export::{
+        entries_to_export, export_passwords, is_encrypted_bundle_file, ExportFormat,
+    },
+    password_generator::generate_password,
+    password_health::health_report,
+    password_import::{
+        add_imported_passwords, mark_duplicates, read_import_bundle, read_import_file,
+    },
+    passwords_utils::{
+        create_group, delete_entry, delete_group, find_group, get_passwords,
+        move_entry_to_database, new_database, otp_uri_from_qr_code_image, parent_group_id,
+        passwords_from_database, rename_group, save_database, update_entry,
+    },
+    secret_service::{handle_secret_service_request, set_secret_service_group},
+    ssh_agent::{sign, ssh_keys_from_database},
 };
 
+/// Rebuilds the passwords list and group tree shown on the page after the database has changed
+fn refresh_passwords(state: &mut PasswordsPage) {
+    (state.root_group, state.passwords_list) = passwords_from_database(&state.database);
+}
+
+/// Lets the apps waiting through the Secret Service know the database has been unlocked
+fn answer_secret_service_unlocks(state: &mut PasswordsPage) {
+    state
+        .pending_secret_service_unlocks
+        .drain(..)
+        .for_each(|request| request.respond(Ok(SecretServiceReply::Done)));
+}
+
+/// Signs with the SSH key the client asked for, None if it isn't one of the database's keys or signing failed
+fn ssh_signature(state: &PasswordsPage, action: &SshAgentAction) -> Option<SshAgentReply> {
+    let SshAgentAction::Sign {
+        key_blob,
+        data,
+        flags,
+    } = action
+    else {
+        return None;
+    };
+    let ssh_key = state
+        .ssh_keys
+        .iter()
+        .find(|ssh_key| &ssh_key.key_blob == key_blob)?;
+    match sign(ssh_key, data, *flags) {
+        Ok(signature) => Some(SshAgentReply::Signature(signature)),
+        Err(err) => {
+            println!(
+                "Couldn't sign with the SSH key from {}: {err}",
+                ssh_key.title
+            );
+            None
+        }
+    }
+}
+
+/// Clears the shown database and everything on the page taken from it
+fn clear_shown_database(state: &mut PasswordsPage) {
+    state.database = new_database();
+    refresh_passwords(state);
+    state.selected_group_id = state.root_group.id;
+    state.current_entry_group_id = state.root_group.id;
+    state.selected_password_entry = None;
+    state.current_title_text = String::new();
+    state.current_url_text = String::new();
+    state.current_username_text = String::new();
+    state.current_password_text = Zeroizing::default();
+    state.current_otp_text = String::new();
+    state.health_findings.clear();
+    state.breach_counts.clear();
+    state.import_preview = None;
+    state.import_bundle_path = None;
+    state.import_bundle_password_text = Zeroizing::default();
+    state.show_export_view = false;
+    state.export_group_ids.clear();
+    state.export_tags.clear();
+    state.export_master_password_text = Zeroizing::default();
+    state.export_bundle_password_text = Zeroizing::default();
+}
+
+/// Drops the decrypted databases and everything on the page taken from them, so no secrets stay in memory while locked.
+/// Passwords and master passwords are held in `Zeroizing` strings, which wipe them as they're dropped
+fn clear_decrypted_data(state: &mut PasswordsPage) {
+    clear_shown_database(state);
+    state.master_password_reentry_field_text = Zeroizing::default();
+    state.other_databases.clear();
+    if let Some(request) = state.pending_browser_association.take() {
+        request.respond(Err(BrowserError::DatabaseNotOpened));
+    }
+    state.ssh_keys.clear();
+    state
+        .pending_ssh_sign_requests
+        .drain(..)
+        .for_each(|request| request.respond(None));
+}
+
+/// Takes the shown database and its key off the page, so it can stay open while another is shown
+fn take_shown_database(state: &mut PasswordsPage) -> OpenDatabase {
+    let shown_database = OpenDatabase {
+        path: state.selected_keepass_file.take(),
+        master_password: mem::take(&mut state.master_password_field_text),
+        key_file: state.selected_key_file.take(),
+        database: mem::replace(&mut state.database, new_database()),
+        root_group: state.root_group.clone(),
+        passwords_list: mem::take(&mut state.passwords_list),
+        is_dirty: mem::take(&mut state.is_dirty),
+    };
+    clear_shown_database(state);
+    shown_database
+}
+
+/// Shows an open database on the page, replacing whatever was shown
+fn show_database(state: &mut PasswordsPage, open_database: OpenDatabase) {
+    clear_shown_database(state);
+    state.is_unlocked = true;
+    state.selected_keepass_file = open_database.path;
+    state.master_password_field_text = open_database.master_password;
+    state.selected_key_file = open_database.key_file;
+    state.selected_group_id = open_database.root_group.id;
+    state.current_entry_group_id = open_database.root_group.id;
+    state.current_group_name_text = String::new();
+    state.database = open_database.database;
+    state.root_group = open_database.root_group;
+    state.passwords_list = open_database.passwords_list;
+    state.is_dirty = open_database.is_dirty;
+}
+
 pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<Message> {
     match message {
         PasswordsPageMessage::UpdatePasswordEntry => {
             state.is_dirty = true;
-            if let Some(selected_password) = &state.selected_password_entry {
-                if let Some(password_index) = state
-                    .passwords_list
-                    .iter()
-                    .position(|x| x.id == selected_password.id)
-                {
-                    state.passwords_list[password_index] = Password {
-                        id: selected_password.id,
-                        title: state.current_title_text.clone(),
-                        username: state.current_username_text.clone(),
-                        url: state.current_url_text.clone(),
-                        password: state.current_password_text.clone(),
-                    };
-                }
+            if let Some(selected_password) = &mut state.selected_password_entry {
+                *selected_password = Password {
+                    id: selected_password.id,
+                    title: state.current_title_text.clone(),
+                    username: state.current_username_text.clone(),
+                    url: state.current_url_text.clone(),
+                    password: state.current_password_text.clone(),
+                    otp: state.current_otp_text.clone(),
+                    group_id: state.current_entry_group_id,
+                    last_modified: selected_password.last_modified,
+                };
+                update_entry(&mut state.database, selected_password);
             } else {
-                state.passwords_list.push(Password {
-                    id: uuid::Uuid::new_v4(),
-                    title: mem::take(&mut state.current_title_text),
-                    username: mem::take(&mut state.current_username_text),
-                    url: mem::take(&mut state.current_url_text),
-                    password: mem::take(&mut state.current_password_text),
-                });
+                update_entry(
+                    &mut state.database,
+                    &Password {
+                        id: uuid::Uuid::new_v4(),
+                        title: mem::take(&mut state.current_title_text),
+                        username: mem::take(&mut state.current_username_text),
+                        url: mem::take(&mut state.current_url_text),
+                        password: mem::take(&mut state.current_password_text),
+                        otp: mem::take(&mut state.current_otp_text),
+                        group_id: state.current_entry_group_id,
+                        last_modified: None,
+                    },
+                );
             }
+            refresh_passwords(state);
+            return Task::done(Message::Passwords(
+                PasswordsPageMessage::CheckBreachedPasswords,
+            ));
         }
         PasswordsPageMessage::DeletePasswordEntry(id_to_delete) => {
-            if let Some(password_index) = state
-                .passwords_list
-                .iter()
-                .position(|x| x.id == id_to_delete)
-            {
-                state.passwords_list.remove(password_index);
+            if delete_entry(&mut state.database, id_to_delete) {
+                refresh_passwords(state);
                 state.is_dirty = true;
                 state.selected_password_entry = None;
             }
@@ -57,13 +193,12 @@ pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<
                 } else {
                     Some(state.master_password_field_text.clone())
                 };
-                state.master_password_field_text = String::new();
-                let selected_key_file = state.selected_key_file.take();
+                // The key is kept to save the database with, each open database has its own
                 return Task::perform(
-                    get_passwords(keepass_file_path, password, selected_key_file),
-                    |passwords_list_result| match passwords_list_result {
-                        Ok(passwords_list) => Message::Passwords(
-                            PasswordsPageMessage::RetrievedPasswordsList(Some(passwords_list)),
+                    get_passwords(keepass_file_path, password, state.selected_key_file.clone()),
+                    |database_result| match database_result {
+                        Ok(database) => Message::Passwords(
+                            PasswordsPageMessage::RetrievedDatabase(Some(database)),
                         ),
                         Err(err) => Message::ShowToast(
                             false,
@@ -76,20 +211,43 @@ pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<
                 state.is_unlocked = false;
             };
         }
-        PasswordsPageMessage::RetrievedPasswordsList(passwords_list_option) => {
-            if let Some(passwords_list) = passwords_list_option {
+        PasswordsPageMessage::RetrievedDatabase(database_option) => {
+            if let Some(database) = database_option {
                 state.is_unlocked = true;
-                state.passwords_list = passwords_list;
+                state.selected_group_id = database.root.uuid;
+                state.current_entry_group_id = database.root.uuid;
+                state.collapsed_group_ids.clear();
+                state.database = database;
+                refresh_passwords(state);
                 state.incorrect_password_entered = false;
+                state.last_activity = Instant::now();
+                answer_secret_service_unlocks(state);
+                let (ssh_keys, ssh_key_errors) = ssh_keys_from_database(&state.database);
+                state.ssh_keys.extend(ssh_keys);
+                let check_breached_passwords_task = Task::done(Message::Passwords(
+                    PasswordsPageMessage::CheckBreachedPasswords,
+                ));
+                if ssh_key_errors.is_empty() {
+                    return check_breached_passwords_task;
+                }
+                return check_breached_passwords_task.chain(Task::done(Message::ShowToast(
+                    false,
+                    format!("Couldn't load SSH keys: {}", ssh_key_errors.join(", ")),
+                )));
             } else {
                 state.passwords_list = vec![];
                 state.is_unlocked = false;
                 state.incorrect_password_entered = true;
             }
         }
-        PasswordsPageMessage::UpdateMasterPasswordField(s) => state.master_password_field_text = s,
+        PasswordsPageMessage::UpdateMasterPasswordField(s) => {
+            state.master_password_field_text = Zeroizing::new(s)
+        }
         PasswordsPageMessage::SelectPassword(mut password) => {
             state.selected_password_entry = password.clone();
+            state.current_entry_group_id = password
+                .as_ref()
+                .map_or(state.selected_group_id, |password| password.group_id);
             state.current_title_text = password
                 .as_mut()
                 .map_or(String::new(), |password| mem::take(&mut password.title));
@@ -99,14 +257,113 @@ pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<
             state.current_username_text = password
                 .as_mut()
                 .map_or(String::new(), |password| mem::take(&mut password.username));
-            state.current_password_text = password
+            state.current_password_text =
+                password.as_mut().map_or(Zeroizing::default(), |password| {
+                    mem::take(&mut password.password)
+                });
+            state.current_otp_text = password
                 .as_mut()
-                .map_or(String::new(), |password| mem::take(&mut password.password));
+                .map_or(String::new(), |password| mem::take(&mut password.otp));
+        }
+        PasswordsPageMessage::SelectGroup(group_id) => {
+            state.selected_group_id = group_id;
+            if state.selected_password_entry.is_none() {
+                state.current_entry_group_id = group_id;
+            }
+            state.current_group_name_text = find_group(&state.root_group, group_id)
+                .map(|group| group.name.clone())
+                .unwrap_or_default();
+        }
+        PasswordsPageMessage::ToggleGroupCollapsed(group_id) => {
+            if !state.collapsed_group_ids.remove(&group_id) {
+                state.collapsed_group_ids.insert(group_id);
+            }
+        }
+        PasswordsPageMessage::UpdateCurrentGroupNameText(s) => state.current_group_name_text = s,
+        PasswordsPageMessage::CreateGroup => {
+            if !state.current_group_name_text.is_empty() {
+                if let Some(new_group_id) = create_group(
+                    &mut state.database,
+                    state.selected_group_id,
+                    &state.current_group_name_text,
+                ) {
+                    state.collapsed_group_ids.remove(&state.selected_group_id);
+                    state.selected_group_id = new_group_id;
+                    refresh_passwords(state);
+                    state.is_dirty = true;
+                }
+            }
+        }
+        PasswordsPageMessage::RenameSelectedGroup => {
+            if !state.current_group_name_text.is_empty() {
+                if rename_group(
+                    &mut state.database,
+                    state.selected_group_id,
+                    &state.current_group_name_text,
+                ) {
+                    refresh_passwords(state);
+                    state.is_dirty = true;
+                }
+            }
+        }
+        PasswordsPageMessage::DeleteSelectedGroup => {
+            if let Some(parent_group_id) =
+                parent_group_id(&state.root_group, state.selected_group_id)
+            {
+                if delete_group(&mut state.database, state.selected_group_id) {
+                    refresh_passwords(state);
+                    if state.current_entry_group_id == state.selected_group_id {
+                        state.current_entry_group_id = parent_group_id;
+                    }
+                    if let Some(selected_password) = &mut state.selected_password_entry {
+                        if selected_password.group_id == state.selected_group_id {
+                            selected_password.group_id = parent_group_id;
+                        }
+                    }
+                    state.collapsed_group_ids.remove(&state.selected_group_id);
+                    state.selected_group_id = parent_group_id;
+                    state.current_group_name_text = String::new();
+                    state.is_dirty = true;
+                }
+            }
+        }
+        PasswordsPageMessage::SetCurrentEntryGroup(group_id) => {
+            state.current_entry_group_id = group_id
         }
         PasswordsPageMessage::UpdateCurrentTitleText(s) => state.current_title_text = s,
         PasswordsPageMessage::UpdateCurrentUrlText(s) => state.current_url_text = s,
         PasswordsPageMessage::UpdateCurrentUsernameText(s) => state.current_username_text = s,
-        PasswordsPageMessage::UpdateCurrentPasswordText(s) => state.current_password_text = s,
+        PasswordsPageMessage::UpdateCurrentPasswordText(s) => {
+            state.current_password_text = Zeroizing::new(s)
+        }
+        PasswordsPageMessage::UpdateCurrentOtpText(s) => state.current_otp_text = s,
+        PasswordsPageMessage::PickTotpQrCodeImage => {
+            return Task::perform(
+                async {
+                    FileDialog::new()
+                        .add_filter("image", &["png", "jpg", "jpeg", "webp", "bmp"])
+                        .pick_file()
+                },
+                |selected_file| {
+                    Message::Passwords(PasswordsPageMessage::ScanTotpQrCodeImage(selected_file))
+                },
+            );
+        }
+        PasswordsPageMessage::ScanTotpQrCodeImage(selected_file) => {
+            if let Some(image_path) = selected_file {
+                return Task::perform(otp_uri_from_qr_code_image(image_path), |otp_uri_result| {
+                    match otp_uri_result {
+                        Ok(otp_uri) => {
+                            Message::Passwords(PasswordsPageMessage::UpdateCurrentOtpText(otp_uri))
+                        }
+                        Err(err) => {
+                            Message::ShowToast(false, format!("Failed to read QR code: {err}"))
+                        }
+                    }
+                });
+            }
+        }
+        PasswordsPageMessage::TotpTick => (),
         PasswordsPageMessage::UpdatePasswordsFilter(filter) => {
             state.current_passwords_list_filter = filter
         }
@@ -122,15 +379,29 @@ pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<
                     state.selected_keepass_file.clone(),
                     password,
                     state.selected_key_file.clone(),
-                    state.passwords_list.clone(),
+                    state.database.clone(),
                 ),
                 |(is_success, content)| Message::ShowToast(is_success, content),
             );
         }
         PasswordsPageMessage::Lock => {
+            if !state.is_unlocked && state.other_databases.is_empty() {
+                return Task::none();
+            }
+            // The other open databases are closed, the shown one stays selected to unlock again
+            let mut save_tasks: Vec<Task<Message>> = mem::take(&mut state.other_databases)
+                .into_iter()
+                .filter(|open_database| open_database.is_dirty)
+                .map(|open_database| Task::perform(open_database.save(), |_| Message::None))
+                .collect();
             state.is_unlocked = false;
+            if let Some(secret_service_connection) = state.secret_service_connection.as_mut() {
+                secret_service_connection.database_locked();
+            }
+            let master_password_field_text = mem::take(&mut state.master_password_field_text);
+            let database = mem::replace(&mut state.database, new_database());
+            clear_decrypted_data(state);
             if state.is_dirty {
-                let master_password_field_text = mem::take(&mut state.master_password_field_text);
                 let key_file_option = mem::take(&mut state.selected_key_file);
                 let password = if master_password_field_text.is_empty() {
                     None
@@ -138,16 +409,17 @@ pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<
                     Some(master_password_field_text)
                 };
                 state.is_dirty = false;
-                return Task::perform(
+                save_tasks.push(Task::perform(
                     save_database(
                         state.selected_keepass_file.clone(),
                         password,
                         key_file_option,
-                        state.passwords_list.clone(),
+                        database,
                     ),
                     |_| Message::None,
-                );
+                ));
             }
+            return Task::batch(save_tasks);
         }
         PasswordsPageMessage::ToggleShowSidebar => state.show_sidebar = !state.show_sidebar,
         PasswordsPageMessage::ToggleHideMasterPassword => {
@@ -169,6 +441,17 @@ pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<
             );
         }
         PasswordsPageMessage::SetDatabaseFile(selected_file) => {
+            if selected_file.is_some()
+                && state
+                    .other_databases
+                    .iter()
+                    .any(|open_database| open_database.path == selected_file)
+            {
+                return Task::done(Message::ShowToast(
+                    false,
+                    String::from("That database is already open"),
+                ));
+            }
             state.selected_keepass_file = selected_file;
         }
         PasswordsPageMessage::StartCreatingNewKeepassFile => {
@@ -187,7 +470,7 @@ pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<
             );
         }
         PasswordsPageMessage::UpdateMasterPasswordReentryField(s) => {
-            state.master_password_reentry_field_text = s
+            state.master_password_reentry_field_text = Zeroizing::new(s)
         }
         PasswordsPageMessage::ToggleHideMasterPasswordReentry => {
             state.hide_master_password_reentry_entry = !state.hide_master_password_reentry_entry
@@ -198,8 +481,14 @@ pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<
                 || state.selected_key_file.is_some()
             {
                 state.is_unlocked = true;
+                state.last_activity = Instant::now();
+                state.database = new_database();
+                state.selected_group_id = state.database.root.uuid;
+                state.current_entry_group_id = state.database.root.uuid;
+                refresh_passwords(state);
                 state.passwords_dont_match = false;
                 state.is_creating_new_keepass_file = false;
+                answer_secret_service_unlocks(state);
             } else if state.master_password_field_text != state.master_password_reentry_field_text {
                 state.passwords_dont_match = true;
             }
@@ -214,15 +503,616 @@ pub fn update(state: &mut PasswordsPage, message: PasswordsPageMessage) -> Task<
             state.selected_key_file = selected_file;
         }
         PasswordsPageMessage::LockAndDeselectDatabase => {
-            state.selected_keepass_file = None;
-            state.is_unlocked = false;
             state.is_creating_new_keepass_file = false;
+            let passwords_list = &state.passwords_list;
+            state.ssh_keys.retain(|ssh_key| {
+                !passwords_list
+                    .iter()
+                    .any(|password| password.id == ssh_key.entry_id)
+            });
+            // Unsaved changes are saved before the database is closed, the same as when locking
+            let shown_database = take_shown_database(state);
+            let save_task = if shown_database.is_dirty {
+                Task::perform(shown_database.save(), |(is_success, content)| {
+                    Message::ShowToast(is_success, content)
+                })
+            } else {
+                Task::none()
+            };
+            if state.other_databases.is_empty() {
+                state.is_unlocked = false;
+                clear_decrypted_data(state);
+                return save_task;
+            } else {
+                // Closing one of several open databases shows the next one
+                let open_database = state.other_databases.remove(0);
+                show_database(state, open_database);
+                return Task::batch([
+                    save_task,
+                    Task::done(Message::Passwords(
+                        PasswordsPageMessage::CheckBreachedPasswords,
+                    )),
+                ]);
+            }
+        }
+        PasswordsPageMessage::OpenAnotherDatabase => {
+            if state.is_unlocked {
+                let shown_database = take_shown_database(state);
+                state.other_databases.push(shown_database);
+                state.is_unlocked = false;
+            }
+        }
+        PasswordsPageMessage::ShowOpenDatabase(index) => {
+            if state.is_unlocked && index < state.other_databases.len() {
+                let shown_database = take_shown_database(state);
+                let open_database = mem::replace(&mut state.other_databases[index], shown_database);
+                show_database(state, open_database);
+                return Task::done(Message::Passwords(
+                    PasswordsPageMessage::CheckBreachedPasswords,
+                ));
+            }
+        }
+        PasswordsPageMessage::OpenEntryInDatabase(index, password_id) => {
+            if let Some(password) = state.other_databases.get(index).and_then(|open_database| {
+                open_database
+                    .passwords_list
+                    .iter()
+                    .find(|password| password.id == password_id)
+                    .cloned()
+            }) {
+                return Task::done(Message::Passwords(PasswordsPageMessage::ShowOpenDatabase(
+                    index,
+                )))
+                .chain(Task::done(Message::Passwords(
+                    PasswordsPageMessage::SelectPassword(Some(password)),
+                )));
+            }
+        }
+        PasswordsPageMessage::MoveSelectedEntryToDatabase(index) => {
+            if let (Some(selected_password), Some(open_database)) = (
+                &state.selected_password_entry,
+                state.other_databases.get_mut(index),
+            ) {
+                if move_entry_to_database(
+                    &mut state.database,
+                    &mut open_database.database,
+                    selected_password.id,
+                ) {
+                    (open_database.root_group, open_database.passwords_list) =
+                        passwords_from_database(&open_database.database);
+                    // Both are left marked as changed so they're saved again when locking, in case these saves fail
+                    open_database.is_dirty = true;
+                    let save_moved_to_task = open_database.clone().save();
+                    refresh_passwords(state);
+                    state.is_dirty = true;
+                    let save_moved_from_task = save_database(
+                        state.selected_keepass_file.clone(),
+                        if state.master_password_field_text.is_empty() {
+                            None
+                        } else {
+                            Some(state.master_password_field_text.clone())
+                        },
+                        state.selected_key_file.clone(),
+                        state.database.clone(),
+                    );
+                    // The database moved to is saved first, so the entry isn't lost if that fails
+                    return Task::done(Message::Passwords(PasswordsPageMessage::SelectPassword(
+                        None,
+                    )))
+                    .chain(Task::perform(
+                        async move {
+                            let (is_success, content) = save_moved_to_task.await;
+                            if is_success {
+                                save_moved_from_task.await
+                            } else {
+                                (is_success, content)
+                            }
+                        },
+                        |(is_success, content)| Message::ShowToast(is_success, content),
+                    ));
+                }
+            }
         }
         PasswordsPageMessage::GeneratePassword => {
-            let mut rng = thread_rng();
-            state.current_password_text = (0..30)
-                .map(|_| rng.gen_range(33u8..127u8) as char) // ASCII range for printable characters
-                .collect();
+            state.current_password_text =
+                Zeroizing::new(generate_password(&state.generator_settings));
+        }
+        PasswordsPageMessage::ToggleShowPasswordGenerator => {
+            state.show_password_generator = !state.show_password_generator
+        }
+        PasswordsPageMessage::UpdateGeneratorSettings(generator_settings) => {
+            state.generator_settings = generator_settings
+        }
+        PasswordsPageMessage::UpdateNewGeneratorPresetNameText(s) => {
+            state.new_generator_preset_name_text = s
+        }
+        PasswordsPageMessage::SaveGeneratorPreset => {
+            if !state.new_generator_preset_name_text.is_empty() {
+                let preset = PasswordGeneratorPreset {
+                    name: mem::take(&mut state.new_generator_preset_name_text),
+                    settings: state.generator_settings.clone(),
+                };
+                // Saving with the name of an existing preset replaces it
+                if let Some(existing_preset) = state
+                    .generator_presets
+                    .iter_mut()
+                    .find(|existing_preset| existing_preset.name == preset.name)
+                {
+                    *existing_preset = preset;
+                } else {
+                    state.generator_presets.push(preset);
+                }
+                return Task::done(Message::Settings(
+                    SettingsPageMessage::PasswordsSetGeneratorPresets(
+                        state.generator_presets.clone(),
+                    ),
+                ));
+            }
+        }
+        PasswordsPageMessage::LoadGeneratorPreset(preset) => {
+            state.new_generator_preset_name_text = preset.name;
+            state.generator_settings = preset.settings;
+        }
+        PasswordsPageMessage::ToggleShowHealthReport => {
+            state.show_health_report = !state.show_health_report;
+            if state.show_health_report {
+                state.import_preview = None;
+                state.show_export_view = false;
+                return Task::done(Message::Passwords(
+                    PasswordsPageMessage::RefreshHealthReport,
+                ));
+            }
+        }
+        PasswordsPageMessage::RefreshHealthReport => {
+            state.is_checking_health = true;
+            let passwords_list = state.passwords_list.clone();
+            let max_password_age_months = state.max_password_age_months;
+            return Task::perform(
+                async move {
+                    health_report(
+                        &passwords_list,
+                        max_password_age_months,
+                        chrono::Utc::now().naive_utc(),
+                    )
+                },
+                |health_findings| {
+                    Message::Passwords(PasswordsPageMessage::SetHealthFindings(health_findings))
+                },
+            );
+        }
+        PasswordsPageMessage::SetHealthFindings(health_findings) => {
+            state.health_findings = health_findings;
+            state.is_checking_health = false;
+        }
+        PasswordsPageMessage::SetBreachedHashesPath(breached_hashes_path) => {
+            state.breached_hashes_path = breached_hashes_path;
+            state.breach_counts.clear();
+            if state.is_unlocked {
+                return Task::done(Message::Passwords(
+                    PasswordsPageMessage::CheckBreachedPasswords,
+                ));
+            }
+        }
+        PasswordsPageMessage::CheckBreachedPasswords => {
+            if let Some(breached_hashes_path) = state.breached_hashes_path.clone() {
+                state.is_checking_breaches = true;
+                let passwords_list = state.passwords_list.clone();
+                return Task::perform(
+                    async move { breached_password_counts(&breached_hashes_path, &passwords_list) },
+                    |breach_counts_result| {
+                        Message::Passwords(PasswordsPageMessage::SetBreachCounts(
+                            breach_counts_result,
+                        ))
+                    },
+                );
+            }
+        }
+        PasswordsPageMessage::SetBreachCounts(breach_counts_result) => {
+            state.is_checking_breaches = false;
+            match breach_counts_result {
+                Ok(breach_counts) => state.breach_counts = breach_counts,
+                Err(err) => {
+                    return Task::done(Message::ShowToast(
+                        false,
+                        format!("Failed to check for breached passwords: {err}"),
+                    ))
+                }
+            }
+        }
+        PasswordsPageMessage::ToggleShowOnlyCompromised => {
+            state.show_only_compromised = !state.show_only_compromised
+        }
+        PasswordsPageMessage::RecordActivity => state.last_activity = Instant::now(),
+        PasswordsPageMessage::AutoLockTick => {
+            if state.auto_lock_minutes > 0
+                && state.last_activity.elapsed()
+                    >= Duration::from_secs(u64::from(state.auto_lock_minutes) * 60)
+            {
+                return Task::done(Message::Passwords(PasswordsPageMessage::Lock));
+            }
+        }
+        PasswordsPageMessage::CheckWindowMinimised(window_id) => {
+            // Wayland doesn't say whether a window is minimised, so losing focus locks the database there
+            return window::get_minimized(window_id).map(|is_minimised| {
+                if is_minimised != Some(false) {
+                    Message::Passwords(PasswordsPageMessage::Lock)
+                } else {
+                    Message::None
+                }
+            });
+        }
+        PasswordsPageMessage::SetAutoLockMinutes(minutes) => state.auto_lock_minutes = minutes,
+        PasswordsPageMessage::SetLockOnScreenLock(b) => state.lock_on_screen_lock = b,
+        PasswordsPageMessage::SetLockOnMinimise(b) => state.lock_on_minimise = b,
+        PasswordsPageMessage::SetSshAgentConfirmUse(b) => state.ssh_agent_confirm_use = b,
+        PasswordsPageMessage::ToggleExtraToolsMenu => {
+            state.show_extra_tools_menu = !state.show_extra_tools_menu
+        }
+        PasswordsPageMessage::ImportPasswords => {
+            state.show_extra_tools_menu = false;
+            return Task::perform(
+                async {
+                    FileDialog::new()
+                        .add_filter("password export", &["json", "1pux", "csv"])
+                        .pick_file()
+                },
+                |selected_file| {
+                    Message::Passwords(PasswordsPageMessage::ImportPasswordsFromFile(selected_file))
+                },
+            );
+        }
+        PasswordsPageMessage::ImportPasswordsFromFile(selected_file) => {
+            if let Some(selected_file) = selected_file {
+                if is_encrypted_bundle_file(&selected_file) {
+                    // The bundle's password is asked for before it can be read
+                    state.show_health_report = false;
+                    state.show_export_view = false;
+                    state.import_preview = None;
+                    state.import_bundle_path = Some(selected_file);
+                    return Task::none();
+                }
+                return Task::perform(read_import_file(selected_file), |result| {
+                    Message::Passwords(PasswordsPageMessage::SetImportPreview(result))
+                });
+            }
+        }
+        PasswordsPageMessage::SetImportPreview(result) => match result {
+            Ok(mut imported_passwords) => {
+                if imported_passwords.is_empty() {
+                    return Task::done(Message::ShowToast(
+                        false,
+                        String::from("No logins found to import"),
+                    ));
+                }
+                mark_duplicates(&mut imported_passwords, &state.passwords_list);
+                state.show_health_report = false;
+                state.show_export_view = false;
+                state.import_bundle_path = None;
+                state.import_preview = Some(imported_passwords);
+            }
+            Err(err) => {
+                return Task::done(Message::ShowToast(
+                    false,
+                    format!("Failed to import passwords: {err}"),
+                ))
+            }
+        },
+        PasswordsPageMessage::ConfirmImport => {
+            if let Some(imported_passwords) = state.import_preview.take() {
+                let added_count = add_imported_passwords(
+                    &mut state.database,
+                    &imported_passwords,
+                    state.selected_group_id,
+                );
+                refresh_passwords(state);
+                state.is_dirty = true;
+                return Task::done(Message::Passwords(PasswordsPageMessage::SaveDatabaseToFile))
+                    .chain(Task::done(Message::ShowToast(
+                        true,
+                        format!(
+                            "Imported passwords: {added_count} added, {} duplicates skipped",
+                            imported_passwords.len() - added_count
+                        ),
+                    )))
+                    .chain(Task::done(Message::Passwords(
+                        PasswordsPageMessage::CheckBreachedPasswords,
+                    )));
+            }
+        }
+        PasswordsPageMessage::CancelImport => {
+            state.import_preview = None;
+            state.import_bundle_path = None;
+            state.import_bundle_password_text = Zeroizing::default();
+        }
+        PasswordsPageMessage::UpdateImportBundlePasswordText(s) => {
+            state.import_bundle_password_text = Zeroizing::new(s)
+        }
+        PasswordsPageMessage::DecryptImportBundle => {
+            if let Some(import_bundle_path) = state.import_bundle_path.clone() {
+                return Task::perform(
+                    read_import_bundle(
+                        import_bundle_path,
+                        mem::take(&mut state.import_bundle_password_text),
+                    ),
+                    |result| Message::Passwords(PasswordsPageMessage::SetImportPreview(result)),
+                );
+            }
+        }
+        PasswordsPageMessage::ToggleShowExportView => {
+            state.show_extra_tools_menu = false;
+            state.show_export_view = !state.show_export_view;
+            if state.show_export_view {
+                state.show_health_report = false;
+                state.import_preview = None;
+                state.import_bundle_path = None;
+            } else {
+                state.export_master_password_text = Zeroizing::default();
+                state.export_bundle_password_text = Zeroizing::default();
+            }
+        }
+        PasswordsPageMessage::ToggleExportGroup(group_id) => {
+            if !state.export_group_ids.remove(&group_id) {
+                state.export_group_ids.insert(group_id);
+            }
+        }
+        PasswordsPageMessage::ToggleExportTag(tag) => {
+            if !state.export_tags.remove(&tag) {
+                state.export_tags.insert(tag);
+            }
+        }
+        PasswordsPageMessage::SetExportFormat(export_format) => state.export_format = export_format,
+        PasswordsPageMessage::UpdateExportMasterPasswordText(s) => {
+            state.export_master_password_text = Zeroizing::new(s)
+        }
+        PasswordsPageMessage::UpdateExportBundlePasswordText(s) => {
+            state.export_bundle_password_text = Zeroizing::new(s)
+        }
+        PasswordsPageMessage::StartExport => {
+            // The database is opened again with the re-entered master password to check it before anything is exported
+            let Some(keepass_file_path) = state.selected_keepass_file.clone() else {
+                return Task::done(Message::ShowToast(
+                    false,
+                    String::from("Save the database to a file before exporting"),
+                ));
+            };
+            let password = if state.export_master_password_text.is_empty() {
+                None
+            } else {
+                Some(mem::take(&mut state.export_master_password_text))
+            };
+            state.is_exporting = true;
+            return Task::perform(
+                get_passwords(keepass_file_path, password, state.selected_key_file.clone()),
+                |database_result| {
+                    Message::Passwords(PasswordsPageMessage::ExportMasterPasswordChecked(
+                        database_result.is_ok(),
+                    ))
+                },
+            );
+        }
+        PasswordsPageMessage::ExportMasterPasswordChecked(is_correct) => {
+            if !is_correct {
+                state.is_exporting = false;
+                return Task::done(Message::ShowToast(
+                    false,
+                    String::from("Incorrect master password, nothing was exported"),
+                ));
+            }
+            let (extension, file_name) = match state.export_format {
+                ExportFormat::Csv => ("csv", "passwords.csv"),
+                ExportFormat::EncryptedBundle => ("json", "passwords-bundle.json"),
+            };
+            return Task::perform(
+                async move {
+                    FileDialog::new()
+                        .add_filter(extension, &[extension])
+                        .set_file_name(file_name)
+                        .save_file()
+                },
+                |selected_file| {
+                    Message::Passwords(PasswordsPageMessage::ExportToFile(selected_file))
+                },
+            );
+        }
+        PasswordsPageMessage::ExportToFile(selected_file) => {
+            let Some(selected_file) = selected_file else {
+                state.is_exporting = false;
+                return Task::none();
+            };
+            return Task::perform(
+                export_passwords(
+                    entries_to_export(&state.database, &state.export_group_ids, &state.export_tags),
+                    state.export_format,
+                    state.export_bundle_password_text.clone(),
+                    selected_file,
+                ),
+                |result| Message::Passwords(PasswordsPageMessage::ExportFinished(result)),
+            );
+        }
+        PasswordsPageMessage::ExportFinished(result) => {
+            state.is_exporting = false;
+            match result {
+                Ok(exported_count) => {
+                    state.show_export_view = false;
+                    state.export_bundle_password_text = Zeroizing::default();
+                    return Task::done(Message::ShowToast(
+                        true,
+                        format!("Exported {exported_count} entries"),
+                    ));
+                }
+                Err(err) => {
+                    return Task::done(Message::ShowToast(
+                        false,
+                        format!("Failed to export passwords: {err}"),
+                    ))
+                }
+            }
+        }
+        PasswordsPageMessage::HandleBrowserRequest(request) => {
+            if !state.is_unlocked {
+                request.respond(Err(BrowserError::DatabaseNotOpened));
+                return Task::none();
+            }
+            match request.action.as_str() {
+                "associate" => {
+                    // Only one browser is asked about at a time
+                    if let Some(previous_request) =
+                        state.pending_browser_association.replace(request)
+                    {
+                        previous_request.respond(Err(BrowserError::ActionCancelledOrDenied));
+                    }
+                    state.browser_association_name_text = String::new();
+                    return Task::done(Message::ShowToast(
+                        true,
+                        String::from("A browser is asking to connect to the password database"),
+                    ));
+                }
+                "generate-password" => request.respond(Ok(json!({
+                    "password": generate_password(&state.generator_settings),
+                }))),
+                "lock-database" => {
+                    request.respond(Ok(json!({})));
+                    return Task::done(Message::Passwords(PasswordsPageMessage::Lock));
+                }
+                _ => {
+                    match handle_browser_request(
+                        &mut state.database,
+                        &request.action,
+                        &request.message,
+                    ) {
+                        Ok((reply, is_changed)) => {
+                            if is_changed {
+                                refresh_passwords(state);
+                                state.is_dirty = true;
+                            }
+                            request.respond(Ok(reply));
+                        }
+                        Err(err) => request.respond(Err(err)),
+                    }
+                }
+            }
+        }
+        PasswordsPageMessage::UpdateBrowserAssociationNameText(s) => {
+            state.browser_association_name_text = s
+        }
+        PasswordsPageMessage::ConfirmBrowserAssociation(is_allowed) => {
+            if let Some(request) = state.pending_browser_association.take() {
+                let id = state.browser_association_name_text.trim().to_string();
+                state.browser_association_name_text = String::new();
+                match request.message["idKey"].as_str() {
+                    Some(id_key) if is_allowed && !id.is_empty() => {
+                        add_association(&mut state.database, &id, id_key);
+                        state.is_dirty = true;
+                        request.respond(Ok(json!({
+                            "hash": database_hash(&state.database),
+                            "id": id,
+                        })));
+                        return Task::done(Message::Passwords(
+                            PasswordsPageMessage::SaveDatabaseToFile,
+                        ));
+                    }
+                    Some(_) if is_allowed => request.respond(Err(BrowserError::AssociationFailed)),
+                    _ => request.respond(Err(BrowserError::ActionCancelledOrDenied)),
+                }
+            }
+        }
+        PasswordsPageMessage::SetSecretServiceConnection(connection) => {
+            state.secret_service_connection = Some(connection)
+        }
+        PasswordsPageMessage::HandleSecretServiceRequest(request) => match &request.action {
+            SecretServiceAction::IsUnlocked => {
+                request.respond(Ok(SecretServiceReply::IsUnlocked(state.is_unlocked)))
+            }
+            SecretServiceAction::Unlock => {
+                if state.is_unlocked {
+                    request.respond(Ok(SecretServiceReply::Done));
+                } else {
+                    state.pending_secret_service_unlocks.push(request);
+                    return Task::done(Message::ShowToast(
+                        true,
+                        String::from("An app is asking for its secrets, unlock the password database to share them"),
+                    ));
+                }
+            }
+            SecretServiceAction::Lock => {
+                request.respond(Ok(SecretServiceReply::Done));
+                return Task::done(Message::Passwords(PasswordsPageMessage::Lock));
+            }
+            _ if !state.is_unlocked => request.respond(Err(SecretError::IsLocked(String::from(
+                "The password database is locked",
+            )))),
+            action => match handle_secret_service_request(&mut state.database, action) {
+                Ok((reply, is_changed)) => {
+                    if is_changed {
+                        refresh_passwords(state);
+                        state.is_dirty = true;
+                    }
+                    request.respond(Ok(reply));
+                }
+                Err(err) => request.respond(Err(err)),
+            },
+        },
+        PasswordsPageMessage::ShareSelectedGroupWithSecretService => {
+            set_secret_service_group(&mut state.database, state.selected_group_id);
+            state.is_dirty = true;
+        }
+        PasswordsPageMessage::HandleSshAgentRequest(request) => match &request.action {
+            SshAgentAction::ListIdentities => request.respond(Some(SshAgentReply::Identities(
+                state
+                    .ssh_keys
+                    .iter()
+                    .map(|ssh_key| (ssh_key.key_blob.clone(), ssh_key.comment.clone()))
+                    .collect(),
+            ))),
+            SshAgentAction::Sign { key_blob, .. } => {
+                let needs_confirmation = state
+                    .ssh_keys
+                    .iter()
+                    .find(|ssh_key| &ssh_key.key_blob == key_blob)
+                    .map(|ssh_key| state.ssh_agent_confirm_use || ssh_key.needs_confirmation);
+                if needs_confirmation == Some(true) {
+                    state.pending_ssh_sign_requests.push(request);
+                    return Task::done(Message::ShowToast(
+                        true,
+                        String::from("An SSH client wants to use a key from the password database"),
+                    ));
+                }
+                request.respond(ssh_signature(state, &request.action));
+            }
+        },
+        PasswordsPageMessage::ConfirmSshKeyUse(is_allowed) => {
+            if !state.pending_ssh_sign_requests.is_empty() {
+                let request = state.pending_ssh_sign_requests.remove(0);
+                request.respond(if is_allowed {
+                    ssh_signature(state, &request.action)
+                } else {
+                    None
+                });
+            }
+        }
+        PasswordsPageMessage::OpenHealthFinding(password_id) => {
+            if let Some(password) = state
+                .passwords_list
+                .iter()
+                .find(|password| password.id == password_id)
+                .cloned()
+            {
+                state.show_health_report = false;
+                return Task::done(Message::Passwords(PasswordsPageMessage::SelectPassword(
+                    Some(password),
+                )));
+            }
+        }
+        PasswordsPageMessage::SetMaxPasswordAgeMonths(months) => {
+            state.max_password_age_months = months
+        }
+        PasswordsPageMessage::DeleteGeneratorPreset(preset_name) => {
+            state
+                .generator_presets
+                .retain(|preset| preset.name != preset_name);
+            return Task::done(Message::Settings(
+                SettingsPageMessage::PasswordsSetGeneratorPresets(state.generator_presets.clone()),
+            ));
         }
     }
     Task::none()
@@ -1,18 +1,34 @@
+use std::path::PathBuf;
+
 use fluent_templates::Loader;
 use iced::Alignment::Center;
 
 use iced::alignment::{Horizontal, Vertical};
 use iced::widget::scrollable::{Direction, Scrollbar};
 use iced::widget::{
-    button, column, container, row, svg, text, text_input, Scrollable, Space, Svg, Tooltip,
+    button, checkbox, column, container, pick_list, progress_bar, row, slider, svg, text,
+    text_input, Scrollable, Space, Svg, Tooltip,
 };
 use iced::Element;
 use iced::{Font, Length};
+use iced_aw::{drop_down, DropDown};
+use zeroize::Zeroizing;
 
 use crate::app::Message;
+use crate::utils::ssh_agent_utils::{SshAgentAction, SshAgentRequest};
 use crate::LOCALES;
 
-use super::page::{PasswordsPage, PasswordsPageMessage};
+use super::browser_integration::is_association_name_taken;
+use super::page::{
+    OpenDatabase, Password, PasswordGeneratorMode, PasswordGeneratorPreset,
+    PasswordGeneratorSettings, PasswordGroup, PasswordsPage, PasswordsPageMessage,
+};
+use super::password_export::{database_tags, selected_entry_count, ExportFormat};
+use super::password_generator::entropy_bits;
+use super::password_health::HealthIssue;
+use super::password_import::ImportedPassword;
+use super::passwords_utils::{current_totp_code, group_paths};
+use super::secret_service::secret_service_group_id;
 
 pub fn main_view(state: &PasswordsPage) -> Element<Message> {
     if state.is_creating_new_keepass_file {
@@ -30,7 +46,21 @@ pub fn main_view(state: &PasswordsPage) -> Element<Message> {
             } else {
                 column![].into()
             },
-            entry_edit_view(state)
+            if let Some(request) = state.pending_ssh_sign_requests.first() {
+                ssh_key_use_view(state, request)
+            } else if state.pending_browser_association.is_some() {
+                browser_association_view(state)
+            } else if let Some(imported_passwords) = &state.import_preview {
+                import_preview_view(state, imported_passwords)
+            } else if state.import_bundle_path.is_some() {
+                import_bundle_password_view(state)
+            } else if state.show_export_view {
+                export_view(state)
+            } else if state.show_health_report {
+                health_report_view(state)
+            } else {
+                entry_edit_view(state)
+            }
         ]
         .into()
     } else {
@@ -189,64 +219,382 @@ fn creating_new_database_choose_path_view(state: &PasswordsPage) -> Element<Mess
     .into()
 }
 
+/// The name a database is shown by, its file name without the extension
+fn database_name(path: Option<&PathBuf>) -> String {
+    path.and_then(|path| path.file_stem())
+        .map(|file_stem| file_stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
 fn sidebar_view(state: &PasswordsPage) -> Element<Message> {
+    let mut database_rows: Vec<Element<Message>> = vec![];
+    if !state.other_databases.is_empty() {
+        database_rows.push(
+            text(database_name(state.selected_keepass_file.as_ref()))
+                .size(18)
+                .font(Font {
+                    weight: iced::font::Weight::Bold,
+                    ..Default::default()
+                })
+                .into(),
+        );
+    }
+    database_rows.extend(group_tree_view(state, &state.root_group, 0));
+    state
+        .other_databases
+        .iter()
+        .enumerate()
+        .for_each(|(index, open_database)| {
+            database_rows.extend(other_database_view(state, index, open_database))
+        });
     column![
         text_input(
             &LOCALES.lookup(&state.locale, "filter"),
             &state.current_passwords_list_filter
         )
         .on_input(|s| { Message::Passwords(PasswordsPageMessage::UpdatePasswordsFilter(s)) }),
-        Scrollable::new(
-            column(
-                state
-                    .passwords_list
-                    .iter()
-                    .filter(|password| password
-                        .title
-                        .to_lowercase()
-                        .contains(&state.current_passwords_list_filter.to_lowercase()))
-                    .map(|password| {
-                        button(
-                            text(if !password.title.is_empty() {
-                                password.title.clone()
-                            } else {
-                                LOCALES.lookup(&state.locale, "no-title")
-                            })
-                            .font(Font {
-                                weight: iced::font::Weight::Semibold,
-                                ..Default::default()
-                            })
-                            .width(Length::Fill)
-                            .align_x(Horizontal::Center),
-                        )
-                        .on_press(Message::Passwords(PasswordsPageMessage::SelectPassword(
-                            Some(password.clone()),
-                        )))
-                        .style(
-                            if let Some(selected_password) = &state.selected_password_entry {
-                                if selected_password.id == password.id {
-                                    button::secondary
-                                } else {
-                                    button::primary
-                                }
-                            } else {
-                                button::primary
-                            },
-                        )
+        compromised_filter_view(state),
+        group_actions_view(state),
+        Scrollable::new(column(database_rows).spacing(5))
+            .direction(Direction::Vertical(Scrollbar::new()))
+            .height(Length::Fill),
+        button(
+            text(LOCALES.lookup(&state.locale, "open-another-database"))
+                .width(Length::Fill)
+                .align_x(Center)
+        )
+        .on_press(Message::Passwords(
+            PasswordsPageMessage::OpenAnotherDatabase
+        ))
+        .style(button::secondary)
+        .width(Length::Fill),
+    ]
+    .spacing(5)
+    .width(Length::FillPortion(1))
+    .into()
+}
+
+/// A row to show another open database in place of the shown one, followed by its entries matching the filter while
+/// filtering, so searches cover every open database
+fn other_database_view<'a>(
+    state: &'a PasswordsPage,
+    index: usize,
+    open_database: &'a OpenDatabase,
+) -> Vec<Element<'a, Message>> {
+    let is_filtering =
+        !state.current_passwords_list_filter.is_empty() || state.show_only_compromised;
+    let mut rows: Vec<Element<Message>> = vec![button(
+        text(database_name(open_database.path.as_ref()))
+            .size(18)
+            .font(Font {
+                weight: iced::font::Weight::Bold,
+                ..Default::default()
+            })
+            .width(Length::Fill),
+    )
+    .on_press(Message::Passwords(PasswordsPageMessage::ShowOpenDatabase(
+        index,
+    )))
+    .style(button::text)
+    .width(Length::Fill)
+    .into()];
+    if !is_filtering {
+        return rows;
+    }
+    rows.extend(
+        open_database
+            .passwords_list
+            .iter()
+            .filter(|password| password_matches_filter(state, password))
+            .map(|password| {
+                row![
+                    Space::with_width(15),
+                    button(
+                        text(if !password.title.is_empty() {
+                            password.title.clone()
+                        } else {
+                            LOCALES.lookup(&state.locale, "no-title")
+                        })
+                        .font(Font {
+                            weight: iced::font::Weight::Semibold,
+                            ..Default::default()
+                        })
                         .width(Length::Fill)
-                        .into()
-                    })
+                        .align_x(Horizontal::Center),
+                    )
+                    .on_press(Message::Passwords(
+                        PasswordsPageMessage::OpenEntryInDatabase(index, password.id)
+                    ))
+                    .width(Length::Fill)
+                ]
+                .into()
+            }),
+    );
+    rows
+}
+
+/// A checkbox to only show entries with compromised passwords, if breached password hashes have been set
+fn compromised_filter_view(state: &PasswordsPage) -> Element<Message> {
+    if state.breached_hashes_path.is_some() {
+        row![
+            checkbox(
+                format!(
+                    "{} ({})",
+                    LOCALES.lookup(&state.locale, "compromised"),
+                    state.breach_counts.len()
+                ),
+                state.show_only_compromised
             )
-            .spacing(5)
+            .on_toggle(|_| Message::Passwords(PasswordsPageMessage::ToggleShowOnlyCompromised))
+            .width(Length::Fill),
+            if state.is_checking_breaches {
+                text(LOCALES.lookup(&state.locale, "checking-passwords"))
+            } else {
+                text("")
+            },
+        ]
+        .align_y(Center)
+        .into()
+    } else {
+        column![].into()
+    }
+}
+
+/// The name field and buttons to create a group inside the selected group, and rename or delete the selected group.
+/// The selected group can also be picked to hold the secrets other apps store through the Secret Service
+fn group_actions_view(state: &PasswordsPage) -> Element<Message> {
+    let can_edit_name = !state.current_group_name_text.is_empty();
+    let is_shared_group = secret_service_group_id(&state.database) == Some(state.selected_group_id);
+    column![
+        text_input(
+            &LOCALES.lookup(&state.locale, "group-name"),
+            &state.current_group_name_text
         )
-        .direction(Direction::Vertical(Scrollbar::new()))
-        .height(Length::Fill)
-        .width(Length::FillPortion(1)),
+        .on_input(|s| Message::Passwords(PasswordsPageMessage::UpdateCurrentGroupNameText(s)))
+        .on_submit(Message::Passwords(PasswordsPageMessage::CreateGroup)),
+        row![
+            button(text(LOCALES.lookup(&state.locale, "new-group")))
+                .on_press_maybe(
+                    can_edit_name.then_some(Message::Passwords(PasswordsPageMessage::CreateGroup))
+                )
+                .style(button::success),
+            button(text(LOCALES.lookup(&state.locale, "rename-group"))).on_press_maybe(
+                can_edit_name.then_some(Message::Passwords(
+                    PasswordsPageMessage::RenameSelectedGroup
+                ))
+            ),
+            button(text(LOCALES.lookup(&state.locale, "delete-group")))
+                .on_press_maybe((state.selected_group_id != state.root_group.id).then_some(
+                    Message::Passwords(PasswordsPageMessage::DeleteSelectedGroup)
+                ))
+                .style(button::danger),
+        ]
+        .spacing(5),
+        if is_shared_group {
+            Element::from(text(
+                LOCALES.lookup(&state.locale, "group-shared-with-apps"),
+            ))
+        } else {
+            button(text(LOCALES.lookup(&state.locale, "share-group-with-apps")))
+                .on_press(Message::Passwords(
+                    PasswordsPageMessage::ShareSelectedGroupWithSecretService,
+                ))
+                .style(button::secondary)
+                .into()
+        },
     ]
     .spacing(5)
     .into()
 }
 
+fn password_matches_filter(state: &PasswordsPage, password: &Password) -> bool {
+    password
+        .title
+        .to_lowercase()
+        .contains(&state.current_passwords_list_filter.to_lowercase())
+        && (!state.show_only_compromised || state.breach_counts.contains_key(&password.id))
+}
+
+/// Whether the group or any group inside it has entries matching the filter
+fn group_has_matching_passwords(state: &PasswordsPage, group: &PasswordGroup) -> bool {
+    state
+        .passwords_list
+        .iter()
+        .any(|password| password.group_id == group.id && password_matches_filter(state, password))
+        || group
+            .child_groups
+            .iter()
+            .any(|child_group| group_has_matching_passwords(state, child_group))
+}
+
+/// A row for the group followed by, unless it's collapsed, its child groups and entries, indented by depth.
+/// While filtering, groups without matching entries are hidden and the rest are expanded
+fn group_tree_view<'a>(
+    state: &'a PasswordsPage,
+    group: &'a PasswordGroup,
+    depth: u16,
+) -> Vec<Element<'a, Message>> {
+    let is_filtering =
+        !state.current_passwords_list_filter.is_empty() || state.show_only_compromised;
+    if is_filtering && !group_has_matching_passwords(state, group) {
+        return vec![];
+    }
+    let is_collapsed = !is_filtering && state.collapsed_group_ids.contains(&group.id);
+    let mut rows: Vec<Element<Message>> = vec![row![
+        Space::with_width(depth * 15),
+        button(text(if is_collapsed { "+" } else { "-" }))
+            .on_press(Message::Passwords(
+                PasswordsPageMessage::ToggleGroupCollapsed(group.id)
+            ))
+            .style(button::text),
+        button(
+            text(group.name.clone())
+                .font(Font {
+                    weight: iced::font::Weight::Bold,
+                    ..Default::default()
+                })
+                .width(Length::Fill)
+        )
+        .on_press(Message::Passwords(PasswordsPageMessage::SelectGroup(
+            group.id
+        )))
+        .style(if state.selected_group_id == group.id {
+            button::secondary
+        } else {
+            button::text
+        })
+        .width(Length::Fill),
+    ]
+    .align_y(Center)
+    .into()];
+    if is_collapsed {
+        return rows;
+    }
+    group
+        .child_groups
+        .iter()
+        .for_each(|child_group| rows.extend(group_tree_view(state, child_group, depth + 1)));
+    rows.extend(
+        state
+            .passwords_list
+            .iter()
+            .filter(|password| {
+                password.group_id == group.id && password_matches_filter(state, password)
+            })
+            .map(|password| {
+                row![
+                    Space::with_width((depth + 1) * 15),
+                    button(
+                        text(if !password.title.is_empty() {
+                            password.title.clone()
+                        } else {
+                            LOCALES.lookup(&state.locale, "no-title")
+                        })
+                        .font(Font {
+                            weight: iced::font::Weight::Semibold,
+                            ..Default::default()
+                        })
+                        .width(Length::Fill)
+                        .align_x(Horizontal::Center),
+                    )
+                    .on_press(Message::Passwords(PasswordsPageMessage::SelectPassword(
+                        Some(password.clone()),
+                    )))
+                    .style(
+                        if let Some(selected_password) = &state.selected_password_entry {
+                            if selected_password.id == password.id {
+                                button::secondary
+                            } else {
+                                button::primary
+                            }
+                        } else {
+                            button::primary
+                        },
+                    )
+                    .width(Length::Fill)
+                ]
+                .into()
+            }),
+    );
+    rows
+}
+
+/// A group which an entry can be moved into in the entry edit view
+#[derive(Debug, Clone, PartialEq)]
+struct GroupOption {
+    group_id: uuid::Uuid,
+    path: String,
+}
+
+impl std::fmt::Display for GroupOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path)
+    }
+}
+
+fn entry_group_picker_view(state: &PasswordsPage) -> Element<Message> {
+    let group_options: Vec<GroupOption> = group_paths(&state.root_group)
+        .into_iter()
+        .map(|(group_id, path)| GroupOption { group_id, path })
+        .collect();
+    let selected_option = group_options
+        .iter()
+        .find(|group_option| group_option.group_id == state.current_entry_group_id)
+        .cloned();
+    pick_list(group_options, selected_option, |group_option| {
+        Message::Passwords(PasswordsPageMessage::SetCurrentEntryGroup(
+            group_option.group_id,
+        ))
+    })
+    .width(Length::Fill)
+    .into()
+}
+
+/// Another open database which the selected entry can be moved into
+#[derive(Debug, Clone, PartialEq)]
+struct DatabaseOption {
+    index: usize,
+    name: String,
+}
+
+impl std::fmt::Display for DatabaseOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Moves the selected entry into the root group of the picked database, if another database is open
+fn entry_database_picker_view(state: &PasswordsPage) -> Element<Message> {
+    if state.selected_password_entry.is_none() || state.other_databases.is_empty() {
+        return column![].into();
+    }
+    let database_options: Vec<DatabaseOption> = state
+        .other_databases
+        .iter()
+        .enumerate()
+        .map(|(index, open_database)| DatabaseOption {
+            index,
+            name: database_name(open_database.path.as_ref()),
+        })
+        .collect();
+    column![
+        text(LOCALES.lookup(&state.locale, "move-to-database")),
+        pick_list(
+            database_options,
+            None::<DatabaseOption>,
+            |database_option| {
+                Message::Passwords(PasswordsPageMessage::MoveSelectedEntryToDatabase(
+                    database_option.index,
+                ))
+            }
+        )
+        .placeholder(LOCALES.lookup(&state.locale, "pick-database"))
+        .width(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
 fn entry_edit_view(state: &PasswordsPage) -> Element<Message> {
     column![
         if state.selected_password_entry.is_none() {
@@ -411,6 +759,29 @@ fn entry_edit_view(state: &PasswordsPage) -> Element<Message> {
             } else {
                 column![]
             },
+            if state.selected_password_entry.is_none() {
+                column![Tooltip::new(
+                    button(
+                        Svg::new(svg::Handle::from_memory(include_bytes!(
+                            "../../../icons/settings.svg"
+                        )))
+                        .height(Length::Fill)
+                    )
+                    .on_press(Message::Passwords(
+                        PasswordsPageMessage::ToggleShowPasswordGenerator
+                    ))
+                    .style(if state.show_password_generator {
+                        button::secondary
+                    } else {
+                        button::primary
+                    })
+                    .width(Length::FillPortion(1)),
+                    text(LOCALES.lookup(&state.locale, "password-generator-options")),
+                    iced::widget::tooltip::Position::Bottom,
+                ),]
+            } else {
+                column![]
+            },
             Tooltip::new(
                 button(
                     Svg::new(svg::Handle::from_memory(include_bytes!(
@@ -418,7 +789,7 @@ fn entry_edit_view(state: &PasswordsPage) -> Element<Message> {
                     )))
                     .height(Length::Fill)
                 )
-                .on_press(Message::CopyValueToClipboard(
+                .on_press(Message::CopySecretToClipboard(
                     state.current_password_text.clone()
                 ))
                 .width(Length::FillPortion(1)),
@@ -427,6 +798,16 @@ fn entry_edit_view(state: &PasswordsPage) -> Element<Message> {
             )
         ]
         .height(Length::Shrink),
+        breach_warning_view(state),
+        if state.show_password_generator && state.selected_password_entry.is_none() {
+            password_generator_view(state)
+        } else {
+            column![].into()
+        },
+        totp_view(state),
+        text(LOCALES.lookup(&state.locale, "group")),
+        entry_group_picker_view(state),
+        entry_database_picker_view(state),
         button(text(if state.selected_password_entry.is_none() {
             LOCALES.lookup(&state.locale, "add-entry")
         } else {
@@ -443,6 +824,723 @@ fn entry_edit_view(state: &PasswordsPage) -> Element<Message> {
     .into()
 }
 
+/// A warning shown under the password of a selected entry whose saved password is in the breached password hashes
+fn breach_warning_view(state: &PasswordsPage) -> Element<Message> {
+    if let Some(breach_count) = state
+        .selected_password_entry
+        .as_ref()
+        .and_then(|password| state.breach_counts.get(&password.id))
+    {
+        text(format!(
+            "{} {breach_count} {}",
+            LOCALES.lookup(&state.locale, "password-found-in-breaches"),
+            LOCALES.lookup(&state.locale, "times")
+        ))
+        .style(text::danger)
+        .into()
+    } else {
+        column![].into()
+    }
+}
+
+/// Options for the generate password button, with saved presets and an estimate of how strong the results are
+fn password_generator_view(state: &PasswordsPage) -> Element<Message> {
+    let settings = &state.generator_settings;
+    let update_settings = |new_settings| {
+        Message::Passwords(PasswordsPageMessage::UpdateGeneratorSettings(new_settings))
+    };
+    let mode_options: Element<Message> = match settings.mode {
+        PasswordGeneratorMode::Characters => column![
+            row![
+                text(format!(
+                    "{} {}",
+                    LOCALES.lookup(&state.locale, "length"),
+                    settings.length
+                ))
+                .width(Length::Fixed(100.0)),
+                slider(4..=128, settings.length, move |length| update_settings(
+                    PasswordGeneratorSettings {
+                        length,
+                        ..settings.clone()
+                    }
+                )),
+            ]
+            .spacing(10),
+            row![
+                checkbox(
+                    LOCALES.lookup(&state.locale, "lowercase"),
+                    settings.use_lowercase
+                )
+                .on_toggle(move |use_lowercase| update_settings(
+                    PasswordGeneratorSettings {
+                        use_lowercase,
+                        ..settings.clone()
+                    }
+                )),
+                checkbox(
+                    LOCALES.lookup(&state.locale, "uppercase"),
+                    settings.use_uppercase
+                )
+                .on_toggle(move |use_uppercase| update_settings(
+                    PasswordGeneratorSettings {
+                        use_uppercase,
+                        ..settings.clone()
+                    }
+                )),
+                checkbox(LOCALES.lookup(&state.locale, "digits"), settings.use_digits).on_toggle(
+                    move |use_digits| update_settings(PasswordGeneratorSettings {
+                        use_digits,
+                        ..settings.clone()
+                    })
+                ),
+                checkbox(
+                    LOCALES.lookup(&state.locale, "symbols"),
+                    settings.use_symbols
+                )
+                .on_toggle(move |use_symbols| update_settings(
+                    PasswordGeneratorSettings {
+                        use_symbols,
+                        ..settings.clone()
+                    }
+                )),
+            ]
+            .spacing(20),
+            checkbox(
+                LOCALES.lookup(&state.locale, "exclude-look-alike-characters"),
+                settings.exclude_look_alike_characters
+            )
+            .on_toggle(move |exclude_look_alike_characters| update_settings(
+                PasswordGeneratorSettings {
+                    exclude_look_alike_characters,
+                    ..settings.clone()
+                }
+            )),
+            checkbox(
+                LOCALES.lookup(&state.locale, "include-each-character-class"),
+                settings.include_each_class
+            )
+            .on_toggle(move |include_each_class| update_settings(
+                PasswordGeneratorSettings {
+                    include_each_class,
+                    ..settings.clone()
+                }
+            )),
+        ]
+        .spacing(10)
+        .into(),
+        PasswordGeneratorMode::Passphrase => column![
+            row![
+                text(format!(
+                    "{} {}",
+                    LOCALES.lookup(&state.locale, "word-count"),
+                    settings.word_count
+                ))
+                .width(Length::Fixed(100.0)),
+                slider(3..=20, settings.word_count, move |word_count| {
+                    update_settings(PasswordGeneratorSettings {
+                        word_count,
+                        ..settings.clone()
+                    })
+                }),
+            ]
+            .spacing(10),
+            row![
+                text(LOCALES.lookup(&state.locale, "word-separator")),
+                text_input("", &settings.word_separator)
+                    .on_input(
+                        move |word_separator| update_settings(PasswordGeneratorSettings {
+                            word_separator,
+                            ..settings.clone()
+                        })
+                    )
+                    .width(Length::Fixed(60.0)),
+            ]
+            .spacing(10)
+            .align_y(Center),
+            checkbox(
+                LOCALES.lookup(&state.locale, "capitalise-words"),
+                settings.capitalise_words
+            )
+            .on_toggle(move |capitalise_words| update_settings(
+                PasswordGeneratorSettings {
+                    capitalise_words,
+                    ..settings.clone()
+                }
+            )),
+        ]
+        .spacing(10)
+        .into(),
+    };
+    let preset_name_exists = state
+        .generator_presets
+        .iter()
+        .any(|preset| preset.name == state.new_generator_preset_name_text);
+    container(
+        column![
+            row![
+                button(text(LOCALES.lookup(&state.locale, "random-characters")))
+                    .on_press(update_settings(PasswordGeneratorSettings {
+                        mode: PasswordGeneratorMode::Characters,
+                        ..settings.clone()
+                    }))
+                    .style(if settings.mode == PasswordGeneratorMode::Characters {
+                        button::primary
+                    } else {
+                        button::secondary
+                    }),
+                button(text(LOCALES.lookup(&state.locale, "passphrase")))
+                    .on_press(update_settings(PasswordGeneratorSettings {
+                        mode: PasswordGeneratorMode::Passphrase,
+                        ..settings.clone()
+                    }))
+                    .style(if settings.mode == PasswordGeneratorMode::Passphrase {
+                        button::primary
+                    } else {
+                        button::secondary
+                    }),
+            ]
+            .spacing(10),
+            mode_options,
+            text(format!(
+                "{} {:.0} {}",
+                LOCALES.lookup(&state.locale, "entropy"),
+                entropy_bits(settings),
+                LOCALES.lookup(&state.locale, "bits")
+            )),
+            row![
+                pick_list(
+                    state.generator_presets.clone(),
+                    None::<PasswordGeneratorPreset>,
+                    |preset| Message::Passwords(PasswordsPageMessage::LoadGeneratorPreset(preset))
+                )
+                .placeholder(LOCALES.lookup(&state.locale, "load-preset")),
+                text_input(
+                    &LOCALES.lookup(&state.locale, "preset-name"),
+                    &state.new_generator_preset_name_text
+                )
+                .on_input(|s| Message::Passwords(
+                    PasswordsPageMessage::UpdateNewGeneratorPresetNameText(s)
+                )),
+                button(text(LOCALES.lookup(&state.locale, "save-preset"))).on_press_maybe(
+                    (!state.new_generator_preset_name_text.is_empty()).then_some(
+                        Message::Passwords(PasswordsPageMessage::SaveGeneratorPreset)
+                    )
+                ),
+                button(text(LOCALES.lookup(&state.locale, "delete-preset")))
+                    .on_press_maybe(preset_name_exists.then(|| Message::Passwords(
+                        PasswordsPageMessage::DeleteGeneratorPreset(
+                            state.new_generator_preset_name_text.clone()
+                        )
+                    )))
+                    .style(button::danger),
+            ]
+            .spacing(10)
+            .align_y(Center),
+        ]
+        .spacing(10),
+    )
+    .padding(10)
+    .style(container::bordered_box)
+    .into()
+}
+
+/// The TOTP URI field with a button to read it from a QR code screenshot, and the live code once the URI is valid
+fn totp_view(state: &PasswordsPage) -> Element<Message> {
+    let totp_code_view: Element<Message> = if state.current_otp_text.is_empty() {
+        column![].into()
+    } else if let Some(totp_code) = current_totp_code(&state.current_otp_text) {
+        row![
+            text(totp_code.code.clone()).size(28).font(Font::MONOSPACE),
+            container(
+                progress_bar(
+                    0.0..=totp_code.period as f32,
+                    totp_code.seconds_remaining as f32
+                )
+                .width(Length::Fixed(100.0))
+                .height(Length::Fixed(10.0))
+                .style(progress_bar::primary)
+            )
+            .height(Length::Fixed(20.0))
+            .align_y(Center),
+            text(format!("{}s", totp_code.seconds_remaining)),
+            Tooltip::new(
+                button(
+                    Svg::new(svg::Handle::from_memory(include_bytes!(
+                        "../../../icons/copy.svg"
+                    )))
+                    .height(Length::Fixed(20.0))
+                )
+                .on_press(Message::CopySecretToClipboard(Zeroizing::new(
+                    totp_code.code
+                ))),
+                text(LOCALES.lookup(&state.locale, "copy")),
+                iced::widget::tooltip::Position::Bottom,
+            )
+        ]
+        .spacing(10)
+        .align_y(Center)
+        .into()
+    } else {
+        text(LOCALES.lookup(&state.locale, "invalid-totp-uri"))
+            .style(text::danger)
+            .into()
+    };
+    column![
+        text(LOCALES.lookup(&state.locale, "totp")),
+        row![
+            text_input(
+                &LOCALES.lookup(&state.locale, "paste-otpauth-uri"),
+                &state.current_otp_text
+            )
+            .on_input(|s| Message::Passwords(PasswordsPageMessage::UpdateCurrentOtpText(s)))
+            .secure(state.hide_current_password_entry)
+            .width(Length::FillPortion(9)),
+            Tooltip::new(
+                button(
+                    Svg::new(svg::Handle::from_memory(include_bytes!(
+                        "../../../icons/scanner.svg"
+                    )))
+                    .height(Length::Fill)
+                )
+                .on_press(Message::Passwords(
+                    PasswordsPageMessage::PickTotpQrCodeImage
+                ))
+                .width(Length::FillPortion(1)),
+                text(LOCALES.lookup(&state.locale, "scan-qr-code-from-image")),
+                iced::widget::tooltip::Position::Bottom,
+            )
+        ]
+        .height(Length::Shrink),
+        totp_code_view,
+    ]
+    .spacing(10)
+    .into()
+}
+
+/// The problems found with the entries, each of which opens its entry in the entry edit view when pressed
+fn health_report_view(state: &PasswordsPage) -> Element<Message> {
+    column![
+        row![
+            text(LOCALES.lookup(&state.locale, "password-health-report"))
+                .size(24)
+                .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "refresh"))).on_press_maybe(
+                (!state.is_checking_health).then_some(Message::Passwords(
+                    PasswordsPageMessage::RefreshHealthReport
+                ))
+            ),
+        ]
+        .align_y(Center),
+        text(if state.is_checking_health {
+            LOCALES.lookup(&state.locale, "checking-passwords")
+        } else if state.health_findings.is_empty() {
+            LOCALES.lookup(&state.locale, "no-problems-found")
+        } else {
+            format!(
+                "{} {}",
+                state.health_findings.len(),
+                LOCALES.lookup(&state.locale, "problems-found")
+            )
+        }),
+        Scrollable::new(
+            column(state.health_findings.iter().map(|finding| {
+                let title = state
+                    .passwords_list
+                    .iter()
+                    .find(|password| password.id == finding.password_id)
+                    .map(|password| {
+                        if password.title.is_empty() {
+                            LOCALES.lookup(&state.locale, "no-title")
+                        } else {
+                            password.title.clone()
+                        }
+                    })
+                    .unwrap_or_default();
+                button(row![
+                    text(title)
+                        .font(Font {
+                            weight: iced::font::Weight::Semibold,
+                            ..Default::default()
+                        })
+                        .width(Length::FillPortion(1)),
+                    text(health_issue_description(state, &finding.issue))
+                        .width(Length::FillPortion(2)),
+                ])
+                .on_press(Message::Passwords(PasswordsPageMessage::OpenHealthFinding(
+                    finding.password_id,
+                )))
+                .style(match finding.issue {
+                    HealthIssue::WeakPassword(_) | HealthIssue::ReusedPassword(_) => button::danger,
+                    _ => button::secondary,
+                })
+                .width(Length::Fill)
+                .into()
+            }))
+            .spacing(5)
+        )
+        .direction(Direction::Vertical(Scrollbar::new()))
+        .height(Length::Fill),
+    ]
+    .spacing(10)
+    .padding(20)
+    .height(Length::Fill)
+    .width(Length::FillPortion(2))
+    .into()
+}
+
+/// The entries read from an export, with duplicates marked, to check before they're added to the database
+fn import_preview_view<'a>(
+    state: &'a PasswordsPage,
+    imported_passwords: &'a [ImportedPassword],
+) -> Element<'a, Message> {
+    let duplicate_count = imported_passwords
+        .iter()
+        .filter(|imported_password| imported_password.is_duplicate)
+        .count();
+    let import_group_path = group_paths(&state.root_group)
+        .into_iter()
+        .find(|(group_id, _)| *group_id == state.selected_group_id)
+        .map(|(_, path)| path)
+        .unwrap_or_default();
+    column![
+        row![
+            text(LOCALES.lookup(&state.locale, "import-preview"))
+                .size(24)
+                .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "cancel")))
+                .on_press(Message::Passwords(PasswordsPageMessage::CancelImport))
+                .style(button::secondary),
+            button(text(LOCALES.lookup(&state.locale, "import-and-save")))
+                .on_press_maybe(
+                    (duplicate_count < imported_passwords.len())
+                        .then_some(Message::Passwords(PasswordsPageMessage::ConfirmImport))
+                )
+                .style(button::success),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        text(format!(
+            "{} {}, {} {}",
+            imported_passwords.len() - duplicate_count,
+            LOCALES.lookup(&state.locale, "new-entries"),
+            duplicate_count,
+            LOCALES.lookup(&state.locale, "duplicates-skipped")
+        )),
+        text(format!(
+            "{}: {import_group_path}",
+            LOCALES.lookup(&state.locale, "import-into-group")
+        )),
+        Scrollable::new(
+            column(imported_passwords.iter().map(|imported_password| {
+                row![
+                    text(imported_password.title.clone())
+                        .font(Font {
+                            weight: iced::font::Weight::Semibold,
+                            ..Default::default()
+                        })
+                        .width(Length::FillPortion(2)),
+                    text(imported_password.username.clone()).width(Length::FillPortion(2)),
+                    text(imported_password.folder_path.join(" / ")).width(Length::FillPortion(2)),
+                    if imported_password.is_duplicate {
+                        text(LOCALES.lookup(&state.locale, "duplicate")).style(text::danger)
+                    } else {
+                        text("")
+                    }
+                    .width(Length::FillPortion(1)),
+                ]
+                .spacing(10)
+                .into()
+            }))
+            .spacing(5)
+        )
+        .direction(Direction::Vertical(Scrollbar::new()))
+        .height(Length::Fill),
+    ]
+    .spacing(10)
+    .padding(20)
+    .height(Length::Fill)
+    .width(Length::FillPortion(2))
+    .into()
+}
+
+/// Asks whether a browser extension that sent an associate request may use the database, and what to call it
+fn browser_association_view(state: &PasswordsPage) -> Element<Message> {
+    let association_name = state.browser_association_name_text.trim();
+    let is_name_taken = is_association_name_taken(&state.database, association_name);
+    column![
+        text(LOCALES.lookup(&state.locale, "browser-association-request")).size(24),
+        text(LOCALES.lookup(&state.locale, "browser-association-description")),
+        text_input(
+            &LOCALES.lookup(&state.locale, "browser-association-name"),
+            &state.browser_association_name_text
+        )
+        .on_input(|s| Message::Passwords(
+            PasswordsPageMessage::UpdateBrowserAssociationNameText(s)
+        )),
+        if is_name_taken {
+            text(LOCALES.lookup(&state.locale, "browser-association-name-taken"))
+                .style(text::danger)
+        } else {
+            text("")
+        },
+        row![
+            button(text(LOCALES.lookup(&state.locale, "allow"))).on_press_maybe(
+                if association_name.is_empty() || is_name_taken {
+                    None
+                } else {
+                    Some(Message::Passwords(
+                        PasswordsPageMessage::ConfirmBrowserAssociation(true),
+                    ))
+                }
+            ),
+            button(text(LOCALES.lookup(&state.locale, "deny")))
+                .on_press(Message::Passwords(
+                    PasswordsPageMessage::ConfirmBrowserAssociation(false)
+                ))
+                .style(button::secondary),
+        ]
+        .spacing(10),
+    ]
+    .spacing(10)
+    .padding(20)
+    .height(Length::Fill)
+    .width(Length::FillPortion(2))
+    .into()
+}
+
+/// Asks whether an SSH client may sign with one of the database's keys
+fn ssh_key_use_view<'a>(
+    state: &'a PasswordsPage,
+    request: &SshAgentRequest,
+) -> Element<'a, Message> {
+    let key_title = match &request.action {
+        SshAgentAction::Sign { key_blob, .. } => state
+            .ssh_keys
+            .iter()
+            .find(|ssh_key| &ssh_key.key_blob == key_blob)
+            .map(|ssh_key| ssh_key.title.as_str())
+            .unwrap_or(""),
+        SshAgentAction::ListIdentities => "",
+    };
+    column![
+        text(LOCALES.lookup(&state.locale, "ssh-key-use-request")).size(24),
+        text(LOCALES.lookup(&state.locale, "ssh-key-use-description")),
+        text(key_title).font(Font {
+            weight: iced::font::Weight::Bold,
+            ..Default::default()
+        }),
+        row![
+            button(text(LOCALES.lookup(&state.locale, "allow"))).on_press(Message::Passwords(
+                PasswordsPageMessage::ConfirmSshKeyUse(true)
+            )),
+            button(text(LOCALES.lookup(&state.locale, "deny")))
+                .on_press(Message::Passwords(PasswordsPageMessage::ConfirmSshKeyUse(
+                    false
+                )))
+                .style(button::secondary),
+        ]
+        .spacing(10),
+    ]
+    .spacing(10)
+    .padding(20)
+    .height(Length::Fill)
+    .width(Length::FillPortion(2))
+    .into()
+}
+
+/// Asks for the password of the encrypted bundle picked for import
+fn import_bundle_password_view(state: &PasswordsPage) -> Element<Message> {
+    column![
+        row![
+            text(LOCALES.lookup(&state.locale, "import-passwords"))
+                .size(24)
+                .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "cancel")))
+                .on_press(Message::Passwords(PasswordsPageMessage::CancelImport))
+                .style(button::secondary),
+        ]
+        .align_y(Center),
+        text(LOCALES.lookup(&state.locale, "enter-bundle-password")),
+        row![
+            text_input(
+                &LOCALES.lookup(&state.locale, "bundle-password"),
+                &state.import_bundle_password_text
+            )
+            .secure(true)
+            .on_input(
+                |s| Message::Passwords(PasswordsPageMessage::UpdateImportBundlePasswordText(s))
+            )
+            .on_submit(Message::Passwords(
+                PasswordsPageMessage::DecryptImportBundle
+            )),
+            button(text(LOCALES.lookup(&state.locale, "open-bundle"))).on_press(
+                Message::Passwords(PasswordsPageMessage::DecryptImportBundle)
+            ),
+        ]
+        .spacing(10),
+    ]
+    .spacing(10)
+    .padding(20)
+    .height(Length::Fill)
+    .width(Length::FillPortion(2))
+    .into()
+}
+
+/// Picks which groups and tags to export and in what format, the master password has to be re-entered to export
+fn export_view(state: &PasswordsPage) -> Element<Message> {
+    let selected_count =
+        selected_entry_count(&state.database, &state.export_group_ids, &state.export_tags);
+    let can_export = selected_count > 0
+        && !state.is_exporting
+        && (state.export_format == ExportFormat::Csv
+            || !state.export_bundle_password_text.is_empty());
+    let tags = database_tags(&state.database);
+    column![
+        row![
+            text(LOCALES.lookup(&state.locale, "export-passwords"))
+                .size(24)
+                .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "cancel")))
+                .on_press(Message::Passwords(
+                    PasswordsPageMessage::ToggleShowExportView
+                ))
+                .style(button::secondary),
+        ]
+        .align_y(Center),
+        row![
+            button(text(LOCALES.lookup(&state.locale, "csv-file")))
+                .on_press(Message::Passwords(PasswordsPageMessage::SetExportFormat(
+                    ExportFormat::Csv
+                )))
+                .style(if state.export_format == ExportFormat::Csv {
+                    button::primary
+                } else {
+                    button::secondary
+                }),
+            button(text(LOCALES.lookup(&state.locale, "encrypted-bundle")))
+                .on_press(Message::Passwords(PasswordsPageMessage::SetExportFormat(
+                    ExportFormat::EncryptedBundle
+                )))
+                .style(if state.export_format == ExportFormat::EncryptedBundle {
+                    button::primary
+                } else {
+                    button::secondary
+                }),
+        ]
+        .spacing(10),
+        match state.export_format {
+            ExportFormat::Csv => Element::from(
+                text(LOCALES.lookup(&state.locale, "csv-export-warning")).style(text::danger),
+            ),
+            ExportFormat::EncryptedBundle => text_input(
+                &LOCALES.lookup(&state.locale, "bundle-password"),
+                &state.export_bundle_password_text
+            )
+            .secure(true)
+            .on_input(
+                |s| Message::Passwords(PasswordsPageMessage::UpdateExportBundlePasswordText(s))
+            )
+            .into(),
+        },
+        row![
+            column![
+                text(LOCALES.lookup(&state.locale, "export-groups")).size(18),
+                Scrollable::new(
+                    column(
+                        group_paths(&state.root_group)
+                            .into_iter()
+                            .map(|(group_id, path)| {
+                                checkbox(path, state.export_group_ids.contains(&group_id))
+                                    .on_toggle(move |_| {
+                                        Message::Passwords(PasswordsPageMessage::ToggleExportGroup(
+                                            group_id,
+                                        ))
+                                    })
+                                    .into()
+                            })
+                    )
+                    .spacing(5)
+                )
+                .direction(Direction::Vertical(Scrollbar::new()))
+                .height(Length::Fill),
+            ]
+            .spacing(5)
+            .width(Length::Fill),
+            column![
+                text(LOCALES.lookup(&state.locale, "export-tags")).size(18),
+                Scrollable::new(
+                    column(tags.into_iter().map(|tag| {
+                        checkbox(tag.clone(), state.export_tags.contains(&tag))
+                            .on_toggle(move |_| {
+                                Message::Passwords(PasswordsPageMessage::ToggleExportTag(
+                                    tag.clone(),
+                                ))
+                            })
+                            .into()
+                    }))
+                    .spacing(5)
+                )
+                .direction(Direction::Vertical(Scrollbar::new()))
+                .height(Length::Fill),
+            ]
+            .spacing(5)
+            .width(Length::Fill),
+        ]
+        .spacing(10)
+        .height(Length::Fill),
+        text(format!(
+            "{selected_count} {}",
+            LOCALES.lookup(&state.locale, "entries-selected")
+        )),
+        text(LOCALES.lookup(&state.locale, "re-enter-master-password-to-export")),
+        row![
+            text_input(
+                &LOCALES.lookup(&state.locale, "master-password"),
+                &state.export_master_password_text
+            )
+            .secure(true)
+            .on_input(
+                |s| Message::Passwords(PasswordsPageMessage::UpdateExportMasterPasswordText(s))
+            )
+            .on_submit_maybe(
+                can_export.then_some(Message::Passwords(PasswordsPageMessage::StartExport))
+            ),
+            button(text(LOCALES.lookup(&state.locale, "export")))
+                .on_press_maybe(
+                    can_export.then_some(Message::Passwords(PasswordsPageMessage::StartExport))
+                )
+                .style(button::success),
+        ]
+        .spacing(10),
+    ]
+    .spacing(10)
+    .padding(20)
+    .height(Length::Fill)
+    .width(Length::FillPortion(2))
+    .into()
+}
+
+fn health_issue_description(state: &PasswordsPage, issue: &HealthIssue) -> String {
+    match issue {
+        HealthIssue::WeakPassword(score) => format!(
+            "{} ({score}/4)",
+            LOCALES.lookup(&state.locale, "weak-password")
+        ),
+        HealthIssue::ReusedPassword(use_count) => format!(
+            "{} {use_count} {}",
+            LOCALES.lookup(&state.locale, "password-used-by"),
+            LOCALES.lookup(&state.locale, "entries")
+        ),
+        HealthIssue::OldPassword(last_modified) => format!(
+            "{} {}",
+            LOCALES.lookup(&state.locale, "not-changed-since"),
+            last_modified.format("%Y-%m-%d")
+        ),
+        HealthIssue::EmptyUsername => LOCALES.lookup(&state.locale, "empty-username"),
+        HealthIssue::InsecureUrl => LOCALES.lookup(&state.locale, "insecure-url"),
+    }
+}
+
 fn existing_database_selected_and_locked_view(state: &PasswordsPage) -> Element<Message> {
     column![
         text(LOCALES.lookup(
@@ -591,6 +1689,40 @@ fn no_database_selected_view(state: &PasswordsPage) -> Element<Message> {
 
 pub fn tool_view(state: &PasswordsPage) -> Element<Message> {
     if state.is_unlocked {
+        let underlay = Tooltip::new(
+            button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                "../../../icons/view-more.svg"
+            ))))
+            .on_press(Message::Passwords(
+                PasswordsPageMessage::ToggleExtraToolsMenu,
+            )),
+            text(LOCALES.lookup(&state.locale, "more-tools")),
+            iced::widget::tooltip::Position::Bottom,
+        );
+        let overlay = column![
+            button(
+                text(LOCALES.lookup(&state.locale, "import-passwords"))
+                    .width(Length::Fill)
+                    .align_x(Center),
+            )
+            .on_press(Message::Passwords(PasswordsPageMessage::ImportPasswords)),
+            button(
+                text(LOCALES.lookup(&state.locale, "export-passwords"))
+                    .width(Length::Fill)
+                    .align_x(Center),
+            )
+            .on_press(Message::Passwords(
+                PasswordsPageMessage::ToggleShowExportView
+            )),
+        ]
+        .spacing(5)
+        .width(Length::Fixed(200.0));
+        let drop_down = DropDown::new(underlay, overlay, state.show_extra_tools_menu)
+            .on_dismiss(Message::Passwords(
+                PasswordsPageMessage::ToggleExtraToolsMenu,
+            ))
+            .width(Length::Fill)
+            .alignment(drop_down::Alignment::Bottom);
         row![
             Tooltip::new(
                 button(Svg::new(svg::Handle::from_memory(include_bytes!(
@@ -623,6 +1755,21 @@ pub fn tool_view(state: &PasswordsPage) -> Element<Message> {
                 text(LOCALES.lookup(&state.locale, "lock-shortcut")),
                 iced::widget::tooltip::Position::Bottom
             ),
+            Tooltip::new(
+                button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                    "../../../icons/key.svg"
+                ))))
+                .on_press(Message::Passwords(
+                    PasswordsPageMessage::ToggleShowHealthReport
+                ))
+                .style(if state.show_health_report {
+                    button::secondary
+                } else {
+                    button::primary
+                }),
+                text(LOCALES.lookup(&state.locale, "password-health-report")),
+                iced::widget::tooltip::Position::Bottom
+            ),
             Tooltip::new(
                 button(Svg::new(svg::Handle::from_memory(include_bytes!(
                     "../../../icons/ok.svg"
@@ -635,11 +1782,13 @@ pub fn tool_view(state: &PasswordsPage) -> Element<Message> {
                 }),
                 text(LOCALES.lookup(&state.locale, "save-changes")),
                 iced::widget::tooltip::Position::Bottom,
-            )
+            ),
+            drop_down
         ]
         .width(Length::FillPortion(1))
         .into()
-    } else if state.is_creating_new_keepass_file {
+    } else if state.is_creating_new_keepass_file || !state.other_databases.is_empty() {
+        // Going back while opening another database shows the databases already open
         row![Tooltip::new(
             button(Svg::new(svg::Handle::from_memory(include_bytes!(
                 "../../../icons/back.svg"
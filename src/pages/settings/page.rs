@@ -4,6 +4,8 @@ use iced::{Element, Task, Theme};
 
 use crate::app::Message;
 use crate::config::AppConfig;
+use crate::pages::passwords::page::PasswordGeneratorPreset;
+use crate::pages::tasks::{page::RunningTimer, tasks_utils::SavedTaskFilter};
 
 use super::update::update;
 use super::view::{main_view, tool_view};
@@ -49,6 +51,8 @@ pub struct SettingsPage {
     pub(crate) save_message: String,
     pub(crate) current_tab: SettingsTab,
     pub(crate) server_url_editor_text: String,
+    pub(crate) caldav_server_port_editor_text: String,
+    pub(crate) caldav_server_password_editor_text: String,
 }
 
 #[derive(Debug, Clone)]
@@ -69,6 +73,20 @@ pub enum SettingsPageMessage {
     PasswordsPickDefaultDatabase,
     PasswordsSetDefaultDatabase(Option<PathBuf>),
     PasswordsSetShowSidebarOnStart(bool),
+    PasswordsSetGeneratorPresets(Vec<PasswordGeneratorPreset>),
+    PasswordsSetMaxPasswordAgeMonths(u8),
+    PasswordsPickBreachedHashesFile,
+    PasswordsPickBreachedHashesFolder,
+    PasswordsSetBreachedHashesPath(Option<PathBuf>),
+    PasswordsSetClipboardClearSeconds(u16),
+    PasswordsSetAutoLockMinutes(u16),
+    PasswordsSetLockOnScreenLock(bool),
+    PasswordsSetLockOnMinimise(bool),
+    PasswordsSetBrowserIntegrationEnabled(bool),
+    PasswordsInstallBrowserManifests,
+    PasswordsSetSecretServiceEnabled(bool),
+    PasswordsSetSshAgentEnabled(bool),
+    PasswordsSetSshAgentConfirmUse(bool),
     TasksPickDefaultProjectFolder,
     TasksSetDefaultProjectFolder(Option<PathBuf>),
     TasksPickDefaultProjectFile,
@@ -78,6 +96,16 @@ pub enum SettingsPageMessage {
     TasksSetConfirmBeforeDelete(bool),
     TasksSetRightClickToEditTask(bool),
     TasksSetShowTaskCompletionToolbar(bool),
+    TasksSetSavedFilters(Vec<SavedTaskFilter>),
+    TasksSetCaldavServerEnabled(bool),
+    TasksUpdateCaldavServerPort(String),
+    TasksSetCaldavServerPort,
+    TasksUpdateCaldavServerPassword(String),
+    TasksSetCaldavServerPassword,
+    TasksSetCaldavServerPasswordHash(String),
+    TasksSetCaldavServerAllowNetworkAccess(bool),
+    TasksSetRunningTimer(Option<RunningTimer>),
+    TasksSetReminderNotificationsEnabled(bool),
     GalleryPickDefaultFolder,
     GallerySetDefaultFolder(Option<PathBuf>),
     GallerySetRunThumbnailGenerationOnStart(bool),
@@ -103,6 +131,8 @@ impl SettingsPage {
             current_tab: SettingsTab::General,
             save_message: String::from("Settings saved"),
             server_url_editor_text: app_config.sync_config.server_url.clone(),
+            caldav_server_port_editor_text: app_config.tasks_config.caldav_server_port.to_string(),
+            caldav_server_password_editor_text: String::new(),
         }
     }
 
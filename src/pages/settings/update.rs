@@ -9,6 +9,8 @@ use crate::pages::gallery::page::GalleryPageMessage;
 use crate::pages::notes::page::NotesPageMessage;
 use crate::pages::passwords::page::PasswordsPageMessage;
 use crate::pages::tasks::page::TasksPageMessage;
+use crate::utils::browser_integration_utils::install_native_messaging_manifests;
+use crate::utils::{caldav_utils, secret_service_utils};
 use crate::{app::Message, Page};
 
 use super::page::{SettingsPage, SettingsPageMessage};
@@ -115,6 +117,118 @@ pub fn update(
         SettingsPageMessage::PasswordsSetShowSidebarOnStart(b) => {
             app_config.passwords_config.show_sidebar_on_start = b;
         }
+        SettingsPageMessage::PasswordsSetGeneratorPresets(generator_presets) => {
+            app_config.passwords_config.generator_presets = generator_presets;
+        }
+        SettingsPageMessage::PasswordsSetMaxPasswordAgeMonths(months) => {
+            app_config.passwords_config.max_password_age_months = months;
+            return Task::done(Message::Passwords(
+                PasswordsPageMessage::SetMaxPasswordAgeMonths(months),
+            ));
+        }
+        SettingsPageMessage::PasswordsPickBreachedHashesFile => {
+            return Task::perform(
+                async { FileDialog::new().add_filter("text", &["txt"]).pick_file() },
+                |selected_file| {
+                    Message::Settings(SettingsPageMessage::PasswordsSetBreachedHashesPath(
+                        selected_file,
+                    ))
+                },
+            );
+        }
+        SettingsPageMessage::PasswordsPickBreachedHashesFolder => {
+            return Task::perform(
+                async { FileDialog::new().pick_folder() },
+                |selected_folder| {
+                    Message::Settings(SettingsPageMessage::PasswordsSetBreachedHashesPath(
+                        selected_folder,
+                    ))
+                },
+            );
+        }
+        SettingsPageMessage::PasswordsSetBreachedHashesPath(breached_hashes_path) => {
+            app_config.passwords_config.breached_hashes_path = breached_hashes_path.clone();
+            return Task::done(Message::SaveConfig).chain(Task::done(Message::Passwords(
+                PasswordsPageMessage::SetBreachedHashesPath(breached_hashes_path),
+            )));
+        }
+        SettingsPageMessage::PasswordsSetClipboardClearSeconds(seconds) => {
+            app_config.passwords_config.clipboard_clear_seconds = seconds;
+        }
+        SettingsPageMessage::PasswordsSetAutoLockMinutes(minutes) => {
+            app_config.passwords_config.auto_lock_minutes = minutes;
+            return Task::done(Message::Passwords(
+                PasswordsPageMessage::SetAutoLockMinutes(minutes),
+            ));
+        }
+        SettingsPageMessage::PasswordsSetLockOnScreenLock(b) => {
+            app_config.passwords_config.lock_on_screen_lock = b;
+            return Task::done(Message::SaveConfig).chain(Task::done(Message::Passwords(
+                PasswordsPageMessage::SetLockOnScreenLock(b),
+            )));
+        }
+        SettingsPageMessage::PasswordsSetLockOnMinimise(b) => {
+            app_config.passwords_config.lock_on_minimise = b;
+            return Task::done(Message::SaveConfig).chain(Task::done(Message::Passwords(
+                PasswordsPageMessage::SetLockOnMinimise(b),
+            )));
+        }
+        SettingsPageMessage::PasswordsSetBrowserIntegrationEnabled(b) => {
+            app_config.passwords_config.browser_integration_enabled = b;
+        }
+        SettingsPageMessage::PasswordsInstallBrowserManifests => {
+            return Task::perform(install_native_messaging_manifests(), |result| {
+                match result {
+                    Ok((manifest_paths, _)) if manifest_paths.is_empty() => Message::ShowToast(
+                        false,
+                        String::from("No supported browsers were found to install into"),
+                    ),
+                    Ok((manifest_paths, backup_paths)) if backup_paths.is_empty() => {
+                        Message::ShowToast(
+                            true,
+                            format!(
+                                "Installed browser integration for {} browsers",
+                                manifest_paths.len()
+                            ),
+                        )
+                    }
+                    Ok((manifest_paths, backup_paths)) => Message::ShowToast(
+                        true,
+                        format!(
+                            "Installed browser integration for {} browsers, the previous manifests were backed up to {}",
+                            manifest_paths.len(),
+                            backup_paths
+                                .iter()
+                                .map(|backup_path| backup_path.to_string_lossy())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    ),
+                    Err(err) => Message::ShowToast(
+                        false,
+                        format!("Couldn't install browser integration: {err}"),
+                    ),
+                }
+            });
+        }
+        SettingsPageMessage::PasswordsSetSecretServiceEnabled(b) => {
+            app_config.passwords_config.secret_service_enabled = b;
+            if let Some(command) = secret_service_utils::flatpak_override_command().filter(|_| b) {
+                return Task::done(Message::SaveConfig).chain(Task::done(Message::ShowToast(
+                    true,
+                    format!("To let the Flatpak provide the Secret Service, run: {command}"),
+                )));
+            }
+        }
+        SettingsPageMessage::PasswordsSetSshAgentEnabled(b) => {
+            app_config.passwords_config.ssh_agent_enabled = b;
+        }
+        SettingsPageMessage::PasswordsSetSshAgentConfirmUse(b) => {
+            app_config.passwords_config.ssh_agent_confirm_use = b;
+            return Task::done(Message::SaveConfig).chain(Task::done(Message::Passwords(
+                PasswordsPageMessage::SetSshAgentConfirmUse(b),
+            )));
+        }
         SettingsPageMessage::TasksPickDefaultProjectFolder => {
             return Task::perform(
                 async {
@@ -185,6 +299,71 @@ pub fn update(
                 TasksPageMessage::SetRightClickToEditTask(b),
             )));
         }
+        SettingsPageMessage::TasksSetSavedFilters(saved_filters) => {
+            app_config.tasks_config.saved_filters = saved_filters;
+        }
+        SettingsPageMessage::TasksSetCaldavServerEnabled(b) => {
+            app_config.tasks_config.caldav_server_enabled = b;
+        }
+        SettingsPageMessage::TasksUpdateCaldavServerPort(s) => {
+            state.caldav_server_port_editor_text = s;
+            return Task::none();
+        }
+        SettingsPageMessage::TasksSetCaldavServerPort => {
+            match state.caldav_server_port_editor_text.trim().parse::<u16>() {
+                Ok(port) if port != 0 => {
+                    app_config.tasks_config.caldav_server_port = port;
+                    return Task::done(Message::SaveConfig).chain(Task::done(Message::ShowToast(
+                        true,
+                        format!("CalDAV server port set to {port}"),
+                    )));
+                }
+                _ => {
+                    return Task::done(Message::ShowToast(
+                        false,
+                        String::from("Invalid port, must be a number between 1 and 65535"),
+                    ))
+                }
+            }
+        }
+        SettingsPageMessage::TasksUpdateCaldavServerPassword(s) => {
+            state.caldav_server_password_editor_text = s;
+            return Task::none();
+        }
+        SettingsPageMessage::TasksSetCaldavServerPassword => {
+            if state.caldav_server_password_editor_text.is_empty() {
+                return Task::done(Message::ShowToast(
+                    false,
+                    String::from("The CalDAV server password can't be empty"),
+                ));
+            }
+            let password = std::mem::take(&mut state.caldav_server_password_editor_text);
+            return Task::perform(
+                async move { caldav_utils::hash_password(&password) },
+                |password_hash_result| match password_hash_result {
+                    Ok(password_hash) => Message::Settings(
+                        SettingsPageMessage::TasksSetCaldavServerPasswordHash(password_hash),
+                    ),
+                    Err(err) => Message::ShowToast(false, err),
+                },
+            );
+        }
+        SettingsPageMessage::TasksSetCaldavServerPasswordHash(password_hash) => {
+            app_config.tasks_config.caldav_server_password_hash = password_hash;
+            return Task::done(Message::SaveConfig).chain(Task::done(Message::ShowToast(
+                true,
+                String::from("CalDAV server password set"),
+            )));
+        }
+        SettingsPageMessage::TasksSetCaldavServerAllowNetworkAccess(b) => {
+            app_config.tasks_config.caldav_server_allow_network_access = b;
+        }
+        SettingsPageMessage::TasksSetRunningTimer(running_timer) => {
+            app_config.tasks_config.running_timer = running_timer;
+        }
+        SettingsPageMessage::TasksSetReminderNotificationsEnabled(b) => {
+            app_config.tasks_config.reminder_notifications_enabled = b;
+        }
         SettingsPageMessage::GalleryPickDefaultFolder => {
             return Task::perform(
                 async {
@@ -2,12 +2,14 @@ use fluent_templates::Loader;
 use iced::{
     border,
     widget::{
-        button, column, container, pick_list, row, scrollable, text, text_input, toggler, Space,
+        button, column, container, pick_list, row, scrollable, slider, text, text_input, toggler,
+        Space,
     },
     Alignment, Background, Element, Length, Theme,
 };
 use iced_aw::Spinner;
 
+use crate::utils::{secret_service_utils, ssh_agent_utils};
 use crate::{app::Message, Page};
 use crate::{config::AppConfig, LOCALES};
 
@@ -209,6 +211,74 @@ fn tasks_tab<'a>(state: &'a SettingsPage, app_config: &'a AppConfig) -> Element<
                     .on_toggle(|b| Message::Settings(
                         SettingsPageMessage::TasksSetRightClickToEditTask(b)
                     )),
+                toggler(app_config.tasks_config.reminder_notifications_enabled)
+                    .label(LOCALES.lookup(&state.locale, "enable-reminder-notifications"))
+                    .on_toggle(|b| Message::Settings(
+                        SettingsPageMessage::TasksSetReminderNotificationsEnabled(b)
+                    )),
+                toggler(app_config.tasks_config.caldav_server_enabled)
+                    .label(LOCALES.lookup(&state.locale, "enable-caldav-server"))
+                    .on_toggle(|b| Message::Settings(
+                        SettingsPageMessage::TasksSetCaldavServerEnabled(b)
+                    )),
+                toggler(app_config.tasks_config.caldav_server_allow_network_access)
+                    .label(LOCALES.lookup(&state.locale, "caldav-server-allow-network-access"))
+                    .on_toggle(|b| Message::Settings(
+                        SettingsPageMessage::TasksSetCaldavServerAllowNetworkAccess(b)
+                    )),
+                text(if app_config.tasks_config.caldav_server_allow_network_access {
+                    format!(
+                        "{}: http://<{}>:{}/",
+                        LOCALES.lookup(&state.locale, "caldav-server-address"),
+                        LOCALES.lookup(&state.locale, "this-computers-ip-address"),
+                        app_config.tasks_config.caldav_server_port
+                    )
+                } else {
+                    format!(
+                        "{}: http://127.0.0.1:{}/",
+                        LOCALES.lookup(&state.locale, "caldav-server-address"),
+                        app_config.tasks_config.caldav_server_port
+                    )
+                }),
+                row![
+                    text(LOCALES.lookup(&state.locale, "caldav-server-port")),
+                    Space::with_width(Length::Fixed(20.0)),
+                    text_input(
+                        &LOCALES.lookup(&state.locale, "caldav-server-port"),
+                        &state.caldav_server_port_editor_text
+                    )
+                    .width(Length::Fixed(200.0))
+                    .on_input(|s| Message::Settings(
+                        SettingsPageMessage::TasksUpdateCaldavServerPort(s)
+                    ))
+                    .on_submit(Message::Settings(
+                        SettingsPageMessage::TasksSetCaldavServerPort
+                    )),
+                    button(text(LOCALES.lookup(&state.locale, "set-port"))).on_press(
+                        Message::Settings(SettingsPageMessage::TasksSetCaldavServerPort)
+                    )
+                ]
+                .width(Length::Fill),
+                row![
+                    text(LOCALES.lookup(&state.locale, "caldav-server-password")),
+                    Space::with_width(Length::Fixed(20.0)),
+                    text_input(
+                        &LOCALES.lookup(&state.locale, "caldav-server-password-placeholder"),
+                        &state.caldav_server_password_editor_text
+                    )
+                    .secure(true)
+                    .width(Length::Fixed(200.0))
+                    .on_input(|s| Message::Settings(
+                        SettingsPageMessage::TasksUpdateCaldavServerPassword(s)
+                    ))
+                    .on_submit(Message::Settings(
+                        SettingsPageMessage::TasksSetCaldavServerPassword
+                    )),
+                    button(text(LOCALES.lookup(&state.locale, "set-password"))).on_press(
+                        Message::Settings(SettingsPageMessage::TasksSetCaldavServerPassword)
+                    )
+                ]
+                .width(Length::Fill),
             ]
             .padding(20)
             .spacing(30),
@@ -324,6 +394,164 @@ fn passwords_tab<'a>(state: &'a SettingsPage, app_config: &'a AppConfig) -> Elem
                     .on_toggle(|b| Message::Settings(
                         SettingsPageMessage::PasswordsSetShowSidebarOnStart(b)
                     )),
+                row![
+                    text(format!(
+                        "{} {}",
+                        LOCALES.lookup(&state.locale, "max-password-age-months"),
+                        app_config.passwords_config.max_password_age_months
+                    ))
+                    .width(Length::FillPortion(1)),
+                    slider(
+                        1..=60,
+                        app_config.passwords_config.max_password_age_months,
+                        |months| Message::Settings(
+                            SettingsPageMessage::PasswordsSetMaxPasswordAgeMonths(months)
+                        )
+                    )
+                    .on_release(Message::SaveConfig)
+                    .width(Length::FillPortion(2)),
+                ]
+                .spacing(20),
+                row![
+                    text(format!(
+                        "{} {}",
+                        LOCALES.lookup(&state.locale, "clipboard-clear-seconds"),
+                        if app_config.passwords_config.clipboard_clear_seconds == 0 {
+                            LOCALES.lookup(&state.locale, "never")
+                        } else {
+                            app_config
+                                .passwords_config
+                                .clipboard_clear_seconds
+                                .to_string()
+                        }
+                    ))
+                    .width(Length::FillPortion(1)),
+                    slider(
+                        0..=300,
+                        app_config.passwords_config.clipboard_clear_seconds,
+                        |seconds| Message::Settings(
+                            SettingsPageMessage::PasswordsSetClipboardClearSeconds(seconds)
+                        )
+                    )
+                    .step(5u16)
+                    .on_release(Message::SaveConfig)
+                    .width(Length::FillPortion(2)),
+                ]
+                .spacing(20),
+                row![
+                    text(format!(
+                        "{} {}",
+                        LOCALES.lookup(&state.locale, "auto-lock-minutes"),
+                        if app_config.passwords_config.auto_lock_minutes == 0 {
+                            LOCALES.lookup(&state.locale, "never")
+                        } else {
+                            app_config.passwords_config.auto_lock_minutes.to_string()
+                        }
+                    ))
+                    .width(Length::FillPortion(1)),
+                    slider(
+                        0..=120,
+                        app_config.passwords_config.auto_lock_minutes,
+                        |minutes| Message::Settings(
+                            SettingsPageMessage::PasswordsSetAutoLockMinutes(minutes)
+                        )
+                    )
+                    .on_release(Message::SaveConfig)
+                    .width(Length::FillPortion(2)),
+                ]
+                .spacing(20),
+                toggler(app_config.passwords_config.lock_on_screen_lock)
+                    .label(LOCALES.lookup(&state.locale, "lock-on-screen-lock"))
+                    .on_toggle(|b| Message::Settings(
+                        SettingsPageMessage::PasswordsSetLockOnScreenLock(b)
+                    )),
+                toggler(app_config.passwords_config.lock_on_minimise)
+                    .label(LOCALES.lookup(&state.locale, "lock-on-minimise"))
+                    .on_toggle(|b| Message::Settings(
+                        SettingsPageMessage::PasswordsSetLockOnMinimise(b)
+                    )),
+                row![
+                    toggler(app_config.passwords_config.browser_integration_enabled)
+                        .label(LOCALES.lookup(&state.locale, "enable-browser-integration"))
+                        .on_toggle(|b| Message::Settings(
+                            SettingsPageMessage::PasswordsSetBrowserIntegrationEnabled(b)
+                        ))
+                        .width(Length::Fill),
+                    button(text(
+                        LOCALES.lookup(&state.locale, "install-browser-integration")
+                    ))
+                    .on_press(Message::Settings(
+                        SettingsPageMessage::PasswordsInstallBrowserManifests
+                    )),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+                toggler(app_config.passwords_config.secret_service_enabled)
+                    .label(LOCALES.lookup(&state.locale, "enable-secret-service"))
+                    .on_toggle(|b| Message::Settings(
+                        SettingsPageMessage::PasswordsSetSecretServiceEnabled(b)
+                    )),
+                text(
+                    secret_service_utils::flatpak_override_command()
+                        .map(|command| format!(
+                            "{}: {command}",
+                            LOCALES.lookup(&state.locale, "secret-service-flatpak-override")
+                        ))
+                        .unwrap_or_default()
+                ),
+                toggler(app_config.passwords_config.ssh_agent_enabled)
+                    .label(LOCALES.lookup(&state.locale, "enable-ssh-agent"))
+                    .on_toggle(|b| Message::Settings(
+                        SettingsPageMessage::PasswordsSetSshAgentEnabled(b)
+                    )),
+                toggler(app_config.passwords_config.ssh_agent_confirm_use)
+                    .label(LOCALES.lookup(&state.locale, "ssh-agent-confirm-use"))
+                    .on_toggle(|b| Message::Settings(
+                        SettingsPageMessage::PasswordsSetSshAgentConfirmUse(b)
+                    )),
+                text(format!(
+                    "{}: SSH_AUTH_SOCK={}",
+                    LOCALES.lookup(&state.locale, "ssh-agent-socket"),
+                    ssh_agent_utils::socket_path().display()
+                )),
+                row![
+                    text(
+                        app_config
+                            .passwords_config
+                            .breached_hashes_path
+                            .as_ref()
+                            .map(|value| format!(
+                                "{}: {value:?}",
+                                LOCALES.lookup(&state.locale, "breached-password-hashes")
+                            ))
+                            .unwrap_or(
+                                LOCALES
+                                    .lookup(&state.locale, "no-breached-password-hashes-selected")
+                            )
+                    )
+                    .align_x(Alignment::Center)
+                    .width(Length::Fill),
+                    button(text(LOCALES.lookup(&state.locale, "select-hash-file"))).on_press(
+                        Message::Settings(SettingsPageMessage::PasswordsPickBreachedHashesFile)
+                    ),
+                    button(text(LOCALES.lookup(&state.locale, "select-range-folder"))).on_press(
+                        Message::Settings(SettingsPageMessage::PasswordsPickBreachedHashesFolder)
+                    ),
+                    button(text(LOCALES.lookup(&state.locale, "clear")))
+                        .on_press_maybe(
+                            app_config
+                                .passwords_config
+                                .breached_hashes_path
+                                .is_some()
+                                .then_some(Message::Settings(
+                                    SettingsPageMessage::PasswordsSetBreachedHashesPath(None)
+                                ))
+                        )
+                        .style(button::danger),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .width(Length::Fill),
             ]
             .padding(20)
             .spacing(30),
@@ -0,0 +1,230 @@
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke, Text};
+use iced::{Color, Pixels, Point, Rectangle, Renderer, Size, Theme};
+
+use crate::app::Message;
+
+use super::tasks_utils::FlowDay;
+
+/// Space left around the plot for the axis labels
+const LEFT_MARGIN: f32 = 40.0;
+const BOTTOM_MARGIN: f32 = 24.0;
+const TOP_MARGIN: f32 = 24.0;
+const RIGHT_MARGIN: f32 = 10.0;
+const LABEL_SIZE: f32 = 12.0;
+
+/// Stacked areas of how many tasks were in each Kanban column on each day, with Done at the bottom
+pub struct CumulativeFlowChart {
+    pub flow_days: Vec<FlowDay>,
+    /// The names of the Backlog, To Do, Doing and Done columns, for the legend
+    pub column_names: [String; 4],
+}
+
+/// A line of how many tasks weren't done yet on each day
+pub struct BurndownChart {
+    pub flow_days: Vec<FlowDay>,
+}
+
+impl canvas::Program<Message> for CumulativeFlowChart {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let plot_area = plot_area(bounds.size());
+        let max_value = self
+            .flow_days
+            .iter()
+            .map(|flow_day| flow_day.column_counts.iter().sum::<usize>())
+            .max()
+            .unwrap_or_default()
+            .max(1);
+        let column_colours = column_colours(theme);
+
+        // Done is at the bottom, with each column's band stacked on the ones after it on the board
+        for column_index in 0..4 {
+            let band_top: Vec<usize> = self
+                .flow_days
+                .iter()
+                .map(|flow_day| flow_day.column_counts[column_index..].iter().sum())
+                .collect();
+            let band_bottom: Vec<usize> = self
+                .flow_days
+                .iter()
+                .map(|flow_day| flow_day.column_counts[column_index + 1..].iter().sum())
+                .collect();
+            let mut band_outline = points(&band_top, max_value, plot_area);
+            band_outline.extend(points(&band_bottom, max_value, plot_area).into_iter().rev());
+            let band = Path::new(|builder| {
+                band_outline
+                    .iter()
+                    .enumerate()
+                    .for_each(|(point_index, point)| {
+                        if point_index == 0 {
+                            builder.move_to(*point);
+                        } else {
+                            builder.line_to(*point);
+                        }
+                    });
+                builder.close();
+            });
+            frame.fill(&band, column_colours[column_index]);
+        }
+
+        self.column_names
+            .iter()
+            .zip(column_colours)
+            .enumerate()
+            .for_each(|(column_index, (column_name, column_colour))| {
+                frame.fill_text(Text {
+                    content: column_name.clone(),
+                    position: Point::new(LEFT_MARGIN + column_index as f32 * 100.0, 4.0),
+                    color: column_colour,
+                    size: Pixels(LABEL_SIZE),
+                    ..Text::default()
+                });
+            });
+        draw_axes(&mut frame, theme, plot_area, max_value, &self.flow_days);
+        vec![frame.into_geometry()]
+    }
+}
+
+impl canvas::Program<Message> for BurndownChart {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let plot_area = plot_area(bounds.size());
+        let open_tasks: Vec<usize> = self.flow_days.iter().map(FlowDay::open_tasks).collect();
+        let max_value = open_tasks.iter().copied().max().unwrap_or_default().max(1);
+
+        let line = Path::new(|builder| {
+            points(&open_tasks, max_value, plot_area)
+                .into_iter()
+                .enumerate()
+                .for_each(|(point_index, point)| {
+                    if point_index == 0 {
+                        builder.move_to(point);
+                    } else {
+                        builder.line_to(point);
+                    }
+                });
+        });
+        frame.stroke(
+            &line,
+            Stroke::default()
+                .with_color(theme.palette().primary)
+                .with_width(2.0),
+        );
+        draw_axes(&mut frame, theme, plot_area, max_value, &self.flow_days);
+        vec![frame.into_geometry()]
+    }
+}
+
+fn plot_area(size: Size) -> Rectangle {
+    Rectangle {
+        x: LEFT_MARGIN,
+        y: TOP_MARGIN,
+        width: (size.width - LEFT_MARGIN - RIGHT_MARGIN).max(1.0),
+        height: (size.height - TOP_MARGIN - BOTTOM_MARGIN).max(1.0),
+    }
+}
+
+/// Spreads the values evenly across the plot, a single day is drawn as a flat line across it
+fn points(values: &[usize], max_value: usize, plot_area: Rectangle) -> Vec<Point> {
+    let y = |value: usize| plot_area.y + plot_area.height * (1.0 - value as f32 / max_value as f32);
+    match values {
+        [value] => vec![
+            Point::new(plot_area.x, y(*value)),
+            Point::new(plot_area.x + plot_area.width, y(*value)),
+        ],
+        _ => values
+            .iter()
+            .enumerate()
+            .map(|(value_index, value)| {
+                Point::new(
+                    plot_area.x + value_index as f32 * plot_area.width / (values.len() - 1) as f32,
+                    y(*value),
+                )
+            })
+            .collect(),
+    }
+}
+
+fn draw_axes(
+    frame: &mut Frame,
+    theme: &Theme,
+    plot_area: Rectangle,
+    max_value: usize,
+    flow_days: &[FlowDay],
+) {
+    let text_colour = theme.palette().text;
+    let axes = Path::new(|builder| {
+        builder.move_to(Point::new(plot_area.x, plot_area.y));
+        builder.line_to(Point::new(plot_area.x, plot_area.y + plot_area.height));
+        builder.line_to(Point::new(
+            plot_area.x + plot_area.width,
+            plot_area.y + plot_area.height,
+        ));
+    });
+    frame.stroke(
+        &axes,
+        Stroke::default().with_color(text_colour).with_width(1.0),
+    );
+
+    let label = |content: String, position: Point| Text {
+        content,
+        position,
+        color: text_colour,
+        size: Pixels(LABEL_SIZE),
+        ..Text::default()
+    };
+    frame.fill_text(label(
+        max_value.to_string(),
+        Point::new(4.0, plot_area.y - LABEL_SIZE / 2.0),
+    ));
+    frame.fill_text(label(
+        String::from("0"),
+        Point::new(4.0, plot_area.y + plot_area.height - LABEL_SIZE / 2.0),
+    ));
+    let label_y = plot_area.y + plot_area.height + 6.0;
+    if let Some(first_day) = flow_days.first() {
+        frame.fill_text(label(
+            first_day.date.format("%Y-%m-%d").to_string(),
+            Point::new(plot_area.x, label_y),
+        ));
+    }
+    if let Some(last_day) = flow_days.last().filter(|_| flow_days.len() > 1) {
+        frame.fill_text(Text {
+            horizontal_alignment: iced::alignment::Horizontal::Right,
+            ..label(
+                last_day.date.format("%Y-%m-%d").to_string(),
+                Point::new(plot_area.x + plot_area.width, label_y),
+            )
+        });
+    }
+}
+
+/// The colours for the Backlog, To Do, Doing and Done columns
+fn column_colours(theme: &Theme) -> [Color; 4] {
+    let palette = theme.extended_palette();
+    [
+        palette.background.strong.color,
+        palette.primary.base.color,
+        palette.danger.base.color,
+        palette.success.base.color,
+    ]
+}
@@ -1,3 +1,5 @@
+mod charts;
 pub mod page;
+pub mod tasks_utils;
 mod update;
 mod view;
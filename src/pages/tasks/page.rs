@@ -1,15 +1,20 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, PathBuf};
+use std::time::Duration;
 
+use chrono::{DateTime, NaiveDate, Utc};
 use iced::event::Status;
 use iced::keyboard::key::Named;
 use iced::keyboard::{self, Key, Modifiers};
 use iced::widget::text_editor;
-use iced::{event, Element, Event, Task};
+use iced::{event, time, Element, Event, Subscription, Task};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::app::Message;
 
+use super::tasks_utils::{SavedTaskFilter, TaskActivity, TaskFileFormat, TaskSummary};
 use super::update::update;
 use super::view::{main_view, tool_view};
 
@@ -17,10 +22,18 @@ pub const ARCHIVED_FILE_NAME: &str = ".archived";
 pub const TASK_TITLE_TEXT_INPUT_ID: &str = "TASK_TITLE_TEXT_INPUT_ID";
 pub const NEW_PROJECT_TEXT_INPUT_ID: &str = "NEW_PROJECT_TEXT_INPUT_ID";
 pub const RENAME_PROJECT_TEXT_INPUT_ID: &str = "RENAME_PROJECT_TEXT_INPUT_ID";
+pub const SEARCH_ALL_PROJECTS_TEXT_INPUT_ID: &str = "SEARCH_ALL_PROJECTS_TEXT_INPUT_ID";
 pub const BACKLOG_ID: &str = "BACKLOG_ID";
 pub const TODO_ID: &str = "TODO_ID";
 pub const DOING_ID: &str = "DOING_ID";
 pub const DONE_ID: &str = "DONE_ID";
+pub const NOTE_LINK_SCHEME: &str = "note://";
+/// Keeps the folder separators readable in note links
+const NOTE_PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'.')
+    .remove(b'-')
+    .remove(b'_');
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TaskViewType {
@@ -28,7 +41,7 @@ pub enum TaskViewType {
     List,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TaskCompletionState {
     Backlog,
     ToDo,
@@ -42,6 +55,126 @@ pub struct TaskData {
     pub title: String,
     pub description: String,
     pub completion_state: TaskCompletionState,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
+    /// The IDs of the tasks which have to be done before this one can start, which can be in other projects
+    #[serde(default)]
+    pub blocked_by: Vec<Uuid>,
+    /// When to show a desktop notification reminding about this task, if at all
+    #[serde(default)]
+    pub reminder: Option<DateTime<Utc>>,
+    /// The note checklist item this task was created from, if any, which gets ticked when the task is done
+    #[serde(default)]
+    pub note_link: Option<NoteLink>,
+    /// Every change of completion state, oldest first, used for the project statistics
+    #[serde(default)]
+    pub state_transitions: Vec<StateTransition>,
+}
+
+impl TaskData {
+    /// Changes the completion state, recording the transition if it is different, and returns the previous state
+    pub fn set_completion_state(
+        &mut self,
+        completion_state: TaskCompletionState,
+    ) -> TaskCompletionState {
+        if self.completion_state != completion_state {
+            self.state_transitions.push(StateTransition {
+                timestamp: Utc::now(),
+                from: Some(self.completion_state.clone()),
+                to: completion_state.clone(),
+            });
+        }
+        std::mem::replace(&mut self.completion_state, completion_state)
+    }
+
+    /// Records that the task was created now, unless it already has a history, such as when importing a JSON project
+    pub fn record_created(&mut self) {
+        if self.state_transitions.is_empty() {
+            self.state_transitions.push(StateTransition {
+                timestamp: Utc::now(),
+                from: None,
+                to: self.completion_state.clone(),
+            });
+        }
+    }
+}
+
+/// A task moving between Kanban columns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateTransition {
+    pub timestamp: DateTime<Utc>,
+    /// None when the task was created
+    pub from: Option<TaskCompletionState>,
+    pub to: TaskCompletionState,
+}
+
+/// Points to a line in a note, with the path relative to the notes folder so it still works on other synced devices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteLink {
+    pub note_path: PathBuf,
+    /// Counted from 0, but shown and written in links counted from 1
+    pub line: usize,
+}
+
+impl NoteLink {
+    /// Formats the link so it can be written into task descriptions and notes, like note://Meetings/Weekly.md#L12
+    pub fn to_url(&self) -> String {
+        format!(
+            "{NOTE_LINK_SCHEME}{}#L{}",
+            utf8_percent_encode(&self.note_path.to_string_lossy(), NOTE_PATH_ENCODE_SET),
+            self.line + 1
+        )
+    }
+
+    pub fn from_url(url: &str) -> Option<Self> {
+        let (encoded_note_path, line_str) = match url
+            .strip_prefix(NOTE_LINK_SCHEME)?
+            .split_once('#')
+        {
+            Some((encoded_note_path, fragment)) => (encoded_note_path, fragment.strip_prefix('L')),
+            None => (url.strip_prefix(NOTE_LINK_SCHEME)?, None),
+        };
+        let note_path = PathBuf::from(
+            percent_decode_str(encoded_note_path)
+                .decode_utf8()
+                .ok()?
+                .to_string(),
+        );
+        // Links can only point inside the notes folder
+        if note_path.as_os_str().is_empty()
+            || note_path
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+        Some(Self {
+            note_path,
+            line: line_str
+                .and_then(|line_str| line_str.parse::<usize>().ok())
+                .unwrap_or(1)
+                .saturating_sub(1),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// A timer which is currently running on a task, saved in the config so it keeps running after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningTimer {
+    pub project_file: PathBuf,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub started_at: DateTime<Utc>,
 }
 
 impl Default for TaskData {
@@ -51,6 +184,13 @@ impl Default for TaskData {
             title: String::new(),
             description: String::new(),
             completion_state: TaskCompletionState::Backlog,
+            labels: vec![],
+            due_date: None,
+            time_entries: vec![],
+            blocked_by: vec![],
+            reminder: None,
+            note_link: None,
+            state_transitions: vec![],
         }
     }
 }
@@ -64,6 +204,30 @@ pub struct TaskPageConfig {
     pub confirm_before_delete: bool,
     pub show_task_completion_toolbar: bool,
     pub right_click_to_edit_task: bool,
+    #[serde(default)]
+    pub saved_filters: Vec<SavedTaskFilter>,
+    #[serde(default)]
+    pub caldav_server_enabled: bool,
+    #[serde(default = "default_caldav_server_port")]
+    pub caldav_server_port: u16,
+    /// Calendar apps on other devices can only connect if this is set, otherwise the server only listens on this computer
+    #[serde(default)]
+    pub caldav_server_allow_network_access: bool,
+    /// The Argon2 hash of the password clients log in with, the server won't start without one
+    #[serde(default)]
+    pub caldav_server_password_hash: String,
+    #[serde(default)]
+    pub running_timer: Option<RunningTimer>,
+    #[serde(default = "default_reminder_notifications_enabled")]
+    pub reminder_notifications_enabled: bool,
+}
+
+fn default_caldav_server_port() -> u16 {
+    5232
+}
+
+fn default_reminder_notifications_enabled() -> bool {
+    true
 }
 
 impl Default for TaskPageConfig {
@@ -76,6 +240,13 @@ impl Default for TaskPageConfig {
             confirm_before_delete: true,
             show_task_completion_toolbar: false,
             right_click_to_edit_task: true,
+            saved_filters: vec![],
+            caldav_server_enabled: false,
+            caldav_server_port: default_caldav_server_port(),
+            caldav_server_allow_network_access: false,
+            caldav_server_password_hash: String::new(),
+            running_timer: None,
+            reminder_notifications_enabled: default_reminder_notifications_enabled(),
         }
     }
 }
@@ -137,6 +308,61 @@ pub struct TasksPage {
     pub(crate) archived_list: Vec<String>,
     /// Whether to list projects which have been archived
     pub(crate) show_archived_projects: bool,
+    /// The contents of the task labels field, comma separated
+    pub(crate) current_task_labels_text: String,
+    /// The contents of the task due date field, in YYYY-MM-DD format
+    pub(crate) current_task_due_date_text: String,
+    /// The contents of the task reminder field, in YYYY-MM-DD HH:MM format in local time
+    pub(crate) current_task_reminder_text: String,
+    /// Whether to show the UI to search the tasks of every project in the folder
+    pub(crate) show_search_all_projects_view: bool,
+    /// The string to search every project's tasks for
+    pub(crate) search_all_projects_text: String,
+    /// Whether the search across projects should include archived projects
+    pub(crate) search_include_archived_projects: bool,
+    /// The tasks matching the search across projects, along with the project file they're in
+    pub(crate) search_all_projects_results: Vec<(PathBuf, TaskData)>,
+    /// The ID of a task to open for editing once its project has finished loading, if any
+    pub(crate) task_to_open_after_project_load: Option<Uuid>,
+    /// The filter queries saved by the user
+    pub(crate) saved_filters: Vec<SavedTaskFilter>,
+    /// The index of the saved filter currently being applied, if any
+    pub(crate) selected_saved_filter: Option<usize>,
+    /// Whether the user is in the middle of creating a new saved filter
+    pub(crate) is_creating_new_saved_filter: bool,
+    /// The saved filter being built by the new filter UI
+    pub(crate) new_saved_filter: SavedTaskFilter,
+    /// The contents of the due date field of the new saved filter, in YYYY-MM-DD format
+    pub(crate) new_saved_filter_due_date_text: String,
+    /// The timer currently running on a task, if any, only one can run at a time
+    pub(crate) running_timer: Option<RunningTimer>,
+    /// Whether to show the report of time tracked on the current project
+    pub(crate) show_time_report_view: bool,
+    /// Every task in every project in the folder, used to show which tasks block each other across projects
+    pub(crate) task_index: HashMap<Uuid, TaskSummary>,
+    /// The IDs of the tasks blocking the task being edited
+    pub(crate) current_task_blocked_by: Vec<Uuid>,
+    /// Whether to show the graph of which tasks block each other in the current project
+    pub(crate) show_dependency_graph_view: bool,
+    /// Whether to show the cumulative flow diagram, burndown and cycle time of the current project
+    pub(crate) show_statistics_view: bool,
+    /// Earlier versions of the current project's tasks, newest last, to go back to with undo
+    pub(crate) undo_stack: Vec<Vec<TaskData>>,
+    /// Versions of the current project's tasks which were undone, newest last, to go back to with redo
+    pub(crate) redo_stack: Vec<Vec<TaskData>>,
+    /// The tasks as they were last loaded or saved, compared against when saving to add to the activity log
+    pub(crate) saved_tasks_list: Vec<TaskData>,
+    /// The activity log entries for the task being edited, newest first
+    pub(crate) current_task_history: Vec<TaskActivity>,
+    /// The tasks selected with Ctrl or Shift click, which the bulk actions apply to
+    pub(crate) selected_task_ids: HashSet<Uuid>,
+    /// The task last Ctrl or Shift clicked, where a Shift click selects from
+    pub(crate) last_clicked_task_id: Option<Uuid>,
+    /// The modifier keys currently held, used to tell selecting clicks apart from dragging
+    pub(crate) keyboard_modifiers: Modifiers,
+    /// The labels to add to every selected task, comma separated
+    pub(crate) bulk_labels_text: String,
+    pub(crate) show_confirm_bulk_delete_dialog: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -184,6 +410,66 @@ pub enum TasksPageMessage {
     ToggleShowSidebar,
     ToggleConfirmBeforeDeleteDialog,
     ToggleTaskViewType,
+    UpdateTaskLabels(String),
+    UpdateTaskDueDate(String),
+    UpdateTaskReminder(String),
+    ToggleSearchAllProjectsView,
+    ToggleSearchIncludeArchivedProjects,
+    UpdateSearchAllProjectsText(String),
+    SearchAllProjects,
+    SetSearchAllProjectsResults(Vec<(PathBuf, TaskData)>),
+    OpenSearchResult(PathBuf, Uuid),
+    SelectSavedFilter(Option<usize>),
+    DeleteSavedFilter(usize),
+    StartCreatingNewSavedFilter,
+    CancelCreateNewSavedFilter,
+    CreateNewSavedFilter,
+    UpdateNewSavedFilterName(String),
+    UpdateNewSavedFilterText(String),
+    UpdateNewSavedFilterLabel(String),
+    UpdateNewSavedFilterDueDate(String),
+    SetNewSavedFilterCompletionState(Option<TaskCompletionState>),
+    ImportTasks,
+    ImportTasksFromFile(Option<PathBuf>),
+    AddImportedTasks(Result<Vec<TaskData>, String>),
+    ExportTasks(TaskFileFormat),
+    ReloadProjectFile(PathBuf),
+    StartTimer(Uuid),
+    StopTimer,
+    TimerTick,
+    ToggleTimeReportView,
+    ExportTimeReport,
+    LoadTaskIndex,
+    SetTaskIndex(HashMap<Uuid, TaskSummary>),
+    AddBlockerToCurrentTask(Uuid),
+    RemoveBlockerFromCurrentTask(Uuid),
+    ToggleDependencyGraphView,
+    ToggleStatisticsView,
+    Undo,
+    Redo,
+    SetCurrentTaskHistory(Vec<TaskActivity>),
+    OpenNoteLink(NoteLink),
+    SendProjectsListToNotesPage,
+    AddTasksToProject(PathBuf, Vec<TaskData>),
+    SetKeyboardModifiers(Modifiers),
+    /// Ctrl click toggles the task in the selection, Shift click selects every task between it and the last one clicked
+    TaskClicked(Uuid),
+    SelectAllInColumn(TaskCompletionState),
+    ClearTaskSelection,
+    BulkSetTaskCompletionState(TaskCompletionState),
+    BulkDeleteTasksWithConfirmationCheck,
+    ToggleConfirmBulkDeleteDialog,
+    BulkDeleteTasks,
+    UpdateBulkLabelsText(String),
+    BulkAddLabels,
+    /// Moves the selected tasks to the project with this name
+    BulkMoveToProject(String),
+    /// Removes tasks from the project file they were moved from once they've been saved to the named project
+    RemoveMovedTasks(PathBuf, String, Vec<Uuid>),
+    /// Marks the task in this project file as done the same way as in the app, even if the project isn't open
+    MarkTaskDoneInProject(PathBuf, Uuid),
+    /// A task in a project which isn't open was marked as done
+    TaskMarkedDoneInProject(TaskData),
 }
 
 impl TasksPage {
@@ -225,6 +511,34 @@ impl TasksPage {
             filter_projects_text: String::new(),
             archived_list: vec![],
             show_archived_projects: false,
+            current_task_labels_text: String::new(),
+            current_task_due_date_text: String::new(),
+            current_task_reminder_text: String::new(),
+            show_search_all_projects_view: false,
+            search_all_projects_text: String::new(),
+            search_include_archived_projects: false,
+            search_all_projects_results: vec![],
+            task_to_open_after_project_load: None,
+            saved_filters: config.saved_filters.clone(),
+            selected_saved_filter: None,
+            is_creating_new_saved_filter: false,
+            new_saved_filter: SavedTaskFilter::default(),
+            new_saved_filter_due_date_text: String::new(),
+            running_timer: config.running_timer.clone(),
+            show_time_report_view: false,
+            task_index: HashMap::new(),
+            current_task_blocked_by: vec![],
+            show_dependency_graph_view: false,
+            show_statistics_view: false,
+            undo_stack: vec![],
+            redo_stack: vec![],
+            saved_tasks_list: vec![],
+            current_task_history: vec![],
+            selected_task_ids: HashSet::new(),
+            last_clicked_task_id: None,
+            keyboard_modifiers: Modifiers::default(),
+            bulk_labels_text: String::new(),
+            show_confirm_bulk_delete_dialog: false,
         }
     }
 
@@ -244,38 +558,74 @@ impl TasksPage {
         main_view(self)
     }
 
-    pub fn subscription() -> iced::Subscription<Message> {
-        // Keyboard shortcuts
-        event::listen_with(|event, status, _id| match (event, status) {
-            (
-                Event::Keyboard(keyboard::Event::KeyPressed {
-                    key: Key::Character(pressed_char),
-                    modifiers: Modifiers::CTRL,
-                    ..
-                }),
-                _,
-            ) => {
-                if pressed_char.as_ref() == "n" || pressed_char.as_ref() == "N" {
-                    Some(Message::Tasks(TasksPageMessage::StartCreatingNewTask))
-                } else if pressed_char.as_ref() == "b" || pressed_char.as_ref() == "B" {
-                    Some(Message::Tasks(TasksPageMessage::ToggleShowSidebar))
-                } else if pressed_char.as_ref() == "l" || pressed_char.as_ref() == "L" {
-                    Some(Message::Tasks(TasksPageMessage::ToggleTaskViewType))
-                } else if pressed_char.as_ref() == "s" || pressed_char.as_ref() == "S" {
-                    Some(Message::Tasks(TasksPageMessage::UpdateCurrentTask))
-                } else {
-                    None
+    pub fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions_vec = vec![
+            // Keyboard shortcuts
+            event::listen_with(|event, status, _id| match (event, status) {
+                (
+                    Event::Keyboard(keyboard::Event::KeyPressed {
+                        key: Key::Character(pressed_char),
+                        modifiers: Modifiers::CTRL,
+                        ..
+                    }),
+                    _,
+                ) => {
+                    if pressed_char.as_ref() == "n" || pressed_char.as_ref() == "N" {
+                        Some(Message::Tasks(TasksPageMessage::StartCreatingNewTask))
+                    } else if pressed_char.as_ref() == "b" || pressed_char.as_ref() == "B" {
+                        Some(Message::Tasks(TasksPageMessage::ToggleShowSidebar))
+                    } else if pressed_char.as_ref() == "l" || pressed_char.as_ref() == "L" {
+                        Some(Message::Tasks(TasksPageMessage::ToggleTaskViewType))
+                    } else if pressed_char.as_ref() == "s" || pressed_char.as_ref() == "S" {
+                        Some(Message::Tasks(TasksPageMessage::UpdateCurrentTask))
+                    } else if pressed_char.as_ref() == "f" || pressed_char.as_ref() == "F" {
+                        Some(Message::Tasks(
+                            TasksPageMessage::ToggleSearchAllProjectsView,
+                        ))
+                    } else if (pressed_char.as_ref() == "z" || pressed_char.as_ref() == "Z")
+                        && status == Status::Ignored
+                    {
+                        // Left to the text editor when one is focused, so it undoes the typing instead
+                        Some(Message::Tasks(TasksPageMessage::Undo))
+                    } else {
+                        None
+                    }
                 }
-            }
-            (
-                Event::Keyboard(keyboard::Event::KeyPressed {
-                    key: Key::Named(Named::Escape),
-                    ..
-                }),
-                Status::Ignored,
-            ) => Some(Message::Tasks(TasksPageMessage::EscapeKeyPressed)),
-            _ => None,
-        })
+                (
+                    Event::Keyboard(keyboard::Event::KeyPressed {
+                        key: Key::Character(pressed_char),
+                        modifiers,
+                        ..
+                    }),
+                    Status::Ignored,
+                ) if modifiers == Modifiers::CTRL | Modifiers::SHIFT => {
+                    if pressed_char.as_ref() == "z" || pressed_char.as_ref() == "Z" {
+                        Some(Message::Tasks(TasksPageMessage::Redo))
+                    } else {
+                        None
+                    }
+                }
+                (
+                    Event::Keyboard(keyboard::Event::KeyPressed {
+                        key: Key::Named(Named::Escape),
+                        ..
+                    }),
+                    Status::Ignored,
+                ) => Some(Message::Tasks(TasksPageMessage::EscapeKeyPressed)),
+                (Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)), _) => Some(
+                    Message::Tasks(TasksPageMessage::SetKeyboardModifiers(modifiers)),
+                ),
+                _ => None,
+            }),
+        ];
+        if self.running_timer.is_some() {
+            // Keeps the running timer display up to date
+            subscriptions_vec.push(
+                time::every(Duration::from_secs(1))
+                    .map(|_| Message::Tasks(TasksPageMessage::TimerTick)),
+            );
+        }
+        Subscription::batch(subscriptions_vec)
     }
 
     pub fn tool_view(&self) -> Element<Message> {
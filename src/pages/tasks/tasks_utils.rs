@@ -0,0 +1,2027 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::Write,
+    mem,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use loro::{Container, LoroDoc, LoroMap, LoroResult, LoroText, LoroValue, ValueOrContainer};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use uuid::Uuid;
+
+use crate::constants::LORO_TASKS_ID;
+
+use super::page::{NoteLink, TaskCompletionState, TaskData, TimeEntry, ARCHIVED_FILE_NAME};
+
+const REMINDER_FORMAT: &str = "%Y-%m-%d %H:%M";
+const ACTIVITY_LOG_EXTENSION: &str = "activity.jsonl";
+const CRDT_POSITION_FIELD: &str = "position";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SavedTaskFilter {
+    /// The name to display for this filter in the sidebar
+    pub name: String,
+    /// Only match tasks in this completion state, if any
+    pub completion_state: Option<TaskCompletionState>,
+    /// Only match tasks which have this label, if any
+    pub label: Option<String>,
+    /// Only match tasks due on or before this date, if any
+    pub due_on_or_before: Option<NaiveDate>,
+    /// Only match tasks whose title, description or labels contain this string, if not empty
+    pub text: String,
+}
+
+impl SavedTaskFilter {
+    pub fn matches(&self, task: &TaskData) -> bool {
+        self.completion_state
+            .as_ref()
+            .is_none_or(|completion_state| *completion_state == task.completion_state)
+            && self.label.as_ref().is_none_or(|label| {
+                task.labels
+                    .iter()
+                    .any(|task_label| task_label.eq_ignore_ascii_case(label))
+            })
+            && self.due_on_or_before.is_none_or(|due_on_or_before| {
+                task.due_date
+                    .is_some_and(|due_date| due_date <= due_on_or_before)
+            })
+            && task_matches_text(task, &self.text)
+    }
+}
+
+/// Case insensitive check for whether the title, description or any of the labels of a task contain the given text
+pub fn task_matches_text(task: &TaskData, text: &str) -> bool {
+    let text = text.to_lowercase();
+    task.title.to_lowercase().contains(&text)
+        || task.description.to_lowercase().contains(&text)
+        || task
+            .labels
+            .iter()
+            .any(|label| label.to_lowercase().contains(&text))
+}
+
+/// Parses a comma separated list of labels, ignoring empty entries
+pub fn parse_labels(labels_text: &str) -> Vec<String> {
+    labels_text
+        .split(',')
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect()
+}
+
+pub fn read_tasks_from_project_file(project_file: &Path) -> Vec<TaskData> {
+    if let Ok(task_json) = fs::read_to_string(project_file) {
+        serde_json::from_str(&task_json).unwrap_or_default()
+    } else {
+        vec![]
+    }
+}
+
+/// Lists the project files in the folder, sorted by path, optionally leaving out archived projects
+pub fn list_project_files(selected_folder: &Path, include_archived_projects: bool) -> Vec<PathBuf> {
+    let archived_list: Vec<String> = if let Ok(archived_projects_json) =
+        fs::read_to_string(selected_folder.join(ARCHIVED_FILE_NAME))
+    {
+        serde_json::from_str(&archived_projects_json).unwrap_or_default()
+    } else {
+        vec![]
+    };
+    let mut projects_list: Vec<PathBuf> = match fs::read_dir(selected_folder) {
+        Ok(directory_iterator) => directory_iterator
+            .filter_map(|read_dir_object| read_dir_object.ok())
+            .map(|read_dir_object| read_dir_object.path())
+            .filter(|path| {
+                path.extension().is_some_and(|extension_os_str| {
+                    extension_os_str
+                        .to_str()
+                        .is_some_and(|extension| extension == "json")
+                })
+            })
+            .filter(|path| {
+                include_archived_projects
+                    || !archived_list.contains(
+                        &path
+                            .file_stem()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_lowercase(),
+                    )
+            })
+            .collect(),
+        Err(err) => {
+            println!("Error reading directory: {err:?}");
+            vec![]
+        }
+    };
+    projects_list.sort_unstable();
+    projects_list
+}
+
+/// Searches every project file in the folder for tasks matching the text, and the saved filter if given.
+/// The tasks of the open project are passed in, so they're searched rather than its file.
+pub async fn search_all_projects(
+    selected_folder: PathBuf,
+    search_text: String,
+    include_archived_projects: bool,
+    saved_filter: Option<SavedTaskFilter>,
+    current_project: Option<(PathBuf, Vec<TaskData>)>,
+) -> Vec<(PathBuf, TaskData)> {
+    list_project_files(&selected_folder, include_archived_projects)
+        .into_iter()
+        .flat_map(|project_file| {
+            match current_project.as_ref() {
+                Some((current_project_file, current_tasks_list))
+                    if *current_project_file == project_file =>
+                {
+                    current_tasks_list.clone()
+                }
+                _ => read_tasks_from_project_file(&project_file),
+            }
+            .into_iter()
+            .filter(|task| {
+                task_matches_text(task, &search_text)
+                    && saved_filter
+                        .as_ref()
+                        .is_none_or(|saved_filter| saved_filter.matches(task))
+            })
+            .map(|task| (project_file.clone(), task))
+            .collect::<Vec<(PathBuf, TaskData)>>()
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskFileFormat {
+    TodoTxt,
+    Csv,
+    ICalendar,
+}
+
+impl TaskFileFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TaskFileFormat::TodoTxt => "txt",
+            TaskFileFormat::Csv => "csv",
+            TaskFileFormat::ICalendar => "ics",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase())
+            .as_deref()
+        {
+            Some("txt") => Some(TaskFileFormat::TodoTxt),
+            Some("csv") => Some(TaskFileFormat::Csv),
+            Some("ics") | Some("ical") | Some("ifb") => Some(TaskFileFormat::ICalendar),
+            _ => None,
+        }
+    }
+}
+
+const ICALENDAR_STATE_PROPERTY: &str = "X-IDIRFEIN-STATE";
+/// Namespace used to turn non UUID identifiers from other apps into stable task IDs, the same as the RFC 4122 URL namespace
+const FOREIGN_ID_NAMESPACE: Uuid = Uuid::from_bytes([
+    0x6b, 0xa7, 0xb8, 0x11, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8,
+]);
+
+pub fn completion_state_code(completion_state: &TaskCompletionState) -> &'static str {
+    match completion_state {
+        TaskCompletionState::Backlog => "backlog",
+        TaskCompletionState::ToDo => "todo",
+        TaskCompletionState::Doing => "doing",
+        TaskCompletionState::Done => "done",
+    }
+}
+
+pub fn parse_completion_state(completion_state_str: &str) -> Option<TaskCompletionState> {
+    match completion_state_str.trim().to_lowercase().as_str() {
+        "backlog" => Some(TaskCompletionState::Backlog),
+        "todo" | "to do" | "needs-action" => Some(TaskCompletionState::ToDo),
+        "doing" | "in progress" | "in-process" => Some(TaskCompletionState::Doing),
+        "done" | "completed" | "cancelled" => Some(TaskCompletionState::Done),
+        _ => None,
+    }
+}
+
+/// Gets the task ID for an identifier from another app, keeping it if its a UUID, otherwise deriving a stable one from it
+pub fn task_id_from_foreign_id(foreign_id: &str) -> Uuid {
+    let foreign_id = foreign_id.trim();
+    Uuid::parse_str(foreign_id).unwrap_or_else(|_| {
+        let mut hasher = Sha1::new();
+        hasher.update(FOREIGN_ID_NAMESPACE.as_bytes());
+        hasher.update(foreign_id.as_bytes());
+        let hash = hasher.finalize();
+        let mut uuid_bytes = [0u8; 16];
+        uuid_bytes.copy_from_slice(&hash[..16]);
+        uuid::Builder::from_sha1_bytes(uuid_bytes).into_uuid()
+    })
+}
+
+/// Merges imported tasks into a project, updating the fields the file formats can store on tasks which have the same ID and adding the rest.
+/// Descriptions are kept on existing tasks when the imported task has none, as todo.txt can't store them.
+/// Returns the number of tasks updated and the number added.
+pub fn merge_imported_tasks(
+    tasks_list: &mut Vec<TaskData>,
+    imported_tasks: Vec<TaskData>,
+) -> (usize, usize) {
+    let mut updated_count = 0;
+    let mut added_count = 0;
+    imported_tasks.into_iter().for_each(|mut imported_task| {
+        if let Some(existing_task) = tasks_list
+            .iter_mut()
+            .find(|task| task.id == imported_task.id)
+        {
+            existing_task.title = imported_task.title;
+            if !imported_task.description.is_empty() {
+                existing_task.description = imported_task.description;
+            }
+            existing_task.set_completion_state(imported_task.completion_state);
+            existing_task.labels = imported_task.labels;
+            existing_task.due_date = imported_task.due_date;
+            updated_count += 1;
+        } else {
+            imported_task.record_created();
+            tasks_list.push(imported_task);
+            added_count += 1;
+        }
+    });
+    (updated_count, added_count)
+}
+
+fn tasks_are_the_same(first_task: &TaskData, second_task: &TaskData) -> bool {
+    serde_json::to_value(first_task).ok() == serde_json::to_value(second_task).ok()
+}
+
+/// Merges changes made to a project file on disk into unsaved changes made in the app, task by task.
+/// Tasks only changed on one side take that side's version, tasks changed on both keep the app's version,
+/// and tasks added on either side are kept.
+pub fn merge_tasks_lists(
+    saved_tasks_list: &[TaskData],
+    tasks_list: &[TaskData],
+    file_tasks_list: &[TaskData],
+) -> Vec<TaskData> {
+    let saved_tasks: HashMap<Uuid, &TaskData> = saved_tasks_list
+        .iter()
+        .map(|task| (task.id, task))
+        .collect();
+    let file_tasks: HashMap<Uuid, &TaskData> =
+        file_tasks_list.iter().map(|task| (task.id, task)).collect();
+    let mut merged_tasks_list: Vec<TaskData> = tasks_list
+        .iter()
+        .filter_map(
+            |task| match (saved_tasks.get(&task.id), file_tasks.get(&task.id)) {
+                (Some(saved_task), Some(file_task)) if tasks_are_the_same(task, saved_task) => {
+                    Some((*file_task).clone())
+                }
+                // Deleted in the file and not changed in the app
+                (Some(saved_task), None) if tasks_are_the_same(task, saved_task) => None,
+                _ => Some(task.clone()),
+            },
+        )
+        .collect();
+    file_tasks_list
+        .iter()
+        .filter(|file_task| !tasks_list.iter().any(|task| task.id == file_task.id))
+        .filter(|file_task| {
+            // Deleted in the app, unless it was changed in the file since
+            saved_tasks
+                .get(&file_task.id)
+                .is_none_or(|saved_task| !tasks_are_the_same(file_task, saved_task))
+        })
+        .for_each(|file_task| merged_tasks_list.push(file_task.clone()));
+    merged_tasks_list
+}
+
+pub fn parse_tasks(
+    file_content: &str,
+    file_format: TaskFileFormat,
+) -> Result<Vec<TaskData>, String> {
+    match file_format {
+        TaskFileFormat::TodoTxt => Ok(parse_todo_txt(file_content)),
+        TaskFileFormat::Csv => parse_csv(file_content),
+        TaskFileFormat::ICalendar => Ok(parse_icalendar(file_content)),
+    }
+}
+
+pub fn serialise_tasks(
+    tasks_list: &[TaskData],
+    file_format: TaskFileFormat,
+    project_name: &str,
+) -> Result<String, String> {
+    match file_format {
+        TaskFileFormat::TodoTxt => Ok(tasks_to_todo_txt(tasks_list)),
+        TaskFileFormat::Csv => tasks_to_csv(tasks_list),
+        TaskFileFormat::ICalendar => Ok(tasks_to_icalendar(tasks_list, project_name)),
+    }
+}
+
+pub async fn import_tasks_from_file(file_path: PathBuf) -> Result<Vec<TaskData>, String> {
+    let file_format = TaskFileFormat::from_path(&file_path).ok_or(String::from(
+        "Unsupported file type, use .txt, .csv or .ics",
+    ))?;
+    let file_content = fs::read_to_string(&file_path).map_err(|err| err.to_string())?;
+    parse_tasks(&file_content, file_format)
+}
+
+pub async fn export_tasks_to_file(
+    tasks_list: Vec<TaskData>,
+    file_format: TaskFileFormat,
+    project_name: String,
+    file_path: PathBuf,
+) -> (bool, String) {
+    match serialise_tasks(&tasks_list, file_format, &project_name) {
+        Ok(serialised_tasks) => match fs::write(&file_path, serialised_tasks) {
+            Ok(_) => (true, format!("Exported tasks to {file_path:?}")),
+            Err(err) => (false, format!("Failed to write exported tasks: {err:?}")),
+        },
+        Err(err) => (false, format!("Failed to export tasks: {err}")),
+    }
+}
+
+/// A label as a todo.txt token. Labels imported from `+projects` keep their `+`, the rest are
+/// written as `@contexts`. Whitespace and `%` are percent-encoded so the label stays one token
+fn todo_txt_label(label: &str) -> String {
+    let (prefix, name) = match label.strip_prefix('+') {
+        Some(project_name) => ('+', project_name),
+        None => ('@', label),
+    };
+    let mut token = String::from(prefix);
+    name.chars().for_each(|c| {
+        if c.is_whitespace() || c == '%' {
+            token.extend(utf8_percent_encode(
+                c.encode_utf8(&mut [0; 4]),
+                NON_ALPHANUMERIC,
+            ));
+        } else {
+            token.push(c);
+        }
+    });
+    token
+}
+
+/// The label from a todo.txt `@context` or `+project` token, see `todo_txt_label`
+fn label_from_todo_txt(token: &str) -> Option<String> {
+    let (is_project, name) = token
+        .strip_prefix('@')
+        .map(|context_name| (false, context_name))
+        .or_else(|| {
+            token
+                .strip_prefix('+')
+                .map(|project_name| (true, project_name))
+        })
+        .filter(|(_, name)| !name.is_empty())?;
+    let name = percent_decode_str(name).decode_utf8_lossy();
+    Some(if is_project {
+        format!("+{name}")
+    } else {
+        name.into_owned()
+    })
+}
+
+/// Converts tasks to todo.txt lines, using `state:`, `due:` and `id:` tags and labels as `@contexts` or `+projects`
+fn tasks_to_todo_txt(tasks_list: &[TaskData]) -> String {
+    tasks_list
+        .iter()
+        .map(|task| {
+            let mut line = String::new();
+            if task.completion_state == TaskCompletionState::Done {
+                line.push_str("x ");
+            }
+            line.push_str(&task.title.replace('\n', " "));
+            task.labels.iter().for_each(|label| {
+                line.push(' ');
+                line.push_str(&todo_txt_label(label));
+            });
+            if let Some(due_date) = task.due_date {
+                line.push_str(&format!(" due:{}", due_date.format("%Y-%m-%d")));
+            }
+            if task.completion_state != TaskCompletionState::Done {
+                line.push_str(&format!(
+                    " state:{}",
+                    completion_state_code(&task.completion_state)
+                ));
+            }
+            line.push_str(&format!(" id:{}", task.id));
+            line
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+        + "\n"
+}
+
+fn parse_todo_txt(file_content: &str) -> Vec<TaskData> {
+    file_content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut task = TaskData::default();
+            let mut tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.first() == Some(&"x") {
+                task.completion_state = TaskCompletionState::Done;
+                tokens.remove(0);
+                // Completion date, then creation date
+                while tokens
+                    .first()
+                    .is_some_and(|token| NaiveDate::parse_from_str(token, "%Y-%m-%d").is_ok())
+                {
+                    tokens.remove(0);
+                }
+            } else {
+                task.completion_state = TaskCompletionState::ToDo;
+                if tokens.first().is_some_and(|token| {
+                    token.len() == 3
+                        && token.starts_with('(')
+                        && token.ends_with(')')
+                        && token.chars().nth(1).is_some_and(|c| c.is_ascii_uppercase())
+                }) {
+                    tokens.remove(0);
+                }
+                if tokens
+                    .first()
+                    .is_some_and(|token| NaiveDate::parse_from_str(token, "%Y-%m-%d").is_ok())
+                {
+                    tokens.remove(0);
+                }
+            }
+            let mut title_tokens: Vec<&str> = vec![];
+            tokens.into_iter().for_each(|token| {
+                if let Some(label) = label_from_todo_txt(token) {
+                    task.labels.push(label);
+                } else if let Some(due_date) = token
+                    .strip_prefix("due:")
+                    .and_then(|due_str| NaiveDate::parse_from_str(due_str, "%Y-%m-%d").ok())
+                {
+                    task.due_date = Some(due_date);
+                } else if let Some(id_str) = token.strip_prefix("id:").filter(|id| !id.is_empty()) {
+                    task.id = task_id_from_foreign_id(id_str);
+                } else if let Some(completion_state) = token
+                    .strip_prefix("state:")
+                    .and_then(parse_completion_state)
+                {
+                    if task.completion_state != TaskCompletionState::Done {
+                        task.completion_state = completion_state;
+                    }
+                } else {
+                    title_tokens.push(token);
+                }
+            });
+            task.title = title_tokens.join(" ");
+            task
+        })
+        .collect()
+}
+
+const CSV_HEADERS: [&str; 6] = ["id", "title", "description", "state", "labels", "due_date"];
+
+fn tasks_to_csv(tasks_list: &[TaskData]) -> Result<String, String> {
+    let mut csv_writer = csv::Writer::from_writer(vec![]);
+    csv_writer
+        .write_record(CSV_HEADERS)
+        .map_err(|err| err.to_string())?;
+    for task in tasks_list {
+        csv_writer
+            .write_record([
+                task.id.to_string(),
+                task.title.clone(),
+                task.description.clone(),
+                completion_state_code(&task.completion_state).to_string(),
+                task.labels.join(", "),
+                task.due_date
+                    .map(|due_date| due_date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default(),
+            ])
+            .map_err(|err| err.to_string())?;
+    }
+    String::from_utf8(csv_writer.into_inner().map_err(|err| err.to_string())?)
+        .map_err(|err| err.to_string())
+}
+
+fn parse_csv(file_content: &str) -> Result<Vec<TaskData>, String> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(file_content.as_bytes());
+    let headers: Vec<String> = csv_reader
+        .headers()
+        .map_err(|err| err.to_string())?
+        .iter()
+        .map(|header| header.to_lowercase().replace(' ', "_"))
+        .collect();
+    let column_index = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.as_str()))
+    };
+    let id_column = column_index(&["id", "uid", "uuid"]);
+    let title_column = column_index(&["title", "summary", "name", "task"])
+        .ok_or(String::from("CSV file needs a title column"))?;
+    let description_column = column_index(&["description", "notes", "details"]);
+    let state_column = column_index(&["state", "status", "completion_state"]);
+    let labels_column = column_index(&["labels", "label", "tags", "categories"]);
+    let due_date_column = column_index(&["due_date", "due", "due_on"]);
+
+    csv_reader
+        .records()
+        .map(|record_result| {
+            let record = record_result.map_err(|err| err.to_string())?;
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|column| record.get(column))
+                    .unwrap_or_default()
+            };
+            let mut task = TaskData {
+                title: field(Some(title_column)).to_string(),
+                description: field(description_column).to_string(),
+                labels: parse_labels(field(labels_column)),
+                ..Default::default()
+            };
+            if !field(id_column).is_empty() {
+                task.id = task_id_from_foreign_id(field(id_column));
+            }
+            if let Some(completion_state) = parse_completion_state(field(state_column)) {
+                task.completion_state = completion_state;
+            }
+            if !field(due_date_column).is_empty() {
+                task.due_date = Some(
+                    NaiveDate::parse_from_str(field(due_date_column), "%Y-%m-%d").map_err(
+                        |_| format!("Couldn't read due date {:?}", field(due_date_column)),
+                    )?,
+                );
+            }
+            Ok(task)
+        })
+        .collect()
+}
+
+fn escape_icalendar_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape_icalendar_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(escaped_char) => unescaped.push(escaped_char),
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Splits a text list value on commas which haven't been escaped
+fn split_icalendar_list(value: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut current_item = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current_item.push(c);
+                if let Some(escaped_char) = chars.next() {
+                    current_item.push(escaped_char);
+                }
+            }
+            ',' => items.push(mem::take(&mut current_item)),
+            _ => current_item.push(c),
+        }
+    }
+    items.push(current_item);
+    items
+        .into_iter()
+        .map(|item| unescape_icalendar_text(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Folds a content line so no line is longer than 75 bytes, as required by RFC 5545
+fn fold_icalendar_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut current_line_length = 0;
+    line.chars().for_each(|c| {
+        if current_line_length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            current_line_length = 1;
+        }
+        folded.push(c);
+        current_line_length += c.len_utf8();
+    });
+    folded.push_str("\r\n");
+    folded
+}
+
+pub fn task_to_vtodo(task: &TaskData) -> String {
+    let mut vtodo = String::new();
+    vtodo.push_str("BEGIN:VTODO\r\n");
+    vtodo.push_str(&fold_icalendar_line(&format!("UID:{}", task.id)));
+    vtodo.push_str(&format!(
+        "DTSTAMP:{}\r\n",
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    vtodo.push_str(&fold_icalendar_line(&format!(
+        "SUMMARY:{}",
+        escape_icalendar_text(&task.title)
+    )));
+    if !task.description.is_empty() {
+        vtodo.push_str(&fold_icalendar_line(&format!(
+            "DESCRIPTION:{}",
+            escape_icalendar_text(&task.description)
+        )));
+    }
+    vtodo.push_str(match task.completion_state {
+        TaskCompletionState::Backlog | TaskCompletionState::ToDo => "STATUS:NEEDS-ACTION\r\n",
+        TaskCompletionState::Doing => "STATUS:IN-PROCESS\r\n",
+        TaskCompletionState::Done => "STATUS:COMPLETED\r\n",
+    });
+    vtodo.push_str(&format!(
+        "{ICALENDAR_STATE_PROPERTY}:{}\r\n",
+        completion_state_code(&task.completion_state).to_uppercase()
+    ));
+    if !task.labels.is_empty() {
+        vtodo.push_str(&fold_icalendar_line(&format!(
+            "CATEGORIES:{}",
+            task.labels
+                .iter()
+                .map(|label| escape_icalendar_text(label))
+                .collect::<Vec<String>>()
+                .join(",")
+        )));
+    }
+    if let Some(due_date) = task.due_date {
+        vtodo.push_str(&format!("DUE;VALUE=DATE:{}\r\n", due_date.format("%Y%m%d")));
+    }
+    vtodo.push_str("END:VTODO\r\n");
+    vtodo
+}
+
+fn tasks_to_icalendar(tasks_list: &[TaskData], project_name: &str) -> String {
+    let mut icalendar = String::new();
+    icalendar.push_str("BEGIN:VCALENDAR\r\n");
+    icalendar.push_str("VERSION:2.0\r\n");
+    icalendar.push_str("PRODID:-//IdirFein//Tasks//EN\r\n");
+    icalendar.push_str(&fold_icalendar_line(&format!(
+        "X-WR-CALNAME:{}",
+        escape_icalendar_text(project_name)
+    )));
+    tasks_list
+        .iter()
+        .for_each(|task| icalendar.push_str(&task_to_vtodo(task)));
+    icalendar.push_str("END:VCALENDAR\r\n");
+    icalendar
+}
+
+/// Splits a content line into its name and value, dropping any parameters
+fn split_icalendar_content_line(line: &str) -> Option<(String, &str)> {
+    let mut is_in_quotes = false;
+    let value_start = line.char_indices().find_map(|(index, c)| match c {
+        '"' => {
+            is_in_quotes = !is_in_quotes;
+            None
+        }
+        ':' if !is_in_quotes => Some(index),
+        _ => None,
+    })?;
+    let (name_and_params, value) = (&line[..value_start], &line[value_start + 1..]);
+    let name = name_and_params
+        .split_once(';')
+        .map_or(name_and_params, |(name, _params)| name);
+    Some((name.to_uppercase(), value))
+}
+
+fn parse_icalendar(file_content: &str) -> Vec<TaskData> {
+    // Unfold lines which were split over multiple lines
+    let unfolded_content = file_content
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut tasks_list = vec![];
+    let mut current_task: Option<TaskData> = None;
+    let mut nested_component_depth = 0;
+    let mut has_explicit_state = false;
+    unfolded_content
+        .lines()
+        .filter_map(split_icalendar_content_line)
+        .for_each(
+            |(name, value)| match (name.as_str(), current_task.as_mut()) {
+                ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                    current_task = Some(TaskData {
+                        completion_state: TaskCompletionState::ToDo,
+                        ..Default::default()
+                    });
+                    has_explicit_state = false;
+                }
+                ("BEGIN", Some(_)) => nested_component_depth += 1,
+                ("END", Some(_)) if nested_component_depth > 0 => nested_component_depth -= 1,
+                ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => {
+                    if let Some(task) = current_task.take() {
+                        tasks_list.push(task);
+                    }
+                }
+                (_, Some(_)) if nested_component_depth > 0 => (),
+                ("UID", Some(task)) => task.id = task_id_from_foreign_id(value),
+                ("SUMMARY", Some(task)) => task.title = unescape_icalendar_text(value),
+                ("DESCRIPTION", Some(task)) => task.description = unescape_icalendar_text(value),
+                ("CATEGORIES", Some(task)) => task.labels.extend(split_icalendar_list(value)),
+                ("STATUS", Some(task)) if !has_explicit_state => {
+                    if let Some(completion_state) = parse_completion_state(value) {
+                        task.completion_state = completion_state;
+                    }
+                }
+                (ICALENDAR_STATE_PROPERTY, Some(task)) => {
+                    if let Some(completion_state) = parse_completion_state(value) {
+                        task.completion_state = completion_state;
+                        has_explicit_state = true;
+                    }
+                }
+                ("DUE", Some(task)) => {
+                    task.due_date = value
+                        .get(..8)
+                        .and_then(|date_str| NaiveDate::parse_from_str(date_str, "%Y%m%d").ok());
+                }
+                _ => (),
+            },
+        );
+    tasks_list
+}
+
+/// The total time tracked on a task, not including any timer which is still running
+pub fn total_tracked_time(task: &TaskData) -> TimeDelta {
+    task.time_entries
+        .iter()
+        .map(|time_entry| time_entry.end - time_entry.start)
+        .sum()
+}
+
+/// Formats a duration as hours, minutes and seconds, e.g. 1:05:09
+pub fn format_duration(duration: TimeDelta) -> String {
+    let total_seconds = duration.num_seconds().max(0);
+    format!(
+        "{}:{:02}:{:02}",
+        total_seconds / 3600,
+        total_seconds / 60 % 60,
+        total_seconds % 60
+    )
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeReportRow {
+    pub date: NaiveDate,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub duration: TimeDelta,
+}
+
+/// Totals the time tracked on each task for each day, sorted by date then task title.
+/// Time entries count towards the local date they were started on.
+pub fn time_report(tasks_list: &[TaskData]) -> Vec<TimeReportRow> {
+    let mut totals: BTreeMap<(NaiveDate, String, Uuid), TimeDelta> = BTreeMap::new();
+    tasks_list.iter().for_each(|task| {
+        task.time_entries.iter().for_each(|time_entry| {
+            *totals
+                .entry((
+                    time_entry.start.with_timezone(&Local).date_naive(),
+                    task.title.clone(),
+                    task.id,
+                ))
+                .or_insert(TimeDelta::zero()) += time_entry.end - time_entry.start;
+        })
+    });
+    totals
+        .into_iter()
+        .map(|((date, task_title, task_id), duration)| TimeReportRow {
+            date,
+            task_id,
+            task_title,
+            duration,
+        })
+        .collect()
+}
+
+/// Totals the rows of a time report by task, sorted by task title
+pub fn time_by_task(report_rows: &[TimeReportRow]) -> Vec<(String, TimeDelta)> {
+    let mut totals: BTreeMap<(String, Uuid), TimeDelta> = BTreeMap::new();
+    report_rows.iter().for_each(|report_row| {
+        *totals
+            .entry((report_row.task_title.clone(), report_row.task_id))
+            .or_insert(TimeDelta::zero()) += report_row.duration;
+    });
+    totals
+        .into_iter()
+        .map(|((task_title, _task_id), duration)| (task_title, duration))
+        .collect()
+}
+
+/// Totals the rows of a time report by day, sorted by date
+pub fn time_by_day(report_rows: &[TimeReportRow]) -> Vec<(NaiveDate, TimeDelta)> {
+    let mut totals: BTreeMap<NaiveDate, TimeDelta> = BTreeMap::new();
+    report_rows.iter().for_each(|report_row| {
+        *totals.entry(report_row.date).or_insert(TimeDelta::zero()) += report_row.duration;
+    });
+    totals.into_iter().collect()
+}
+
+fn time_report_to_csv(report_rows: &[TimeReportRow]) -> Result<String, String> {
+    let mut csv_writer = csv::Writer::from_writer(vec![]);
+    csv_writer
+        .write_record(["date", "task_id", "task_title", "hours"])
+        .map_err(|err| err.to_string())?;
+    for report_row in report_rows {
+        csv_writer
+            .write_record([
+                report_row.date.format("%Y-%m-%d").to_string(),
+                report_row.task_id.to_string(),
+                report_row.task_title.clone(),
+                format!("{:.2}", report_row.duration.num_seconds() as f64 / 3600.0),
+            ])
+            .map_err(|err| err.to_string())?;
+    }
+    String::from_utf8(csv_writer.into_inner().map_err(|err| err.to_string())?)
+        .map_err(|err| err.to_string())
+}
+
+pub async fn export_time_report_to_file(
+    report_rows: Vec<TimeReportRow>,
+    file_path: PathBuf,
+) -> (bool, String) {
+    match time_report_to_csv(&report_rows) {
+        Ok(time_report_csv) => match fs::write(&file_path, time_report_csv) {
+            Ok(_) => (true, format!("Exported time report to {file_path:?}")),
+            Err(err) => (false, format!("Failed to write time report: {err:?}")),
+        },
+        Err(err) => (false, format!("Failed to export time report: {err}")),
+    }
+}
+
+/// The number of tasks in each column at the end of a day, in Backlog, To Do, Doing and Done order
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowDay {
+    pub date: NaiveDate,
+    pub column_counts: [usize; 4],
+}
+
+impl FlowDay {
+    pub fn open_tasks(&self) -> usize {
+        self.column_counts[..3].iter().sum()
+    }
+}
+
+fn column_index(completion_state: &TaskCompletionState) -> usize {
+    match completion_state {
+        TaskCompletionState::Backlog => 0,
+        TaskCompletionState::ToDo => 1,
+        TaskCompletionState::Doing => 2,
+        TaskCompletionState::Done => 3,
+    }
+}
+
+/// Works out which column a task was in just before a time, or None if it hadn't been created yet.
+/// Tasks from before transitions were recorded count as being in their earliest known column all along.
+fn completion_state_before(task: &TaskData, time: DateTime<Utc>) -> Option<&TaskCompletionState> {
+    match task
+        .state_transitions
+        .iter()
+        .rev()
+        .find(|transition| transition.timestamp < time)
+    {
+        Some(transition) => Some(&transition.to),
+        None => match task.state_transitions.first() {
+            Some(first_transition) => first_transition.from.as_ref(),
+            None => Some(&task.completion_state),
+        },
+    }
+}
+
+/// Counts the tasks in each column at the end of every day from the first recorded transition up to today, using local dates
+pub fn cumulative_flow(tasks_list: &[TaskData], today: NaiveDate) -> Vec<FlowDay> {
+    let first_date = tasks_list
+        .iter()
+        .filter_map(|task| task.state_transitions.first())
+        .map(|transition| transition.timestamp.with_timezone(&Local).date_naive())
+        .min()
+        .unwrap_or(today)
+        .min(today);
+    first_date
+        .iter_days()
+        .take_while(|date| *date <= today)
+        .map(|date| {
+            let next_midnight = (date + TimeDelta::days(1)).and_time(NaiveTime::MIN);
+            let end_of_day = next_midnight
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|end_of_day| end_of_day.with_timezone(&Utc))
+                .unwrap_or_else(|| next_midnight.and_utc());
+            let mut column_counts = [0; 4];
+            tasks_list
+                .iter()
+                .filter_map(|task| completion_state_before(task, end_of_day))
+                .for_each(|completion_state| column_counts[column_index(completion_state)] += 1);
+            FlowDay {
+                date,
+                column_counts,
+            }
+        })
+        .collect()
+}
+
+/// The average time finished tasks took from last being moved to Doing to being moved to Done
+pub fn average_cycle_time(tasks_list: &[TaskData]) -> Option<TimeDelta> {
+    let cycle_times: Vec<TimeDelta> = tasks_list
+        .iter()
+        .filter(|task| task.completion_state == TaskCompletionState::Done)
+        .filter_map(|task| {
+            let done_index = task
+                .state_transitions
+                .iter()
+                .rposition(|transition| transition.to == TaskCompletionState::Done)?;
+            let started_transition = task.state_transitions[..done_index]
+                .iter()
+                .rev()
+                .find(|transition| transition.to == TaskCompletionState::Doing)?;
+            Some(task.state_transitions[done_index].timestamp - started_transition.timestamp)
+        })
+        .collect();
+    if cycle_times.is_empty() {
+        None
+    } else {
+        Some(
+            cycle_times
+                .iter()
+                .fold(TimeDelta::zero(), |total, cycle_time| total + *cycle_time)
+                / cycle_times.len() as i32,
+        )
+    }
+}
+
+/// Changes the tasks in a project file on disk, used when the project might not be the one open in the app.
+/// The change is saved the same way as edits in the app, so it's recorded in the activity log and CRDT.
+pub fn update_project_file<T>(
+    project_file: &Path,
+    update_tasks: impl FnOnce(&mut Vec<TaskData>) -> Result<T, String>,
+) -> Result<T, String> {
+    let saved_tasks_list = read_tasks_from_project_file(project_file);
+    let mut tasks_list = saved_tasks_list.clone();
+    let update_result = update_tasks(&mut tasks_list)?;
+    save_project_file(project_file, &saved_tasks_list, &tasks_list)
+        .map_err(|err| format!("Failed to save the project file: {err}"))?;
+    Ok(update_result)
+}
+
+/// Adds a time entry to a task in a project which isn't currently open
+pub async fn add_time_entry_to_project_file(
+    project_file: PathBuf,
+    task_id: Uuid,
+    time_entry: TimeEntry,
+) -> Result<(), String> {
+    update_task_in_project_file(&project_file, task_id, |task| {
+        task.time_entries.push(time_entry)
+    })
+    .map_err(|err| format!("Couldn't save the tracked time: {err}"))
+}
+
+/// Changes a single task in a project file on disk, used when the project might not be the one open in the app
+pub fn update_task_in_project_file(
+    project_file: &Path,
+    task_id: Uuid,
+    update_task: impl FnOnce(&mut TaskData),
+) -> Result<(), String> {
+    update_project_file(project_file, |tasks_list| {
+        update_task(
+            tasks_list
+                .iter_mut()
+                .find(|task| task.id == task_id)
+                .ok_or(String::from("The task no longer exists"))?,
+        );
+        Ok(())
+    })
+}
+
+/// Adds tasks to the end of a project file on disk, used when the project isn't the one open in the app
+pub fn add_tasks_to_project_file(
+    project_file: &Path,
+    mut new_tasks: Vec<TaskData>,
+) -> Result<(), String> {
+    update_project_file(project_file, |tasks_list| {
+        tasks_list.append(&mut new_tasks);
+        Ok(())
+    })
+}
+
+/// Finds note links like note://Meetings/Weekly.md#L12 in text, such as a task description
+pub fn note_links_in_text(text: &str) -> Vec<NoteLink> {
+    let note_link_regex =
+        Regex::new(r"note://[^\s)\]>]+").expect("Note link regex should be valid");
+    note_link_regex
+        .find_iter(text)
+        .filter_map(|note_link_match| NoteLink::from_url(note_link_match.as_str()))
+        .collect()
+}
+
+/// The note a task was created from, followed by any other notes linked in its description
+pub fn task_note_links(note_link: Option<&NoteLink>, description: &str) -> Vec<NoteLink> {
+    let mut note_links: Vec<NoteLink> = note_link.into_iter().cloned().collect();
+    note_links_in_text(description)
+        .into_iter()
+        .for_each(|description_note_link| {
+            if !note_links.contains(&description_note_link) {
+                note_links.push(description_note_link);
+            }
+        });
+    note_links
+}
+
+/// Formats a reminder in local time, the same way it's typed into the task edit dialog
+pub fn format_reminder(reminder: DateTime<Utc>) -> String {
+    reminder
+        .with_timezone(&Local)
+        .format(REMINDER_FORMAT)
+        .to_string()
+}
+
+/// Reads a reminder typed into the task edit dialog in local time
+pub fn parse_reminder(reminder_text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(reminder_text.trim(), REMINDER_FORMAT)
+        .ok()?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|reminder| reminder.with_timezone(&Utc))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TaskActivityKind {
+    Created,
+    Moved {
+        from: TaskCompletionState,
+        to: TaskCompletionState,
+    },
+    Edited,
+    Deleted,
+}
+
+/// An entry in the activity log of a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskActivity {
+    pub timestamp: DateTime<Utc>,
+    pub task_id: Uuid,
+    /// The title of the task at the time, so the log still makes sense after the task is renamed or deleted
+    pub task_title: String,
+    pub kind: TaskActivityKind,
+}
+
+/// The activity log is kept next to the project file, with one JSON entry per line so it only ever gets appended to
+pub fn activity_log_file(project_file: &Path) -> PathBuf {
+    project_file.with_extension(ACTIVITY_LOG_EXTENSION)
+}
+
+fn task_details_changed(old_task: &TaskData, new_task: &TaskData) -> bool {
+    old_task.title != new_task.title
+        || old_task.description != new_task.description
+        || old_task.labels != new_task.labels
+        || old_task.due_date != new_task.due_date
+        || old_task.blocked_by != new_task.blocked_by
+        || old_task.reminder != new_task.reminder
+}
+
+/// Works out what happened to each task between two versions of the tasks in a project.
+/// Tracked time isn't counted as an edit, since it's added every time a timer stops.
+pub fn task_activity_between(
+    old_tasks_list: &[TaskData],
+    new_tasks_list: &[TaskData],
+    timestamp: DateTime<Utc>,
+) -> Vec<TaskActivity> {
+    let activity = |task: &TaskData, kind: TaskActivityKind| TaskActivity {
+        timestamp,
+        task_id: task.id,
+        task_title: task.title.clone(),
+        kind,
+    };
+    let mut task_activity = vec![];
+    new_tasks_list.iter().for_each(|new_task| {
+        match old_tasks_list
+            .iter()
+            .find(|old_task| old_task.id == new_task.id)
+        {
+            None => task_activity.push(activity(new_task, TaskActivityKind::Created)),
+            Some(old_task) => {
+                if old_task.completion_state != new_task.completion_state {
+                    task_activity.push(activity(
+                        new_task,
+                        TaskActivityKind::Moved {
+                            from: old_task.completion_state.clone(),
+                            to: new_task.completion_state.clone(),
+                        },
+                    ));
+                }
+                if task_details_changed(old_task, new_task) {
+                    task_activity.push(activity(new_task, TaskActivityKind::Edited));
+                }
+            }
+        }
+    });
+    old_tasks_list
+        .iter()
+        .filter(|old_task| {
+            !new_tasks_list
+                .iter()
+                .any(|new_task| new_task.id == old_task.id)
+        })
+        .for_each(|old_task| task_activity.push(activity(old_task, TaskActivityKind::Deleted)));
+    task_activity
+}
+
+pub fn append_to_activity_log(
+    project_file: &Path,
+    task_activity: &[TaskActivity],
+) -> Result<(), String> {
+    if task_activity.is_empty() {
+        return Ok(());
+    }
+    let mut serialised_activity = String::new();
+    for activity in task_activity {
+        serialised_activity.push_str(
+            &serde_json::to_string(activity)
+                .map_err(|err| format!("Couldn't serialise task activity to JSON: {err:?}"))?,
+        );
+        serialised_activity.push('\n');
+    }
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(activity_log_file(project_file))
+        .and_then(|mut activity_log| activity_log.write_all(serialised_activity.as_bytes()))
+        .map_err(|err| format!("Failed to write to the activity log: {err:?}"))
+}
+
+/// Reads the activity log entries for one task, newest first
+pub fn read_task_history(project_file: &Path, task_id: Uuid) -> Vec<TaskActivity> {
+    let mut task_history: Vec<TaskActivity> = fs::read_to_string(activity_log_file(project_file))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<TaskActivity>(line).ok())
+        .filter(|activity| activity.task_id == task_id)
+        .collect();
+    task_history.reverse();
+    task_history
+}
+
+/// Carries tracked time over into an undone or redone version of the tasks, so undoing never loses time entries
+pub fn keep_time_entries(restored_tasks_list: &mut [TaskData], current_tasks_list: &[TaskData]) {
+    restored_tasks_list.iter_mut().for_each(|restored_task| {
+        if let Some(current_task) = current_tasks_list
+            .iter()
+            .find(|current_task| current_task.id == restored_task.id)
+        {
+            restored_task.time_entries = current_task.time_entries.clone();
+        }
+    });
+}
+
+/// The CRDT for a project is kept in a hidden file next to it, the same way notes are
+pub fn tasks_crdt_file(project_file: &Path) -> PathBuf {
+    project_file.with_file_name(format!(
+        ".{}.loro",
+        project_file
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+    ))
+}
+
+/// Reads the CRDT for a project, or an empty one if it hasn't been saved yet
+pub fn read_tasks_crdt(project_file: &Path) -> LoroDoc {
+    let tasks_crdt = LoroDoc::new();
+    if let Ok(tasks_crdt_bytes) = fs::read(tasks_crdt_file(project_file)) {
+        let _ = tasks_crdt.import(&tasks_crdt_bytes);
+    }
+    tasks_crdt
+}
+
+/// Brings the CRDT for a project up to date with the tasks and saves it, called whenever the project file is saved
+pub fn save_tasks_crdt(project_file: &Path, tasks_list: &[TaskData]) -> Result<(), String> {
+    let tasks_crdt = read_tasks_crdt(project_file);
+    update_tasks_crdt(&tasks_crdt, tasks_list)
+        .map_err(|err| format!("Failed to update the tasks CRDT: {err:?}"))?;
+    fs::write(tasks_crdt_file(project_file), tasks_crdt.export_snapshot())
+        .map_err(|err| format!("Failed to save the tasks CRDT: {err:?}"))
+}
+
+/// Saves a project file along with its activity log and CRDT, the tasks last saved are used to work out the activity
+pub fn save_project_file(
+    project_file: &Path,
+    saved_tasks_list: &[TaskData],
+    tasks_list: &[TaskData],
+) -> Result<(), String> {
+    let serialised_tasks_list = serde_json::to_string(tasks_list)
+        .map_err(|err| format!("Couldn't serialise tasks list object to JSON: {err:?}"))?;
+    fs::write(project_file, serialised_tasks_list)
+        .map_err(|err| format!("Failed on file write: {err:?}"))?;
+    append_to_activity_log(
+        project_file,
+        &task_activity_between(saved_tasks_list, tasks_list, Utc::now()),
+    )?;
+    save_tasks_crdt(project_file, tasks_list)
+}
+
+/// Changes the CRDT to match the tasks, only touching the fields which differ so merging keeps edits from both sides.
+/// Each task is a map keyed by its ID, with the title and description as text so they merge character by character,
+/// and every other field stored as a JSON string so each one merges as last writer wins.
+pub fn update_tasks_crdt(tasks_crdt: &LoroDoc, tasks_list: &[TaskData]) -> LoroResult<()> {
+    let tasks_map = tasks_crdt.get_map(LORO_TASKS_ID);
+    let mut removed_task_ids = vec![];
+    tasks_map.for_each(|task_id, _| {
+        if !tasks_list.iter().any(|task| task.id.to_string() == task_id) {
+            removed_task_ids.push(task_id.to_string());
+        }
+    });
+    for task_id in removed_task_ids {
+        tasks_map.delete(&task_id)?;
+    }
+    for (position, task) in tasks_list.iter().enumerate() {
+        let task_id = task.id.to_string();
+        let task_map = match tasks_map.get(&task_id) {
+            Some(ValueOrContainer::Container(Container::Map(task_map))) => task_map,
+            _ => tasks_map.insert_container(&task_id, LoroMap::new())?,
+        };
+        update_crdt_text_field(&task_map, "title", &task.title)?;
+        update_crdt_text_field(&task_map, "description", &task.description)?;
+        let mut other_fields: Vec<(String, String)> = match serde_json::to_value(task) {
+            Ok(serde_json::Value::Object(task_fields)) => task_fields
+                .into_iter()
+                .filter(|(field_name, _)| {
+                    !["id", "title", "description"].contains(&field_name.as_str())
+                })
+                .map(|(field_name, field_value)| (field_name, field_value.to_string()))
+                .collect(),
+            _ => vec![],
+        };
+        other_fields.push((String::from(CRDT_POSITION_FIELD), position.to_string()));
+        for (field_name, encoded_value) in other_fields {
+            let is_unchanged = matches!(
+                task_map.get(&field_name),
+                Some(ValueOrContainer::Value(LoroValue::String(existing_value))) if *existing_value == encoded_value
+            );
+            if !is_unchanged {
+                task_map.insert(&field_name, encoded_value)?;
+            }
+        }
+    }
+    tasks_crdt.commit();
+    Ok(())
+}
+
+/// Replaces the changed middle of a text field, keeping the unchanged start and end so concurrent edits elsewhere in it survive
+fn update_crdt_text_field(task_map: &LoroMap, field_name: &str, new_text: &str) -> LoroResult<()> {
+    let text_field = match task_map.get(field_name) {
+        Some(ValueOrContainer::Container(Container::Text(text_field))) => text_field,
+        _ => task_map.insert_container(field_name, LoroText::new())?,
+    };
+    let old_chars: Vec<char> = text_field.to_string().chars().collect();
+    let new_chars: Vec<char> = new_text.chars().collect();
+    if old_chars == new_chars {
+        return Ok(());
+    }
+    let common_prefix_len = old_chars
+        .iter()
+        .zip(new_chars.iter())
+        .take_while(|(old_char, new_char)| old_char == new_char)
+        .count();
+    let common_suffix_len = old_chars[common_prefix_len..]
+        .iter()
+        .rev()
+        .zip(new_chars[common_prefix_len..].iter().rev())
+        .take_while(|(old_char, new_char)| old_char == new_char)
+        .count();
+    let deleted_len = old_chars.len() - common_prefix_len - common_suffix_len;
+    if deleted_len > 0 {
+        text_field.delete(common_prefix_len, deleted_len)?;
+    }
+    let inserted_text: String = new_chars[common_prefix_len..new_chars.len() - common_suffix_len]
+        .iter()
+        .collect();
+    if !inserted_text.is_empty() {
+        text_field.insert(common_prefix_len, &inserted_text)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the tasks list from the CRDT, in the order they were last saved in
+pub fn tasks_from_crdt(tasks_crdt: &LoroDoc) -> Vec<TaskData> {
+    let mut positioned_tasks: Vec<(u64, TaskData)> = vec![];
+    tasks_crdt
+        .get_map(LORO_TASKS_ID)
+        .for_each(|task_id, task_value| {
+            let ValueOrContainer::Container(Container::Map(task_map)) = task_value else {
+                return;
+            };
+            let mut task_fields = serde_json::Map::new();
+            task_fields.insert(
+                String::from("id"),
+                serde_json::Value::String(task_id.to_string()),
+            );
+            task_map.for_each(|field_name, field_value| match field_value {
+                ValueOrContainer::Container(Container::Text(text_field)) => {
+                    task_fields.insert(
+                        field_name.to_string(),
+                        serde_json::Value::String(text_field.to_string()),
+                    );
+                }
+                ValueOrContainer::Value(LoroValue::String(encoded_value)) => {
+                    if let Ok(field_value) = serde_json::from_str(&encoded_value) {
+                        task_fields.insert(field_name.to_string(), field_value);
+                    }
+                }
+                _ => (),
+            });
+            let position = task_fields
+                .remove(CRDT_POSITION_FIELD)
+                .and_then(|position| position.as_u64())
+                .unwrap_or(u64::MAX);
+            if let Ok(task) =
+                serde_json::from_value::<TaskData>(serde_json::Value::Object(task_fields))
+            {
+                positioned_tasks.push((position, task));
+            }
+        });
+    positioned_tasks.sort_by(
+        |(first_position, first_task), (second_position, second_task)| {
+            first_position
+                .cmp(second_position)
+                .then(first_task.id.cmp(&second_task.id))
+        },
+    );
+    positioned_tasks.into_iter().map(|(_, task)| task).collect()
+}
+
+/// The details of a task in any project in the folder, used to check which tasks block each other across projects
+#[derive(Debug, Clone)]
+pub struct TaskSummary {
+    pub project_file: PathBuf,
+    pub title: String,
+    pub completion_state: TaskCompletionState,
+    pub blocked_by: Vec<Uuid>,
+}
+
+/// Reads every task in every project in the folder, including archived projects, indexed by ID
+pub async fn load_task_index(selected_folder: PathBuf) -> HashMap<Uuid, TaskSummary> {
+    list_project_files(&selected_folder, true)
+        .into_iter()
+        .flat_map(|project_file| {
+            read_tasks_from_project_file(&project_file)
+                .into_iter()
+                .map(|task| {
+                    (
+                        task.id,
+                        TaskSummary {
+                            project_file: project_file.clone(),
+                            title: task.title,
+                            completion_state: task.completion_state,
+                            blocked_by: task.blocked_by,
+                        },
+                    )
+                })
+                .collect::<Vec<(Uuid, TaskSummary)>>()
+        })
+        .collect()
+}
+
+/// Finds a task by ID, checking the open project first as it may have changes which the index doesn't have yet
+pub fn find_task_summary(
+    task_id: Uuid,
+    tasks_list: &[TaskData],
+    current_project_file: Option<&PathBuf>,
+    task_index: &HashMap<Uuid, TaskSummary>,
+) -> Option<TaskSummary> {
+    tasks_list
+        .iter()
+        .find(|task| task.id == task_id)
+        .map(|task| TaskSummary {
+            project_file: current_project_file.cloned().unwrap_or_default(),
+            title: task.title.clone(),
+            completion_state: task.completion_state.clone(),
+            blocked_by: task.blocked_by.clone(),
+        })
+        .or_else(|| task_index.get(&task_id).cloned())
+}
+
+/// The titles of the tasks blocking this one which aren't done yet, blockers which have been deleted are ignored
+pub fn unfinished_blockers(
+    blocked_by: &[Uuid],
+    tasks_list: &[TaskData],
+    current_project_file: Option<&PathBuf>,
+    task_index: &HashMap<Uuid, TaskSummary>,
+) -> Vec<String> {
+    blocked_by
+        .iter()
+        .filter_map(|blocker_id| {
+            find_task_summary(*blocker_id, tasks_list, current_project_file, task_index)
+        })
+        .filter(|blocker| blocker.completion_state != TaskCompletionState::Done)
+        .map(|blocker| blocker.title)
+        .collect()
+}
+
+/// The titles of the tasks which were blocked by the completed task, and which now have no unfinished blockers
+pub fn tasks_unblocked_by(
+    completed_task_id: Uuid,
+    tasks_list: &[TaskData],
+    current_project_file: Option<&PathBuf>,
+    task_index: &HashMap<Uuid, TaskSummary>,
+) -> Vec<String> {
+    let other_project_tasks = task_index
+        .iter()
+        .filter(|(task_id, task_summary)| {
+            Some(&task_summary.project_file) != current_project_file
+                && !tasks_list.iter().any(|task| task.id == **task_id)
+        })
+        .map(|(_task_id, task_summary)| (&task_summary.title, &task_summary.blocked_by));
+    tasks_list
+        .iter()
+        .map(|task| (&task.title, &task.blocked_by))
+        .chain(other_project_tasks)
+        .filter(|(_title, blocked_by)| {
+            blocked_by.contains(&completed_task_id)
+                && unfinished_blockers(blocked_by, tasks_list, current_project_file, task_index)
+                    .is_empty()
+        })
+        .map(|(title, _blocked_by)| title.clone())
+        .collect()
+}
+
+/// Whether making the task blocked by the new blocker would make the task end up blocking itself
+pub fn would_create_dependency_cycle(
+    task_id: Uuid,
+    new_blocker_id: Uuid,
+    tasks_list: &[TaskData],
+    current_project_file: Option<&PathBuf>,
+    task_index: &HashMap<Uuid, TaskSummary>,
+) -> bool {
+    let mut tasks_to_check = vec![new_blocker_id];
+    let mut checked_tasks = HashSet::new();
+    while let Some(checking_task_id) = tasks_to_check.pop() {
+        if checking_task_id == task_id {
+            return true;
+        }
+        if checked_tasks.insert(checking_task_id) {
+            if let Some(task_summary) = find_task_summary(
+                checking_task_id,
+                tasks_list,
+                current_project_file,
+                task_index,
+            ) {
+                tasks_to_check.extend(task_summary.blocked_by);
+            }
+        }
+    }
+    false
+}
+
+/// Groups the tasks in a project into levels, where each task is only blocked by tasks from the same project in earlier levels.
+/// Any tasks stuck in a dependency cycle are put together in the last level.
+pub fn dependency_levels(tasks_list: &[TaskData]) -> Vec<Vec<Uuid>> {
+    let project_task_ids: HashSet<Uuid> = tasks_list.iter().map(|task| task.id).collect();
+    let mut placed_task_ids: HashSet<Uuid> = HashSet::new();
+    let mut remaining_tasks: Vec<&TaskData> = tasks_list.iter().collect();
+    let mut levels = vec![];
+    while !remaining_tasks.is_empty() {
+        let (ready_tasks, blocked_tasks): (Vec<&TaskData>, Vec<&TaskData>) =
+            remaining_tasks.into_iter().partition(|task| {
+                task.blocked_by
+                    .iter()
+                    .filter(|blocker_id| project_task_ids.contains(blocker_id))
+                    .all(|blocker_id| placed_task_ids.contains(blocker_id))
+            });
+        if ready_tasks.is_empty() {
+            levels.push(blocked_tasks.iter().map(|task| task.id).collect());
+            break;
+        }
+        ready_tasks.iter().for_each(|task| {
+            placed_task_ids.insert(task.id);
+        });
+        levels.push(ready_tasks.iter().map(|task| task.id).collect());
+        remaining_tasks = blocked_tasks;
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use crate::pages::tasks::page::StateTransition;
+
+    use super::*;
+
+    fn example_tasks() -> Vec<TaskData> {
+        vec![
+            TaskData {
+                title: String::from("Write report"),
+                description: String::from("Include the figures; and, the tables\nfrom last week"),
+                completion_state: TaskCompletionState::Doing,
+                labels: vec![String::from("work"), String::from("writing")],
+                due_date: NaiveDate::from_ymd_opt(2025, 3, 14),
+                ..Default::default()
+            },
+            TaskData {
+                title: String::from("Buy milk"),
+                completion_state: TaskCompletionState::Done,
+                ..Default::default()
+            },
+            TaskData {
+                title: String::from("Plan holiday"),
+                completion_state: TaskCompletionState::Backlog,
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn assert_tasks_match(
+        imported_tasks: &[TaskData],
+        original_tasks: &[TaskData],
+        check_description: bool,
+    ) {
+        assert_eq!(imported_tasks.len(), original_tasks.len());
+        imported_tasks
+            .iter()
+            .zip(original_tasks)
+            .for_each(|(imported_task, original_task)| {
+                assert_eq!(imported_task.id, original_task.id);
+                assert_eq!(imported_task.title, original_task.title);
+                assert_eq!(
+                    imported_task.completion_state,
+                    original_task.completion_state
+                );
+                assert_eq!(imported_task.labels, original_task.labels);
+                assert_eq!(imported_task.due_date, original_task.due_date);
+                if check_description {
+                    assert_eq!(imported_task.description, original_task.description);
+                }
+            });
+    }
+
+    #[test]
+    fn test_todo_txt_round_trip() {
+        let tasks_list = example_tasks();
+        let todo_txt = serialise_tasks(&tasks_list, TaskFileFormat::TodoTxt, "Test").unwrap();
+        let imported_tasks = parse_tasks(&todo_txt, TaskFileFormat::TodoTxt).unwrap();
+        assert_tasks_match(&imported_tasks, &tasks_list, false);
+    }
+
+    #[test]
+    fn test_todo_txt_labels_round_trip() {
+        let tasks_list = vec![TaskData {
+            title: String::from("Book flights"),
+            completion_state: TaskCompletionState::ToDo,
+            labels: vec![
+                String::from("to do"),
+                String::from("50%\toff"),
+                String::from("+family"),
+                String::from("saoire"),
+                String::from("fáilte"),
+            ],
+            ..Default::default()
+        }];
+        let todo_txt = serialise_tasks(&tasks_list, TaskFileFormat::TodoTxt, "Test").unwrap();
+        assert!(todo_txt
+            .starts_with("Book flights @to%20do @50%25%09off +family @saoire @fáilte state:todo "));
+        let imported_tasks = parse_tasks(&todo_txt, TaskFileFormat::TodoTxt).unwrap();
+        assert_tasks_match(&imported_tasks, &tasks_list, false);
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let tasks_list = example_tasks();
+        let csv = serialise_tasks(&tasks_list, TaskFileFormat::Csv, "Test").unwrap();
+        let imported_tasks = parse_tasks(&csv, TaskFileFormat::Csv).unwrap();
+        assert_tasks_match(&imported_tasks, &tasks_list, true);
+    }
+
+    #[test]
+    fn test_icalendar_round_trip() {
+        let tasks_list = example_tasks();
+        let icalendar = serialise_tasks(&tasks_list, TaskFileFormat::ICalendar, "Test").unwrap();
+        assert!(icalendar
+            .lines()
+            .all(|line| line.trim_end_matches('\r').len() <= 75));
+        let imported_tasks = parse_tasks(&icalendar, TaskFileFormat::ICalendar).unwrap();
+        assert_tasks_match(&imported_tasks, &tasks_list, true);
+    }
+
+    #[test]
+    fn test_parse_todo_txt_from_other_apps() {
+        let imported_tasks = parse_tasks(
+            "(A) 2025-01-02 Call Mum @phone +family due:2025-01-05\nx 2025-01-03 2025-01-01 Pay rent id:rent\n",
+            TaskFileFormat::TodoTxt,
+        )
+        .unwrap();
+        assert_eq!(imported_tasks[0].title, "Call Mum");
+        assert_eq!(imported_tasks[0].labels, vec!["phone", "+family"]);
+        assert_eq!(
+            imported_tasks[0].due_date,
+            NaiveDate::from_ymd_opt(2025, 1, 5)
+        );
+        assert_eq!(
+            imported_tasks[0].completion_state,
+            TaskCompletionState::ToDo
+        );
+        assert_eq!(imported_tasks[1].title, "Pay rent");
+        assert_eq!(
+            imported_tasks[1].completion_state,
+            TaskCompletionState::Done
+        );
+        assert_eq!(imported_tasks[1].id, task_id_from_foreign_id("rent"));
+    }
+
+    #[test]
+    fn test_merge_imported_tasks_keeps_descriptions() {
+        let mut tasks_list = example_tasks();
+        let mut imported_tasks = parse_tasks(
+            &serialise_tasks(&tasks_list, TaskFileFormat::TodoTxt, "Test").unwrap(),
+            TaskFileFormat::TodoTxt,
+        )
+        .unwrap();
+        imported_tasks[0].completion_state = TaskCompletionState::Done;
+        imported_tasks.push(TaskData::default());
+        let (updated_count, added_count) = merge_imported_tasks(&mut tasks_list, imported_tasks);
+        assert_eq!((updated_count, added_count), (3, 1));
+        assert_eq!(tasks_list[0].completion_state, TaskCompletionState::Done);
+        assert_eq!(tasks_list[0].description, example_tasks()[0].description);
+    }
+
+    #[test]
+    fn test_merge_tasks_lists() {
+        let saved_tasks_list = example_tasks();
+        let mut tasks_list = saved_tasks_list.clone();
+        tasks_list[0].title = String::from("Edited in the app");
+        tasks_list.remove(2);
+        let app_task = TaskData {
+            title: String::from("Added in the app"),
+            ..Default::default()
+        };
+        tasks_list.push(app_task.clone());
+        let mut file_tasks_list = saved_tasks_list.clone();
+        file_tasks_list[0].title = String::from("Edited in the file");
+        file_tasks_list[1].title = String::from("Also edited in the file");
+        let file_task = TaskData {
+            title: String::from("Added in the file"),
+            ..Default::default()
+        };
+        file_tasks_list.push(file_task.clone());
+        let merged_tasks_list = merge_tasks_lists(&saved_tasks_list, &tasks_list, &file_tasks_list);
+        let merged_titles: Vec<&str> = merged_tasks_list
+            .iter()
+            .map(|task| task.title.as_str())
+            .collect();
+        assert_eq!(
+            merged_titles,
+            vec![
+                "Edited in the app",
+                "Also edited in the file",
+                "Added in the app",
+                "Added in the file"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_time_report_totals() {
+        let start = Local
+            .with_ymd_and_hms(2025, 3, 10, 9, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let time_entry = |start_offset_minutes: i64, length_minutes: i64| TimeEntry {
+            start: start + TimeDelta::minutes(start_offset_minutes),
+            end: start + TimeDelta::minutes(start_offset_minutes + length_minutes),
+        };
+        let tasks_list = vec![
+            TaskData {
+                title: String::from("Invoice client"),
+                time_entries: vec![time_entry(0, 30), time_entry(60, 15)],
+                ..Default::default()
+            },
+            TaskData {
+                title: String::from("Call supplier"),
+                time_entries: vec![time_entry(24 * 60, 90)],
+                ..Default::default()
+            },
+        ];
+        assert_eq!(total_tracked_time(&tasks_list[0]), TimeDelta::minutes(45));
+        assert_eq!(format_duration(TimeDelta::seconds(3909)), "1:05:09");
+        let report_rows = time_report(&tasks_list);
+        assert_eq!(report_rows.len(), 2);
+        assert_eq!(report_rows[0].task_title, "Invoice client");
+        assert_eq!(
+            time_by_task(&report_rows),
+            vec![
+                (String::from("Call supplier"), TimeDelta::minutes(90)),
+                (String::from("Invoice client"), TimeDelta::minutes(45))
+            ]
+        );
+        assert_eq!(time_by_day(&report_rows).len(), 2);
+    }
+
+    #[test]
+    fn test_cumulative_flow_and_cycle_time() {
+        let day = |day_of_month: u32, hour: u32| {
+            Local
+                .with_ymd_and_hms(2025, 3, day_of_month, hour, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let transition = |timestamp, from, to| StateTransition {
+            timestamp,
+            from,
+            to,
+        };
+        let tasks_list = vec![
+            TaskData {
+                title: String::from("Fix the fence"),
+                completion_state: TaskCompletionState::Done,
+                state_transitions: vec![
+                    transition(day(10, 9), None, TaskCompletionState::ToDo),
+                    transition(
+                        day(11, 9),
+                        Some(TaskCompletionState::ToDo),
+                        TaskCompletionState::Doing,
+                    ),
+                    transition(
+                        day(12, 9),
+                        Some(TaskCompletionState::Doing),
+                        TaskCompletionState::Done,
+                    ),
+                ],
+                ..Default::default()
+            },
+            TaskData {
+                title: String::from("Paint the shed"),
+                completion_state: TaskCompletionState::Done,
+                state_transitions: vec![
+                    transition(day(11, 9), None, TaskCompletionState::Doing),
+                    transition(
+                        day(11, 21),
+                        Some(TaskCompletionState::Doing),
+                        TaskCompletionState::Done,
+                    ),
+                ],
+                ..Default::default()
+            },
+            // Made before transitions were recorded
+            TaskData {
+                title: String::from("Clear the gutters"),
+                completion_state: TaskCompletionState::Backlog,
+                ..Default::default()
+            },
+        ];
+        let flow_days = cumulative_flow(&tasks_list, NaiveDate::from_ymd_opt(2025, 3, 12).unwrap());
+        assert_eq!(
+            flow_days
+                .iter()
+                .map(|flow_day| flow_day.column_counts)
+                .collect::<Vec<[usize; 4]>>(),
+            vec![[1, 1, 0, 0], [1, 0, 1, 1], [1, 0, 0, 2]]
+        );
+        assert_eq!(
+            flow_days
+                .iter()
+                .map(FlowDay::open_tasks)
+                .collect::<Vec<usize>>(),
+            vec![2, 2, 1]
+        );
+        assert_eq!(average_cycle_time(&tasks_list), Some(TimeDelta::hours(18)));
+        assert_eq!(average_cycle_time(&tasks_list[2..]), None);
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let mut tasks_list = example_tasks();
+        tasks_list[1].blocked_by = vec![tasks_list[2].id];
+        tasks_list[0].blocked_by = vec![tasks_list[1].id, Uuid::new_v4()];
+        let task_index = HashMap::new();
+        assert_eq!(
+            dependency_levels(&tasks_list),
+            vec![
+                vec![tasks_list[2].id],
+                vec![tasks_list[1].id],
+                vec![tasks_list[0].id]
+            ]
+        );
+        assert!(would_create_dependency_cycle(
+            tasks_list[2].id,
+            tasks_list[0].id,
+            &tasks_list,
+            None,
+            &task_index
+        ));
+        assert!(!would_create_dependency_cycle(
+            tasks_list[0].id,
+            tasks_list[2].id,
+            &tasks_list,
+            None,
+            &task_index
+        ));
+        // The second task is done, so the first task is only blocked by it if it's undone
+        assert!(
+            unfinished_blockers(&tasks_list[0].blocked_by, &tasks_list, None, &task_index)
+                .is_empty()
+        );
+        assert_eq!(
+            tasks_unblocked_by(tasks_list[1].id, &tasks_list, None, &task_index),
+            vec![tasks_list[0].title.clone()]
+        );
+        tasks_list[1].completion_state = TaskCompletionState::ToDo;
+        assert_eq!(
+            unfinished_blockers(&tasks_list[0].blocked_by, &tasks_list, None, &task_index),
+            vec![tasks_list[1].title.clone()]
+        );
+        assert!(tasks_unblocked_by(tasks_list[2].id, &tasks_list, None, &task_index).is_empty());
+    }
+
+    #[test]
+    fn test_activity_log() {
+        let projects_folder = tempfile::tempdir().unwrap();
+        let project_file = projects_folder.path().join("Home.json");
+        let old_tasks_list = example_tasks();
+        let mut new_tasks_list = old_tasks_list.clone();
+        new_tasks_list[0].completion_state = TaskCompletionState::Done;
+        new_tasks_list[0].time_entries.push(TimeEntry {
+            start: Utc::now(),
+            end: Utc::now(),
+        });
+        new_tasks_list[1].title = String::from("Buy more milk");
+        let deleted_task = new_tasks_list.remove(2);
+        let created_task = TaskData {
+            title: String::from("Clean the car"),
+            ..Default::default()
+        };
+        new_tasks_list.push(created_task.clone());
+
+        let task_activity = task_activity_between(&old_tasks_list, &new_tasks_list, Utc::now());
+        let activity_summary: Vec<(Uuid, TaskActivityKind)> = task_activity
+            .iter()
+            .map(|activity| (activity.task_id, activity.kind.clone()))
+            .collect();
+        assert_eq!(
+            activity_summary,
+            vec![
+                (
+                    old_tasks_list[0].id,
+                    TaskActivityKind::Moved {
+                        from: TaskCompletionState::Doing,
+                        to: TaskCompletionState::Done
+                    }
+                ),
+                (old_tasks_list[1].id, TaskActivityKind::Edited),
+                (created_task.id, TaskActivityKind::Created),
+                (deleted_task.id, TaskActivityKind::Deleted),
+            ]
+        );
+        assert!(task_activity_between(&new_tasks_list, &new_tasks_list, Utc::now()).is_empty());
+
+        append_to_activity_log(&project_file, &task_activity).unwrap();
+        append_to_activity_log(
+            &project_file,
+            &task_activity_between(&new_tasks_list, &old_tasks_list, Utc::now()),
+        )
+        .unwrap();
+        assert_eq!(
+            read_task_history(&project_file, old_tasks_list[0].id)
+                .into_iter()
+                .map(|activity| activity.kind)
+                .collect::<Vec<TaskActivityKind>>(),
+            vec![
+                TaskActivityKind::Moved {
+                    from: TaskCompletionState::Done,
+                    to: TaskCompletionState::Doing
+                },
+                TaskActivityKind::Moved {
+                    from: TaskCompletionState::Doing,
+                    to: TaskCompletionState::Done
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_task_in_project_file() {
+        let projects_folder = tempfile::tempdir().unwrap();
+        let project_file = projects_folder.path().join("Home.json");
+        let tasks_list = example_tasks();
+        fs::write(&project_file, serde_json::to_string(&tasks_list).unwrap()).unwrap();
+        update_task_in_project_file(&project_file, tasks_list[1].id, |task| {
+            task.title = String::from("Buy more milk")
+        })
+        .unwrap();
+        assert_eq!(
+            read_tasks_from_project_file(&project_file)[1].title,
+            "Buy more milk"
+        );
+        assert_eq!(
+            read_task_history(&project_file, tasks_list[1].id)
+                .into_iter()
+                .map(|activity| activity.kind)
+                .collect::<Vec<TaskActivityKind>>(),
+            vec![TaskActivityKind::Edited]
+        );
+        assert!(tasks_crdt_file(&project_file).exists());
+        assert!(update_task_in_project_file(&project_file, Uuid::new_v4(), |_| ()).is_err());
+    }
+
+    #[test]
+    fn test_tasks_crdt_merge() {
+        let base_tasks_list = example_tasks();
+        let first_crdt = LoroDoc::new();
+        update_tasks_crdt(&first_crdt, &base_tasks_list).unwrap();
+        assert_eq!(
+            serde_json::to_string(&tasks_from_crdt(&first_crdt)).unwrap(),
+            serde_json::to_string(&base_tasks_list).unwrap()
+        );
+        let second_crdt = LoroDoc::new();
+        second_crdt.import(&first_crdt.export_snapshot()).unwrap();
+
+        let mut first_tasks_list = base_tasks_list.clone();
+        first_tasks_list[0].title = String::from("Write the monthly report");
+        first_tasks_list[1].completion_state = TaskCompletionState::ToDo;
+        update_tasks_crdt(&first_crdt, &first_tasks_list).unwrap();
+
+        let mut second_tasks_list = base_tasks_list.clone();
+        second_tasks_list[0]
+            .description
+            .push_str("\nAnd the charts");
+        second_tasks_list.remove(2);
+        let added_task = TaskData {
+            title: String::from("Clean the car"),
+            ..Default::default()
+        };
+        second_tasks_list.push(added_task.clone());
+        update_tasks_crdt(&second_crdt, &second_tasks_list).unwrap();
+
+        first_crdt.import(&second_crdt.export_snapshot()).unwrap();
+        second_crdt.import(&first_crdt.export_snapshot()).unwrap();
+        let merged_tasks_list = tasks_from_crdt(&first_crdt);
+        assert_eq!(
+            serde_json::to_string(&merged_tasks_list).unwrap(),
+            serde_json::to_string(&tasks_from_crdt(&second_crdt)).unwrap()
+        );
+        assert_eq!(merged_tasks_list.len(), 3);
+        assert_eq!(merged_tasks_list[0].title, "Write the monthly report");
+        assert_eq!(
+            merged_tasks_list[0].description,
+            format!("{}\nAnd the charts", base_tasks_list[0].description)
+        );
+        assert_eq!(
+            merged_tasks_list[1].completion_state,
+            TaskCompletionState::ToDo
+        );
+        assert_eq!(merged_tasks_list[2].id, added_task.id);
+    }
+
+    #[test]
+    fn test_note_links() {
+        let note_link = NoteLink {
+            note_path: PathBuf::from("Meetings/Weekly catch up.md"),
+            line: 11,
+        };
+        assert_eq!(
+            note_link.to_url(),
+            "note://Meetings/Weekly%20catch%20up.md#L12"
+        );
+        assert_eq!(
+            NoteLink::from_url(&note_link.to_url()),
+            Some(note_link.clone())
+        );
+        assert_eq!(NoteLink::from_url("note://../secrets.md#L1"), None);
+        assert_eq!(NoteLink::from_url("https://example.com"), None);
+
+        let description = format!(
+            "Follow up on [the meeting]({}) and note://Ideas.md",
+            note_link.to_url()
+        );
+        assert_eq!(
+            task_note_links(Some(&note_link), &description),
+            vec![
+                note_link,
+                NoteLink {
+                    note_path: PathBuf::from("Ideas.md"),
+                    line: 0
+                }
+            ]
+        );
+    }
+}
@@ -4,6 +4,7 @@ use std::{
     path::PathBuf,
 };
 
+use chrono::{NaiveDate, Utc};
 use iced::{
     advanced::widget::Id,
     widget::{text_editor, text_input},
@@ -11,14 +12,32 @@ use iced::{
 };
 use iced_aw::widget::zones_on_point;
 use rfd::FileDialog;
+use uuid::Uuid;
 
-use crate::app::Message;
+use crate::{
+    app::Message,
+    pages::{notes::page::NotesPageMessage, settings::page::SettingsPageMessage},
+    Page,
+};
 
 use super::page::{
-    TaskCompletionState, TaskData, TasksPage, TasksPageMessage, ARCHIVED_FILE_NAME, BACKLOG_ID,
-    DOING_ID, DONE_ID, NEW_PROJECT_TEXT_INPUT_ID, RENAME_PROJECT_TEXT_INPUT_ID,
-    TASK_TITLE_TEXT_INPUT_ID, TODO_ID,
+    RunningTimer, TaskCompletionState, TaskData, TasksPage, TasksPageMessage, TimeEntry,
+    ARCHIVED_FILE_NAME, BACKLOG_ID, DOING_ID, DONE_ID, NEW_PROJECT_TEXT_INPUT_ID,
+    RENAME_PROJECT_TEXT_INPUT_ID, SEARCH_ALL_PROJECTS_TEXT_INPUT_ID, TASK_TITLE_TEXT_INPUT_ID,
+    TODO_ID,
+};
+use super::tasks_utils::{
+    activity_log_file, add_tasks_to_project_file, add_time_entry_to_project_file,
+    export_tasks_to_file, export_time_report_to_file, format_reminder, import_tasks_from_file,
+    keep_time_entries, list_project_files, load_task_index, merge_imported_tasks,
+    merge_tasks_lists, parse_labels, parse_reminder, read_task_history,
+    read_tasks_from_project_file, save_project_file, search_all_projects, tasks_crdt_file,
+    tasks_unblocked_by, time_report, unfinished_blockers, update_project_file,
+    would_create_dependency_cycle, SavedTaskFilter,
 };
+use super::view::task_is_visible;
+
+const MAX_UNDO_STEPS: usize = 100;
 
 pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message> {
     match message {
@@ -148,33 +167,112 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
             state.archived_list = archived_list;
             return Task::done(Message::Tasks(TasksPageMessage::PickProjectFile(
                 state.current_project_file.clone(),
-            )));
+            )))
+            .chain(Task::done(Message::Tasks(TasksPageMessage::LoadTaskIndex)));
         }
         TasksPageMessage::SetTasksList(tasks_list, project_path) => {
+            state.saved_tasks_list = tasks_list.clone();
             state.tasks_list = tasks_list;
+            state.undo_stack.clear();
+            state.redo_stack.clear();
+            state.selected_task_ids.clear();
+            state.last_clicked_task_id = None;
             state.current_project_file = Some(project_path);
+            if let Some(task_id) = state.task_to_open_after_project_load.take() {
+                return Task::done(Message::Tasks(TasksPageMessage::OpenEditDialogForTask(
+                    task_id,
+                )));
+            }
         }
         TasksPageMessage::SelectTaskToEdit(task_uuid) => state.current_task_id = task_uuid,
         TasksPageMessage::DeleteTask(id_to_delete) => {
             state.show_confirm_before_delete_dialog = false;
             if let Some(task_index) = state.tasks_list.iter().position(|x| x.id == id_to_delete) {
+                record_undo_snapshot(state);
                 state.tasks_list.remove(task_index);
                 state.is_dirty = true;
             }
+            if state
+                .running_timer
+                .as_ref()
+                .is_some_and(|running_timer| running_timer.task_id == id_to_delete)
+            {
+                state.running_timer = None;
+                return Task::done(Message::Tasks(TasksPageMessage::SaveProject)).chain(
+                    Task::done(Message::Settings(
+                        SettingsPageMessage::TasksSetRunningTimer(None),
+                    )),
+                );
+            }
             return Task::done(Message::Tasks(TasksPageMessage::SaveProject));
         }
         TasksPageMessage::SetTaskCompletionState(id_to_edit, task_completion_state) => {
             if let Some(task_index) = state.tasks_list.iter().position(|x| x.id == id_to_edit) {
-                state
+                if state.tasks_list[task_index].completion_state != task_completion_state {
+                    record_undo_snapshot(state);
+                }
+                let previous_completion_state = state
                     .tasks_list
                     .get_mut(task_index)
                     .expect("Shouldn't be possible for this to fail")
-                    .completion_state = task_completion_state;
+                    .set_completion_state(task_completion_state.clone());
                 state.is_dirty = true;
+                if previous_completion_state != task_completion_state {
+                    if task_completion_state == TaskCompletionState::Doing {
+                        let blockers = unfinished_blockers(
+                            &state.tasks_list[task_index].blocked_by,
+                            &state.tasks_list,
+                            state.current_project_file.as_ref(),
+                            &state.task_index,
+                        );
+                        if !blockers.is_empty() {
+                            return Task::done(Message::Tasks(TasksPageMessage::SaveProject))
+                                .chain(Task::done(Message::ShowToast(
+                                    false,
+                                    format!(
+                                        "This task is still blocked by: {}",
+                                        blockers.join(", ")
+                                    ),
+                                )));
+                        }
+                    } else if task_completion_state == TaskCompletionState::Done {
+                        return Task::done(Message::Tasks(TasksPageMessage::SaveProject)).chain(
+                            follow_up_finished_task(state, &state.tasks_list[task_index]),
+                        );
+                    }
+                }
             }
             return Task::done(Message::Tasks(TasksPageMessage::SaveProject));
         }
         TasksPageMessage::UpdateCurrentTask => {
+            let due_date = if state.current_task_due_date_text.trim().is_empty() {
+                None
+            } else {
+                match NaiveDate::parse_from_str(state.current_task_due_date_text.trim(), "%Y-%m-%d")
+                {
+                    Ok(due_date) => Some(due_date),
+                    Err(_) => {
+                        return Task::done(Message::ShowToast(
+                            false,
+                            String::from("Due date must be in the format YYYY-MM-DD"),
+                        ));
+                    }
+                }
+            };
+            let reminder = if state.current_task_reminder_text.trim().is_empty() {
+                None
+            } else {
+                match parse_reminder(&state.current_task_reminder_text) {
+                    Some(reminder) => Some(reminder),
+                    None => {
+                        return Task::done(Message::ShowToast(
+                            false,
+                            String::from("Reminder must be in the format YYYY-MM-DD HH:MM"),
+                        ));
+                    }
+                }
+            };
+            record_undo_snapshot(state);
             match state.current_task_id {
                 Some(task_id) => {
                     if let Some(task_index) = state.tasks_list.iter().position(|x| x.id == task_id)
@@ -189,19 +287,49 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
                             .get_mut(task_index)
                             .expect("Shouldn't be possible for this to fail")
                             .description = state.current_task_description_content.text();
+                        state
+                            .tasks_list
+                            .get_mut(task_index)
+                            .expect("Shouldn't be possible for this to fail")
+                            .labels = parse_labels(&state.current_task_labels_text);
+                        state
+                            .tasks_list
+                            .get_mut(task_index)
+                            .expect("Shouldn't be possible for this to fail")
+                            .due_date = due_date;
+                        state
+                            .tasks_list
+                            .get_mut(task_index)
+                            .expect("Shouldn't be possible for this to fail")
+                            .blocked_by = mem::take(&mut state.current_task_blocked_by);
+                        state
+                            .tasks_list
+                            .get_mut(task_index)
+                            .expect("Shouldn't be possible for this to fail")
+                            .reminder = reminder;
                         state.is_dirty = true;
                     }
                 }
                 None => {
-                    state.tasks_list.push(TaskData {
+                    let mut new_task = TaskData {
                         title: mem::take(&mut state.current_task_title_text),
                         description: state.current_task_description_content.text(),
+                        labels: parse_labels(&state.current_task_labels_text),
+                        due_date,
+                        blocked_by: mem::take(&mut state.current_task_blocked_by),
+                        reminder,
                         ..Default::default()
-                    });
+                    };
+                    new_task.record_created();
+                    state.tasks_list.push(new_task);
                     state.is_dirty = true;
                 }
             };
             state.current_task_description_content = text_editor::Content::default();
+            state.current_task_labels_text = String::new();
+            state.current_task_due_date_text = String::new();
+            state.current_task_reminder_text = String::new();
+            state.current_task_blocked_by = vec![];
             state.current_task_id = None;
             state.show_task_edit_dialog = false;
             return Task::done(Message::Tasks(TasksPageMessage::SaveProject));
@@ -209,20 +337,13 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
         TasksPageMessage::SaveProject => {
             if state.is_dirty {
                 if let Some(current_project_file) = state.current_project_file.clone() {
-                    let serialised_tasks_list_option = serde_json::to_string(&state.tasks_list);
+                    let saved_tasks_list =
+                        mem::replace(&mut state.saved_tasks_list, state.tasks_list.clone());
+                    let tasks_list = state.tasks_list.clone();
                     return Task::perform(
-                        async {
-                            match serialised_tasks_list_option {
-                                Ok(serialised_tasks_list) => {
-                                    match fs::write(current_project_file, serialised_tasks_list) {
-                                        Ok(_) => Ok(()),
-                                        Err(err) => Err(format!("PROJECT SAVE FAILED: Failed on file write: {err:?}")),
-                                    }
-                                }
-                                Err(err) => Err(format!(
-                                    "PROJECT SAVE FAILED: Couldn't serialise tasks list object to JSON: {err:?}"
-                                )),
-                            }
+                        async move {
+                            save_project_file(&current_project_file, &saved_tasks_list, &tasks_list)
+                                .map_err(|err| format!("PROJECT SAVE FAILED: {err}"))
                         },
                         |result| match result {
                             Ok(_) => Message::None,
@@ -237,7 +358,13 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
             state.current_task_id = None;
             state.current_task_title_text = String::new();
             state.current_task_description_content = text_editor::Content::with_text("");
-            return text_input::focus(text_input::Id::new(TASK_TITLE_TEXT_INPUT_ID));
+            state.current_task_labels_text = String::new();
+            state.current_task_due_date_text = String::new();
+            state.current_task_reminder_text = String::new();
+            state.current_task_blocked_by = vec![];
+            state.current_task_history = vec![];
+            return text_input::focus(text_input::Id::new(TASK_TITLE_TEXT_INPUT_ID))
+                .chain(Task::done(Message::Tasks(TasksPageMessage::LoadTaskIndex)));
         }
         TasksPageMessage::UpdateTaskTitle(s) => state.current_task_title_text = s,
         TasksPageMessage::UpdateTaskDescription(action) => {
@@ -270,7 +397,45 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
                         .expect("Shouldn't fail")
                         .description,
                 );
-                return text_input::focus(text_input::Id::new(TASK_TITLE_TEXT_INPUT_ID));
+                state.current_task_labels_text = state
+                    .tasks_list
+                    .get(task_index)
+                    .expect("Shouldn't fail")
+                    .labels
+                    .join(", ");
+                state.current_task_due_date_text = state
+                    .tasks_list
+                    .get(task_index)
+                    .expect("Shouldn't fail")
+                    .due_date
+                    .map(|due_date| due_date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+                state.current_task_reminder_text = state
+                    .tasks_list
+                    .get(task_index)
+                    .expect("Shouldn't fail")
+                    .reminder
+                    .map(format_reminder)
+                    .unwrap_or_default();
+                state.current_task_blocked_by = state
+                    .tasks_list
+                    .get(task_index)
+                    .expect("Shouldn't fail")
+                    .blocked_by
+                    .clone();
+                state.current_task_history = vec![];
+                let load_history_task = match state.current_project_file.clone() {
+                    Some(current_project_file) => Task::perform(
+                        async move { read_task_history(&current_project_file, task_id) },
+                        |task_history| {
+                            Message::Tasks(TasksPageMessage::SetCurrentTaskHistory(task_history))
+                        },
+                    ),
+                    None => Task::none(),
+                };
+                return text_input::focus(text_input::Id::new(TASK_TITLE_TEXT_INPUT_ID))
+                    .chain(Task::done(Message::Tasks(TasksPageMessage::LoadTaskIndex)))
+                    .chain(load_history_task);
             }
         }
         TasksPageMessage::StartCreatingNewProject => {
@@ -302,8 +467,15 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
             state.show_task_edit_dialog = false;
             state.current_task_title_text = String::new();
             state.current_task_description_content = text_editor::Content::with_text("");
+            state.current_task_labels_text = String::new();
+            state.current_task_due_date_text = String::new();
+            state.current_task_reminder_text = String::new();
+            state.current_task_blocked_by = vec![];
             state.current_task_id = None;
             state.show_confirm_before_delete_dialog = false;
+            state.is_creating_new_saved_filter = false;
+            state.show_confirm_bulk_delete_dialog = false;
+            state.selected_task_ids.clear();
         }
         TasksPageMessage::DropTask(task_id, cursor_position, _rectangle) => {
             return zones_on_point(
@@ -314,26 +486,29 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
             );
         }
         TasksPageMessage::HandleTaskDropZones(task_id, zones) => {
-            if let Some(dropped_zone) = zones.first() {
+            let dropped_completion_state = zones.first().and_then(|dropped_zone| {
                 if dropped_zone.0 == Id::new(BACKLOG_ID) {
-                    return Task::done(Message::Tasks(TasksPageMessage::SetTaskCompletionState(
-                        task_id,
-                        TaskCompletionState::Backlog,
-                    )));
+                    Some(TaskCompletionState::Backlog)
                 } else if dropped_zone.0 == Id::new(TODO_ID) {
-                    return Task::done(Message::Tasks(TasksPageMessage::SetTaskCompletionState(
-                        task_id,
-                        TaskCompletionState::ToDo,
-                    )));
+                    Some(TaskCompletionState::ToDo)
                 } else if dropped_zone.0 == Id::new(DOING_ID) {
-                    return Task::done(Message::Tasks(TasksPageMessage::SetTaskCompletionState(
-                        task_id,
-                        TaskCompletionState::Doing,
-                    )));
+                    Some(TaskCompletionState::Doing)
                 } else if dropped_zone.0 == Id::new(DONE_ID) {
+                    Some(TaskCompletionState::Done)
+                } else {
+                    None
+                }
+            });
+            if let Some(dropped_completion_state) = dropped_completion_state {
+                // Dragging one of the selected tasks moves all of them
+                if state.selected_task_ids.contains(&task_id) {
+                    return Task::done(Message::Tasks(
+                        TasksPageMessage::BulkSetTaskCompletionState(dropped_completion_state),
+                    ));
+                } else {
                     return Task::done(Message::Tasks(TasksPageMessage::SetTaskCompletionState(
                         task_id,
-                        TaskCompletionState::Done,
+                        dropped_completion_state,
                     )));
                 }
             }
@@ -350,6 +525,8 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
         TasksPageMessage::DeleteProject => {
             if let Some(current_project_file) = state.current_project_being_managed.as_ref() {
                 let _ = fs::remove_file(current_project_file);
+                let _ = fs::remove_file(activity_log_file(current_project_file));
+                let _ = fs::remove_file(tasks_crdt_file(current_project_file));
                 state.current_project_being_managed = None;
                 state.display_delete_project_view = false;
                 return Task::done(Message::Tasks(TasksPageMessage::LoadProjectsList)).chain(
@@ -365,6 +542,19 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
 
                     new_path.set_extension("json");
                     let _ = fs::rename(current_project_file, &new_path);
+                    let _ = fs::rename(
+                        activity_log_file(current_project_file),
+                        activity_log_file(&new_path),
+                    );
+                    let _ = fs::rename(
+                        tasks_crdt_file(current_project_file),
+                        tasks_crdt_file(&new_path),
+                    );
+                    if let Some(running_timer) = state.running_timer.as_mut() {
+                        if &running_timer.project_file == current_project_file {
+                            running_timer.project_file = new_path.clone();
+                        }
+                    }
                     if state.current_project_file == state.current_project_being_managed {
                         state.current_project_file = Some(new_path);
                     }
@@ -378,9 +568,13 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
                         String::from("No selected project to rename"),
                     ));
                 }
-                return Task::done(Message::Tasks(TasksPageMessage::SaveProject)).chain(
-                    Task::done(Message::Tasks(TasksPageMessage::LoadProjectsList)),
-                );
+                return Task::done(Message::Tasks(TasksPageMessage::SaveProject))
+                    .chain(Task::done(Message::Tasks(
+                        TasksPageMessage::LoadProjectsList,
+                    )))
+                    .chain(Task::done(Message::Settings(
+                        SettingsPageMessage::TasksSetRunningTimer(state.running_timer.clone()),
+                    )));
             } else {
                 return Task::done(Message::ShowToast(
                     false,
@@ -481,6 +675,798 @@ pub fn update(state: &mut TasksPage, message: TasksPageMessage) -> Task<Message>
         TasksPageMessage::ToggleShowArchivedProjects => {
             state.show_archived_projects = !state.show_archived_projects;
         }
+        TasksPageMessage::UpdateTaskLabels(s) => state.current_task_labels_text = s,
+        TasksPageMessage::UpdateTaskDueDate(s) => state.current_task_due_date_text = s,
+        TasksPageMessage::UpdateTaskReminder(s) => state.current_task_reminder_text = s,
+        TasksPageMessage::ToggleSearchAllProjectsView => {
+            state.show_search_all_projects_view = !state.show_search_all_projects_view;
+            if state.show_search_all_projects_view {
+                return Task::done(Message::Tasks(TasksPageMessage::SearchAllProjects)).chain(
+                    text_input::focus(text_input::Id::new(SEARCH_ALL_PROJECTS_TEXT_INPUT_ID)),
+                );
+            }
+        }
+        TasksPageMessage::ToggleSearchIncludeArchivedProjects => {
+            state.search_include_archived_projects = !state.search_include_archived_projects;
+            return Task::done(Message::Tasks(TasksPageMessage::SearchAllProjects));
+        }
+        TasksPageMessage::UpdateSearchAllProjectsText(s) => state.search_all_projects_text = s,
+        TasksPageMessage::SearchAllProjects => {
+            if let Some(selected_folder) = state.selected_folder.clone() {
+                let saved_filter = state
+                    .selected_saved_filter
+                    .and_then(|filter_index| state.saved_filters.get(filter_index))
+                    .cloned();
+                // The current project is searched as it is in the app, so the results include unsaved changes
+                let current_project = state
+                    .current_project_file
+                    .clone()
+                    .map(|current_project_file| (current_project_file, state.tasks_list.clone()));
+                return Task::perform(
+                    search_all_projects(
+                        selected_folder,
+                        state.search_all_projects_text.clone(),
+                        state.search_include_archived_projects,
+                        saved_filter,
+                        current_project,
+                    ),
+                    |search_results| {
+                        Message::Tasks(TasksPageMessage::SetSearchAllProjectsResults(
+                            search_results,
+                        ))
+                    },
+                );
+            }
+        }
+        TasksPageMessage::SetSearchAllProjectsResults(search_results) => {
+            state.search_all_projects_results = search_results;
+        }
+        TasksPageMessage::OpenSearchResult(project_file, task_id) => {
+            state.show_search_all_projects_view = false;
+            if state
+                .current_project_file
+                .as_ref()
+                .is_some_and(|current_project_file| *current_project_file == project_file)
+            {
+                return Task::done(Message::Tasks(TasksPageMessage::OpenEditDialogForTask(
+                    task_id,
+                )));
+            } else {
+                state.task_to_open_after_project_load = Some(task_id);
+                return Task::done(Message::Tasks(TasksPageMessage::PickProjectFile(Some(
+                    project_file,
+                ))));
+            }
+        }
+        TasksPageMessage::SelectSavedFilter(filter_index) => {
+            state.selected_saved_filter = filter_index;
+            if state.show_search_all_projects_view {
+                return Task::done(Message::Tasks(TasksPageMessage::SearchAllProjects));
+            }
+        }
+        TasksPageMessage::DeleteSavedFilter(filter_index) => {
+            if filter_index < state.saved_filters.len() {
+                state.saved_filters.remove(filter_index);
+                state.selected_saved_filter = None;
+                return Task::done(Message::Settings(
+                    SettingsPageMessage::TasksSetSavedFilters(state.saved_filters.clone()),
+                ));
+            }
+        }
+        TasksPageMessage::StartCreatingNewSavedFilter => {
+            state.is_creating_new_saved_filter = true;
+            state.new_saved_filter = SavedTaskFilter::default();
+            state.new_saved_filter_due_date_text = String::new();
+        }
+        TasksPageMessage::CancelCreateNewSavedFilter => {
+            state.is_creating_new_saved_filter = false;
+            state.new_saved_filter = SavedTaskFilter::default();
+            state.new_saved_filter_due_date_text = String::new();
+        }
+        TasksPageMessage::CreateNewSavedFilter => {
+            if state.new_saved_filter.name.trim().is_empty() {
+                return Task::done(Message::ShowToast(
+                    false,
+                    String::from("Saved filters need a name"),
+                ));
+            }
+            if state.new_saved_filter_due_date_text.trim().is_empty() {
+                state.new_saved_filter.due_on_or_before = None;
+            } else {
+                match NaiveDate::parse_from_str(
+                    state.new_saved_filter_due_date_text.trim(),
+                    "%Y-%m-%d",
+                ) {
+                    Ok(due_date) => state.new_saved_filter.due_on_or_before = Some(due_date),
+                    Err(_) => {
+                        return Task::done(Message::ShowToast(
+                            false,
+                            String::from("Due date must be in the format YYYY-MM-DD"),
+                        ));
+                    }
+                }
+            }
+            state
+                .saved_filters
+                .push(mem::take(&mut state.new_saved_filter));
+            state.new_saved_filter_due_date_text = String::new();
+            state.is_creating_new_saved_filter = false;
+            state.selected_saved_filter = Some(state.saved_filters.len() - 1);
+            return Task::done(Message::Settings(
+                SettingsPageMessage::TasksSetSavedFilters(state.saved_filters.clone()),
+            ));
+        }
+        TasksPageMessage::UpdateNewSavedFilterName(s) => state.new_saved_filter.name = s,
+        TasksPageMessage::UpdateNewSavedFilterText(s) => state.new_saved_filter.text = s,
+        TasksPageMessage::UpdateNewSavedFilterLabel(s) => {
+            state.new_saved_filter.label = if s.is_empty() { None } else { Some(s) };
+        }
+        TasksPageMessage::UpdateNewSavedFilterDueDate(s) => {
+            state.new_saved_filter_due_date_text = s
+        }
+        TasksPageMessage::SetNewSavedFilterCompletionState(completion_state) => {
+            state.new_saved_filter.completion_state = completion_state;
+        }
+        TasksPageMessage::ImportTasks => {
+            state.show_extra_tools_menu = false;
+            return Task::perform(
+                async {
+                    FileDialog::new()
+                        .add_filter("tasks", &["txt", "csv", "ics"])
+                        .pick_file()
+                },
+                |selected_file| {
+                    Message::Tasks(TasksPageMessage::ImportTasksFromFile(selected_file))
+                },
+            );
+        }
+        TasksPageMessage::ImportTasksFromFile(selected_file) => {
+            if let Some(selected_file) = selected_file {
+                return Task::perform(import_tasks_from_file(selected_file), |result| {
+                    Message::Tasks(TasksPageMessage::AddImportedTasks(result))
+                });
+            }
+        }
+        TasksPageMessage::AddImportedTasks(result) => match result {
+            Ok(imported_tasks) => {
+                record_undo_snapshot(state);
+                let (updated_count, added_count) =
+                    merge_imported_tasks(&mut state.tasks_list, imported_tasks);
+                state.is_dirty = true;
+                return Task::done(Message::Tasks(TasksPageMessage::SaveProject)).chain(
+                    Task::done(Message::ShowToast(
+                        true,
+                        format!("Imported tasks: {added_count} added, {updated_count} updated"),
+                    )),
+                );
+            }
+            Err(err) => {
+                return Task::done(Message::ShowToast(
+                    false,
+                    format!("Failed to import tasks: {err}"),
+                ))
+            }
+        },
+        TasksPageMessage::ExportTasks(file_format) => {
+            state.show_extra_tools_menu = false;
+            if let Some(current_project_file) = state.current_project_file.as_ref() {
+                let project_name = current_project_file
+                    .file_stem()
+                    .map(|file_stem| file_stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let tasks_list = state.tasks_list.clone();
+                return Task::perform(
+                    async move {
+                        let selected_file = FileDialog::new()
+                            .set_file_name(format!("{project_name}.{}", file_format.extension()))
+                            .add_filter(file_format.extension(), &[file_format.extension()])
+                            .save_file()?;
+                        Some(
+                            export_tasks_to_file(
+                                tasks_list,
+                                file_format,
+                                project_name,
+                                selected_file,
+                            )
+                            .await,
+                        )
+                    },
+                    |result| match result {
+                        Some((is_success, toast_text)) => {
+                            Message::ShowToast(is_success, toast_text)
+                        }
+                        None => Message::None,
+                    },
+                );
+            }
+        }
+        TasksPageMessage::ReloadProjectFile(project_file) => {
+            if state.current_project_file.as_ref() == Some(&project_file) {
+                let file_tasks_list = read_tasks_from_project_file(&project_file);
+                // Our own saves are reported too, and leave nothing to reload
+                if serde_json::to_string(&file_tasks_list).ok()
+                    == serde_json::to_string(&state.saved_tasks_list).ok()
+                {
+                    return Task::none();
+                }
+                // Kept as a step so the change from outside the app can be undone like any other
+                record_undo_snapshot(state);
+                let has_unsaved_changes = state.is_dirty
+                    && serde_json::to_string(&state.tasks_list).ok()
+                        != serde_json::to_string(&state.saved_tasks_list).ok();
+                if has_unsaved_changes {
+                    state.tasks_list = merge_tasks_lists(
+                        &state.saved_tasks_list,
+                        &state.tasks_list,
+                        &file_tasks_list,
+                    );
+                    state.saved_tasks_list = file_tasks_list;
+                    return Task::done(Message::ShowToast(
+                        true,
+                        String::from(
+                            "The project was changed outside the app, the changes have been merged with your unsaved edits",
+                        ),
+                    ));
+                }
+                state.tasks_list = file_tasks_list.clone();
+                state.saved_tasks_list = file_tasks_list;
+            }
+        }
+        TasksPageMessage::StartTimer(task_id) => {
+            if let Some(current_project_file) = state.current_project_file.clone() {
+                if let Some(task_title) = state
+                    .tasks_list
+                    .iter()
+                    .find(|task| task.id == task_id)
+                    .map(|task| task.title.clone())
+                {
+                    // Only one timer can run at a time, so stop any timer which is already running
+                    let stop_timer_task = stop_running_timer(state);
+                    state.running_timer = Some(RunningTimer {
+                        project_file: current_project_file,
+                        task_id,
+                        task_title,
+                        started_at: Utc::now(),
+                    });
+                    return stop_timer_task.chain(Task::done(Message::Settings(
+                        SettingsPageMessage::TasksSetRunningTimer(state.running_timer.clone()),
+                    )));
+                }
+            }
+        }
+        TasksPageMessage::StopTimer => {
+            return stop_running_timer(state).chain(Task::done(Message::Settings(
+                SettingsPageMessage::TasksSetRunningTimer(None),
+            )));
+        }
+        TasksPageMessage::TimerTick => (),
+        TasksPageMessage::ToggleTimeReportView => {
+            state.show_extra_tools_menu = false;
+            state.show_search_all_projects_view = false;
+            state.show_dependency_graph_view = false;
+            state.show_statistics_view = false;
+            state.show_time_report_view = !state.show_time_report_view;
+        }
+        TasksPageMessage::ExportTimeReport => {
+            if let Some(current_project_file) = state.current_project_file.as_ref() {
+                let project_name = current_project_file
+                    .file_stem()
+                    .map(|file_stem| file_stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let report_rows = time_report(&state.tasks_list);
+                return Task::perform(
+                    async move {
+                        let selected_file = FileDialog::new()
+                            .set_file_name(format!("{project_name} time report.csv"))
+                            .add_filter("csv", &["csv"])
+                            .save_file()?;
+                        Some(export_time_report_to_file(report_rows, selected_file).await)
+                    },
+                    |result| match result {
+                        Some((is_success, toast_text)) => {
+                            Message::ShowToast(is_success, toast_text)
+                        }
+                        None => Message::None,
+                    },
+                );
+            }
+        }
+        TasksPageMessage::LoadTaskIndex => {
+            if let Some(selected_folder) = state.selected_folder.clone() {
+                return Task::perform(load_task_index(selected_folder), |task_index| {
+                    Message::Tasks(TasksPageMessage::SetTaskIndex(task_index))
+                });
+            }
+        }
+        TasksPageMessage::SetTaskIndex(task_index) => state.task_index = task_index,
+        TasksPageMessage::AddBlockerToCurrentTask(blocker_id) => {
+            // New tasks can't be blocking anything yet, so can't end up blocking themselves
+            if let Some(task_id) = state.current_task_id {
+                if blocker_id == task_id
+                    || would_create_dependency_cycle(
+                        task_id,
+                        blocker_id,
+                        &state.tasks_list,
+                        state.current_project_file.as_ref(),
+                        &state.task_index,
+                    )
+                {
+                    return Task::done(Message::ShowToast(
+                        false,
+                        String::from("That task is blocked by this one, so it can't block it too"),
+                    ));
+                }
+            }
+            if !state.current_task_blocked_by.contains(&blocker_id) {
+                state.current_task_blocked_by.push(blocker_id);
+            }
+        }
+        TasksPageMessage::RemoveBlockerFromCurrentTask(blocker_id) => {
+            state
+                .current_task_blocked_by
+                .retain(|current_blocker_id| *current_blocker_id != blocker_id);
+        }
+        TasksPageMessage::ToggleDependencyGraphView => {
+            state.show_extra_tools_menu = false;
+            state.show_search_all_projects_view = false;
+            state.show_time_report_view = false;
+            state.show_statistics_view = false;
+            state.show_dependency_graph_view = !state.show_dependency_graph_view;
+            if state.show_dependency_graph_view {
+                return Task::done(Message::Tasks(TasksPageMessage::LoadTaskIndex));
+            }
+        }
+        TasksPageMessage::ToggleStatisticsView => {
+            state.show_extra_tools_menu = false;
+            state.show_search_all_projects_view = false;
+            state.show_time_report_view = false;
+            state.show_dependency_graph_view = false;
+            state.show_statistics_view = !state.show_statistics_view;
+        }
+        TasksPageMessage::Undo => {
+            if !state.show_task_edit_dialog {
+                if let Some(mut previous_tasks_list) = state.undo_stack.pop() {
+                    keep_time_entries(&mut previous_tasks_list, &state.tasks_list);
+                    state
+                        .redo_stack
+                        .push(mem::replace(&mut state.tasks_list, previous_tasks_list));
+                    state.is_dirty = true;
+                    return Task::done(Message::Tasks(TasksPageMessage::SaveProject));
+                }
+            }
+        }
+        TasksPageMessage::Redo => {
+            if !state.show_task_edit_dialog {
+                if let Some(mut next_tasks_list) = state.redo_stack.pop() {
+                    keep_time_entries(&mut next_tasks_list, &state.tasks_list);
+                    state
+                        .undo_stack
+                        .push(mem::replace(&mut state.tasks_list, next_tasks_list));
+                    state.is_dirty = true;
+                    return Task::done(Message::Tasks(TasksPageMessage::SaveProject));
+                }
+            }
+        }
+        TasksPageMessage::SetCurrentTaskHistory(task_history) => {
+            state.current_task_history = task_history
+        }
+        TasksPageMessage::OpenNoteLink(note_link) => {
+            return Task::done(Message::ChangePage(Page::Notes)).chain(Task::done(Message::Notes(
+                NotesPageMessage::OpenNoteLink(note_link),
+            )));
+        }
+        TasksPageMessage::SendProjectsListToNotesPage => {
+            // The projects list is only loaded once the tasks page has been opened
+            let projects_list = state
+                .selected_folder
+                .as_ref()
+                .map(|selected_folder| list_project_files(selected_folder, false))
+                .unwrap_or_default();
+            return Task::done(Message::Notes(NotesPageMessage::SetTaskProjectsList(
+                projects_list,
+            )));
+        }
+        TasksPageMessage::AddTasksToProject(project_file, mut new_tasks) => {
+            new_tasks.iter_mut().for_each(TaskData::record_created);
+            let new_tasks_count = new_tasks.len();
+            let project_name = project_file
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            if state.current_project_file.as_ref() == Some(&project_file) {
+                record_undo_snapshot(state);
+                state.tasks_list.append(&mut new_tasks);
+                state.is_dirty = true;
+                return Task::done(Message::Tasks(TasksPageMessage::SaveProject)).chain(
+                    Task::done(Message::ShowToast(
+                        true,
+                        format!("Added {new_tasks_count} tasks to {project_name}"),
+                    )),
+                );
+            } else {
+                return Task::perform(
+                    async move { add_tasks_to_project_file(&project_file, new_tasks) },
+                    move |result| match result {
+                        Ok(_) => Message::ShowToast(
+                            true,
+                            format!("Added {new_tasks_count} tasks to {project_name}"),
+                        ),
+                        Err(err_string) => Message::ShowToast(false, err_string),
+                    },
+                );
+            }
+        }
+        TasksPageMessage::SetKeyboardModifiers(modifiers) => {
+            state.keyboard_modifiers = modifiers;
+        }
+        TasksPageMessage::TaskClicked(task_id) => {
+            let anchor_task_id = state.last_clicked_task_id.replace(task_id);
+            if state.keyboard_modifiers.shift() {
+                let ordered_task_ids = visible_task_ids_in_board_order(state);
+                let clicked_position = ordered_task_ids.iter().position(|id| *id == task_id);
+                let anchor_position = anchor_task_id
+                    .and_then(|anchor_task_id| {
+                        ordered_task_ids.iter().position(|id| *id == anchor_task_id)
+                    })
+                    .or(clicked_position);
+                if let (Some(clicked_position), Some(anchor_position)) =
+                    (clicked_position, anchor_position)
+                {
+                    state.selected_task_ids.extend(
+                        &ordered_task_ids[clicked_position.min(anchor_position)
+                            ..=clicked_position.max(anchor_position)],
+                    );
+                }
+            } else if !state.selected_task_ids.remove(&task_id) {
+                state.selected_task_ids.insert(task_id);
+            }
+        }
+        TasksPageMessage::SelectAllInColumn(completion_state) => {
+            let column_task_ids: Vec<Uuid> = state
+                .tasks_list
+                .iter()
+                .filter(|task| task.completion_state == completion_state)
+                .filter(|task| task_is_visible(state, task))
+                .map(|task| task.id)
+                .collect();
+            state.selected_task_ids.extend(column_task_ids);
+        }
+        TasksPageMessage::ClearTaskSelection => {
+            state.selected_task_ids.clear();
+            state.last_clicked_task_id = None;
+        }
+        TasksPageMessage::BulkSetTaskCompletionState(completion_state) => {
+            if state.tasks_list.iter().any(|task| {
+                state.selected_task_ids.contains(&task.id)
+                    && task.completion_state != completion_state
+            }) {
+                record_undo_snapshot(state);
+            }
+            let mut changed_task_ids = vec![];
+            for task in state
+                .tasks_list
+                .iter_mut()
+                .filter(|task| state.selected_task_ids.contains(&task.id))
+            {
+                if task.set_completion_state(completion_state.clone()) != completion_state {
+                    changed_task_ids.push(task.id);
+                }
+            }
+            state.is_dirty = true;
+            state.selected_task_ids.clear();
+            let mut save_task = Task::done(Message::Tasks(TasksPageMessage::SaveProject));
+            if completion_state == TaskCompletionState::Doing {
+                let still_blocked_tasks: Vec<String> = state
+                    .tasks_list
+                    .iter()
+                    .filter(|task| changed_task_ids.contains(&task.id))
+                    .filter(|task| {
+                        !unfinished_blockers(
+                            &task.blocked_by,
+                            &state.tasks_list,
+                            state.current_project_file.as_ref(),
+                            &state.task_index,
+                        )
+                        .is_empty()
+                    })
+                    .map(|task| task.title.clone())
+                    .collect();
+                if !still_blocked_tasks.is_empty() {
+                    save_task = save_task.chain(Task::done(Message::ShowToast(
+                        false,
+                        format!(
+                            "These tasks are still blocked: {}",
+                            still_blocked_tasks.join(", ")
+                        ),
+                    )));
+                }
+            } else if completion_state == TaskCompletionState::Done {
+                for task in state
+                    .tasks_list
+                    .iter()
+                    .filter(|task| changed_task_ids.contains(&task.id))
+                {
+                    if let Some(note_link) = task.note_link.clone() {
+                        save_task = save_task.chain(Task::done(Message::Notes(
+                            NotesPageMessage::TickChecklistItem(note_link, task.title.clone()),
+                        )));
+                    }
+                }
+            }
+            return save_task;
+        }
+        TasksPageMessage::BulkDeleteTasksWithConfirmationCheck => {
+            if state.should_confirm_before_delete {
+                state.show_confirm_bulk_delete_dialog = true;
+            } else {
+                return Task::done(Message::Tasks(TasksPageMessage::BulkDeleteTasks));
+            }
+        }
+        TasksPageMessage::ToggleConfirmBulkDeleteDialog => {
+            state.show_confirm_bulk_delete_dialog = !state.show_confirm_bulk_delete_dialog;
+        }
+        TasksPageMessage::BulkDeleteTasks => {
+            state.show_confirm_bulk_delete_dialog = false;
+            if state
+                .tasks_list
+                .iter()
+                .any(|task| state.selected_task_ids.contains(&task.id))
+            {
+                record_undo_snapshot(state);
+                state
+                    .tasks_list
+                    .retain(|task| !state.selected_task_ids.contains(&task.id));
+                state.is_dirty = true;
+            }
+            let deleted_running_timer_task =
+                state.running_timer.as_ref().is_some_and(|running_timer| {
+                    state.selected_task_ids.contains(&running_timer.task_id)
+                });
+            state.selected_task_ids.clear();
+            if deleted_running_timer_task {
+                state.running_timer = None;
+                return Task::done(Message::Tasks(TasksPageMessage::SaveProject)).chain(
+                    Task::done(Message::Settings(
+                        SettingsPageMessage::TasksSetRunningTimer(None),
+                    )),
+                );
+            }
+            return Task::done(Message::Tasks(TasksPageMessage::SaveProject));
+        }
+        TasksPageMessage::UpdateBulkLabelsText(s) => state.bulk_labels_text = s,
+        TasksPageMessage::BulkAddLabels => {
+            let new_labels = parse_labels(&state.bulk_labels_text);
+            if new_labels.is_empty() || state.selected_task_ids.is_empty() {
+                return Task::none();
+            }
+            record_undo_snapshot(state);
+            for task in state
+                .tasks_list
+                .iter_mut()
+                .filter(|task| state.selected_task_ids.contains(&task.id))
+            {
+                for new_label in &new_labels {
+                    if !task.labels.contains(new_label) {
+                        task.labels.push(new_label.clone());
+                    }
+                }
+            }
+            state.bulk_labels_text = String::new();
+            state.is_dirty = true;
+            return Task::done(Message::Tasks(TasksPageMessage::SaveProject));
+        }
+        TasksPageMessage::BulkMoveToProject(project_name) => {
+            let Some(source_project_file) = state.current_project_file.clone() else {
+                return Task::none();
+            };
+            let Some(target_project_file) = state
+                .projects_list
+                .iter()
+                .find(|project_file| {
+                    project_file
+                        .file_stem()
+                        .is_some_and(|file_stem| file_stem.to_string_lossy() == project_name)
+                })
+                .filter(|project_file| **project_file != source_project_file)
+                .cloned()
+            else {
+                return Task::none();
+            };
+            // Stop the timer first so the time tracked so far moves with the task
+            let mut stop_timer_task = Task::none();
+            if state.running_timer.as_ref().is_some_and(|running_timer| {
+                state.selected_task_ids.contains(&running_timer.task_id)
+            }) {
+                stop_timer_task = stop_running_timer(state).chain(Task::done(Message::Settings(
+                    SettingsPageMessage::TasksSetRunningTimer(None),
+                )));
+            }
+            let moved_tasks: Vec<TaskData> = state
+                .tasks_list
+                .iter()
+                .filter(|task| state.selected_task_ids.contains(&task.id))
+                .cloned()
+                .collect();
+            let moved_task_ids: Vec<Uuid> = moved_tasks.iter().map(|task| task.id).collect();
+            // The tasks are only removed from this project once they've been saved to the other one
+            return stop_timer_task.chain(Task::perform(
+                async move { add_tasks_to_project_file(&target_project_file, moved_tasks) },
+                move |result| match result {
+                    Ok(_) => Message::Tasks(TasksPageMessage::RemoveMovedTasks(
+                        source_project_file.clone(),
+                        project_name.clone(),
+                        moved_task_ids.clone(),
+                    )),
+                    Err(err) => Message::ShowToast(
+                        false,
+                        format!("Failed to move the tasks to {project_name}: {err}"),
+                    ),
+                },
+            ));
+        }
+        TasksPageMessage::RemoveMovedTasks(source_project_file, project_name, moved_task_ids) => {
+            let moved_message = format!("Moved {} tasks to {project_name}", moved_task_ids.len());
+            // Another project may have been opened while the tasks were being added to the new one
+            if state.current_project_file.as_ref() != Some(&source_project_file) {
+                return Task::perform(
+                    async move {
+                        update_project_file(&source_project_file, |tasks_list| {
+                            tasks_list.retain(|task| !moved_task_ids.contains(&task.id));
+                            Ok(())
+                        })
+                    },
+                    move |result| match result {
+                        Ok(_) => Message::ShowToast(true, moved_message.clone()),
+                        Err(err) => Message::ShowToast(
+                            false,
+                            format!(
+                                "Couldn't remove the moved tasks from their old project: {err}"
+                            ),
+                        ),
+                    },
+                );
+            }
+            record_undo_snapshot(state);
+            state
+                .tasks_list
+                .retain(|task| !moved_task_ids.contains(&task.id));
+            state
+                .selected_task_ids
+                .retain(|task_id| !moved_task_ids.contains(task_id));
+            state.is_dirty = true;
+            return Task::done(Message::Tasks(TasksPageMessage::SaveProject))
+                .chain(Task::done(Message::ShowToast(true, moved_message)));
+        }
+        TasksPageMessage::MarkTaskDoneInProject(project_file, task_id) => {
+            if state.current_project_file.as_ref() == Some(&project_file) {
+                return Task::done(Message::Tasks(TasksPageMessage::SetTaskCompletionState(
+                    task_id,
+                    TaskCompletionState::Done,
+                )));
+            }
+            return Task::perform(
+                async move {
+                    update_project_file(&project_file, |tasks_list| {
+                        let task = tasks_list
+                            .iter_mut()
+                            .find(|task| task.id == task_id)
+                            .ok_or(String::from("The task no longer exists"))?;
+                        let previous_completion_state =
+                            task.set_completion_state(TaskCompletionState::Done);
+                        Ok((previous_completion_state != TaskCompletionState::Done)
+                            .then(|| task.clone()))
+                    })
+                },
+                |result| match result {
+                    Ok(Some(finished_task)) => {
+                        Message::Tasks(TasksPageMessage::TaskMarkedDoneInProject(finished_task))
+                    }
+                    Ok(None) => Message::None,
+                    Err(err) => {
+                        Message::ShowToast(false, format!("Couldn't mark the task as done: {err}"))
+                    }
+                },
+            );
+        }
+        TasksPageMessage::TaskMarkedDoneInProject(finished_task) => {
+            // Keep the index in step until it's reloaded, so the tasks it was blocking count as unblocked
+            if let Some(task_summary) = state.task_index.get_mut(&finished_task.id) {
+                task_summary.completion_state = TaskCompletionState::Done;
+            }
+            return follow_up_finished_task(state, &finished_task)
+                .chain(Task::done(Message::Tasks(TasksPageMessage::LoadTaskIndex)));
+        }
     }
     Task::none()
 }
+
+/// Lists the tasks which the finished task was blocking, and ticks off the note checklist item it was made from
+fn follow_up_finished_task(state: &TasksPage, finished_task: &TaskData) -> Task<Message> {
+    let mut follow_up_task = Task::none();
+    let unblocked_tasks = tasks_unblocked_by(
+        finished_task.id,
+        &state.tasks_list,
+        state.current_project_file.as_ref(),
+        &state.task_index,
+    );
+    if !unblocked_tasks.is_empty() {
+        follow_up_task = follow_up_task.chain(Task::done(Message::ShowToast(
+            true,
+            format!(
+                "These tasks are no longer blocked: {}",
+                unblocked_tasks.join(", ")
+            ),
+        )));
+    }
+    if let Some(note_link) = finished_task.note_link.clone() {
+        follow_up_task = follow_up_task.chain(Task::done(Message::Notes(
+            NotesPageMessage::TickChecklistItem(note_link, finished_task.title.clone()),
+        )));
+    }
+    follow_up_task
+}
+
+/// Saves the current tasks so the change about to be made can be undone
+fn record_undo_snapshot(state: &mut TasksPage) {
+    state.undo_stack.push(state.tasks_list.clone());
+    if state.undo_stack.len() > MAX_UNDO_STEPS {
+        state.undo_stack.remove(0);
+    }
+    state.redo_stack.clear();
+}
+
+/// The IDs of the tasks which pass the filters, column by column as they are shown on the board
+fn visible_task_ids_in_board_order(state: &TasksPage) -> Vec<Uuid> {
+    [
+        TaskCompletionState::Backlog,
+        TaskCompletionState::ToDo,
+        TaskCompletionState::Doing,
+        TaskCompletionState::Done,
+    ]
+    .iter()
+    .flat_map(|completion_state| {
+        state
+            .tasks_list
+            .iter()
+            .filter(move |task| task.completion_state == *completion_state)
+            .filter(move |task| task_is_visible(state, task))
+            .map(|task| task.id)
+    })
+    .collect()
+}
+
+/// Stops the running timer, if there is one, and adds the time to its task.
+/// Returns the task which saves the time entry to the task's project file.
+fn stop_running_timer(state: &mut TasksPage) -> Task<Message> {
+    let Some(running_timer) = state.running_timer.take() else {
+        return Task::none();
+    };
+    let time_entry = TimeEntry {
+        start: running_timer.started_at,
+        end: Utc::now(),
+    };
+    if state.current_project_file.as_ref() == Some(&running_timer.project_file) {
+        if let Some(task) = state
+            .tasks_list
+            .iter_mut()
+            .find(|task| task.id == running_timer.task_id)
+        {
+            task.time_entries.push(time_entry);
+            state.is_dirty = true;
+        }
+        Task::done(Message::Tasks(TasksPageMessage::SaveProject))
+    } else {
+        Task::perform(
+            add_time_entry_to_project_file(
+                running_timer.project_file,
+                running_timer.task_id,
+                time_entry,
+            ),
+            |result| match result {
+                Ok(_) => Message::None,
+                Err(err_string) => Message::ShowToast(false, err_string),
+            },
+        )
+    }
+}
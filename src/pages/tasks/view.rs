@@ -2,24 +2,42 @@ use crate::{
     pages::tasks::page::{TaskViewType, NEW_PROJECT_TEXT_INPUT_ID},
     LOCALES,
 };
+use chrono::{Local, TimeDelta, Utc};
 use fluent_templates::Loader;
 use iced::{
     alignment::{Horizontal, Vertical},
     widget::{
-        button, column, container, row, scrollable, svg, text, text_editor, text_input, MouseArea,
-        Row, Space, Svg, Tooltip,
+        button, canvas, column, container, pick_list, row, scrollable, svg, text, text_editor,
+        text_input, MouseArea, Row, Space, Svg, Tooltip,
     },
     Alignment::Center,
-    Element, Font, Length,
+    Border, Element, Font, Length, Theme,
 };
 use iced_aw::{drag_and_drop::droppable, drop_down, DropDown};
 
 use crate::app::Message;
 
+use super::charts::{BurndownChart, CumulativeFlowChart};
 use super::page::{
-    TaskCompletionState, TaskData, TasksPage, TasksPageMessage, BACKLOG_ID, DOING_ID, DONE_ID,
-    RENAME_PROJECT_TEXT_INPUT_ID, TASK_TITLE_TEXT_INPUT_ID, TODO_ID,
+    NoteLink, TaskCompletionState, TaskData, TasksPage, TasksPageMessage, BACKLOG_ID, DOING_ID,
+    DONE_ID, RENAME_PROJECT_TEXT_INPUT_ID, SEARCH_ALL_PROJECTS_TEXT_INPUT_ID,
+    TASK_TITLE_TEXT_INPUT_ID, TODO_ID,
 };
+use super::tasks_utils::{
+    average_cycle_time, cumulative_flow, dependency_levels, find_task_summary, format_duration,
+    task_matches_text, task_note_links, time_by_day, time_by_task, time_report, total_tracked_time,
+    unfinished_blockers, TaskActivityKind, TaskFileFormat,
+};
+use uuid::Uuid;
+
+/// Whether a task matches the filter text and the selected saved filter, if any
+pub(super) fn task_is_visible(state: &TasksPage, task: &TaskData) -> bool {
+    task_matches_text(task, &state.filter_tasks_text)
+        && state
+            .selected_saved_filter
+            .and_then(|filter_index| state.saved_filters.get(filter_index))
+            .is_none_or(|saved_filter| saved_filter.matches(task))
+}
 
 pub fn main_view(state: &TasksPage) -> Element<Message> {
     if state.selected_folder.is_none() {
@@ -31,7 +49,15 @@ pub fn main_view(state: &TasksPage) -> Element<Message> {
             } else {
                 column![].into()
             },
-            if state.current_project_file.is_some() {
+            if state.show_search_all_projects_view {
+                search_all_projects_view(state)
+            } else if state.show_time_report_view && state.current_project_file.is_some() {
+                time_report_view(state)
+            } else if state.show_dependency_graph_view && state.current_project_file.is_some() {
+                dependency_graph_view(state)
+            } else if state.show_statistics_view && state.current_project_file.is_some() {
+                statistics_view(state)
+            } else if state.current_project_file.is_some() {
                 column![
                     row![
                         text(
@@ -65,6 +91,13 @@ pub fn main_view(state: &TasksPage) -> Element<Message> {
                     } else {
                         column![].into()
                     },
+                    if state.show_confirm_bulk_delete_dialog {
+                        confirm_bulk_delete_view(state)
+                    } else if !state.selected_task_ids.is_empty() {
+                        bulk_actions_view(state)
+                    } else {
+                        column![].into()
+                    },
                     match state.task_view_type {
                         TaskViewType::Kanban => kanban_view(state),
                         TaskViewType::List => list_view(state),
@@ -93,9 +126,33 @@ fn kanban_view_item<'a>(state: &'a TasksPage, task: &'a TaskData) -> Element<'a,
             .width(Length::Fill)
             .align_x(Center),
         text(&task.description),
+        if task.labels.is_empty() {
+            column![]
+        } else {
+            column![text(task.labels.join(", ")).font(Font {
+                style: iced::font::Style::Italic,
+                ..Default::default()
+            })]
+        },
+        if let Some(due_date) = task.due_date {
+            column![text(format!(
+                "{} {}",
+                LOCALES.lookup(&state.locale, "due"),
+                due_date.format("%Y-%m-%d")
+            ))]
+        } else {
+            column![]
+        },
+        tracked_time_view(state, task),
+        blocked_view(state, task),
+        note_links_view(
+            state,
+            task_note_links(task.note_link.as_ref(), &task.description)
+        ),
     ]
     .into();
-    droppable(
+    let is_selected = state.selected_task_ids.contains(&task.id);
+    let mut task_card = MouseArea::new(
         container(
             column![
                 if state.right_click_to_edit_task {
@@ -171,6 +228,7 @@ fn kanban_view_item<'a>(state: &'a TasksPage, task: &'a TaskData) -> Element<'a,
                     row![]
                 },
                 row![
+                    timer_button(state, task),
                     button(Svg::new(svg::Handle::from_memory(include_bytes!(
                         "../../../icons/edit.svg"
                     ))))
@@ -190,16 +248,612 @@ fn kanban_view_item<'a>(state: &'a TasksPage, task: &'a TaskData) -> Element<'a,
             ]
             .padding(5),
         )
-        .style(container::bordered_box),
+        .style(move |theme| task_container_style(theme, is_selected)),
+    );
+    // Only catch clicks while selecting, otherwise they would stop the card being dragged
+    if is_selecting(state) {
+        task_card = task_card.on_press(Message::Tasks(TasksPageMessage::TaskClicked(task.id)));
+    }
+    droppable(task_card)
+        .on_drop(|point, rectangle| {
+            Message::Tasks(TasksPageMessage::DropTask(task.id, point, rectangle))
+        })
+        .into()
+}
+
+fn list_view_item<'a>(state: &'a TasksPage, task: &'a TaskData) -> Element<'a, Message> {
+    let is_selected = state.selected_task_ids.contains(&task.id);
+    let task_row = MouseArea::new(
+        container(
+            row![
+                text(&task.title).width(Length::Fill),
+                blocked_view(state, task),
+                tracked_time_view(state, task),
+                timer_button(state, task)
+            ]
+            .spacing(10)
+            .align_y(Center),
+        )
+        .style(move |theme| {
+            if is_selected {
+                task_container_style(theme, true)
+            } else {
+                container::Style::default()
+            }
+        }),
+    );
+    if is_selecting(state) {
+        task_row
+            .on_press(Message::Tasks(TasksPageMessage::TaskClicked(task.id)))
+            .into()
+    } else {
+        task_row.into()
+    }
+}
+
+/// Whether Ctrl or Shift is held, so clicking a task adds it to the selection
+fn is_selecting(state: &TasksPage) -> bool {
+    state.keyboard_modifiers.control() || state.keyboard_modifiers.shift()
+}
+
+/// Selected tasks get a thicker border in the primary colour
+fn task_container_style(theme: &Theme, is_selected: bool) -> container::Style {
+    let bordered_box = container::bordered_box(theme);
+    if is_selected {
+        container::Style {
+            border: Border {
+                color: theme.palette().primary,
+                width: 2.0,
+                ..bordered_box.border
+            },
+            ..bordered_box
+        }
+    } else {
+        bordered_box
+    }
+}
+
+fn select_all_in_column_button(
+    state: &TasksPage,
+    completion_state: TaskCompletionState,
+) -> Element<Message> {
+    button(
+        text(LOCALES.lookup(&state.locale, "select-all"))
+            .width(Length::Fill)
+            .align_x(Center),
     )
-    .on_drop(|point, rectangle| {
-        Message::Tasks(TasksPageMessage::DropTask(task.id, point, rectangle))
+    .style(button::text)
+    .on_press(Message::Tasks(TasksPageMessage::SelectAllInColumn(
+        completion_state,
+    )))
+    .into()
+}
+
+/// The actions for the tasks selected with Ctrl or Shift click
+fn bulk_actions_view(state: &TasksPage) -> Element<Message> {
+    let other_project_names: Vec<String> = state
+        .projects_list
+        .iter()
+        .filter(|project_file| state.current_project_file.as_ref() != Some(*project_file))
+        .map(|project_file| {
+            project_file
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    column![
+        row![
+            text(format!(
+                "{} {}",
+                LOCALES.lookup(&state.locale, "selected-tasks"),
+                state.selected_task_ids.len()
+            ))
+            .size(20)
+            .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "clear-selection")))
+                .on_press(Message::Tasks(TasksPageMessage::ClearTaskSelection)),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        row([
+            TaskCompletionState::Backlog,
+            TaskCompletionState::ToDo,
+            TaskCompletionState::Doing,
+            TaskCompletionState::Done,
+        ]
+        .into_iter()
+        .map(|completion_state| {
+            button(
+                text(format!(
+                    "{} {}",
+                    LOCALES.lookup(&state.locale, "move-to"),
+                    completion_state_name(state, &completion_state)
+                ))
+                .width(Length::Fill)
+                .align_x(Center),
+            )
+            .width(Length::Fill)
+            .on_press(Message::Tasks(
+                TasksPageMessage::BulkSetTaskCompletionState(completion_state),
+            ))
+            .into()
+        }))
+        .spacing(10),
+        row![
+            text_input(
+                &LOCALES.lookup(&state.locale, "labels-to-add"),
+                &state.bulk_labels_text
+            )
+            .on_input(|s| Message::Tasks(TasksPageMessage::UpdateBulkLabelsText(s)))
+            .on_submit(Message::Tasks(TasksPageMessage::BulkAddLabels))
+            .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "add-labels")))
+                .on_press(Message::Tasks(TasksPageMessage::BulkAddLabels)),
+            pick_list(other_project_names, None::<String>, |project_name| {
+                Message::Tasks(TasksPageMessage::BulkMoveToProject(project_name))
+            })
+            .placeholder(LOCALES.lookup(&state.locale, "move-to-project")),
+            button(text(LOCALES.lookup(&state.locale, "delete")))
+                .style(button::danger)
+                .on_press(Message::Tasks(
+                    TasksPageMessage::BulkDeleteTasksWithConfirmationCheck
+                )),
+        ]
+        .spacing(10)
+        .align_y(Center),
+    ]
+    .spacing(10)
+    .padding(10)
+    .into()
+}
+
+fn confirm_bulk_delete_view(state: &TasksPage) -> Element<Message> {
+    column![
+        text(format!(
+            "{} {}",
+            LOCALES.lookup(&state.locale, "delete-selected-tasks"),
+            state.selected_task_ids.len()
+        ))
+        .width(Length::Fill)
+        .size(24),
+        row![
+            button(
+                text(LOCALES.lookup(&state.locale, "cancel-shortcut"))
+                    .align_x(Center)
+                    .width(Length::Fill)
+            )
+            .width(Length::Fill)
+            .on_press(Message::Tasks(
+                TasksPageMessage::ToggleConfirmBulkDeleteDialog
+            )),
+            button(
+                text(LOCALES.lookup(&state.locale, "delete"))
+                    .align_x(Center)
+                    .width(Length::Fill)
+            )
+            .width(Length::Fill)
+            .style(button::danger)
+            .on_press(Message::Tasks(TasksPageMessage::BulkDeleteTasks))
+        ]
+        .spacing(20)
+    ]
+    .into()
+}
+
+/// Shows which tasks are blocking this one, if any of them aren't done yet
+fn blocked_view<'a>(state: &'a TasksPage, task: &'a TaskData) -> Element<'a, Message> {
+    let blockers = unfinished_blockers(
+        &task.blocked_by,
+        &state.tasks_list,
+        state.current_project_file.as_ref(),
+        &state.task_index,
+    );
+    if blockers.is_empty() {
+        column![].into()
+    } else {
+        text(format!(
+            "{} {}",
+            LOCALES.lookup(&state.locale, "blocked-by"),
+            blockers.join(", ")
+        ))
+        .style(text::danger)
+        .into()
+    }
+}
+
+/// A task which can be picked as a blocker in the task edit dialog
+#[derive(Debug, Clone, PartialEq)]
+struct BlockerOption {
+    task_id: Uuid,
+    label: String,
+}
+
+impl std::fmt::Display for BlockerOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/// Lists the tasks which are blocking the task being edited, and lets more be picked from any project
+fn blockers_editor_view(state: &TasksPage) -> Element<Message> {
+    let mut blocker_options: Vec<BlockerOption> = state
+        .tasks_list
+        .iter()
+        .filter(|task| Some(task.id) != state.current_task_id)
+        .filter(|task| !state.current_task_blocked_by.contains(&task.id))
+        .map(|task| BlockerOption {
+            task_id: task.id,
+            label: task.title.clone(),
+        })
+        .collect();
+    let mut other_project_options: Vec<BlockerOption> = state
+        .task_index
+        .iter()
+        .filter(|(task_id, task_summary)| {
+            Some(&task_summary.project_file) != state.current_project_file.as_ref()
+                && !state.current_task_blocked_by.contains(task_id)
+        })
+        .map(|(task_id, task_summary)| BlockerOption {
+            task_id: *task_id,
+            label: format!(
+                "{} / {}",
+                task_summary
+                    .project_file
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy(),
+                task_summary.title
+            ),
+        })
+        .collect();
+    other_project_options.sort_unstable_by(|first, second| first.label.cmp(&second.label));
+    blocker_options.append(&mut other_project_options);
+    column![
+        text(LOCALES.lookup(&state.locale, "blocked-by")),
+        column(state.current_task_blocked_by.iter().map(|blocker_id| {
+            row![
+                text(
+                    find_task_summary(
+                        *blocker_id,
+                        &state.tasks_list,
+                        state.current_project_file.as_ref(),
+                        &state.task_index
+                    )
+                    .map(|task_summary| task_summary.title)
+                    .unwrap_or(LOCALES.lookup(&state.locale, "deleted-task"))
+                )
+                .width(Length::Fill),
+                button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                    "../../../icons/delete.svg"
+                ))))
+                .style(button::danger)
+                .width(Length::Fixed(50.0))
+                .on_press(Message::Tasks(
+                    TasksPageMessage::RemoveBlockerFromCurrentTask(*blocker_id)
+                ))
+            ]
+            .align_y(Center)
+            .into()
+        }))
+        .spacing(5),
+        pick_list(blocker_options, None::<BlockerOption>, |blocker_option| {
+            Message::Tasks(TasksPageMessage::AddBlockerToCurrentTask(
+                blocker_option.task_id,
+            ))
+        })
+        .placeholder(LOCALES.lookup(&state.locale, "add-blocker"))
+        .width(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+/// Buttons to open the notes linked to a task on the notes page
+fn note_links_view<'a>(state: &TasksPage, note_links: Vec<NoteLink>) -> Element<'a, Message> {
+    column(note_links.into_iter().map(|note_link| {
+        button(text(format!(
+            "{} {} ({} {})",
+            LOCALES.lookup(&state.locale, "open-note"),
+            note_link
+                .note_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            LOCALES.lookup(&state.locale, "line"),
+            note_link.line + 1
+        )))
+        .style(button::text)
+        .on_press(Message::Tasks(TasksPageMessage::OpenNoteLink(note_link)))
+        .into()
+    }))
+    .into()
+}
+
+/// Lists the activity log entries for the task being edited
+fn task_history_view(state: &TasksPage) -> Element<Message> {
+    if state.current_task_history.is_empty() {
+        return column![].into();
+    }
+    column![
+        text(LOCALES.lookup(&state.locale, "history")),
+        scrollable(
+            column(state.current_task_history.iter().map(|activity| {
+                row![
+                    text(
+                        activity
+                            .timestamp
+                            .with_timezone(&Local)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    )
+                    .font(Font::MONOSPACE)
+                    .width(Length::Fixed(160.0)),
+                    text(match &activity.kind {
+                        TaskActivityKind::Created => LOCALES.lookup(&state.locale, "task-created"),
+                        TaskActivityKind::Moved { from, to } => format!(
+                            "{} {} → {}",
+                            LOCALES.lookup(&state.locale, "task-moved"),
+                            completion_state_name(state, from),
+                            completion_state_name(state, to)
+                        ),
+                        TaskActivityKind::Edited => LOCALES.lookup(&state.locale, "task-edited"),
+                        TaskActivityKind::Deleted => LOCALES.lookup(&state.locale, "task-deleted"),
+                    })
+                ]
+                .spacing(10)
+                .into()
+            }))
+            .spacing(5)
+        )
+        .height(Length::Fixed(120.0))
+    ]
+    .spacing(10)
+    .into()
+}
+
+/// Shows the tasks in the current project in columns, with each task to the right of the tasks blocking it
+fn dependency_graph_view(state: &TasksPage) -> Element<Message> {
+    column![
+        row![
+            text(LOCALES.lookup(&state.locale, "dependency-graph"))
+                .size(28)
+                .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "close")))
+                .on_press(Message::Tasks(TasksPageMessage::ToggleDependencyGraphView))
+        ]
+        .spacing(10)
+        .align_y(Center),
+        scrollable(
+            row(dependency_levels(&state.tasks_list)
+                .into_iter()
+                .map(|level| {
+                    column(level.into_iter().filter_map(|task_id| {
+                        state
+                            .tasks_list
+                            .iter()
+                            .find(|task| task.id == task_id)
+                            .map(|task| dependency_graph_item(state, task))
+                    }))
+                    .spacing(10)
+                    .width(Length::Fixed(220.0))
+                    .into()
+                }))
+            .spacing(40)
+        )
+        .direction(scrollable::Direction::Both {
+            vertical: scrollable::Scrollbar::default(),
+            horizontal: scrollable::Scrollbar::default(),
+        })
+    ]
+    .spacing(20)
+    .padding(20)
+    .width(Length::FillPortion(2))
+    .into()
+}
+
+fn dependency_graph_item<'a>(state: &'a TasksPage, task: &'a TaskData) -> Element<'a, Message> {
+    let blocker_titles: Vec<String> = task
+        .blocked_by
+        .iter()
+        .filter_map(|blocker_id| {
+            find_task_summary(
+                *blocker_id,
+                &state.tasks_list,
+                state.current_project_file.as_ref(),
+                &state.task_index,
+            )
+        })
+        .map(|task_summary| task_summary.title)
+        .collect();
+    MouseArea::new(
+        container(
+            column![
+                text(&task.title).size(18),
+                text(completion_state_name(state, &task.completion_state)),
+                if blocker_titles.is_empty() {
+                    column![]
+                } else {
+                    column![text(format!(
+                        "{} {}",
+                        LOCALES.lookup(&state.locale, "after"),
+                        blocker_titles.join(", ")
+                    ))
+                    .font(Font {
+                        style: iced::font::Style::Italic,
+                        ..Default::default()
+                    })]
+                },
+                blocked_view(state, task),
+            ]
+            .spacing(5)
+            .padding(10),
+        )
+        .style(container::bordered_box)
+        .width(Length::Fill),
+    )
+    .on_release(Message::Tasks(TasksPageMessage::OpenEditDialogForTask(
+        task.id,
+    )))
+    .into()
+}
+
+fn is_timer_running_on_task(state: &TasksPage, task: &TaskData) -> bool {
+    state.running_timer.as_ref().is_some_and(|running_timer| {
+        running_timer.task_id == task.id
+            && state.current_project_file.as_ref() == Some(&running_timer.project_file)
     })
+}
+
+/// Shows the total time tracked on a task, including the running timer, if any time has been tracked
+fn tracked_time_view<'a>(state: &'a TasksPage, task: &'a TaskData) -> Element<'a, Message> {
+    let mut tracked_time = total_tracked_time(task);
+    if let Some(running_timer) = state.running_timer.as_ref() {
+        if is_timer_running_on_task(state, task) {
+            tracked_time += Utc::now() - running_timer.started_at;
+        }
+    }
+    if tracked_time > TimeDelta::zero() {
+        text(format!(
+            "{} {}",
+            LOCALES.lookup(&state.locale, "tracked-time"),
+            format_duration(tracked_time)
+        ))
+        .into()
+    } else {
+        column![].into()
+    }
+}
+
+fn timer_button<'a>(state: &'a TasksPage, task: &'a TaskData) -> Element<'a, Message> {
+    if is_timer_running_on_task(state, task) {
+        Tooltip::new(
+            button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                "../../../icons/pause.svg"
+            ))))
+            .style(button::success)
+            .width(Length::Fill)
+            .on_press(Message::Tasks(TasksPageMessage::StopTimer)),
+            text(LOCALES.lookup(&state.locale, "stop-timer")),
+            iced::widget::tooltip::Position::Bottom,
+        )
+        .into()
+    } else {
+        Tooltip::new(
+            button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                "../../../icons/start.svg"
+            ))))
+            .width(Length::Fill)
+            .on_press(Message::Tasks(TasksPageMessage::StartTimer(task.id))),
+            text(LOCALES.lookup(&state.locale, "start-timer")),
+            iced::widget::tooltip::Position::Bottom,
+        )
+        .into()
+    }
+}
+
+fn time_report_view(state: &TasksPage) -> Element<Message> {
+    let report_rows = time_report(&state.tasks_list);
+    column![
+        row![
+            text(LOCALES.lookup(&state.locale, "time-report"))
+                .size(28)
+                .width(Length::Fill),
+            button(text(
+                LOCALES.lookup(&state.locale, "export-time-report-csv")
+            ))
+            .on_press(Message::Tasks(TasksPageMessage::ExportTimeReport)),
+            button(text(LOCALES.lookup(&state.locale, "close")))
+                .on_press(Message::Tasks(TasksPageMessage::ToggleTimeReportView))
+        ]
+        .spacing(10)
+        .align_y(Center),
+        if report_rows.is_empty() {
+            column![text(LOCALES.lookup(&state.locale, "no-time-tracked"))]
+        } else {
+            column![
+                text(LOCALES.lookup(&state.locale, "time-by-task")).size(20),
+                column(
+                    time_by_task(&report_rows)
+                        .into_iter()
+                        .map(|(task_title, duration)| {
+                            row![
+                                text(task_title).width(Length::Fill),
+                                text(format_duration(duration))
+                            ]
+                            .into()
+                        })
+                )
+                .spacing(5),
+                text(LOCALES.lookup(&state.locale, "time-by-day")).size(20),
+                column(
+                    time_by_day(&report_rows)
+                        .into_iter()
+                        .map(|(date, duration)| {
+                            row![
+                                text(date.format("%Y-%m-%d").to_string()).width(Length::Fill),
+                                text(format_duration(duration))
+                            ]
+                            .into()
+                        })
+                )
+                .spacing(5),
+            ]
+            .spacing(10)
+        }
+    ]
+    .spacing(20)
+    .padding(20)
+    .width(Length::FillPortion(2))
     .into()
 }
 
-fn list_view_item(task: &TaskData) -> Element<Message> {
-    text(&task.title).into()
+fn statistics_view(state: &TasksPage) -> Element<Message> {
+    let flow_days = cumulative_flow(&state.tasks_list, Local::now().date_naive());
+    column![
+        row![
+            text(LOCALES.lookup(&state.locale, "project-statistics"))
+                .size(28)
+                .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "close")))
+                .on_press(Message::Tasks(TasksPageMessage::ToggleStatisticsView))
+        ]
+        .spacing(10)
+        .align_y(Center),
+        text(format!(
+            "{} {}",
+            LOCALES.lookup(&state.locale, "average-cycle-time"),
+            match average_cycle_time(&state.tasks_list) {
+                Some(cycle_time) => format!(
+                    "{:.1} {}",
+                    cycle_time.num_minutes() as f64 / (24.0 * 60.0),
+                    LOCALES.lookup(&state.locale, "days")
+                ),
+                None => LOCALES.lookup(&state.locale, "no-finished-tasks"),
+            }
+        )),
+        text(LOCALES.lookup(&state.locale, "cumulative-flow")).size(20),
+        canvas(CumulativeFlowChart {
+            flow_days: flow_days.clone(),
+            column_names: [
+                LOCALES.lookup(&state.locale, "backlog"),
+                LOCALES.lookup(&state.locale, "todo"),
+                LOCALES.lookup(&state.locale, "doing"),
+                LOCALES.lookup(&state.locale, "done"),
+            ],
+        })
+        .width(Length::Fill)
+        .height(Length::Fixed(250.0)),
+        text(LOCALES.lookup(&state.locale, "burndown")).size(20),
+        canvas(BurndownChart { flow_days })
+            .width(Length::Fill)
+            .height(Length::Fixed(250.0)),
+    ]
+    .spacing(20)
+    .padding(20)
+    .width(Length::FillPortion(2))
+    .into()
 }
 
 fn task_edit_dialog(state: &TasksPage) -> Element<Message> {
@@ -235,6 +889,42 @@ fn task_edit_dialog(state: &TasksPage) -> Element<Message> {
             .height(Length::Fixed(300.0))
             .padding(10)
             .font(Font::MONOSPACE),
+        row![
+            text_input(
+                &LOCALES.lookup(&state.locale, "task-labels"),
+                &state.current_task_labels_text
+            )
+            .on_input(|s| Message::Tasks(TasksPageMessage::UpdateTaskLabels(s)))
+            .on_submit(Message::Tasks(TasksPageMessage::UpdateCurrentTask))
+            .width(Length::FillPortion(2)),
+            text_input(
+                &LOCALES.lookup(&state.locale, "task-due-date"),
+                &state.current_task_due_date_text
+            )
+            .on_input(|s| Message::Tasks(TasksPageMessage::UpdateTaskDueDate(s)))
+            .on_submit(Message::Tasks(TasksPageMessage::UpdateCurrentTask))
+            .width(Length::FillPortion(1)),
+            text_input(
+                &LOCALES.lookup(&state.locale, "task-reminder"),
+                &state.current_task_reminder_text
+            )
+            .on_input(|s| Message::Tasks(TasksPageMessage::UpdateTaskReminder(s)))
+            .on_submit(Message::Tasks(TasksPageMessage::UpdateCurrentTask))
+            .width(Length::FillPortion(1)),
+        ]
+        .spacing(20),
+        blockers_editor_view(state),
+        note_links_view(
+            state,
+            task_note_links(
+                state
+                    .current_task_id
+                    .and_then(|task_id| state.tasks_list.iter().find(|task| task.id == task_id))
+                    .and_then(|task| task.note_link.as_ref()),
+                &state.current_task_description_content.text()
+            )
+        ),
+        task_history_view(state),
         row![
             button(
                 text(LOCALES.lookup(&state.locale, "cancel-shortcut"))
@@ -289,6 +979,7 @@ fn kanban_view(state: &TasksPage) -> Element<Message> {
             })
             .width(Length::Fill)
             .align_x(Center),
+            select_all_in_column_button(state, TaskCompletionState::Backlog),
             scrollable(
                 column(
                     state
@@ -298,15 +989,7 @@ fn kanban_view(state: &TasksPage) -> Element<Message> {
                             task.completion_state,
                             TaskCompletionState::Backlog
                         ))
-                        .filter(|task| {
-                            task.title
-                                .to_lowercase()
-                                .contains(&state.filter_tasks_text.to_lowercase())
-                                || task
-                                    .description
-                                    .to_lowercase()
-                                    .contains(&state.filter_tasks_text.to_lowercase())
-                        })
+                        .filter(|task| task_is_visible(state, task))
                         .map(|task| kanban_view_item(state, task))
                 )
                 .padding(5)
@@ -347,21 +1030,14 @@ fn kanban_view(state: &TasksPage) -> Element<Message> {
             })
             .width(Length::Fill)
             .align_x(Center),
+            select_all_in_column_button(state, TaskCompletionState::ToDo),
             scrollable(
                 column(
                     state
                         .tasks_list
                         .iter()
                         .filter(|task| matches!(task.completion_state, TaskCompletionState::ToDo))
-                        .filter(|task| {
-                            task.title
-                                .to_lowercase()
-                                .contains(&state.filter_tasks_text.to_lowercase())
-                                || task
-                                    .description
-                                    .to_lowercase()
-                                    .contains(&state.filter_tasks_text.to_lowercase())
-                        })
+                        .filter(|task| task_is_visible(state, task))
                         .map(|task| kanban_view_item(state, task))
                 )
                 .padding(5)
@@ -402,21 +1078,14 @@ fn kanban_view(state: &TasksPage) -> Element<Message> {
             })
             .width(Length::Fill)
             .align_x(Center),
+            select_all_in_column_button(state, TaskCompletionState::Doing),
             scrollable(
                 column(
                     state
                         .tasks_list
                         .iter()
                         .filter(|task| matches!(task.completion_state, TaskCompletionState::Doing))
-                        .filter(|task| {
-                            task.title
-                                .to_lowercase()
-                                .contains(&state.filter_tasks_text.to_lowercase())
-                                || task
-                                    .description
-                                    .to_lowercase()
-                                    .contains(&state.filter_tasks_text.to_lowercase())
-                        })
+                        .filter(|task| task_is_visible(state, task))
                         .map(|task| kanban_view_item(state, task))
                 )
                 .padding(5)
@@ -457,21 +1126,14 @@ fn kanban_view(state: &TasksPage) -> Element<Message> {
             })
             .width(Length::Fill)
             .align_x(Center),
+            select_all_in_column_button(state, TaskCompletionState::Done),
             scrollable(
                 column(
                     state
                         .tasks_list
                         .iter()
                         .filter(|task| matches!(task.completion_state, TaskCompletionState::Done))
-                        .filter(|task| {
-                            task.title
-                                .to_lowercase()
-                                .contains(&state.filter_tasks_text.to_lowercase())
-                                || task
-                                    .description
-                                    .to_lowercase()
-                                    .contains(&state.filter_tasks_text.to_lowercase())
-                        })
+                        .filter(|task| task_is_visible(state, task))
                         .map(|task| kanban_view_item(state, task))
                 )
                 .padding(5)
@@ -494,6 +1156,7 @@ fn list_view(state: &TasksPage) -> Element<Message> {
                 .width(Length::Fill)
                 .align_x(Center)
                 .size(20),
+            select_all_in_column_button(state, TaskCompletionState::Backlog),
             scrollable(
                 column(
                     state
@@ -503,16 +1166,8 @@ fn list_view(state: &TasksPage) -> Element<Message> {
                             task.completion_state,
                             TaskCompletionState::Backlog
                         ))
-                        .filter(|task| {
-                            task.title
-                                .to_lowercase()
-                                .contains(&state.filter_tasks_text.to_lowercase())
-                                || task
-                                    .description
-                                    .to_lowercase()
-                                    .contains(&state.filter_tasks_text.to_lowercase())
-                        })
-                        .map(|task| list_view_item(task))
+                        .filter(|task| task_is_visible(state, task))
+                        .map(|task| list_view_item(state, task))
                 )
                 .spacing(10)
             )
@@ -522,22 +1177,15 @@ fn list_view(state: &TasksPage) -> Element<Message> {
                 .width(Length::Fill)
                 .align_x(Center)
                 .size(20),
+            select_all_in_column_button(state, TaskCompletionState::ToDo),
             scrollable(
                 column(
                     state
                         .tasks_list
                         .iter()
                         .filter(|task| matches!(task.completion_state, TaskCompletionState::ToDo))
-                        .filter(|task| {
-                            task.title
-                                .to_lowercase()
-                                .contains(&state.filter_tasks_text.to_lowercase())
-                                || task
-                                    .description
-                                    .to_lowercase()
-                                    .contains(&state.filter_tasks_text.to_lowercase())
-                        })
-                        .map(|task| list_view_item(task))
+                        .filter(|task| task_is_visible(state, task))
+                        .map(|task| list_view_item(state, task))
                 )
                 .spacing(10)
             )
@@ -547,22 +1195,15 @@ fn list_view(state: &TasksPage) -> Element<Message> {
                 .width(Length::Fill)
                 .align_x(Center)
                 .size(20),
+            select_all_in_column_button(state, TaskCompletionState::Doing),
             scrollable(
                 column(
                     state
                         .tasks_list
                         .iter()
                         .filter(|task| matches!(task.completion_state, TaskCompletionState::Doing))
-                        .filter(|task| {
-                            task.title
-                                .to_lowercase()
-                                .contains(&state.filter_tasks_text.to_lowercase())
-                                || task
-                                    .description
-                                    .to_lowercase()
-                                    .contains(&state.filter_tasks_text.to_lowercase())
-                        })
-                        .map(|task| list_view_item(task))
+                        .filter(|task| task_is_visible(state, task))
+                        .map(|task| list_view_item(state, task))
                 )
                 .spacing(10)
             )
@@ -572,22 +1213,15 @@ fn list_view(state: &TasksPage) -> Element<Message> {
                 .width(Length::Fill)
                 .align_x(Center)
                 .size(20),
+            select_all_in_column_button(state, TaskCompletionState::Done),
             scrollable(
                 column(
                     state
                         .tasks_list
                         .iter()
                         .filter(|task| matches!(task.completion_state, TaskCompletionState::Done))
-                        .filter(|task| {
-                            task.title
-                                .to_lowercase()
-                                .contains(&state.filter_tasks_text.to_lowercase())
-                                || task
-                                    .description
-                                    .to_lowercase()
-                                    .contains(&state.filter_tasks_text.to_lowercase())
-                        })
-                        .map(|task| list_view_item(task))
+                        .filter(|task| task_is_visible(state, task))
+                        .map(|task| list_view_item(state, task))
                 )
                 .spacing(10)
             )
@@ -941,12 +1575,246 @@ fn sidebar_view(state: &TasksPage) -> Element<Message> {
             )
             .spacing(5)
         )
-        .spacing(5)
+        .spacing(5),
+        Space::with_height(20),
+        saved_filters_view(state),
     ]
     .width(Length::FillPortion(1))
     .into()
 }
 
+fn completion_state_name(state: &TasksPage, completion_state: &TaskCompletionState) -> String {
+    match completion_state {
+        TaskCompletionState::Backlog => LOCALES.lookup(&state.locale, "backlog"),
+        TaskCompletionState::ToDo => LOCALES.lookup(&state.locale, "todo"),
+        TaskCompletionState::Doing => LOCALES.lookup(&state.locale, "doing"),
+        TaskCompletionState::Done => LOCALES.lookup(&state.locale, "done"),
+    }
+}
+
+fn saved_filters_view(state: &TasksPage) -> Element<Message> {
+    if state.is_creating_new_saved_filter {
+        column![
+            text(LOCALES.lookup(&state.locale, "new-saved-filter")).size(20),
+            text_input(
+                &LOCALES.lookup(&state.locale, "saved-filter-name"),
+                &state.new_saved_filter.name
+            )
+            .on_input(|s| Message::Tasks(TasksPageMessage::UpdateNewSavedFilterName(s))),
+            text_input(
+                &LOCALES.lookup(&state.locale, "saved-filter-text"),
+                &state.new_saved_filter.text
+            )
+            .on_input(|s| Message::Tasks(TasksPageMessage::UpdateNewSavedFilterText(s))),
+            text_input(
+                &LOCALES.lookup(&state.locale, "saved-filter-label"),
+                state.new_saved_filter.label.as_deref().unwrap_or_default()
+            )
+            .on_input(|s| Message::Tasks(TasksPageMessage::UpdateNewSavedFilterLabel(s))),
+            text_input(
+                &LOCALES.lookup(&state.locale, "saved-filter-due-on-or-before"),
+                &state.new_saved_filter_due_date_text
+            )
+            .on_input(|s| Message::Tasks(TasksPageMessage::UpdateNewSavedFilterDueDate(s))),
+            row([
+                None,
+                Some(TaskCompletionState::Backlog),
+                Some(TaskCompletionState::ToDo),
+                Some(TaskCompletionState::Doing),
+                Some(TaskCompletionState::Done),
+            ]
+            .into_iter()
+            .map(|completion_state| {
+                button(
+                    text(
+                        completion_state
+                            .as_ref()
+                            .map(|completion_state| completion_state_name(state, completion_state))
+                            .unwrap_or(LOCALES.lookup(&state.locale, "any-state")),
+                    )
+                    .width(Length::Fill)
+                    .align_x(Center),
+                )
+                .style(
+                    if state.new_saved_filter.completion_state == completion_state {
+                        button::secondary
+                    } else {
+                        button::primary
+                    },
+                )
+                .width(Length::Fill)
+                .on_press(Message::Tasks(
+                    TasksPageMessage::SetNewSavedFilterCompletionState(completion_state),
+                ))
+                .into()
+            }))
+            .spacing(5),
+            row![
+                button(
+                    text(LOCALES.lookup(&state.locale, "cancel"))
+                        .width(Length::Fill)
+                        .align_x(Center)
+                )
+                .width(Length::Fill)
+                .on_press(Message::Tasks(TasksPageMessage::CancelCreateNewSavedFilter)),
+                button(
+                    text(LOCALES.lookup(&state.locale, "create"))
+                        .width(Length::Fill)
+                        .align_x(Center)
+                )
+                .width(Length::Fill)
+                .style(button::success)
+                .on_press(Message::Tasks(TasksPageMessage::CreateNewSavedFilter)),
+            ]
+            .spacing(5)
+        ]
+        .spacing(5)
+        .into()
+    } else {
+        column![
+            row![
+                text(LOCALES.lookup(&state.locale, "saved-filters"))
+                    .size(20)
+                    .width(Length::Fill),
+                Tooltip::new(
+                    button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                        "../../../icons/add.svg"
+                    ))))
+                    .on_press(Message::Tasks(
+                        TasksPageMessage::StartCreatingNewSavedFilter
+                    ))
+                    .width(Length::Fixed(50.0))
+                    .height(Length::Fixed(30.0)),
+                    text(LOCALES.lookup(&state.locale, "new-saved-filter")),
+                    iced::widget::tooltip::Position::Right,
+                )
+            ],
+            button(
+                text(LOCALES.lookup(&state.locale, "all-tasks"))
+                    .width(Length::Fill)
+                    .align_x(Center)
+            )
+            .style(if state.selected_saved_filter.is_none() {
+                button::secondary
+            } else {
+                button::primary
+            })
+            .width(Length::Fill)
+            .on_press(Message::Tasks(TasksPageMessage::SelectSavedFilter(None))),
+            column(
+                state
+                    .saved_filters
+                    .iter()
+                    .enumerate()
+                    .map(|(filter_index, saved_filter)| {
+                        row![
+                            button(text(&saved_filter.name).width(Length::Fill).align_x(Center))
+                                .style(if state.selected_saved_filter == Some(filter_index) {
+                                    button::secondary
+                                } else {
+                                    button::primary
+                                })
+                                .width(Length::Fill)
+                                .on_press(Message::Tasks(TasksPageMessage::SelectSavedFilter(
+                                    Some(filter_index)
+                                ))),
+                            Tooltip::new(
+                                button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                                    "../../../icons/delete.svg"
+                                ))))
+                                .style(button::danger)
+                                .on_press(Message::Tasks(TasksPageMessage::DeleteSavedFilter(
+                                    filter_index
+                                )))
+                                .width(Length::Fixed(50.0))
+                                .height(Length::Fixed(30.0)),
+                                text(LOCALES.lookup(&state.locale, "delete")),
+                                iced::widget::tooltip::Position::Right,
+                            )
+                        ]
+                        .spacing(5)
+                        .into()
+                    })
+            )
+            .spacing(5)
+        ]
+        .spacing(5)
+        .into()
+    }
+}
+
+fn search_all_projects_view(state: &TasksPage) -> Element<Message> {
+    column![
+        row![
+            text_input(
+                &LOCALES.lookup(&state.locale, "search-all-projects"),
+                &state.search_all_projects_text
+            )
+            .on_input(|s| Message::Tasks(TasksPageMessage::UpdateSearchAllProjectsText(s)))
+            .on_submit(Message::Tasks(TasksPageMessage::SearchAllProjects))
+            .id(text_input::Id::new(SEARCH_ALL_PROJECTS_TEXT_INPUT_ID))
+            .width(Length::Fill),
+            button(text(LOCALES.lookup(&state.locale, "include-archived")))
+                .style(if state.search_include_archived_projects {
+                    button::secondary
+                } else {
+                    button::primary
+                })
+                .on_press(Message::Tasks(
+                    TasksPageMessage::ToggleSearchIncludeArchivedProjects
+                )),
+        ]
+        .spacing(10),
+        text(format!(
+            "{} {}",
+            LOCALES.lookup(&state.locale, "tasks-count"),
+            state.search_all_projects_results.len()
+        ))
+        .font(Font {
+            style: iced::font::Style::Italic,
+            ..Default::default()
+        }),
+        scrollable(
+            column(
+                state
+                    .search_all_projects_results
+                    .iter()
+                    .map(|(project_file, task)| {
+                        button(row![
+                            text(
+                                project_file
+                                    .file_stem()
+                                    .unwrap_or_default()
+                                    .to_string_lossy()
+                            )
+                            .font(Font {
+                                weight: iced::font::Weight::Semibold,
+                                ..Default::default()
+                            })
+                            .width(Length::FillPortion(1)),
+                            text(&task.title).width(Length::FillPortion(3)),
+                            text(completion_state_name(state, &task.completion_state))
+                                .width(Length::FillPortion(1)),
+                        ])
+                        .style(button::secondary)
+                        .width(Length::Fill)
+                        .on_press(Message::Tasks(TasksPageMessage::OpenSearchResult(
+                            project_file.to_path_buf(),
+                            task.id,
+                        )))
+                        .into()
+                    })
+            )
+            .spacing(5)
+        )
+        .height(Length::Fill)
+    ]
+    .spacing(10)
+    .padding(20)
+    .width(Length::FillPortion(2))
+    .into()
+}
+
 pub fn tool_view(state: &TasksPage) -> Element<Message> {
     let underlay = Tooltip::new(
         button(Svg::new(svg::Handle::from_memory(include_bytes!(
@@ -956,12 +1824,70 @@ pub fn tool_view(state: &TasksPage) -> Element<Message> {
         text(LOCALES.lookup(&state.locale, "more-tools")),
         iced::widget::tooltip::Position::Bottom,
     );
-    let overlay = column![button(
-        text(LOCALES.lookup(&state.locale, "select-projects-folder"))
-            .width(Length::Fill)
-            .align_x(Center),
-    )
-    .on_press(Message::Tasks(TasksPageMessage::PickProjectsFolder)),]
+    let overlay = column![
+        button(
+            text(LOCALES.lookup(&state.locale, "select-projects-folder"))
+                .width(Length::Fill)
+                .align_x(Center),
+        )
+        .on_press(Message::Tasks(TasksPageMessage::PickProjectsFolder)),
+        if state.current_project_file.is_some() {
+            column![
+                button(
+                    text(LOCALES.lookup(&state.locale, "time-report"))
+                        .width(Length::Fill)
+                        .align_x(Center),
+                )
+                .on_press(Message::Tasks(TasksPageMessage::ToggleTimeReportView)),
+                button(
+                    text(LOCALES.lookup(&state.locale, "dependency-graph"))
+                        .width(Length::Fill)
+                        .align_x(Center),
+                )
+                .on_press(Message::Tasks(TasksPageMessage::ToggleDependencyGraphView)),
+                button(
+                    text(LOCALES.lookup(&state.locale, "project-statistics"))
+                        .width(Length::Fill)
+                        .align_x(Center),
+                )
+                .on_press(Message::Tasks(TasksPageMessage::ToggleStatisticsView)),
+                button(
+                    text(LOCALES.lookup(&state.locale, "import-tasks"))
+                        .width(Length::Fill)
+                        .align_x(Center),
+                )
+                .on_press(Message::Tasks(TasksPageMessage::ImportTasks)),
+                button(
+                    text(LOCALES.lookup(&state.locale, "export-todo-txt"))
+                        .width(Length::Fill)
+                        .align_x(Center),
+                )
+                .on_press(Message::Tasks(TasksPageMessage::ExportTasks(
+                    TaskFileFormat::TodoTxt
+                ))),
+                button(
+                    text(LOCALES.lookup(&state.locale, "export-csv"))
+                        .width(Length::Fill)
+                        .align_x(Center),
+                )
+                .on_press(Message::Tasks(TasksPageMessage::ExportTasks(
+                    TaskFileFormat::Csv
+                ))),
+                button(
+                    text(LOCALES.lookup(&state.locale, "export-icalendar"))
+                        .width(Length::Fill)
+                        .align_x(Center),
+                )
+                .on_press(Message::Tasks(TasksPageMessage::ExportTasks(
+                    TaskFileFormat::ICalendar
+                ))),
+            ]
+            .spacing(5)
+        } else {
+            column![]
+        }
+    ]
+    .spacing(5)
     .width(Length::Fixed(200.0));
 
     let drop_down = DropDown::new(underlay, overlay, state.show_extra_tools_menu)
@@ -970,6 +1896,7 @@ pub fn tool_view(state: &TasksPage) -> Element<Message> {
         .alignment(drop_down::Alignment::Bottom);
     if state.selected_folder.is_some() {
         row![
+            running_timer_view(state),
             Tooltip::new(
                 button(Svg::new(svg::Handle::from_memory(include_bytes!(
                     "../../../icons/toggle-sidebar.svg"
@@ -1009,6 +1936,21 @@ pub fn tool_view(state: &TasksPage) -> Element<Message> {
                 text(LOCALES.lookup(&state.locale, "new-task-shortcut")),
                 iced::widget::tooltip::Position::Bottom
             ),
+            Tooltip::new(
+                button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                    "../../../icons/search.svg"
+                ))))
+                .on_press(Message::Tasks(
+                    TasksPageMessage::ToggleSearchAllProjectsView
+                ))
+                .style(if state.show_search_all_projects_view {
+                    button::secondary
+                } else {
+                    button::primary
+                }),
+                text(LOCALES.lookup(&state.locale, "search-all-projects-shortcut")),
+                iced::widget::tooltip::Position::Bottom
+            ),
             drop_down
         ]
         .width(Length::FillPortion(1))
@@ -1017,3 +1959,30 @@ pub fn tool_view(state: &TasksPage) -> Element<Message> {
     }
     .into()
 }
+
+/// Shows the task the timer is running on and how long it's been running for, with a button to stop it
+fn running_timer_view(state: &TasksPage) -> Element<Message> {
+    if let Some(running_timer) = state.running_timer.as_ref() {
+        row![
+            text(format!(
+                "{} {}",
+                running_timer.task_title,
+                format_duration(Utc::now() - running_timer.started_at)
+            )),
+            Tooltip::new(
+                button(Svg::new(svg::Handle::from_memory(include_bytes!(
+                    "../../../icons/pause.svg"
+                ))))
+                .style(button::success)
+                .on_press(Message::Tasks(TasksPageMessage::StopTimer)),
+                text(LOCALES.lookup(&state.locale, "stop-timer")),
+                iced::widget::tooltip::Position::Bottom,
+            )
+        ]
+        .spacing(5)
+        .align_y(Center)
+        .into()
+    } else {
+        row![].into()
+    }
+}
@@ -0,0 +1,544 @@
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    pin::pin,
+    time::Duration,
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use crypto_box::{
+    aead::{Aead, OsRng},
+    PublicKey, SalsaBox, SecretKey,
+};
+use iced::futures::{
+    channel::mpsc, stream::FuturesUnordered, FutureExt, SinkExt, Stream, StreamExt,
+};
+use iced::stream;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{UnixListener, UnixStream},
+};
+
+use crate::constants::APP_ID;
+
+/// The native messaging host name KeePassXC-Browser connects to, which the browser looks up to start our proxy
+pub const NATIVE_MESSAGING_HOST_NAME: &str = "org.keepassxc.keepassxc_browser";
+/// The file name of the proxy binary that the browser starts as the native messaging host
+pub const PROXY_BINARY_NAME: &str = "idirfein-browser-proxy";
+/// The KeePassXC version reported to the extension, which turns features on and off by KeePassXC's version
+const KEEPASSXC_VERSION: &str = "2.7.10";
+/// Browsers don't accept messages from native messaging hosts bigger than this
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+const FIREFOX_EXTENSION_ID: &str = "keepassxc-browser@keepassxc.org";
+const CHROMIUM_EXTENSION_ORIGINS: [&str; 2] = [
+    "chrome-extension://oboonakemofpalcgghocfoadofidjkkk/",
+    "chrome-extension://pdffhmdngciaglkoonimfcmckehcpafo/",
+];
+
+/// The error codes KeePassXC-Browser understands, see
+/// https://github.com/keepassxreboot/keepassxc-browser/blob/develop/keepassxc-protocol.md
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrowserError {
+    DatabaseNotOpened = 1,
+    ClientPublicKeyNotReceived = 3,
+    CannotDecryptMessage = 4,
+    TimeoutOrNotConnected = 5,
+    ActionCancelledOrDenied = 6,
+    AssociationFailed = 8,
+    IncorrectAction = 12,
+    EmptyMessageReceived = 13,
+    NoUrlProvided = 14,
+    NoLoginsFound = 15,
+    NoValidUuidProvided = 18,
+}
+
+impl BrowserError {
+    fn message(self) -> &'static str {
+        match self {
+            BrowserError::DatabaseNotOpened => "Database not opened",
+            BrowserError::ClientPublicKeyNotReceived => "Client public key not received",
+            BrowserError::CannotDecryptMessage => "Cannot decrypt message",
+            BrowserError::TimeoutOrNotConnected => "Timeout or not connected to KeePassXC",
+            BrowserError::ActionCancelledOrDenied => "Action cancelled or denied",
+            BrowserError::AssociationFailed => "KeePassXC association failed, try again",
+            BrowserError::IncorrectAction => "Incorrect action",
+            BrowserError::EmptyMessageReceived => "Empty message received",
+            BrowserError::NoUrlProvided => "No URL provided",
+            BrowserError::NoLoginsFound => "No logins found",
+            BrowserError::NoValidUuidProvided => "No valid UUID provided",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A decrypted request from the extension, which has to be answered with `BrowserRequest::respond`
+    Request(BrowserRequest),
+    Error(String),
+}
+
+/// A request from the extension for the open database
+#[derive(Debug, Clone)]
+pub struct BrowserRequest {
+    /// e.g. "get-logins" or "associate"
+    pub action: String,
+    /// The decrypted message, holding the action's parameters
+    pub message: Value,
+    responder: mpsc::Sender<Result<Value, BrowserError>>,
+}
+
+impl BrowserRequest {
+    /// Sends the reply back to the extension, the version, nonce and success fields are added to it
+    pub fn respond(&self, reply: Result<Value, BrowserError>) {
+        let _ = self.responder.clone().try_send(reply);
+    }
+}
+
+/// Where the app listens for the proxy started by the browser
+pub fn socket_path() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join(format!("{APP_ID}.browser.sock"))
+}
+
+/// Listens on the browser integration socket for KeePassXC-Browser, relayed by the proxy binary.
+/// The key exchange and encryption are handled here, the decrypted requests are passed on to the app
+pub fn serve() -> impl Stream<Item = Event> {
+    stream::channel(100, move |mut output| async move {
+        let socket_path = socket_path();
+        // Left over if the app didn't shut down cleanly
+        let _ = fs::remove_file(&socket_path);
+        let listener = match UnixListener::bind(&socket_path) {
+            Ok(listener) => listener,
+            Err(err) => {
+                let _ = output
+                    .send(Event::Error(format!(
+                        "Failed to start browser integration at {socket_path:?}: {err}"
+                    )))
+                    .await;
+                return;
+            }
+        };
+        let _ = fs::set_permissions(&socket_path, fs::Permissions::from_mode(0o600));
+        let mut connections = FuturesUnordered::new();
+        loop {
+            let mut accept = pin!(listener.accept().fuse());
+            iced::futures::select! {
+                accepted = accept => match accepted {
+                    Ok((unix_stream, _address)) => {
+                        connections.push(handle_connection(unix_stream, output.clone()))
+                    }
+                    Err(err) => {
+                        println!("Browser integration connection error: {err:?}");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                },
+                _ = connections.select_next_some() => (),
+            }
+        }
+    })
+}
+
+/// Reads a native messaging message, a native endian u32 length followed by that much JSON
+async fn read_message(reader: &mut (impl AsyncRead + Unpin)) -> Option<Vec<u8>> {
+    let mut message_length = [0; 4];
+    reader.read_exact(&mut message_length).await.ok()?;
+    let message_length = u32::from_ne_bytes(message_length) as usize;
+    if message_length > MAX_MESSAGE_SIZE {
+        return None;
+    }
+    let mut message = vec![0; message_length];
+    reader.read_exact(&mut message).await.ok()?;
+    Some(message)
+}
+
+async fn write_message(writer: &mut (impl AsyncWrite + Unpin), message: &Value) -> Option<()> {
+    let message = serde_json::to_vec(message).ok()?;
+    writer
+        .write_all(&(message.len() as u32).to_ne_bytes())
+        .await
+        .ok()?;
+    writer.write_all(&message).await.ok()?;
+    writer.flush().await.ok()
+}
+
+async fn handle_connection(mut unix_stream: UnixStream, mut output: mpsc::Sender<Event>) {
+    let mut session = BrowserSession::new();
+    while let Some(message) = read_message(&mut unix_stream).await {
+        let response = match serde_json::from_slice::<Value>(&message) {
+            Ok(request) => session.handle_request(&request, &mut output).await,
+            Err(_) => error_response("", BrowserError::EmptyMessageReceived),
+        };
+        if write_message(&mut unix_stream, &response).await.is_none() {
+            break;
+        }
+    }
+}
+
+fn error_response(action: &str, error: BrowserError) -> Value {
+    json!({
+        "action": action,
+        "errorCode": (error as u8).to_string(),
+        "error": error.message(),
+    })
+}
+
+/// The nonce for a response is the request's nonce incremented as a little endian number, like libsodium's sodium_increment
+fn increment_nonce(nonce: &[u8]) -> Vec<u8> {
+    let mut incremented_nonce = nonce.to_vec();
+    for byte in incremented_nonce.iter_mut() {
+        let (incremented_byte, is_overflow) = byte.overflowing_add(1);
+        *byte = incremented_byte;
+        if !is_overflow {
+            break;
+        }
+    }
+    incremented_nonce
+}
+
+/// The keys for one connection from the extension, which sends its public key in a change-public-keys request first
+struct BrowserSession {
+    secret_key: SecretKey,
+    salsa_box: Option<SalsaBox>,
+    /// Whether the extension has shown it holds a key associated with the open database, which every
+    /// request for entries needs
+    is_associated: bool,
+}
+
+impl BrowserSession {
+    fn new() -> Self {
+        Self {
+            secret_key: SecretKey::generate(&mut OsRng),
+            salsa_box: None,
+            is_associated: false,
+        }
+    }
+
+    async fn handle_request(&mut self, request: &Value, output: &mut mpsc::Sender<Event>) -> Value {
+        let action = request["action"].as_str().unwrap_or_default().to_string();
+        if action == "change-public-keys" {
+            return self.change_public_keys(request);
+        }
+        let (message, nonce) = match self.decrypt_request(request) {
+            Ok(decrypted_request) => decrypted_request,
+            Err(error) => return error_response(&action, error),
+        };
+        let needs_association = !matches!(
+            action.as_str(),
+            "get-databasehash" | "associate" | "test-associate" | "generate-password"
+        );
+        if needs_association && !self.is_associated {
+            return error_response(&action, BrowserError::AssociationFailed);
+        }
+
+        let (responder, mut reply_receiver) = mpsc::channel(1);
+        if output
+            .send(Event::Request(BrowserRequest {
+                action: action.clone(),
+                message,
+                responder,
+            }))
+            .await
+            .is_err()
+        {
+            return error_response(&action, BrowserError::TimeoutOrNotConnected);
+        }
+        match reply_receiver.next().await {
+            Some(Ok(reply)) => {
+                if action == "associate" || action == "test-associate" {
+                    self.is_associated = true;
+                }
+                self.encrypt_reply(&action, reply, &nonce, request.get("requestID"))
+                    .unwrap_or_else(|error| error_response(&action, error))
+            }
+            Some(Err(error)) => error_response(&action, error),
+            None => error_response(&action, BrowserError::ActionCancelledOrDenied),
+        }
+    }
+
+    /// Starts the encrypted session with the extension's public key, replying with ours
+    fn change_public_keys(&mut self, request: &Value) -> Value {
+        let client_public_key = request["publicKey"]
+            .as_str()
+            .and_then(|public_key| BASE64_STANDARD.decode(public_key).ok())
+            .and_then(|public_key| PublicKey::from_slice(&public_key).ok());
+        let nonce = request["nonce"]
+            .as_str()
+            .and_then(|nonce| BASE64_STANDARD.decode(nonce).ok());
+        let (Some(client_public_key), Some(nonce)) = (client_public_key, nonce) else {
+            return error_response(
+                "change-public-keys",
+                BrowserError::ClientPublicKeyNotReceived,
+            );
+        };
+        self.salsa_box = Some(SalsaBox::new(&client_public_key, &self.secret_key));
+        self.is_associated = false;
+        json!({
+            "action": "change-public-keys",
+            "version": KEEPASSXC_VERSION,
+            "publicKey": BASE64_STANDARD.encode(self.secret_key.public_key().as_bytes()),
+            "nonce": BASE64_STANDARD.encode(increment_nonce(&nonce)),
+            "success": "true",
+        })
+    }
+
+    /// Decrypts the request's message, returning it with the request's nonce
+    fn decrypt_request(&self, request: &Value) -> Result<(Value, Vec<u8>), BrowserError> {
+        let salsa_box = self
+            .salsa_box
+            .as_ref()
+            .ok_or(BrowserError::ClientPublicKeyNotReceived)?;
+        let nonce = request["nonce"]
+            .as_str()
+            .and_then(|nonce| BASE64_STANDARD.decode(nonce).ok())
+            .filter(|nonce| nonce.len() == 24)
+            .ok_or(BrowserError::CannotDecryptMessage)?;
+        let ciphertext = request["message"]
+            .as_str()
+            .and_then(|message| BASE64_STANDARD.decode(message).ok())
+            .ok_or(BrowserError::EmptyMessageReceived)?;
+        let plaintext = salsa_box
+            .decrypt(nonce.as_slice().into(), ciphertext.as_slice())
+            .map_err(|_| BrowserError::CannotDecryptMessage)?;
+        let message =
+            serde_json::from_slice(&plaintext).map_err(|_| BrowserError::CannotDecryptMessage)?;
+        Ok((message, nonce))
+    }
+
+    fn encrypt_reply(
+        &self,
+        action: &str,
+        mut reply: Value,
+        request_nonce: &[u8],
+        request_id: Option<&Value>,
+    ) -> Result<Value, BrowserError> {
+        let salsa_box = self
+            .salsa_box
+            .as_ref()
+            .ok_or(BrowserError::ClientPublicKeyNotReceived)?;
+        let nonce = increment_nonce(request_nonce);
+        let encoded_nonce = BASE64_STANDARD.encode(&nonce);
+        if let Some(reply) = reply.as_object_mut() {
+            reply.insert(String::from("version"), json!(KEEPASSXC_VERSION));
+            reply.insert(String::from("success"), json!("true"));
+            reply.insert(String::from("nonce"), json!(encoded_nonce));
+            if let Some(request_id) = request_id {
+                reply.insert(String::from("requestID"), request_id.clone());
+            }
+        }
+        let plaintext =
+            serde_json::to_vec(&reply).map_err(|_| BrowserError::EmptyMessageReceived)?;
+        let ciphertext = salsa_box
+            .encrypt(nonce.as_slice().into(), plaintext.as_slice())
+            .map_err(|_| BrowserError::CannotDecryptMessage)?;
+        let mut response = json!({
+            "action": action,
+            "message": BASE64_STANDARD.encode(ciphertext),
+            "nonce": encoded_nonce,
+        });
+        if let Some(request_id) = request_id {
+            response["requestID"] = request_id.clone();
+        }
+        Ok(response)
+    }
+}
+
+/// The folders Firefox and Chromium based browsers look for native messaging host manifests in
+fn native_messaging_manifest_folders(home: &Path) -> Vec<(PathBuf, bool)> {
+    vec![
+        (home.join(".mozilla/native-messaging-hosts"), true),
+        (home.join(".config/chromium/NativeMessagingHosts"), false),
+        (
+            home.join(".config/google-chrome/NativeMessagingHosts"),
+            false,
+        ),
+        (
+            home.join(".config/BraveSoftware/Brave-Browser/NativeMessagingHosts"),
+            false,
+        ),
+    ]
+}
+
+/// Moves a manifest that points at another program, like KeePassXC's own proxy, out of the way so it can be restored.
+/// Returns where it was moved to, or None if there was nothing to back up
+fn back_up_other_manifest(
+    manifest_path: &Path,
+    proxy_path: &Path,
+) -> Result<Option<PathBuf>, String> {
+    let Ok(existing_manifest) = fs::read_to_string(manifest_path) else {
+        return Ok(None);
+    };
+    let points_at_proxy = serde_json::from_str::<Value>(&existing_manifest)
+        .is_ok_and(|existing_manifest| existing_manifest["path"].as_str() == proxy_path.to_str());
+    if points_at_proxy {
+        return Ok(None);
+    }
+    let backup_path = manifest_path.with_extension("json.bak");
+    if backup_path.exists() {
+        return Err(format!(
+            "{manifest_path:?} points at another program and {backup_path:?} already exists, move one of them before installing"
+        ));
+    }
+    fs::rename(manifest_path, &backup_path).map_err(|err| err.to_string())?;
+    Ok(Some(backup_path))
+}
+
+/// Writes the native messaging host manifests that point the browsers at our proxy binary. KeePassXC's own,
+/// if it's installed, is backed up next to it first. Returns the manifests written and the backups made
+pub async fn install_native_messaging_manifests() -> Result<(Vec<PathBuf>, Vec<PathBuf>), String> {
+    let proxy_path = env::current_exe()
+        .map_err(|err| err.to_string())?
+        .with_file_name(PROXY_BINARY_NAME);
+    if !proxy_path.is_file() {
+        return Err(format!("Proxy binary not found at {proxy_path:?}"));
+    }
+    let home = env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or(String::from("Couldn't find home folder"))?;
+    let mut manifest_paths = vec![];
+    let mut backup_paths = vec![];
+    for (manifest_folder, is_firefox) in native_messaging_manifest_folders(&home) {
+        // Only browsers that are installed get a manifest
+        if !manifest_folder
+            .parent()
+            .is_some_and(|browser_folder| browser_folder.is_dir())
+        {
+            continue;
+        }
+        let mut manifest = json!({
+            "name": NATIVE_MESSAGING_HOST_NAME,
+            "description": "IdirFéin integration with KeePassXC-Browser",
+            "path": proxy_path,
+            "type": "stdio",
+        });
+        if is_firefox {
+            manifest["allowed_extensions"] = json!([FIREFOX_EXTENSION_ID]);
+        } else {
+            manifest["allowed_origins"] = json!(CHROMIUM_EXTENSION_ORIGINS);
+        }
+        fs::create_dir_all(&manifest_folder).map_err(|err| err.to_string())?;
+        let manifest_path = manifest_folder.join(format!("{NATIVE_MESSAGING_HOST_NAME}.json"));
+        backup_paths.extend(back_up_other_manifest(&manifest_path, &proxy_path)?);
+        fs::write(
+            &manifest_path,
+            serde_json::to_string_pretty(&manifest).map_err(|err| err.to_string())?,
+        )
+        .map_err(|err| err.to_string())?;
+        manifest_paths.push(manifest_path);
+    }
+    if manifest_paths.is_empty() {
+        Err(String::from("No supported browsers found"))
+    } else {
+        Ok((manifest_paths, backup_paths))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_browser_session() {
+        assert_eq!(increment_nonce(&[0, 0, 0]), vec![1, 0, 0]);
+        assert_eq!(increment_nonce(&[255, 255, 7]), vec![0, 0, 8]);
+
+        // Plays the part of the extension
+        let client_secret_key = SecretKey::generate(&mut OsRng);
+        let client_nonce = [7u8; 24];
+        let mut session = BrowserSession::new();
+        let response = session.change_public_keys(&json!({
+            "action": "change-public-keys",
+            "publicKey": BASE64_STANDARD.encode(client_secret_key.public_key().as_bytes()),
+            "nonce": BASE64_STANDARD.encode(client_nonce),
+            "clientID": "test",
+        }));
+        assert_eq!(response["success"], "true");
+        assert_eq!(
+            response["nonce"],
+            BASE64_STANDARD.encode(increment_nonce(&client_nonce))
+        );
+        let server_public_key = PublicKey::from_slice(
+            &BASE64_STANDARD
+                .decode(response["publicKey"].as_str().unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        let client_box = SalsaBox::new(&server_public_key, &client_secret_key);
+
+        let request_nonce = [42u8; 24];
+        let request = json!({
+            "action": "get-databasehash",
+            "message": BASE64_STANDARD.encode(client_box.encrypt(
+                request_nonce.as_slice().into(),
+                br#"{"action": "get-databasehash"}"#.as_slice()
+            ).unwrap()),
+            "nonce": BASE64_STANDARD.encode(request_nonce),
+        });
+        let (message, nonce) = session.decrypt_request(&request).unwrap();
+        assert_eq!(message["action"], "get-databasehash");
+
+        let response = session
+            .encrypt_reply("get-databasehash", json!({"hash": "abc"}), &nonce, None)
+            .unwrap();
+        let response_nonce = BASE64_STANDARD
+            .decode(response["nonce"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(response_nonce, increment_nonce(&request_nonce));
+        let reply: Value = serde_json::from_slice(
+            &client_box
+                .decrypt(
+                    response_nonce.as_slice().into(),
+                    BASE64_STANDARD
+                        .decode(response["message"].as_str().unwrap())
+                        .unwrap()
+                        .as_slice(),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(reply["hash"], "abc");
+        assert_eq!(reply["success"], "true");
+        assert_eq!(reply["nonce"], response["nonce"]);
+
+        // A message encrypted with a different key is rejected
+        let other_box = SalsaBox::new(&server_public_key, &SecretKey::generate(&mut OsRng));
+        let forged_request = json!({
+            "action": "get-logins",
+            "message": BASE64_STANDARD.encode(other_box.encrypt(
+                request_nonce.as_slice().into(),
+                br#"{"action": "get-logins"}"#.as_slice()
+            ).unwrap()),
+            "nonce": BASE64_STANDARD.encode(request_nonce),
+        });
+        assert_eq!(
+            session.decrypt_request(&forged_request).err(),
+            Some(BrowserError::CannotDecryptMessage)
+        );
+    }
+
+    #[test]
+    fn test_other_manifests_are_backed_up() {
+        let manifest_folder = tempfile::tempdir().unwrap();
+        let manifest_path = manifest_folder.path().join("host.json");
+        let proxy_path = Path::new("/opt/idirfein/idirfein-proxy");
+        assert_eq!(back_up_other_manifest(&manifest_path, proxy_path), Ok(None));
+
+        fs::write(&manifest_path, json!({"path": proxy_path}).to_string()).unwrap();
+        assert_eq!(back_up_other_manifest(&manifest_path, proxy_path), Ok(None));
+
+        let keepassxc_manifest = json!({"path": "/usr/bin/keepassxc-proxy"}).to_string();
+        fs::write(&manifest_path, &keepassxc_manifest).unwrap();
+        let backup_path = back_up_other_manifest(&manifest_path, proxy_path)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fs::read_to_string(backup_path).unwrap(), keepassxc_manifest);
+        assert!(!manifest_path.exists());
+
+        // An earlier backup is never overwritten
+        fs::write(&manifest_path, &keepassxc_manifest).unwrap();
+        assert!(back_up_other_manifest(&manifest_path, proxy_path).is_err());
+    }
+}
@@ -0,0 +1,974 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    pin::pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use iced::futures::{stream::FuturesUnordered, FutureExt, SinkExt, Stream, StreamExt};
+use iced::stream;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::RngCore;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use uuid::Uuid;
+
+use crate::pages::tasks::{
+    page::TaskData,
+    tasks_utils::{
+        list_project_files, parse_tasks, read_tasks_from_project_file, save_project_file,
+        serialise_tasks, task_id_from_foreign_id, TaskFileFormat,
+    },
+};
+
+const PRINCIPAL_PATH: &str = "/principal/";
+const CALENDAR_HOME_PATH: &str = "/calendars/";
+const MAX_REQUEST_SIZE: usize = 10 * 1024 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Characters which are left as they are in the names of task resources, so hrefs match the names clients put tasks at
+const RESOURCE_NAME_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A client changed the tasks in this project file
+    ProjectFileChanged(PathBuf),
+    Error(String),
+}
+
+/// Hashes the password clients log in with, so only the hash is kept in the config
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt)
+        .map_err(|err| format!("Couldn't hash the CalDAV server password: {err}"))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|password_hash| password_hash.to_string())
+        .map_err(|err| format!("Couldn't hash the CalDAV server password: {err}"))
+}
+
+/// Runs a CalDAV server on the given port, exposing each project in the folder as a collection of VTODOs.
+/// Clients have to log in with the password using HTTP Basic auth, with any username, so the server refuses to start
+/// without one. It only listens on this computer unless network access is allowed.
+pub fn serve(
+    port: u16,
+    allow_network_access: bool,
+    projects_folder: PathBuf,
+    password_hash: String,
+) -> impl Stream<Item = Event> {
+    stream::channel(100, move |mut output| async move {
+        if password_hash.is_empty() {
+            let _ = output
+                .send(Event::Error(String::from(
+                    "Set a password for the CalDAV server before turning it on",
+                )))
+                .await;
+            return;
+        }
+        let address = if allow_network_access {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        // Checking the password hash is slow, so credentials which have already been accepted are remembered
+        let accepted_authorizations = Arc::new(Mutex::new(HashSet::new()));
+        let listener = match TcpListener::bind((address, port)).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                let _ = output
+                    .send(Event::Error(format!(
+                        "Failed to start CalDAV server on port {port}: {err}"
+                    )))
+                    .await;
+                return;
+            }
+        };
+        let mut connections = FuturesUnordered::new();
+        loop {
+            let mut accept = pin!(listener.accept().fuse());
+            iced::futures::select! {
+                accepted = accept => match accepted {
+                    Ok((tcp_stream, _address)) => connections.push(tokio::time::timeout(
+                        REQUEST_TIMEOUT,
+                        handle_connection(
+                            tcp_stream,
+                            projects_folder.clone(),
+                            password_hash.clone(),
+                            accepted_authorizations.clone(),
+                        ),
+                    )),
+                    Err(err) => {
+                        println!("CalDAV connection error: {err:?}");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                },
+                handled = connections.select_next_some() => match handled {
+                    Ok(Some(changed_project_file)) => {
+                        let _ = output
+                            .send(Event::ProjectFileChanged(changed_project_file))
+                            .await;
+                    }
+                    Ok(None) => (),
+                    Err(_) => println!("CalDAV request timed out"),
+                },
+            }
+        }
+    })
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    /// Header names are lowercase
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(|value| value.as_str())
+    }
+}
+
+#[derive(Debug)]
+struct Response {
+    status_code: u16,
+    reason: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: String,
+}
+
+impl Response {
+    fn new(status_code: u16, reason: &'static str) -> Self {
+        Self {
+            status_code,
+            reason,
+            headers: vec![],
+            body: String::new(),
+        }
+    }
+
+    fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    fn body(self, content_type: &'static str, body: String) -> Self {
+        let mut response = self.header("Content-Type", content_type);
+        response.body = body;
+        response
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        let mut response_text = format!(
+            "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status_code,
+            self.reason,
+            self.body.len()
+        );
+        self.headers.iter().for_each(|(name, value)| {
+            response_text.push_str(&format!("{name}: {value}\r\n"));
+        });
+        response_text.push_str("\r\n");
+        response_text.push_str(&self.body);
+        response_text.into_bytes()
+    }
+}
+
+async fn handle_connection(
+    mut tcp_stream: TcpStream,
+    projects_folder: PathBuf,
+    password_hash: String,
+    accepted_authorizations: Arc<Mutex<HashSet<String>>>,
+) -> Option<PathBuf> {
+    let request = read_request(&mut tcp_stream).await?;
+    // Checking the password and reading and writing project files block, so they're kept off the shared executor
+    let (response, changed_project_file) = tokio::task::spawn_blocking(move || {
+        if is_authorised(&request, &password_hash, &accepted_authorizations) {
+            handle_request(&request, &projects_folder)
+        } else {
+            (
+                Response::new(401, "Unauthorized")
+                    .header("WWW-Authenticate", "Basic realm=\"IdirFein\""),
+                None,
+            )
+        }
+    })
+    .await
+    .unwrap_or_else(|err| {
+        println!("CalDAV request handler failed: {err:?}");
+        (Response::new(500, "Internal Server Error"), None)
+    });
+    if let Err(err) = tcp_stream.write_all(&response.into_bytes()).await {
+        println!("Failed to send CalDAV response: {err:?}");
+    }
+    changed_project_file
+}
+
+async fn read_request(tcp_stream: &mut TcpStream) -> Option<Request> {
+    let mut buffer = vec![];
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(index) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break index;
+        }
+        if buffer.len() > MAX_REQUEST_SIZE {
+            return None;
+        }
+        let read_count = tcp_stream.read(&mut chunk).await.ok()?;
+        if read_count == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read_count]);
+    };
+    let header_text = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = header_text.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_uppercase();
+    let path = percent_decode_str(request_line.next()?.split('?').next()?)
+        .decode_utf8()
+        .ok()?
+        .to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    let content_length = headers
+        .get("content-length")
+        .and_then(|content_length| content_length.parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_REQUEST_SIZE {
+        return None;
+    }
+    if headers
+        .get("expect")
+        .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+    {
+        tcp_stream
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+            .await
+            .ok()?;
+    }
+    let mut body = buffer.split_off(header_end + 4);
+    while body.len() < content_length {
+        let read_count = tcp_stream.read(&mut chunk).await.ok()?;
+        if read_count == 0 {
+            return None;
+        }
+        body.extend_from_slice(&chunk[..read_count]);
+    }
+    body.truncate(content_length);
+    Some(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+fn password_matches(password_hash: &str, password: &str) -> bool {
+    PasswordHash::new(password_hash).is_ok_and(|password_hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &password_hash)
+            .is_ok()
+    })
+}
+
+/// Whether the request logs in with the password. A request is never let through without one
+fn is_authorised(
+    request: &Request,
+    password_hash: &str,
+    accepted_authorizations: &Mutex<HashSet<String>>,
+) -> bool {
+    let Some(authorization) = request.header("authorization") else {
+        return false;
+    };
+    if accepted_authorizations
+        .lock()
+        .is_ok_and(|accepted_authorizations| accepted_authorizations.contains(authorization))
+    {
+        return true;
+    }
+    let is_authorised = authorization
+        .strip_prefix("Basic ")
+        .and_then(|encoded_credentials| BASE64_STANDARD.decode(encoded_credentials.trim()).ok())
+        .and_then(|decoded_credentials| String::from_utf8(decoded_credentials).ok())
+        .is_some_and(|credentials| {
+            credentials
+                .split_once(':')
+                .is_some_and(|(_username, given_password)| {
+                    password_matches(password_hash, given_password)
+                })
+        });
+    if is_authorised {
+        if let Ok(mut accepted_authorizations) = accepted_authorizations.lock() {
+            accepted_authorizations.insert(authorization.to_string());
+        }
+    }
+    is_authorised
+}
+
+/// Handles a request, returning the response and the project file which was changed, if any
+fn handle_request(request: &Request, projects_folder: &Path) -> (Response, Option<PathBuf>) {
+    let path_segments: Vec<&str> = request
+        .path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    match (request.method.as_str(), path_segments.as_slice()) {
+        ("OPTIONS", _) => (
+            Response::new(200, "OK")
+                .header("DAV", "1, 3, calendar-access")
+                .header("Allow", "OPTIONS, GET, PUT, DELETE, PROPFIND, REPORT"),
+            None,
+        ),
+        (_, [".well-known", "caldav"]) => (
+            Response::new(301, "Moved Permanently").header("Location", PRINCIPAL_PATH),
+            None,
+        ),
+        ("PROPFIND", [] | ["principal"]) => (
+            multistatus(vec![dav_response(&request.path, &principal_props())]),
+            None,
+        ),
+        ("PROPFIND", ["calendars"]) => {
+            let mut responses = vec![dav_response(
+                CALENDAR_HOME_PATH,
+                &format!(
+                    "<d:resourcetype><d:collection/></d:resourcetype>{}",
+                    principal_props()
+                ),
+            )];
+            if !is_depth_zero(request) {
+                list_project_files(projects_folder, false)
+                    .iter()
+                    .for_each(|project_file| responses.push(project_response(project_file)));
+            }
+            (multistatus(responses), None)
+        }
+        ("PROPFIND", ["calendars", project_name]) => {
+            match find_project_file(projects_folder, project_name) {
+                Some(project_file) => {
+                    let mut responses = vec![project_response(&project_file)];
+                    if !is_depth_zero(request) {
+                        let resource_names = ResourceNames::read(&project_file);
+                        read_tasks_from_project_file(&project_file)
+                            .iter()
+                            .for_each(|task| {
+                                responses.push(task_response(
+                                    project_name,
+                                    &resource_names,
+                                    task,
+                                    false,
+                                ))
+                            });
+                    }
+                    (multistatus(responses), None)
+                }
+                None => (Response::new(404, "Not Found"), None),
+            }
+        }
+        ("PROPFIND", ["calendars", project_name, resource_name]) => {
+            match find_task(projects_folder, project_name, resource_name) {
+                Some((project_file, task)) => (
+                    multistatus(vec![task_response(
+                        project_name,
+                        &ResourceNames::read(&project_file),
+                        &task,
+                        false,
+                    )]),
+                    None,
+                ),
+                None => (Response::new(404, "Not Found"), None),
+            }
+        }
+        ("REPORT", ["calendars", project_name]) => {
+            (report(request, projects_folder, project_name), None)
+        }
+        ("GET", ["calendars", project_name, resource_name]) => {
+            match find_task(projects_folder, project_name, resource_name) {
+                Some((_project_file, task)) => (
+                    Response::new(200, "OK")
+                        .header("ETag", format!("\"{}\"", task_etag(&task)))
+                        .body(
+                            "text/calendar; charset=utf-8",
+                            task_to_calendar(project_name, &task),
+                        ),
+                    None,
+                ),
+                None => (Response::new(404, "Not Found"), None),
+            }
+        }
+        ("PUT", ["calendars", project_name, resource_name]) => {
+            put_task(request, projects_folder, project_name, resource_name)
+        }
+        ("DELETE", ["calendars", project_name, resource_name]) => {
+            delete_task(request, projects_folder, project_name, resource_name)
+        }
+        ("PROPFIND" | "REPORT" | "GET" | "PUT" | "DELETE", _) => {
+            (Response::new(404, "Not Found"), None)
+        }
+        _ => (
+            Response::new(405, "Method Not Allowed")
+                .header("Allow", "OPTIONS, GET, PUT, DELETE, PROPFIND, REPORT"),
+            None,
+        ),
+    }
+}
+
+fn report(request: &Request, projects_folder: &Path, project_name: &str) -> Response {
+    let Some(project_file) = find_project_file(projects_folder, project_name) else {
+        return Response::new(404, "Not Found");
+    };
+    if request.body.contains("sync-collection") {
+        return Response::new(403, "Forbidden");
+    }
+    let include_calendar_data = request.body.contains("calendar-data");
+    let tasks_list = read_tasks_from_project_file(&project_file);
+    let resource_names = ResourceNames::read(&project_file);
+    if request.body.contains("calendar-multiget") {
+        let href_regex = Regex::new(r"<(?:\w+:)?href>([^<]*)</(?:\w+:)?href>")
+            .expect("Regex is known to be valid");
+        multistatus(
+            href_regex
+                .captures_iter(&request.body)
+                .map(|captures| {
+                    let href = percent_decode_str(captures[1].trim())
+                        .decode_utf8_lossy()
+                        .to_string();
+                    let resource_name = href.rsplit('/').next().unwrap_or_default();
+                    match tasks_list
+                        .iter()
+                        .find(|task| task.id == resource_names.task_id(resource_name))
+                    {
+                        Some(task) => task_response(
+                            project_name,
+                            &resource_names,
+                            task,
+                            include_calendar_data,
+                        ),
+                        None => format!(
+                            "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+                            escape_xml(captures[1].trim())
+                        ),
+                    }
+                })
+                .collect(),
+        )
+    } else {
+        multistatus(
+            tasks_list
+                .iter()
+                .map(|task| {
+                    task_response(project_name, &resource_names, task, include_calendar_data)
+                })
+                .collect(),
+        )
+    }
+}
+
+fn put_task(
+    request: &Request,
+    projects_folder: &Path,
+    project_name: &str,
+    resource_name: &str,
+) -> (Response, Option<PathBuf>) {
+    let Some(project_file) = find_project_file(projects_folder, project_name) else {
+        return (Response::new(409, "Conflict"), None);
+    };
+    let saved_tasks_list = read_tasks_from_project_file(&project_file);
+    let mut tasks_list = saved_tasks_list.clone();
+    let mut resource_names = ResourceNames::read(&project_file);
+    let task_id = resource_names.task_id(resource_name);
+    let existing_task_index = tasks_list.iter().position(|task| task.id == task_id);
+    if !preconditions_pass(
+        request,
+        existing_task_index.map(|task_index| &tasks_list[task_index]),
+    ) {
+        return (Response::new(412, "Precondition Failed"), None);
+    }
+    let Some(mut uploaded_task) = parse_tasks(&request.body, TaskFileFormat::ICalendar)
+        .ok()
+        .and_then(|uploaded_tasks| uploaded_tasks.into_iter().next())
+    else {
+        return (Response::new(400, "Bad Request"), None);
+    };
+    // The ID comes from the resource name, which is remembered so the task stays at the URL the client put it at
+    uploaded_task.id = task_id;
+    resource_names.insert(resource_name, task_id);
+    let response = match existing_task_index {
+        Some(task_index) => {
+            let existing_task = &mut tasks_list[task_index];
+            existing_task.title = uploaded_task.title;
+            existing_task.description = uploaded_task.description;
+            existing_task.set_completion_state(uploaded_task.completion_state);
+            existing_task.labels = uploaded_task.labels;
+            existing_task.due_date = uploaded_task.due_date;
+            Response::new(204, "No Content")
+                .header("ETag", format!("\"{}\"", task_etag(existing_task)))
+        }
+        None => {
+            uploaded_task.record_created();
+            let response = Response::new(201, "Created")
+                .header("ETag", format!("\"{}\"", task_etag(&uploaded_task)));
+            tasks_list.push(uploaded_task);
+            response
+        }
+    };
+    match save_project_file(&project_file, &saved_tasks_list, &tasks_list)
+        .and_then(|_| resource_names.write(&project_file))
+    {
+        Ok(_) => (response, Some(project_file)),
+        Err(err) => {
+            println!("CalDAV failed to save project file: {err}");
+            (Response::new(500, "Internal Server Error"), None)
+        }
+    }
+}
+
+fn delete_task(
+    request: &Request,
+    projects_folder: &Path,
+    project_name: &str,
+    resource_name: &str,
+) -> (Response, Option<PathBuf>) {
+    let Some(project_file) = find_project_file(projects_folder, project_name) else {
+        return (Response::new(404, "Not Found"), None);
+    };
+    let saved_tasks_list = read_tasks_from_project_file(&project_file);
+    let mut tasks_list = saved_tasks_list.clone();
+    let mut resource_names = ResourceNames::read(&project_file);
+    let task_id = resource_names.task_id(resource_name);
+    let Some(task_index) = tasks_list.iter().position(|task| task.id == task_id) else {
+        return (Response::new(404, "Not Found"), None);
+    };
+    if !preconditions_pass(request, Some(&tasks_list[task_index])) {
+        return (Response::new(412, "Precondition Failed"), None);
+    }
+    tasks_list.remove(task_index);
+    resource_names.remove(task_id);
+    match save_project_file(&project_file, &saved_tasks_list, &tasks_list)
+        .and_then(|_| resource_names.write(&project_file))
+    {
+        Ok(_) => (Response::new(204, "No Content"), Some(project_file)),
+        Err(err) => {
+            println!("CalDAV failed to save project file: {err}");
+            (Response::new(500, "Internal Server Error"), None)
+        }
+    }
+}
+
+/// Checks the If-Match and If-None-Match headers against the task currently stored at the URL
+fn preconditions_pass(request: &Request, existing_task: Option<&TaskData>) -> bool {
+    let existing_etag = existing_task.map(task_etag);
+    let if_match_passes = request.header("if-match").is_none_or(|if_match| {
+        existing_etag.as_ref().is_some_and(|existing_etag| {
+            if_match == "*"
+                || if_match
+                    .split(',')
+                    .any(|etag| etag.trim().trim_matches('"') == existing_etag)
+        })
+    });
+    let if_none_match_passes = request
+        .header("if-none-match")
+        .is_none_or(|if_none_match| if_none_match != "*" || existing_etag.is_none());
+    if_match_passes && if_none_match_passes
+}
+
+fn is_depth_zero(request: &Request) -> bool {
+    request.header("depth") == Some("0")
+}
+
+/// Finds the project file with this name in the folder, only ever returning files which are listed in the folder
+fn find_project_file(projects_folder: &Path, project_name: &str) -> Option<PathBuf> {
+    list_project_files(projects_folder, false)
+        .into_iter()
+        .find(|project_file| {
+            project_file
+                .file_stem()
+                .is_some_and(|file_stem| file_stem.to_string_lossy() == project_name)
+        })
+}
+
+fn find_task(
+    projects_folder: &Path,
+    project_name: &str,
+    resource_name: &str,
+) -> Option<(PathBuf, TaskData)> {
+    let project_file = find_project_file(projects_folder, project_name)?;
+    let task_id = ResourceNames::read(&project_file).task_id(resource_name);
+    read_tasks_from_project_file(&project_file)
+        .into_iter()
+        .find(|task| task.id == task_id)
+        .map(|task| (project_file, task))
+}
+
+/// The names clients put tasks at, when they aren't the task's ID, kept next to the project file.
+/// Most clients make up their own names, so without these the tasks would move to a different URL after being put.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ResourceNames(HashMap<String, Uuid>);
+
+impl ResourceNames {
+    fn file(project_file: &Path) -> PathBuf {
+        project_file.with_file_name(format!(
+            ".{}.caldav",
+            project_file
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+        ))
+    }
+
+    fn read(project_file: &Path) -> Self {
+        fs::read_to_string(Self::file(project_file))
+            .ok()
+            .and_then(|resource_names_json| serde_json::from_str(&resource_names_json).ok())
+            .unwrap_or_default()
+    }
+
+    fn write(&self, project_file: &Path) -> Result<(), String> {
+        let resource_names_json = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(Self::file(project_file), resource_names_json).map_err(|err| err.to_string())
+    }
+
+    fn task_id(&self, resource_name: &str) -> Uuid {
+        self.0.get(resource_name).copied().unwrap_or_else(|| {
+            task_id_from_foreign_id(resource_name.strip_suffix(".ics").unwrap_or(resource_name))
+        })
+    }
+
+    fn resource_name(&self, task_id: Uuid) -> String {
+        self.0
+            .iter()
+            .find(|(_resource_name, resource_task_id)| **resource_task_id == task_id)
+            .map(|(resource_name, _resource_task_id)| resource_name.clone())
+            .unwrap_or_else(|| format!("{task_id}.ics"))
+    }
+
+    fn insert(&mut self, resource_name: &str, task_id: Uuid) {
+        if resource_name != format!("{task_id}.ics") {
+            self.0.insert(resource_name.to_string(), task_id);
+        }
+    }
+
+    fn remove(&mut self, task_id: Uuid) {
+        self.0
+            .retain(|_resource_name, resource_task_id| *resource_task_id != task_id);
+    }
+}
+
+fn hash_to_hex(data: &[u8]) -> String {
+    Sha1::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn task_etag(task: &TaskData) -> String {
+    hash_to_hex(serde_json::to_string(task).unwrap_or_default().as_bytes())
+}
+
+fn task_to_calendar(project_name: &str, task: &TaskData) -> String {
+    serialise_tasks(
+        std::slice::from_ref(task),
+        TaskFileFormat::ICalendar,
+        project_name,
+    )
+    .unwrap_or_default()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn project_href(project_name: &str) -> String {
+    format!(
+        "{CALENDAR_HOME_PATH}{}/",
+        utf8_percent_encode(project_name, NON_ALPHANUMERIC)
+    )
+}
+
+fn multistatus(responses: Vec<String>) -> Response {
+    Response::new(207, "Multi-Status").body(
+        "application/xml; charset=utf-8",
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:multistatus xmlns:d=\"DAV:\" xmlns:c=\"urn:ietf:params:xml:ns:caldav\" xmlns:cs=\"http://calendarserver.org/ns/\">{}</d:multistatus>",
+            responses.concat()
+        ),
+    )
+}
+
+fn dav_response(href: &str, props: &str) -> String {
+    format!(
+        "<d:response><d:href>{}</d:href><d:propstat><d:prop>{props}</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+        escape_xml(href)
+    )
+}
+
+fn principal_props() -> String {
+    format!(
+        "<d:current-user-principal><d:href>{PRINCIPAL_PATH}</d:href></d:current-user-principal><c:calendar-home-set><d:href>{CALENDAR_HOME_PATH}</d:href></c:calendar-home-set><d:displayname>IdirFein</d:displayname>"
+    )
+}
+
+fn project_response(project_file: &Path) -> String {
+    let project_name = project_file
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let ctag = hash_to_hex(&fs::read(project_file).unwrap_or_default());
+    dav_response(
+        &project_href(&project_name),
+        &format!(
+            "<d:resourcetype><d:collection/><c:calendar/></d:resourcetype><d:displayname>{}</d:displayname><c:supported-calendar-component-set><c:comp name=\"VTODO\"/></c:supported-calendar-component-set><cs:getctag>{ctag}</cs:getctag><d:getetag>\"{ctag}\"</d:getetag><d:current-user-privilege-set><d:privilege><d:read/></d:privilege><d:privilege><d:write/></d:privilege></d:current-user-privilege-set>{}",
+            escape_xml(&project_name),
+            principal_props()
+        ),
+    )
+}
+
+fn task_response(
+    project_name: &str,
+    resource_names: &ResourceNames,
+    task: &TaskData,
+    include_calendar_data: bool,
+) -> String {
+    let calendar_data = if include_calendar_data {
+        format!(
+            "<c:calendar-data>{}</c:calendar-data>",
+            escape_xml(&task_to_calendar(project_name, task))
+        )
+    } else {
+        String::new()
+    };
+    dav_response(
+        &format!(
+            "{}{}",
+            project_href(project_name),
+            utf8_percent_encode(
+                &resource_names.resource_name(task.id),
+                RESOURCE_NAME_ENCODE_SET
+            )
+        ),
+        &format!(
+            "<d:resourcetype/><d:getetag>\"{}\"</d:getetag><d:getcontenttype>text/calendar; charset=utf-8; component=vtodo</d:getcontenttype>{calendar_data}",
+            task_etag(task)
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, headers: &[(&str, &str)], body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_put_get_and_delete_task() {
+        let projects_folder = tempfile::tempdir().unwrap();
+        fs::write(projects_folder.path().join("Home.json"), "[]").unwrap();
+        let task_id = Uuid::new_v4();
+        let task_path = format!("/calendars/Home/{task_id}.ics");
+        let vtodo = format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:{task_id}\r\nSUMMARY:Fix the door\r\nSTATUS:IN-PROCESS\r\nEND:VTODO\r\nEND:VCALENDAR\r\n"
+        );
+
+        let (response, changed_project_file) = handle_request(
+            &request("PUT", &task_path, &[("if-none-match", "*")], &vtodo),
+            projects_folder.path(),
+        );
+        assert_eq!(response.status_code, 201);
+        assert_eq!(
+            changed_project_file,
+            Some(projects_folder.path().join("Home.json"))
+        );
+        let (response, _) = handle_request(
+            &request("PUT", &task_path, &[("if-none-match", "*")], &vtodo),
+            projects_folder.path(),
+        );
+        assert_eq!(response.status_code, 412);
+
+        let (response, _) =
+            handle_request(&request("GET", &task_path, &[], ""), projects_folder.path());
+        assert_eq!(response.status_code, 200);
+        assert!(response.body.contains("SUMMARY:Fix the door"));
+        assert!(response.body.contains("X-IDIRFEIN-STATE:DOING"));
+
+        let (response, _) = handle_request(
+            &request(
+                "PROPFIND",
+                "/calendars/Home/",
+                &[("depth", "1")],
+                "<d:propfind xmlns:d=\"DAV:\"><d:prop><d:getetag/></d:prop></d:propfind>",
+            ),
+            projects_folder.path(),
+        );
+        assert_eq!(response.status_code, 207);
+        assert!(response.body.contains(&format!("{task_id}.ics")));
+
+        let (response, changed_project_file) = handle_request(
+            &request("DELETE", &task_path, &[("if-match", "\"wrong\"")], ""),
+            projects_folder.path(),
+        );
+        assert_eq!(response.status_code, 412);
+        assert_eq!(changed_project_file, None);
+        let (response, _) = handle_request(
+            &request("DELETE", &task_path, &[], ""),
+            projects_folder.path(),
+        );
+        assert_eq!(response.status_code, 204);
+        let project_file = projects_folder.path().join("Home.json");
+        assert!(read_tasks_from_project_file(&project_file).is_empty());
+        // Changes made over CalDAV show up in the task's history and the CRDT, just like edits in the app
+        assert_eq!(
+            crate::pages::tasks::tasks_utils::read_task_history(&project_file, task_id).len(),
+            2
+        );
+        assert!(crate::pages::tasks::tasks_utils::tasks_crdt_file(&project_file).exists());
+    }
+
+    #[test]
+    fn test_tasks_stay_at_the_names_clients_put_them_at() {
+        let projects_folder = tempfile::tempdir().unwrap();
+        fs::write(projects_folder.path().join("Home.json"), "[]").unwrap();
+        let uppercase_task_id = Uuid::new_v4().to_string().to_uppercase();
+        let resource_names = ["foo.ics".to_string(), format!("{uppercase_task_id}.ics")];
+        for (resource_name, title) in resource_names
+            .iter()
+            .zip(["Fix the door", "Paint the fence"])
+        {
+            let vtodo = format!(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:{resource_name}\r\nSUMMARY:{title}\r\nEND:VTODO\r\nEND:VCALENDAR\r\n"
+            );
+            let (response, _) = handle_request(
+                &request(
+                    "PUT",
+                    &format!("/calendars/Home/{resource_name}"),
+                    &[("if-none-match", "*")],
+                    &vtodo,
+                ),
+                projects_folder.path(),
+            );
+            assert_eq!(response.status_code, 201);
+        }
+
+        let (response, _) = handle_request(
+            &request(
+                "PROPFIND",
+                "/calendars/Home/",
+                &[("depth", "1")],
+                "<d:propfind xmlns:d=\"DAV:\"><d:prop><d:getetag/></d:prop></d:propfind>",
+            ),
+            projects_folder.path(),
+        );
+        assert_eq!(response.status_code, 207);
+        let mut served_resource_names: Vec<&str> = response
+            .body
+            .split("<d:href>/calendars/Home/")
+            .skip(1)
+            .filter_map(|rest| rest.split_once("</d:href>"))
+            .map(|(resource_name, _rest)| resource_name)
+            .filter(|resource_name| !resource_name.is_empty())
+            .collect();
+        served_resource_names.sort();
+        let mut expected_resource_names = resource_names.to_vec();
+        expected_resource_names.sort();
+        assert_eq!(served_resource_names, expected_resource_names);
+
+        let (response, _) = handle_request(
+            &request("GET", "/calendars/Home/foo.ics", &[], ""),
+            projects_folder.path(),
+        );
+        assert!(response.body.contains("SUMMARY:Fix the door"));
+        let (response, _) = handle_request(
+            &request(
+                "DELETE",
+                &format!("/calendars/Home/{}", resource_names[1]),
+                &[],
+                "",
+            ),
+            projects_folder.path(),
+        );
+        assert_eq!(response.status_code, 204);
+        assert_eq!(
+            read_tasks_from_project_file(&projects_folder.path().join("Home.json")).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_projects_outside_folder_are_not_served() {
+        let projects_folder = tempfile::tempdir().unwrap();
+        let (response, _) = handle_request(
+            &request("GET", "/calendars/../secret/task.ics", &[], ""),
+            projects_folder.path(),
+        );
+        assert_eq!(response.status_code, 404);
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        let password_hash = hash_password("hunter2").unwrap();
+        assert!(!password_hash.contains("hunter2"));
+        let wrong_password_hash = hash_password("hunter3").unwrap();
+        let authorised_request = request(
+            "GET",
+            "/",
+            &[("authorization", "Basic dXNlcjpodW50ZXIy")],
+            "",
+        );
+        let accepted_authorizations = Mutex::new(HashSet::new());
+        assert!(!is_authorised(
+            &authorised_request,
+            &wrong_password_hash,
+            &accepted_authorizations
+        ));
+        assert!(is_authorised(
+            &authorised_request,
+            &password_hash,
+            &accepted_authorizations
+        ));
+        assert!(!is_authorised(
+            &request("GET", "/", &[], ""),
+            &password_hash,
+            &accepted_authorizations
+        ));
+        // Without a password set nothing is let through
+        assert!(!is_authorised(
+            &request("GET", "/", &[("authorization", "Basic dXNlcjo=")], ""),
+            "",
+            &Mutex::new(HashSet::new())
+        ));
+    }
+}
@@ -0,0 +1,37 @@
+use arboard::Clipboard;
+#[cfg(target_os = "macos")]
+use arboard::SetExtApple;
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+use arboard::SetExtLinux;
+#[cfg(windows)]
+use arboard::SetExtWindows;
+
+/// Puts the secret on the clipboard, marked as sensitive so clipboard managers that follow the
+/// convention (x-kde-passwordManagerHint on Wayland/X11) leave it out of their history
+pub fn copy_secret_to_clipboard(secret: String) -> Result<(), String> {
+    let mut clipboard =
+        Clipboard::new().map_err(|err| format!("Couldn't access clipboard: {err}"))?;
+    clipboard
+        .set()
+        .exclude_from_history()
+        .text(secret)
+        .map_err(|err| format!("Couldn't copy text to clipboard: {err}"))
+}
+
+/// Clears the clipboard if it still holds the secret, so anything copied since isn't lost.
+/// Returns whether it was cleared
+pub fn clear_clipboard_if_unchanged(secret: &str) -> Result<bool, String> {
+    let mut clipboard =
+        Clipboard::new().map_err(|err| format!("Couldn't access clipboard: {err}"))?;
+    if clipboard.get_text().is_ok_and(|text| text == secret) {
+        clipboard
+            .clear()
+            .map_err(|err| format!("Couldn't clear clipboard: {err}"))?;
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
@@ -1,3 +1,10 @@
 pub mod auth_utils;
+pub mod browser_integration_utils;
+pub mod caldav_utils;
+pub mod clipboard_utils;
+pub mod notification_utils;
+pub mod secret_service_utils;
+pub mod session_lock_utils;
+pub mod ssh_agent_utils;
 pub mod socket_utils;
 pub mod sync_utils;
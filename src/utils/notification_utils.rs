@@ -0,0 +1,330 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    pin::pin,
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use fluent_templates::{LanguageIdentifier, Loader};
+use iced::futures::{stream, SinkExt, Stream, StreamExt};
+use iced::stream as iced_stream;
+use uuid::Uuid;
+use zbus::{proxy, zvariant::Value, Connection};
+
+use crate::{
+    pages::tasks::{
+        page::{TaskCompletionState, TaskData},
+        tasks_utils::{
+            list_project_files, read_tasks_from_project_file, update_task_in_project_file,
+        },
+    },
+    LOCALES,
+};
+
+const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const SNOOZE_DURATION: TimeDelta = TimeDelta::minutes(10);
+const SNOOZE_ACTION_KEY: &str = "snooze";
+const MARK_DONE_ACTION_KEY: &str = "mark-done";
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A reminder or notification action changed the tasks in this project file
+    ProjectFileChanged(PathBuf),
+    /// The task in this project file should be marked as done, the same way as in the app
+    MarkTaskDone(PathBuf, Uuid),
+    Error(String),
+}
+
+/// The freedesktop notifications interface, see https://specifications.freedesktop.org/notification-spec/latest/
+#[proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+enum ReminderInput {
+    CheckReminders,
+    ActionInvoked(u32, String),
+    NotificationClosed(u32),
+}
+
+/// Checks every project in the folder for tasks reaching their reminder time, and shows a desktop notification for each one.
+/// A reminder is cleared from its task once shown, so it isn't shown again. Snoozing sets a new reminder on the task,
+/// and marking it as done is passed on to the tasks page.
+pub fn reminders(
+    projects_folder: PathBuf,
+    locale: LanguageIdentifier,
+) -> impl Stream<Item = Event> {
+    iced_stream::channel(100, move |mut output| async move {
+        let notifications_proxy = match connect_to_notifications_service().await {
+            Ok(notifications_proxy) => notifications_proxy,
+            Err(err) => {
+                let _ = output
+                    .send(Event::Error(format!(
+                        "Couldn't connect to the notification service, task reminders won't be shown: {err}"
+                    )))
+                    .await;
+                return;
+            }
+        };
+        let (action_stream, closed_stream) = match (
+            notifications_proxy.receive_action_invoked().await,
+            notifications_proxy.receive_notification_closed().await,
+        ) {
+            (Ok(action_stream), Ok(closed_stream)) => (action_stream, closed_stream),
+            (Err(err), _) | (_, Err(err)) => {
+                let _ = output
+                    .send(Event::Error(format!(
+                        "Couldn't listen for notification actions: {err}"
+                    )))
+                    .await;
+                return;
+            }
+        };
+        let check_stream = stream::unfold(
+            tokio::time::interval(REMINDER_CHECK_INTERVAL),
+            |mut interval| async move {
+                interval.tick().await;
+                Some((ReminderInput::CheckReminders, interval))
+            },
+        );
+        let action_stream = action_stream.filter_map(|signal| async move {
+            signal
+                .args()
+                .ok()
+                .map(|args| ReminderInput::ActionInvoked(*args.id(), args.action_key().to_string()))
+        });
+        let closed_stream = closed_stream.filter_map(|signal| async move {
+            signal
+                .args()
+                .ok()
+                .map(|args| ReminderInput::NotificationClosed(*args.id()))
+        });
+        let mut input_stream = pin!(stream::select(
+            check_stream,
+            stream::select(action_stream, closed_stream)
+        ));
+
+        // Only needed if clearing a reminder fails, so it isn't shown every time the reminders are checked
+        let mut already_notified: HashSet<(Uuid, DateTime<Utc>)> = HashSet::new();
+        let mut open_notifications: HashMap<u32, (PathBuf, Uuid)> = HashMap::new();
+        while let Some(input) = input_stream.next().await {
+            match input {
+                ReminderInput::CheckReminders => {
+                    for (project_file, task) in
+                        due_reminders(&projects_folder, Utc::now(), &already_notified)
+                    {
+                        let Some(reminder) = task.reminder else {
+                            continue;
+                        };
+                        already_notified.insert((task.id, reminder));
+                        match show_reminder_notification(
+                            &notifications_proxy,
+                            &project_file,
+                            &task,
+                            &locale,
+                        )
+                        .await
+                        {
+                            Ok(notification_id) => {
+                                let clear_result =
+                                    update_task_in_project_file(&project_file, task.id, |task| {
+                                        task.reminder = None;
+                                    });
+                                let _ = output
+                                    .send(match clear_result {
+                                        Ok(_) => Event::ProjectFileChanged(project_file.clone()),
+                                        Err(err) => Event::Error(format!(
+                                            "Couldn't clear the reminder for {}: {err}",
+                                            task.title
+                                        )),
+                                    })
+                                    .await;
+                                open_notifications.insert(notification_id, (project_file, task.id));
+                            }
+                            Err(err) => {
+                                let _ = output
+                                    .send(Event::Error(format!(
+                                        "Couldn't show a reminder for {}: {err}",
+                                        task.title
+                                    )))
+                                    .await;
+                            }
+                        }
+                    }
+                }
+                ReminderInput::ActionInvoked(notification_id, action_key) => {
+                    let Some((project_file, task_id)) = open_notifications.remove(&notification_id)
+                    else {
+                        continue;
+                    };
+                    let event = match action_key.as_str() {
+                        SNOOZE_ACTION_KEY => {
+                            match update_task_in_project_file(&project_file, task_id, |task| {
+                                task.reminder = Some(Utc::now() + SNOOZE_DURATION);
+                            }) {
+                                Ok(_) => Event::ProjectFileChanged(project_file),
+                                Err(err) => {
+                                    Event::Error(format!("Couldn't snooze the reminder: {err}"))
+                                }
+                            }
+                        }
+                        MARK_DONE_ACTION_KEY => Event::MarkTaskDone(project_file, task_id),
+                        _ => continue,
+                    };
+                    let _ = output.send(event).await;
+                }
+                ReminderInput::NotificationClosed(notification_id) => {
+                    open_notifications.remove(&notification_id);
+                }
+            }
+        }
+    })
+}
+
+async fn connect_to_notifications_service() -> zbus::Result<NotificationsProxy<'static>> {
+    let connection = Connection::session().await?;
+    NotificationsProxy::new(&connection).await
+}
+
+async fn show_reminder_notification(
+    notifications_proxy: &NotificationsProxy<'_>,
+    project_file: &Path,
+    task: &TaskData,
+    locale: &LanguageIdentifier,
+) -> zbus::Result<u32> {
+    let mut body = format!(
+        "{} {}",
+        LOCALES.lookup(locale, "reminder-project"),
+        project_file
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+    );
+    if let Some(due_date) = task.due_date {
+        body.push_str(&format!(
+            "\n{} {}",
+            LOCALES.lookup(locale, "due"),
+            due_date.format("%Y-%m-%d")
+        ));
+    }
+    notifications_proxy
+        .notify(
+            "Idirfein",
+            0,
+            "task-due",
+            &task.title,
+            &body,
+            &[
+                SNOOZE_ACTION_KEY,
+                &LOCALES.lookup(locale, "snooze-reminder"),
+                MARK_DONE_ACTION_KEY,
+                &LOCALES.lookup(locale, "mark-done"),
+            ],
+            HashMap::from([("urgency", Value::U8(1))]),
+            0,
+        )
+        .await
+}
+
+/// Finds the unfinished tasks in every unarchived project whose reminder time has passed, leaving out ones already notified about
+fn due_reminders(
+    projects_folder: &Path,
+    now: DateTime<Utc>,
+    already_notified: &HashSet<(Uuid, DateTime<Utc>)>,
+) -> Vec<(PathBuf, TaskData)> {
+    list_project_files(projects_folder, false)
+        .into_iter()
+        .flat_map(|project_file| {
+            read_tasks_from_project_file(&project_file)
+                .into_iter()
+                .filter(|task| {
+                    task.completion_state != TaskCompletionState::Done
+                        && task.reminder.is_some_and(|reminder| {
+                            reminder <= now && !already_notified.contains(&(task.id, reminder))
+                        })
+                })
+                .map(|task| (project_file.clone(), task))
+                .collect::<Vec<(PathBuf, TaskData)>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_due_reminders() {
+        let projects_folder = tempfile::tempdir().unwrap();
+        let now = Utc::now();
+        let due_task = TaskData {
+            title: String::from("Water the plants"),
+            reminder: Some(now - TimeDelta::minutes(1)),
+            ..Default::default()
+        };
+        let done_task = TaskData {
+            title: String::from("Pay rent"),
+            completion_state: TaskCompletionState::Done,
+            reminder: Some(now - TimeDelta::minutes(1)),
+            ..Default::default()
+        };
+        let later_task = TaskData {
+            title: String::from("Call home"),
+            reminder: Some(now + TimeDelta::hours(1)),
+            ..Default::default()
+        };
+        let project_file = projects_folder.path().join("Home.json");
+        fs::write(
+            &project_file,
+            serde_json::to_string(&vec![due_task.clone(), done_task, later_task]).unwrap(),
+        )
+        .unwrap();
+
+        let reminders = due_reminders(projects_folder.path(), now, &HashSet::new());
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].0, project_file);
+        assert_eq!(reminders[0].1.id, due_task.id);
+
+        let already_notified = HashSet::from([(due_task.id, due_task.reminder.unwrap())]);
+        assert!(due_reminders(projects_folder.path(), now, &already_notified).is_empty());
+
+        update_task_in_project_file(&project_file, due_task.id, |task| {
+            task.reminder = Some(now + SNOOZE_DURATION);
+        })
+        .unwrap();
+        assert!(due_reminders(projects_folder.path(), now, &HashSet::new()).is_empty());
+        assert_eq!(
+            due_reminders(
+                projects_folder.path(),
+                now + SNOOZE_DURATION,
+                &already_notified
+            )
+            .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,1047 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use hkdf::Hkdf;
+use iced::futures::{channel::mpsc, future, stream::select, SinkExt, Stream, StreamExt};
+use iced::stream;
+use num_bigint::BigUint;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zbus::{
+    fdo, interface,
+    message::Header,
+    object_server::SignalContext,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Type, Value},
+    Connection, DBusError,
+};
+
+/// The well known name apps look for the Secret Service under, see
+/// https://specifications.freedesktop.org/secret-service-spec/latest/
+const SECRET_SERVICE_NAME: &str = "org.freedesktop.secrets";
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+/// The only collection, which is the database group chosen to hold other apps' secrets
+const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection/idirfein";
+/// Apps that store secrets without picking a collection use the default alias
+const DEFAULT_ALIAS: &str = "default";
+const DEFAULT_ALIAS_PATH: &str = "/org/freedesktop/secrets/aliases/default";
+const SESSION_PATH_PREFIX: &str = "/org/freedesktop/secrets/session";
+const PROMPT_PATH_PREFIX: &str = "/org/freedesktop/secrets/prompt";
+/// The session algorithms from the spec, secrets are sent as they are in plain sessions
+const PLAIN_ALGORITHM: &str = "plain";
+const DH_ALGORITHM: &str = "dh-ietf1024-sha256-aes128-cbc-pkcs7";
+/// The 1024 bit MODP group from RFC 2409 that the DH algorithm uses, with a generator of 2
+const DH_PRIME: &str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE65381FFFFFFFFFFFFFFFF";
+const DH_PRIME_LENGTH: usize = 128;
+const AES_BLOCK_LENGTH: usize = 16;
+/// The property names apps set when creating an item
+const ITEM_LABEL_PROPERTY: &str = "org.freedesktop.Secret.Item.Label";
+const ITEM_ATTRIBUTES_PROPERTY: &str = "org.freedesktop.Secret.Item.Attributes";
+
+/// The errors from the Secret Service spec, sent back to the app asking
+#[derive(Debug, DBusError)]
+#[zbus(prefix = "org.freedesktop.Secret.Error")]
+pub enum SecretError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    /// The password database is locked
+    IsLocked(String),
+    NoSession(String),
+    /// The item isn't in the shared group any more
+    NoSuchObject(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The service is on the bus, with the connection to tell it when the database locks
+    Started(SecretServiceConnection),
+    /// Something an app asked the Secret Service for, which has to be answered with `SecretServiceRequest::respond`
+    Request(SecretServiceRequest),
+    Error(String),
+}
+
+/// An item as apps see it, one entry in the shared group
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SecretItem {
+    /// The entry's title
+    pub label: String,
+    /// The entry's fields other than the password, which apps look their secrets up by
+    pub attributes: HashMap<String, String>,
+    /// The entry's password
+    pub secret: Vec<u8>,
+    /// Seconds since the Unix epoch
+    pub created: u64,
+    /// Seconds since the Unix epoch
+    pub modified: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum SecretServiceAction {
+    IsUnlocked,
+    /// Waits until the user unlocks the database in the app
+    Unlock,
+    Lock,
+    /// Finds the items with all of the attributes
+    SearchItems(HashMap<String, String>),
+    GetItem(uuid::Uuid),
+    /// Adds an item, or replaces the secret of the one with the same attributes if replace is set
+    CreateItem {
+        item: SecretItem,
+        replace: bool,
+    },
+    SetSecret(uuid::Uuid, Vec<u8>),
+    SetLabel(uuid::Uuid, String),
+    SetAttributes(uuid::Uuid, HashMap<String, String>),
+    DeleteItem(uuid::Uuid),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretServiceReply {
+    Done,
+    IsUnlocked(bool),
+    Items(Vec<uuid::Uuid>),
+    Item(SecretItem),
+    Created(uuid::Uuid),
+}
+
+/// A request from an app for the open database
+#[derive(Debug, Clone)]
+pub struct SecretServiceRequest {
+    pub action: SecretServiceAction,
+    responder: mpsc::Sender<Result<SecretServiceReply, SecretError>>,
+}
+
+impl SecretServiceRequest {
+    pub fn respond(&self, reply: Result<SecretServiceReply, SecretError>) {
+        let _ = self.responder.clone().try_send(reply);
+    }
+}
+
+/// A secret as it's sent over D-Bus. In encrypted sessions the parameters are the AES IV and the value is encrypted
+#[derive(Debug, Serialize, Deserialize, Type)]
+struct Secret {
+    session: OwnedObjectPath,
+    parameters: Vec<u8>,
+    value: Vec<u8>,
+    content_type: String,
+}
+
+/// Tells the service about changes in the app that no app asked about
+#[derive(Debug, Clone)]
+pub struct SecretServiceConnection(mpsc::Sender<()>);
+
+impl SecretServiceConnection {
+    /// Stops serving the items found while the database was unlocked
+    pub fn database_locked(&mut self) {
+        let _ = self.0.try_send(());
+    }
+}
+
+/// The AES key for a session, None for plain sessions
+type SessionKey = Option<[u8; AES_BLOCK_LENGTH]>;
+
+/// The sessions apps have opened and not closed yet, each only usable by the app that opened it
+#[derive(Default)]
+struct Sessions(HashMap<OwnedObjectPath, (String, SessionKey)>);
+
+impl Sessions {
+    /// Records the session against the unique bus name of the app opening it
+    fn open(&mut self, session: OwnedObjectPath, sender: &str, session_key: SessionKey) {
+        self.0.insert(session, (sender.to_string(), session_key));
+    }
+
+    /// The session's key, if the app asking is the one that opened it
+    fn key(&self, session: &OwnedObjectPath, sender: Option<&str>) -> Option<SessionKey> {
+        self.0
+            .get(session)
+            .filter(|(owner, _)| Some(owner.as_str()) == sender)
+            .map(|(_, session_key)| *session_key)
+    }
+
+    fn close(&mut self, session: &OwnedObjectPath, sender: Option<&str>) -> bool {
+        self.key(session, sender).is_some() && self.0.remove(session).is_some()
+    }
+
+    /// Forgets the sessions of an app that has left the bus, returning their paths
+    fn close_all_owned_by(&mut self, name: &str) -> Vec<OwnedObjectPath> {
+        let sessions: Vec<OwnedObjectPath> = self
+            .0
+            .iter()
+            .filter(|(_, (owner, _))| owner == name)
+            .map(|(session, _)| session.clone())
+            .collect();
+        sessions.iter().for_each(|session| {
+            self.0.remove(session);
+        });
+        sessions
+    }
+}
+
+fn dh_prime() -> BigUint {
+    BigUint::parse_bytes(DH_PRIME.as_bytes(), 16).expect("DH prime should be valid hex")
+}
+
+/// Makes a DH key pair for a session, returning the private key and the public key to send to the app
+fn dh_key_pair() -> (BigUint, Vec<u8>) {
+    let mut private_key_bytes = [0u8; DH_PRIME_LENGTH];
+    rand::thread_rng().fill_bytes(&mut private_key_bytes);
+    let private_key = BigUint::from_bytes_be(&private_key_bytes) % dh_prime();
+    let public_key = BigUint::from(2u32).modpow(&private_key, &dh_prime());
+    (private_key, public_key.to_bytes_be())
+}
+
+/// Works out the AES key shared with the app from its public key, as libsecret and GNOME Keyring do.
+/// None if the public key isn't valid for the group
+fn dh_session_key(private_key: &BigUint, app_public_key: &[u8]) -> Option<[u8; AES_BLOCK_LENGTH]> {
+    let prime = dh_prime();
+    let app_public_key = BigUint::from_bytes_be(app_public_key);
+    if app_public_key <= BigUint::from(1u32) || app_public_key >= &prime - 1u32 {
+        return None;
+    }
+    let shared_secret = app_public_key.modpow(private_key, &prime).to_bytes_be();
+    // Padded to the length of the prime before deriving the key
+    let mut padded_shared_secret = vec![0u8; DH_PRIME_LENGTH - shared_secret.len()];
+    padded_shared_secret.extend(shared_secret);
+    let mut session_key = [0u8; AES_BLOCK_LENGTH];
+    Hkdf::<Sha256>::new(None, &padded_shared_secret)
+        .expand(&[], &mut session_key)
+        .ok()?;
+    Some(session_key)
+}
+
+/// Encrypts a secret for the app, returning the IV and the encrypted value
+fn encrypt_secret(session_key: &[u8; AES_BLOCK_LENGTH], value: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut iv = [0u8; AES_BLOCK_LENGTH];
+    rand::thread_rng().fill_bytes(&mut iv);
+    let encrypted_value = cbc::Encryptor::<aes::Aes128>::new(session_key.into(), &iv.into())
+        .encrypt_padded_vec_mut::<Pkcs7>(value);
+    (iv.to_vec(), encrypted_value)
+}
+
+fn decrypt_secret(
+    session_key: &[u8; AES_BLOCK_LENGTH],
+    iv: &[u8],
+    encrypted_value: &[u8],
+) -> Option<Vec<u8>> {
+    cbc::Decryptor::<aes::Aes128>::new_from_slices(session_key, iv)
+        .ok()?
+        .decrypt_padded_vec_mut::<Pkcs7>(encrypted_value)
+        .ok()
+}
+
+fn object_path(path: String) -> OwnedObjectPath {
+    ObjectPath::from_string_unchecked(path).into()
+}
+
+/// The path used where the spec wants a path but there's nothing, e.g. when no prompt is needed
+fn no_object() -> OwnedObjectPath {
+    object_path(String::from("/"))
+}
+
+fn item_path(id: uuid::Uuid) -> OwnedObjectPath {
+    object_path(format!("{COLLECTION_PATH}/{}", id.simple()))
+}
+
+fn item_id(item_path: &ObjectPath) -> Option<uuid::Uuid> {
+    item_path
+        .strip_prefix(&format!("{COLLECTION_PATH}/"))
+        .and_then(|id| uuid::Uuid::parse_str(id).ok())
+}
+
+/// Shared by every object the service serves, to ask the app for things and add objects for the items it returns
+#[derive(Clone)]
+struct ServiceContext {
+    requests: mpsc::Sender<Event>,
+    connection: Connection,
+    next_object_number: Arc<AtomicU32>,
+    sessions: Arc<Mutex<Sessions>>,
+    /// The items served since the database was unlocked, which are removed when it locks
+    item_ids: Arc<Mutex<HashSet<uuid::Uuid>>>,
+}
+
+impl ServiceContext {
+    /// The key of a session the app calling opened
+    fn session_key(
+        &self,
+        session: &OwnedObjectPath,
+        header: &Header<'_>,
+    ) -> Result<SessionKey, SecretError> {
+        let sender = header.sender().map(|sender| sender.as_str());
+        self.sessions
+            .lock()
+            .ok()
+            .and_then(|sessions| sessions.key(session, sender))
+            .ok_or_else(|| SecretError::NoSession(format!("No session {}", session.as_str())))
+    }
+
+    /// Wraps a secret for sending to the app in the session, encrypting it if the session is encrypted
+    fn secret_for_session(
+        &self,
+        session: OwnedObjectPath,
+        header: &Header<'_>,
+        value: Vec<u8>,
+    ) -> Result<Secret, SecretError> {
+        let (parameters, value) = match self.session_key(&session, header)? {
+            Some(session_key) => encrypt_secret(&session_key, &value),
+            None => (vec![], value),
+        };
+        Ok(Secret {
+            session,
+            parameters,
+            value,
+            content_type: String::from("text/plain"),
+        })
+    }
+
+    /// The value of a secret sent by the app, decrypted if its session is encrypted
+    fn secret_value(&self, secret: Secret, header: &Header<'_>) -> Result<Vec<u8>, SecretError> {
+        match self.session_key(&secret.session, header)? {
+            Some(session_key) => decrypt_secret(&session_key, &secret.parameters, &secret.value)
+                .ok_or_else(|| {
+                    SecretError::ZBus(zbus::Error::Failure(String::from(
+                        "Couldn't decrypt the secret",
+                    )))
+                }),
+            None => Ok(secret.value),
+        }
+    }
+
+    async fn request(
+        &self,
+        action: SecretServiceAction,
+    ) -> Result<SecretServiceReply, SecretError> {
+        let (responder, mut reply_receiver) = mpsc::channel(1);
+        self.requests
+            .clone()
+            .send(Event::Request(SecretServiceRequest { action, responder }))
+            .await
+            .map_err(|_| SecretError::ZBus(zbus::Error::Failure(String::from("App closed"))))?;
+        reply_receiver.next().await.unwrap_or_else(|| {
+            Err(SecretError::ZBus(zbus::Error::Failure(String::from(
+                "No reply from the app",
+            ))))
+        })
+    }
+
+    async fn is_unlocked(&self) -> Result<bool, SecretError> {
+        match self.request(SecretServiceAction::IsUnlocked).await? {
+            SecretServiceReply::IsUnlocked(is_unlocked) => Ok(is_unlocked),
+            _ => Ok(false),
+        }
+    }
+
+    async fn item(&self, id: uuid::Uuid) -> Result<SecretItem, SecretError> {
+        match self.request(SecretServiceAction::GetItem(id)).await? {
+            SecretServiceReply::Item(item) => Ok(item),
+            _ => Err(SecretError::NoSuchObject(format!("No item {id}"))),
+        }
+    }
+
+    /// The paths of the items with all of the attributes, nothing is found while the database is locked
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+    ) -> Result<Vec<OwnedObjectPath>, SecretError> {
+        match self
+            .request(SecretServiceAction::SearchItems(attributes))
+            .await
+        {
+            Ok(SecretServiceReply::Items(item_ids)) => {
+                let mut item_paths = vec![];
+                for item_id in item_ids {
+                    item_paths.push(self.add_item_object(item_id).await?);
+                }
+                Ok(item_paths)
+            }
+            Ok(_) | Err(SecretError::IsLocked(_)) => Ok(vec![]),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Items are served as they're found, as the entries in the group are only known while the database is unlocked
+    async fn add_item_object(&self, id: uuid::Uuid) -> Result<OwnedObjectPath, SecretError> {
+        let item_path = item_path(id);
+        self.connection
+            .object_server()
+            .at(
+                &item_path,
+                SecretServiceItem {
+                    id,
+                    context: self.clone(),
+                },
+            )
+            .await?;
+        if let Ok(mut item_ids) = self.item_ids.lock() {
+            item_ids.insert(id);
+        }
+        Ok(item_path)
+    }
+
+    /// Called when the database locks, apps have to search for the items again once it's unlocked
+    async fn remove_item_objects(&self) {
+        let item_ids: Vec<uuid::Uuid> = self
+            .item_ids
+            .lock()
+            .map(|mut item_ids| item_ids.drain().collect())
+            .unwrap_or_default();
+        for id in item_ids {
+            let _ = self
+                .connection
+                .object_server()
+                .remove::<SecretServiceItem, _>(&item_path(id))
+                .await;
+        }
+    }
+
+    /// Closes the sessions of an app that has left the bus, which can't close them itself
+    async fn close_sessions_owned_by(&self, name: &str) {
+        let sessions = self
+            .sessions
+            .lock()
+            .map(|mut sessions| sessions.close_all_owned_by(name))
+            .unwrap_or_default();
+        for session in sessions {
+            let _ = self
+                .connection
+                .object_server()
+                .remove::<SecretServiceSession, _>(&session)
+                .await;
+        }
+    }
+
+    fn next_object_path(&self, path_prefix: &str) -> OwnedObjectPath {
+        object_path(format!(
+            "{path_prefix}/{}",
+            self.next_object_number.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    /// A prompt for the app to unlock the database, or no prompt if it's already unlocked
+    async fn unlock(
+        &self,
+        objects: Vec<OwnedObjectPath>,
+    ) -> Result<(Vec<OwnedObjectPath>, OwnedObjectPath), SecretError> {
+        if self.is_unlocked().await? {
+            return Ok((objects, no_object()));
+        }
+        let prompt_path = self.next_object_path(PROMPT_PATH_PREFIX);
+        self.connection
+            .object_server()
+            .at(
+                &prompt_path,
+                SecretServicePrompt {
+                    objects,
+                    context: self.clone(),
+                },
+            )
+            .await?;
+        Ok((vec![], prompt_path))
+    }
+}
+
+struct SecretService {
+    context: ServiceContext,
+}
+
+#[interface(name = "org.freedesktop.Secret.Service")]
+impl SecretService {
+    /// Opens a plain session, or an encrypted one where the app's and our DH public keys are swapped
+    async fn open_session(
+        &self,
+        algorithm: &str,
+        input: OwnedValue,
+        #[zbus(header)] header: Header<'_>,
+    ) -> fdo::Result<(OwnedValue, OwnedObjectPath)> {
+        let sender = header
+            .sender()
+            .ok_or(fdo::Error::AccessDenied(String::from("No sender")))?;
+        let (output, session_key) = match algorithm {
+            PLAIN_ALGORITHM => (OwnedValue::from(zbus::zvariant::Str::from("")), None),
+            DH_ALGORITHM => {
+                let app_public_key = Vec::<u8>::try_from(input).map_err(|_| {
+                    fdo::Error::InvalidArgs(String::from("Expected the public key as bytes"))
+                })?;
+                let (private_key, public_key) = dh_key_pair();
+                let session_key = dh_session_key(&private_key, &app_public_key)
+                    .ok_or(fdo::Error::InvalidArgs(String::from("Invalid public key")))?;
+                (
+                    Value::from(public_key)
+                        .try_to_owned()
+                        .map_err(|err| fdo::Error::Failed(err.to_string()))?,
+                    Some(session_key),
+                )
+            }
+            _ => {
+                return Err(fdo::Error::NotSupported(format!(
+                    "Algorithm {algorithm} isn't supported, use {DH_ALGORITHM} or {PLAIN_ALGORITHM}"
+                )))
+            }
+        };
+        let session_path = self.context.next_object_path(SESSION_PATH_PREFIX);
+        if let Ok(mut sessions) = self.context.sessions.lock() {
+            sessions.open(session_path.clone(), sender.as_str(), session_key);
+        }
+        self.context
+            .connection
+            .object_server()
+            .at(
+                &session_path,
+                SecretServiceSession {
+                    context: self.context.clone(),
+                },
+            )
+            .await?;
+        Ok((output, session_path))
+    }
+
+    /// There's only the one collection, so asking for the default collection gets it and nothing else can be made
+    async fn create_collection(
+        &self,
+        _properties: HashMap<String, OwnedValue>,
+        alias: &str,
+    ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+        if alias == DEFAULT_ALIAS {
+            Ok((object_path(COLLECTION_PATH.to_string()), no_object()))
+        } else {
+            Err(fdo::Error::NotSupported(String::from(
+                "Only the default collection is available",
+            )))
+        }
+    }
+
+    /// Returns the unlocked and locked items, items can only be found while the database is unlocked
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+    ) -> Result<(Vec<OwnedObjectPath>, Vec<OwnedObjectPath>), SecretError> {
+        Ok((self.context.search_items(attributes).await?, vec![]))
+    }
+
+    async fn unlock(
+        &self,
+        objects: Vec<OwnedObjectPath>,
+    ) -> Result<(Vec<OwnedObjectPath>, OwnedObjectPath), SecretError> {
+        self.context.unlock(objects).await
+    }
+
+    /// Locks the whole database, like locking it in the app
+    async fn lock(
+        &self,
+        objects: Vec<OwnedObjectPath>,
+    ) -> Result<(Vec<OwnedObjectPath>, OwnedObjectPath), SecretError> {
+        self.context.request(SecretServiceAction::Lock).await?;
+        Ok((objects, no_object()))
+    }
+
+    async fn get_secrets(
+        &self,
+        items: Vec<OwnedObjectPath>,
+        session: OwnedObjectPath,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<HashMap<OwnedObjectPath, Secret>, SecretError> {
+        self.context.session_key(&session, &header)?;
+        let mut secrets = HashMap::new();
+        for item_path in items {
+            let Some(id) = item_id(&item_path) else {
+                continue;
+            };
+            match self.context.item(id).await {
+                Ok(item) => {
+                    secrets.insert(
+                        item_path,
+                        self.context
+                            .secret_for_session(session.clone(), &header, item.secret)?,
+                    );
+                }
+                // Items that have been deleted are left out
+                Err(SecretError::NoSuchObject(_)) => (),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(secrets)
+    }
+
+    async fn read_alias(&self, name: &str) -> OwnedObjectPath {
+        if name == DEFAULT_ALIAS {
+            object_path(COLLECTION_PATH.to_string())
+        } else {
+            no_object()
+        }
+    }
+
+    async fn set_alias(&self, _name: &str, _collection: OwnedObjectPath) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(String::from(
+            "Aliases can't be changed",
+        )))
+    }
+
+    #[zbus(property)]
+    async fn collections(&self) -> Vec<OwnedObjectPath> {
+        vec![object_path(COLLECTION_PATH.to_string())]
+    }
+}
+
+struct SecretServiceCollection {
+    context: ServiceContext,
+}
+
+#[interface(name = "org.freedesktop.Secret.Collection")]
+impl SecretServiceCollection {
+    async fn delete(&self) -> fdo::Result<OwnedObjectPath> {
+        Err(fdo::Error::NotSupported(String::from(
+            "The collection is a group in the password database, it can only be changed in the app",
+        )))
+    }
+
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+    ) -> Result<Vec<OwnedObjectPath>, SecretError> {
+        self.context.search_items(attributes).await
+    }
+
+    async fn create_item(
+        &self,
+        properties: HashMap<String, OwnedValue>,
+        secret: Secret,
+        replace: bool,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(OwnedObjectPath, OwnedObjectPath), SecretError> {
+        let label = properties
+            .get(ITEM_LABEL_PROPERTY)
+            .and_then(|label| <&str>::try_from(&**label).ok())
+            .unwrap_or_default()
+            .to_string();
+        let attributes = properties
+            .get(ITEM_ATTRIBUTES_PROPERTY)
+            .and_then(|attributes| attributes.try_clone().ok())
+            .and_then(|attributes| HashMap::<String, String>::try_from(attributes).ok())
+            .unwrap_or_default();
+        let item = SecretItem {
+            label,
+            attributes,
+            secret: self.context.secret_value(secret, &header)?,
+            ..Default::default()
+        };
+        match self
+            .context
+            .request(SecretServiceAction::CreateItem { item, replace })
+            .await?
+        {
+            SecretServiceReply::Created(id) => {
+                Ok((self.context.add_item_object(id).await?, no_object()))
+            }
+            _ => Err(SecretError::ZBus(zbus::Error::Failure(String::from(
+                "The item wasn't created",
+            )))),
+        }
+    }
+
+    #[zbus(property)]
+    async fn items(&self) -> fdo::Result<Vec<OwnedObjectPath>> {
+        self.context
+            .search_items(HashMap::new())
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    #[zbus(property)]
+    async fn label(&self) -> String {
+        String::from("Idirfein")
+    }
+
+    #[zbus(property)]
+    async fn locked(&self) -> bool {
+        !self.context.is_unlocked().await.unwrap_or(false)
+    }
+
+    #[zbus(property)]
+    async fn created(&self) -> u64 {
+        0
+    }
+
+    #[zbus(property)]
+    async fn modified(&self) -> u64 {
+        0
+    }
+}
+
+/// One entry in the shared group
+struct SecretServiceItem {
+    id: uuid::Uuid,
+    context: ServiceContext,
+}
+
+#[interface(name = "org.freedesktop.Secret.Item")]
+impl SecretServiceItem {
+    async fn delete(&self) -> Result<OwnedObjectPath, SecretError> {
+        self.context
+            .request(SecretServiceAction::DeleteItem(self.id))
+            .await?;
+        self.context
+            .connection
+            .object_server()
+            .remove::<SecretServiceItem, _>(&item_path(self.id))
+            .await?;
+        if let Ok(mut item_ids) = self.context.item_ids.lock() {
+            item_ids.remove(&self.id);
+        }
+        Ok(no_object())
+    }
+
+    async fn get_secret(
+        &self,
+        session: OwnedObjectPath,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<Secret, SecretError> {
+        self.context.session_key(&session, &header)?;
+        let item = self.context.item(self.id).await?;
+        self.context
+            .secret_for_session(session, &header, item.secret)
+    }
+
+    async fn set_secret(
+        &self,
+        secret: Secret,
+        #[zbus(header)] header: Header<'_>,
+    ) -> Result<(), SecretError> {
+        let value = self.context.secret_value(secret, &header)?;
+        self.context
+            .request(SecretServiceAction::SetSecret(self.id, value))
+            .await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    async fn locked(&self) -> bool {
+        !self.context.is_unlocked().await.unwrap_or(false)
+    }
+
+    #[zbus(property)]
+    async fn attributes(&self) -> fdo::Result<HashMap<String, String>> {
+        self.item_property(|item| item.attributes).await
+    }
+
+    #[zbus(property)]
+    async fn set_attributes(&mut self, attributes: HashMap<String, String>) -> fdo::Result<()> {
+        self.context
+            .request(SecretServiceAction::SetAttributes(self.id, attributes))
+            .await
+            .map(|_| ())
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    #[zbus(property)]
+    async fn label(&self) -> fdo::Result<String> {
+        self.item_property(|item| item.label).await
+    }
+
+    #[zbus(property)]
+    async fn set_label(&mut self, label: String) -> fdo::Result<()> {
+        self.context
+            .request(SecretServiceAction::SetLabel(self.id, label))
+            .await
+            .map(|_| ())
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    #[zbus(property)]
+    async fn created(&self) -> fdo::Result<u64> {
+        self.item_property(|item| item.created).await
+    }
+
+    #[zbus(property)]
+    async fn modified(&self) -> fdo::Result<u64> {
+        self.item_property(|item| item.modified).await
+    }
+}
+
+impl SecretServiceItem {
+    async fn item_property<T>(&self, property: impl FnOnce(SecretItem) -> T) -> fdo::Result<T> {
+        self.context
+            .item(self.id)
+            .await
+            .map(property)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+}
+
+/// A session an app has opened, its key is kept in the context until the app closes it or leaves the bus
+struct SecretServiceSession {
+    context: ServiceContext,
+}
+
+#[interface(name = "org.freedesktop.Secret.Session")]
+impl SecretServiceSession {
+    async fn close(
+        &self,
+        #[zbus(object_server)] object_server: &zbus::ObjectServer,
+        #[zbus(header)] header: Header<'_>,
+    ) -> fdo::Result<()> {
+        if let Some(session_path) = header.path() {
+            let sender = header.sender().map(|sender| sender.as_str());
+            let is_closed = self.context.sessions.lock().is_ok_and(|mut sessions| {
+                sessions.close(&OwnedObjectPath::from(session_path.to_owned()), sender)
+            });
+            if !is_closed {
+                return Err(fdo::Error::AccessDenied(String::from(
+                    "Only the app that opened the session can close it",
+                )));
+            }
+            object_server
+                .remove::<SecretServiceSession, _>(session_path)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Waits for the user to unlock the database in the app, on behalf of an app that called Unlock
+struct SecretServicePrompt {
+    /// The objects the app asked to unlock, returned once the database is unlocked
+    objects: Vec<OwnedObjectPath>,
+    context: ServiceContext,
+}
+
+#[interface(name = "org.freedesktop.Secret.Prompt")]
+impl SecretServicePrompt {
+    /// Asks the app to unlock the database, Completed is sent once the user has unlocked it
+    async fn prompt(
+        &self,
+        _window_id: &str,
+        #[zbus(signal_context)] signal_context: SignalContext<'_>,
+    ) {
+        let context = self.context.clone();
+        let objects = self.objects.clone();
+        let signal_context = signal_context.to_owned();
+        // Answered straight away, the app asking waits for the Completed signal instead
+        self.context
+            .connection
+            .executor()
+            .spawn(
+                async move {
+                    let is_unlocked = matches!(
+                        context.request(SecretServiceAction::Unlock).await,
+                        Ok(SecretServiceReply::Done)
+                    );
+                    let unlocked_objects = if is_unlocked { objects } else { vec![] };
+                    let _ = SecretServicePrompt::completed(
+                        &signal_context,
+                        !is_unlocked,
+                        Value::from(unlocked_objects),
+                    )
+                    .await;
+                    let _ = context
+                        .connection
+                        .object_server()
+                        .remove::<SecretServicePrompt, _>(signal_context.path())
+                        .await;
+                },
+                "secret service unlock prompt",
+            )
+            .detach();
+    }
+
+    async fn dismiss(
+        &self,
+        #[zbus(object_server)] object_server: &zbus::ObjectServer,
+        #[zbus(signal_context)] signal_context: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        SecretServicePrompt::completed(
+            &signal_context,
+            true,
+            Value::from(Vec::<OwnedObjectPath>::new()),
+        )
+        .await?;
+        object_server
+            .remove::<SecretServicePrompt, _>(signal_context.path())
+            .await?;
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn completed(
+        signal_context: &SignalContext<'_>,
+        dismissed: bool,
+        result: Value<'_>,
+    ) -> zbus::Result<()>;
+}
+
+/// Connects to the session bus and serves the Secret Service there, answering from the app through `requests`
+async fn start_secret_service(requests: mpsc::Sender<Event>) -> zbus::Result<ServiceContext> {
+    let connection = Connection::session().await?;
+    let context = ServiceContext {
+        requests,
+        connection: connection.clone(),
+        next_object_number: Arc::new(AtomicU32::new(1)),
+        sessions: Arc::new(Mutex::new(Sessions::default())),
+        item_ids: Arc::new(Mutex::new(HashSet::new())),
+    };
+    connection
+        .object_server()
+        .at(
+            SERVICE_PATH,
+            SecretService {
+                context: context.clone(),
+            },
+        )
+        .await?;
+    for collection_path in [COLLECTION_PATH, DEFAULT_ALIAS_PATH] {
+        connection
+            .object_server()
+            .at(
+                collection_path,
+                SecretServiceCollection {
+                    context: context.clone(),
+                },
+            )
+            .await?;
+    }
+    connection.request_name(SECRET_SERVICE_NAME).await?;
+    Ok(context)
+}
+
+/// What the service reacts to between apps' calls
+enum ServiceEvent {
+    DatabaseLocked,
+    /// An app with this unique name has left the bus
+    NameLost(String),
+}
+
+/// The command that lets the Flatpak take the Secret Service name, which it isn't given by default.
+/// None when not running in a Flatpak
+pub fn flatpak_override_command() -> Option<String> {
+    std::env::var("FLATPAK_ID").ok().map(|flatpak_id| {
+        format!("flatpak override --user --own-name={SECRET_SERVICE_NAME} {flatpak_id}")
+    })
+}
+
+/// Serves the Secret Service on the session bus with the shared group of the open database as its collection.
+/// Only one provider can own the name, so this fails if another keyring like GNOME Keyring is running
+pub fn serve() -> impl Stream<Item = Event> {
+    stream::channel(100, move |mut output| async move {
+        // The objects are served for as long as the context, which holds the connection, is kept
+        let started = async {
+            let context = start_secret_service(output.clone()).await?;
+            let name_owner_changes = fdo::DBusProxy::new(&context.connection)
+                .await?
+                .receive_name_owner_changed()
+                .await?;
+            zbus::Result::Ok((context, name_owner_changes))
+        };
+        let (context, name_owner_changes) = match started.await {
+            Ok(started) => started,
+            Err(err) => {
+                let _ = output
+                    .send(Event::Error(format!(
+                        "Couldn't start the Secret Service, is another keyring running?{} {err}",
+                        flatpak_override_command()
+                            .map(|command| format!(" The Flatpak also needs permission: {command}"))
+                            .unwrap_or_default()
+                    )))
+                    .await;
+                return;
+            }
+        };
+        let (lock_sender, lock_receiver) = mpsc::channel(10);
+        let _ = output
+            .send(Event::Started(SecretServiceConnection(lock_sender)))
+            .await;
+        let names_lost = name_owner_changes.filter_map(|name_owner_changed| {
+            future::ready(
+                name_owner_changed
+                    .args()
+                    .ok()
+                    .filter(|args| args.new_owner().is_none())
+                    .map(|args| ServiceEvent::NameLost(args.name().to_string())),
+            )
+        });
+        let mut service_events = select(
+            lock_receiver.map(|()| ServiceEvent::DatabaseLocked),
+            names_lost,
+        );
+        while let Some(service_event) = service_events.next().await {
+            match service_event {
+                ServiceEvent::DatabaseLocked => context.remove_item_objects().await,
+                ServiceEvent::NameLost(name) => context.close_sessions_owned_by(&name).await,
+            }
+        }
+        future::pending::<()>().await;
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dh_session() {
+        // Plays the part of the app, checked against another HKDF and AES implementation
+        let app_private_key = BigUint::from(0xfedcba0987654321u64);
+        let app_public_key = BigUint::from(2u32)
+            .modpow(&app_private_key, &dh_prime())
+            .to_bytes_be();
+        let session_key =
+            dh_session_key(&BigUint::from(0x1234567890abcdefu64), &app_public_key).unwrap();
+        assert_eq!(
+            session_key.to_vec(),
+            BigUint::parse_bytes(b"fdd96bfc0240435cd3a94eb353b46ec3", 16)
+                .unwrap()
+                .to_bytes_be()
+        );
+        let iv: Vec<u8> = (0..16).collect();
+        assert_eq!(
+            decrypt_secret(
+                &session_key,
+                &iv,
+                &BigUint::parse_bytes(b"416f0c2cd2ec64f18fd1c93b771aad6c", 16)
+                    .unwrap()
+                    .to_bytes_be()
+            ),
+            Some(b"hunter2".to_vec())
+        );
+
+        // Both sides work out the same key from a fresh key pair
+        let (private_key, public_key) = dh_key_pair();
+        let session_key = dh_session_key(&private_key, &app_public_key).unwrap();
+        assert_eq!(
+            Some(session_key),
+            dh_session_key(&app_private_key, &public_key)
+        );
+        let (iv, encrypted_value) = encrypt_secret(&session_key, b"hunter2");
+        assert_ne!(encrypted_value, b"hunter2");
+        assert_eq!(
+            decrypt_secret(&session_key, &iv, &encrypted_value),
+            Some(b"hunter2".to_vec())
+        );
+        assert_eq!(dh_session_key(&private_key, &[1]), None);
+    }
+
+    #[test]
+    fn test_sessions_belong_to_the_app_that_opened_them() {
+        let mut sessions = Sessions::default();
+        let first_session = object_path(format!("{SESSION_PATH_PREFIX}/1"));
+        let second_session = object_path(format!("{SESSION_PATH_PREFIX}/2"));
+        sessions.open(first_session.clone(), ":1.42", Some([7; AES_BLOCK_LENGTH]));
+        sessions.open(second_session.clone(), ":1.42", None);
+
+        assert_eq!(
+            sessions.key(&first_session, Some(":1.42")),
+            Some(Some([7; AES_BLOCK_LENGTH]))
+        );
+        assert_eq!(sessions.key(&first_session, Some(":1.43")), None);
+        assert_eq!(sessions.key(&first_session, None), None);
+        assert!(!sessions.close(&first_session, Some(":1.43")));
+        assert!(sessions.close(&first_session, Some(":1.42")));
+        assert_eq!(sessions.key(&first_session, Some(":1.42")), None);
+
+        assert!(sessions.close_all_owned_by(":1.43").is_empty());
+        assert_eq!(
+            sessions.close_all_owned_by(":1.42"),
+            vec![second_session.clone()]
+        );
+        assert_eq!(sessions.key(&second_session, Some(":1.42")), None);
+    }
+}
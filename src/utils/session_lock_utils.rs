@@ -0,0 +1,75 @@
+use std::pin::pin;
+
+use iced::futures::{stream, SinkExt, Stream, StreamExt};
+use iced::stream as iced_stream;
+use zbus::{proxy, Connection};
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The user's session was locked
+    SessionLocked,
+    Error(String),
+}
+
+/// The logind session interface for the session the app is running in, see
+/// https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    /// Sent when something asks for the session to be locked, e.g. `loginctl lock-session` or suspending
+    #[zbus(signal)]
+    fn lock(&self) -> zbus::Result<()>;
+
+    /// Set by the screen locker while the session is locked
+    #[zbus(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+}
+
+/// Watches logind for the session being locked, either through its Lock signal or the screen locker setting LockedHint
+pub fn session_locks() -> impl Stream<Item = Event> {
+    iced_stream::channel(100, move |mut output| async move {
+        let session_proxy = match connect_to_session().await {
+            Ok(session_proxy) => session_proxy,
+            Err(err) => {
+                let _ = output
+                    .send(Event::Error(format!(
+                        "Couldn't connect to logind, the password database won't lock with the screen: {err}"
+                    )))
+                    .await;
+                return;
+            }
+        };
+        let lock_stream = match session_proxy.receive_lock().await {
+            Ok(lock_stream) => lock_stream,
+            Err(err) => {
+                let _ = output
+                    .send(Event::Error(format!(
+                        "Couldn't listen for the session locking: {err}"
+                    )))
+                    .await;
+                return;
+            }
+        };
+        let locked_hint_stream = session_proxy
+            .receive_locked_hint_changed()
+            .await
+            .then(|locked_hint| async move { locked_hint.get().await.unwrap_or(false) });
+        let mut is_locked_stream = pin!(stream::select(
+            lock_stream.map(|_| true),
+            locked_hint_stream
+        ));
+        while let Some(is_locked) = is_locked_stream.next().await {
+            if is_locked {
+                let _ = output.send(Event::SessionLocked).await;
+            }
+        }
+    })
+}
+
+async fn connect_to_session() -> zbus::Result<SessionProxy<'static>> {
+    let connection = Connection::system().await?;
+    SessionProxy::new(&connection).await
+}
@@ -129,6 +129,9 @@ pub fn connect(
                                         state = ConnectionState::Disconnected;
                                     }
                                 }
+                                if let Some(project_file) = sync_manager.changed_project_file.take() {
+                                    let _ = output.send(Event::ProjectFileChanged(project_file)).await;
+                                }
                         }
 
                         received = fused_websocket.select_next_some() => {
@@ -136,6 +139,9 @@ pub fn connect(
                                 Ok(tungstenite::Message::Text(message)) => {
                                    let deserialised_message: ServerFileRequest = serde_json::from_str(&message).unwrap();
                                    let message_to_send_option = sync_manager.handle_server_request(deserialised_message);
+                                   if let Some(project_file) = sync_manager.changed_project_file.take() {
+                                       let _ = output.send(Event::ProjectFileChanged(project_file)).await;
+                                   }
                                    if let Some(message_to_send) = message_to_send_option {
                                        let result = websocket.send(tungstenite::Message::Text(serde_json::to_string(&message_to_send).unwrap())).await;
 
@@ -187,6 +193,8 @@ pub enum Event {
     Connected(Connection),
     Disconnected,
     MessageReceived(ServerMessage),
+    /// Sync merged remote changes into this task project file
+    ProjectFileChanged(PathBuf),
 }
 
 #[derive(Debug, Clone)]
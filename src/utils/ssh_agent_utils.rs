@@ -0,0 +1,252 @@
+use std::{env, fs, os::unix::fs::PermissionsExt, path::PathBuf, pin::pin, time::Duration};
+
+use iced::futures::{
+    channel::mpsc, stream::FuturesUnordered, FutureExt, SinkExt, Stream, StreamExt,
+};
+use iced::stream;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{UnixListener, UnixStream},
+};
+
+use crate::constants::APP_ID;
+
+/// Message numbers from the ssh-agent protocol, see
+/// https://datatracker.ietf.org/doc/html/draft-miller-ssh-agent
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+/// Nothing ssh sends comes close to this, it stops a bad length taking all the memory
+const MAX_MESSAGE_SIZE: usize = 256 * 1024;
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A request from an SSH client, which has to be answered with `SshAgentRequest::respond`
+    Request(SshAgentRequest),
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SshAgentAction {
+    ListIdentities,
+    Sign {
+        /// The public key to sign with, in the SSH wire format
+        key_blob: Vec<u8>,
+        data: Vec<u8>,
+        /// Which RSA signature algorithm to use
+        flags: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SshAgentReply {
+    /// The public key blob and comment of each key
+    Identities(Vec<(Vec<u8>, String)>),
+    /// The signature in the SSH wire format
+    Signature(Vec<u8>),
+}
+
+/// A request from an SSH client for the keys in the open database
+#[derive(Debug, Clone)]
+pub struct SshAgentRequest {
+    pub action: SshAgentAction,
+    responder: mpsc::Sender<Option<SshAgentReply>>,
+}
+
+impl SshAgentRequest {
+    /// Sends the reply back to the client, None tells it the request failed
+    pub fn respond(&self, reply: Option<SshAgentReply>) {
+        let _ = self.responder.clone().try_send(reply);
+    }
+}
+
+/// Where the agent listens, SSH_AUTH_SOCK has to be set to this for ssh to use it
+pub fn socket_path() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join(format!("{APP_ID}.ssh-agent.sock"))
+}
+
+/// Appends a string in the SSH wire format, a big endian u32 length followed by the bytes
+pub fn write_ssh_string(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+fn read_u32(buffer: &mut &[u8]) -> Option<u32> {
+    let (number, rest) = buffer.split_first_chunk::<4>()?;
+    *buffer = rest;
+    Some(u32::from_be_bytes(*number))
+}
+
+fn read_ssh_string(buffer: &mut &[u8]) -> Option<Vec<u8>> {
+    let length = read_u32(buffer)? as usize;
+    if buffer.len() < length {
+        return None;
+    }
+    let (string, rest) = buffer.split_at(length);
+    *buffer = rest;
+    Some(string.to_vec())
+}
+
+/// Parses a request, None for the ones that aren't supported. Adding and removing keys isn't supported as
+/// the keys come from the database
+fn parse_request(message: &[u8]) -> Option<SshAgentAction> {
+    let (message_number, mut contents) = message.split_first()?;
+    match *message_number {
+        SSH_AGENTC_REQUEST_IDENTITIES => Some(SshAgentAction::ListIdentities),
+        SSH_AGENTC_SIGN_REQUEST => Some(SshAgentAction::Sign {
+            key_blob: read_ssh_string(&mut contents)?,
+            data: read_ssh_string(&mut contents)?,
+            flags: read_u32(&mut contents).unwrap_or(0),
+        }),
+        _ => None,
+    }
+}
+
+fn encode_reply(reply: Option<SshAgentReply>) -> Vec<u8> {
+    match reply {
+        Some(SshAgentReply::Identities(identities)) => {
+            let mut message = vec![SSH_AGENT_IDENTITIES_ANSWER];
+            message.extend_from_slice(&(identities.len() as u32).to_be_bytes());
+            identities.iter().for_each(|(key_blob, comment)| {
+                write_ssh_string(&mut message, key_blob);
+                write_ssh_string(&mut message, comment.as_bytes());
+            });
+            message
+        }
+        Some(SshAgentReply::Signature(signature)) => {
+            let mut message = vec![SSH_AGENT_SIGN_RESPONSE];
+            write_ssh_string(&mut message, &signature);
+            message
+        }
+        None => vec![SSH_AGENT_FAILURE],
+    }
+}
+
+/// Listens on the agent socket, passing requests on to the app which holds the keys
+pub fn serve() -> impl Stream<Item = Event> {
+    stream::channel(100, move |mut output| async move {
+        let socket_path = socket_path();
+        // Left over if the app didn't shut down cleanly
+        let _ = fs::remove_file(&socket_path);
+        let listener = match UnixListener::bind(&socket_path) {
+            Ok(listener) => listener,
+            Err(err) => {
+                let _ = output
+                    .send(Event::Error(format!(
+                        "Failed to start the SSH agent at {socket_path:?}: {err}"
+                    )))
+                    .await;
+                return;
+            }
+        };
+        let _ = fs::set_permissions(&socket_path, fs::Permissions::from_mode(0o600));
+        let mut connections = FuturesUnordered::new();
+        loop {
+            let mut accept = pin!(listener.accept().fuse());
+            iced::futures::select! {
+                accepted = accept => match accepted {
+                    Ok((unix_stream, _address)) => {
+                        connections.push(handle_connection(unix_stream, output.clone()))
+                    }
+                    Err(err) => {
+                        println!("SSH agent connection error: {err:?}");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                },
+                _ = connections.select_next_some() => (),
+            }
+        }
+    })
+}
+
+/// Reads a message, a big endian u32 length followed by the message number and its contents
+async fn read_message(reader: &mut (impl AsyncRead + Unpin)) -> Option<Vec<u8>> {
+    let message_length = reader.read_u32().await.ok()? as usize;
+    if message_length > MAX_MESSAGE_SIZE {
+        return None;
+    }
+    let mut message = vec![0; message_length];
+    reader.read_exact(&mut message).await.ok()?;
+    Some(message)
+}
+
+async fn write_message(writer: &mut (impl AsyncWrite + Unpin), message: &[u8]) -> Option<()> {
+    writer.write_u32(message.len() as u32).await.ok()?;
+    writer.write_all(message).await.ok()?;
+    writer.flush().await.ok()
+}
+
+async fn handle_connection(mut unix_stream: UnixStream, mut output: mpsc::Sender<Event>) {
+    while let Some(message) = read_message(&mut unix_stream).await {
+        let reply = match parse_request(&message) {
+            Some(action) => {
+                let (responder, mut reply_receiver) = mpsc::channel(1);
+                if output
+                    .send(Event::Request(SshAgentRequest { action, responder }))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                reply_receiver.next().await.flatten()
+            }
+            None => None,
+        };
+        if write_message(&mut unix_stream, &encode_reply(reply))
+            .await
+            .is_none()
+        {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_messages() {
+        assert_eq!(
+            parse_request(&[SSH_AGENTC_REQUEST_IDENTITIES]),
+            Some(SshAgentAction::ListIdentities)
+        );
+
+        let mut sign_request = vec![SSH_AGENTC_SIGN_REQUEST];
+        write_ssh_string(&mut sign_request, b"key blob");
+        write_ssh_string(&mut sign_request, b"session data");
+        sign_request.extend_from_slice(&4u32.to_be_bytes());
+        assert_eq!(
+            parse_request(&sign_request),
+            Some(SshAgentAction::Sign {
+                key_blob: b"key blob".to_vec(),
+                data: b"session data".to_vec(),
+                flags: 4,
+            })
+        );
+        // A string longer than the message
+        assert_eq!(
+            parse_request(&[SSH_AGENTC_SIGN_REQUEST, 0, 0, 1, 0, 1]),
+            None
+        );
+        // Adding a key isn't supported
+        assert_eq!(parse_request(&[17]), None);
+
+        let mut identities_answer = vec![SSH_AGENT_IDENTITIES_ANSWER, 0, 0, 0, 1];
+        write_ssh_string(&mut identities_answer, b"key blob");
+        write_ssh_string(&mut identities_answer, b"me");
+        assert_eq!(
+            encode_reply(Some(SshAgentReply::Identities(vec![(
+                b"key blob".to_vec(),
+                String::from("me")
+            )]))),
+            identities_answer
+        );
+        assert_eq!(encode_reply(None), vec![SSH_AGENT_FAILURE]);
+    }
+}
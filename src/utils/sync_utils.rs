@@ -12,6 +12,9 @@ use walkdir::WalkDir;
 
 use crate::constants::{APP_ID, LORO_NOTE_ID};
 use crate::pages::sync::page::SYNC_LIST_FILE_NAME;
+use crate::pages::tasks::tasks_utils::{
+    read_tasks_crdt, read_tasks_from_project_file, tasks_from_crdt, update_tasks_crdt,
+};
 
 const DEFAULT_CRYPTO_HASH_SIZE: u32 = 16;
 
@@ -111,6 +114,8 @@ pub struct SyncManager {
     pub client_ignore_list: Vec<String>,
     pub client_ignored_remote_folders_list: Vec<String>,
     pub default_data_storage_folder: PathBuf,
+    /// A task project file which was rewritten from its merged CRDT by the last request, so it has to be reloaded if it's open
+    pub changed_project_file: Option<PathBuf>,
 }
 
 impl SyncManager {
@@ -132,6 +137,7 @@ impl SyncManager {
             client_ignore_list,
             default_data_storage_folder,
             client_ignored_remote_folders_list,
+            changed_project_file: None,
         }
     }
     pub fn get_list_of_files_to_sync(
@@ -298,14 +304,6 @@ impl SyncManager {
                     .get(file_index)
                     .and_then(|file_data| file_data.get_absolute_path(&self.list_of_folders))
                 {
-                    let client_loro_file_bytes = fs::read(&path_to_apply).unwrap();
-                    let server_doc = LoroDoc::new();
-                    let _ = server_doc.import(&server_loro_bytes);
-                    let client_doc = LoroDoc::new();
-                    let _ = client_doc.import_batch(&[client_loro_file_bytes, server_loro_bytes]);
-                    let client_export_bytes = client_doc.export_from(&server_doc.oplog_vv());
-                    let new_client_bytes = client_doc.export_snapshot();
-                    let _ = fs::write(&path_to_apply, new_client_bytes);
                     let base_file_path = path_to_apply.parent().unwrap().join(
                         path_to_apply
                             .file_stem()
@@ -315,8 +313,41 @@ impl SyncManager {
                             .skip(1)
                             .collect::<String>(),
                     );
-                    let updated_base_file_text = client_doc.get_text(LORO_NOTE_ID).to_string();
-                    let _ = fs::write(base_file_path, updated_base_file_text);
+                    // Task projects are JSON files, notes are the plain text of the CRDT
+                    let is_tasks_project = base_file_path
+                        .extension()
+                        .is_some_and(|extension| extension == "json");
+                    let client_loro_file_bytes = if is_tasks_project {
+                        // The project file can be changed without the CRDT, such as by the CalDAV server, so catch it up first
+                        let client_tasks_crdt = read_tasks_crdt(&base_file_path);
+                        let _ = update_tasks_crdt(
+                            &client_tasks_crdt,
+                            &read_tasks_from_project_file(&base_file_path),
+                        );
+                        client_tasks_crdt.export_snapshot()
+                    } else {
+                        fs::read(&path_to_apply).unwrap()
+                    };
+                    let server_doc = LoroDoc::new();
+                    let _ = server_doc.import(&server_loro_bytes);
+                    let client_doc = LoroDoc::new();
+                    let _ = client_doc.import_batch(&[client_loro_file_bytes, server_loro_bytes]);
+                    let client_export_bytes = client_doc.export_from(&server_doc.oplog_vv());
+                    let new_client_bytes = client_doc.export_snapshot();
+                    let _ = fs::write(&path_to_apply, new_client_bytes);
+                    let updated_base_file_text = if is_tasks_project {
+                        serde_json::to_string(&tasks_from_crdt(&client_doc)).ok()
+                    } else {
+                        Some(client_doc.get_text(LORO_NOTE_ID).to_string())
+                    };
+                    // Leave the project file as it is if the merged tasks can't be written out, the CRDT still has them
+                    if let Some(updated_base_file_text) = updated_base_file_text {
+                        if fs::write(&base_file_path, updated_base_file_text).is_ok()
+                            && is_tasks_project
+                        {
+                            self.changed_project_file = Some(base_file_path);
+                        }
+                    }
                     return Some(ClientFileResponse::LoroUpdate(
                         file_index,
                         client_export_bytes,